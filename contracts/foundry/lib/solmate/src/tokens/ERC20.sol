@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+pragma solidity >=0.8.0;
+
+abstract contract ERC20 {
+    function transfer(address to, uint256 amount) public virtual returns (bool);
+    function transferFrom(address from, address to, uint256 amount) public virtual returns (bool);
+}
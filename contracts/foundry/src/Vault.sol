@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./interfaces/IVault.sol";
+import {Owned} from "solmate/auth/Owned.sol";
+import {ERC20} from "solmate/tokens/ERC20.sol";
+
+contract Vault is IVault, Owned {
+    ERC20 public immutable asset;
+    mapping(address => uint256) public balanceOf;
+
+    constructor(ERC20 _asset) Owned(msg.sender) {
+        asset = _asset;
+    }
+
+    function deposit(uint256 amount) external {
+        balanceOf[msg.sender] += amount;
+        asset.transferFrom(msg.sender, address(this), amount);
+    }
+
+    function withdraw(uint256 amount) external {
+        balanceOf[msg.sender] -= amount;
+        asset.transfer(msg.sender, amount);
+    }
+}
@@ -0,0 +1,260 @@
+use alloy_dyn_abi::{DynSolType, DynSolValue, JsonAbiExt, Specifier};
+use alloy_json_abi::Function;
+use alloy_primitives::{hex, Address, Function as FunctionPointer, I256, U256};
+use eyre::{eyre, ContextCompat, Result};
+use itertools::Itertools;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::plain_contract::PlainContract;
+
+/// The complete ABI of a compiled contract, with its constructor, fallback,
+/// events and errors besides the functions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContractAbi {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    pub abi_json: String,
+}
+
+/// Return the ABI of every compiled contract, interfaces included
+pub fn extract_abis(contract: &PlainContract) -> Result<Vec<ContractAbi>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut abis = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        let Some(abi) = artifact.abi.as_ref() else {
+            continue;
+        };
+        abis.push(ContractAbi {
+            contract_id: contract_id.clone(),
+            source_path: file.display().to_string(),
+            contract_name: contract_name.clone(),
+            abi_json: serde_json::to_string(abi)?,
+        });
+    }
+    Ok(abis)
+}
+
+/// Render a decoded ABI value as a human readable string, numbers are printed
+/// in decimal and byte values as `0x` prefixed hex.
+pub fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::FixedBytes(word, size) => hex::encode_prefixed(&word[..*size]),
+        DynSolValue::Address(address) => address.to_checksum(None),
+        DynSolValue::Function(function) => hex::encode_prefixed(function.as_slice()),
+        DynSolValue::Bytes(bytes) => hex::encode_prefixed(bytes),
+        DynSolValue::String(s) => s.clone(),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{}]", values.iter().map(format_value).join(", "))
+        }
+        DynSolValue::Tuple(values) => {
+            format!("({})", values.iter().map(format_value).join(", "))
+        }
+    }
+}
+
+/// Decode a hex string with or without the `0x` prefix
+pub fn decode_hex(data: &str) -> eyre::Result<Vec<u8>> {
+    Ok(hex::decode(data.trim().trim_start_matches("0x"))?)
+}
+
+/// A function from its text signature as stored in the `function` table,
+/// e.g. `transfer(address,uint256)`
+pub fn parse_signature(signature: &str) -> Result<Function> {
+    Function::parse(signature).map_err(|e| eyre!("Invalid signature {signature}: {e}"))
+}
+
+/// Types of the parameters of the function
+pub fn input_types(function: &Function) -> Result<Vec<DynSolType>> {
+    function
+        .inputs
+        .iter()
+        .map(|p| p.resolve().map_err(|e| eyre!("Invalid type {}: {e}", p.ty)))
+        .collect()
+}
+
+/// A random word of `bits` bits, biased towards the edge values 0, 1 and the
+/// maximum, which are the most interesting seeds
+fn random_bits(bits: usize, rng: &mut impl Rng) -> U256 {
+    let max = U256::MAX >> (256 - bits);
+    match rng.gen_range(0..5) {
+        0 => U256::ZERO,
+        1 => U256::from(1),
+        2 => max,
+        _ => U256::from_be_bytes(rng.gen::<[u8; 32]>()) & max,
+    }
+}
+
+/// A random value of the type. Dynamic arrays have up to 3 elements, bytes
+/// and strings up to 64 bytes.
+pub fn random_value(ty: &DynSolType, rng: &mut impl Rng) -> DynSolValue {
+    match ty {
+        DynSolType::Bool => DynSolValue::Bool(rng.gen()),
+        DynSolType::Uint(bits) => DynSolValue::Uint(random_bits(*bits, rng), *bits),
+        DynSolType::Int(bits) => {
+            let raw = random_bits(*bits, rng);
+            // sign extend the two's complement of `bits` bits
+            let raw = match raw.bit(bits - 1) {
+                true => raw | !(U256::MAX >> (256 - bits)),
+                false => raw,
+            };
+            DynSolValue::Int(I256::from_raw(raw), *bits)
+        }
+        DynSolType::Address => DynSolValue::Address(Address::from(rng.gen::<[u8; 20]>())),
+        DynSolType::Function => DynSolValue::Function(FunctionPointer::from(rng.gen::<[u8; 24]>())),
+        DynSolType::FixedBytes(size) => {
+            let mut word = [0u8; 32];
+            rng.fill(&mut word[..*size]);
+            DynSolValue::FixedBytes(word.into(), *size)
+        }
+        DynSolType::Bytes => {
+            let len = rng.gen_range(0..=64);
+            DynSolValue::Bytes((0..len).map(|_| rng.gen()).collect())
+        }
+        DynSolType::String => {
+            let len = rng.gen_range(0..=64);
+            DynSolValue::String((0..len).map(|_| rng.sample(Alphanumeric) as char).collect())
+        }
+        DynSolType::Array(ty) => {
+            let len = rng.gen_range(0..=3);
+            DynSolValue::Array((0..len).map(|_| random_value(ty, rng)).collect())
+        }
+        DynSolType::FixedArray(ty, len) => {
+            DynSolValue::FixedArray((0..*len).map(|_| random_value(ty, rng)).collect())
+        }
+        DynSolType::Tuple(types) => {
+            DynSolValue::Tuple(types.iter().map(|ty| random_value(ty, rng)).collect())
+        }
+    }
+}
+
+/// Calldata of a call to the function with the arguments, parsed from their
+/// text form, e.g. `0xdead...`, `100` or `[1,2]`
+pub fn encode_call(function: &Function, args: &[String]) -> Result<(Vec<DynSolValue>, Vec<u8>)> {
+    let types = input_types(function)?;
+    if types.len() != args.len() {
+        return Err(eyre!(
+            "{} takes {} arguments, got {}",
+            function.signature(),
+            types.len(),
+            args.len()
+        ));
+    }
+    let values = types
+        .iter()
+        .zip(args)
+        .map(|(ty, arg)| {
+            ty.coerce_str(arg)
+                .map_err(|e| eyre!("Invalid {ty} {arg}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let calldata = function.abi_encode_input(&values)?;
+    Ok((values, calldata))
+}
+
+/// Arguments of a call to the function, `None` when the calldata has another
+/// selector or its arguments are not a valid encoding of the parameters
+pub fn decode_call(function: &Function, calldata: &[u8]) -> Option<Vec<DynSolValue>> {
+    if calldata.len() < 4 || calldata[..4] != function.selector()[..] {
+        return None;
+    }
+    function.abi_decode_input(&calldata[4..], true).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn format_nested_values() {
+        let value = DynSolValue::Tuple(vec![
+            DynSolValue::Uint(U256::from(42), 256),
+            DynSolValue::Array(vec![DynSolValue::Bool(true), DynSolValue::Bool(false)]),
+            DynSolValue::Address(Address::ZERO),
+            DynSolValue::Bytes(vec![0xde, 0xad]),
+        ]);
+
+        assert_eq!(
+            format_value(&value),
+            "(42, [true, false], 0x0000000000000000000000000000000000000000, 0xdead)"
+        );
+    }
+
+    #[test]
+    fn encode_calls() -> Result<()> {
+        let transfer = parse_signature("transfer(address,uint256)")?;
+        let (_, calldata) = encode_call(
+            &transfer,
+            &[
+                "0x000000000000000000000000000000000000dEaD".into(),
+                "100".into(),
+            ],
+        )?;
+        assert_eq!(
+            hex::encode(&calldata),
+            "a9059cbb000000000000000000000000000000000000000000000000000000000000dead0000000000000000000000000000000000000000000000000000000000000064"
+        );
+        assert!(encode_call(&transfer, &["100".into()]).is_err());
+
+        let values = decode_call(&transfer, &calldata).expect("Valid transfer calldata");
+        assert_eq!(
+            values.iter().map(format_value).collect_vec(),
+            ["0x000000000000000000000000000000000000dEaD", "100"]
+        );
+        assert!(decode_call(&transfer, &calldata[..20]).is_none());
+        let approve = parse_signature("approve(address,uint256)")?;
+        assert!(decode_call(&approve, &calldata).is_none());
+
+        let function = parse_signature("f(int8,bytes3,(uint16,string)[],bool[2])")?;
+        let types = input_types(&function)?;
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let values = types
+                .iter()
+                .map(|ty| random_value(ty, &mut rng))
+                .collect_vec();
+            let calldata = function.abi_encode_input(&values)?;
+            assert_eq!(function.abi_decode_input(&calldata[4..], true)?, values);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn abis_of_same_named_contracts() -> Result<()> {
+        let abi = |event: &str| {
+            serde_json::json!({
+                "abi": [{"type": "event", "name": event, "inputs": [], "anonymous": false}]
+            })
+        };
+        let contract = PlainContract::with_artifacts(
+            vec![
+                ("src/v1/Token.sol", "Token", abi("Minted")),
+                ("src/v2/Token.sol", "Token", abi("Burned")),
+            ],
+            &[],
+        )?;
+
+        let abis = extract_abis(&contract)?;
+        assert_eq!(
+            abis.iter()
+                .map(|a| (a.source_path.as_str(), a.contract_name.as_str()))
+                .collect_vec(),
+            [("src/v1/Token.sol", "Token"), ("src/v2/Token.sol", "Token")]
+        );
+        assert!(abis[0].abi_json.contains("Minted") && abis[1].abi_json.contains("Burned"));
+
+        Ok(())
+    }
+}
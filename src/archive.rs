@@ -0,0 +1,239 @@
+use eyre::{eyre, Result};
+use flate2::read::GzDecoder;
+use log::{debug, warn};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use crate::plain_contract::{is_contract_file, PlainContract};
+
+/// Whether the path is an archive of contract folders, i.e. a zip, tar or
+/// gzipped tar file
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    path.is_file()
+        && [".zip", ".tar", ".tar.gz", ".tgz"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+}
+
+/// Files of a contract folder in an archive by file name
+type Files = BTreeMap<String, String>;
+
+/// Folder and file name of an archive entry, `None` for entries not belonging
+/// to a contract folder, which are skipped without reading them
+fn entry_folder(path: &str) -> Option<(String, String)> {
+    let path = Path::new(path);
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    if !is_contract_file(&name) {
+        return None;
+    }
+    let folder = path
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Some((folder, name))
+}
+
+/// Add an archive entry to the files of its folder, entries which are not
+/// UTF-8 are skipped with a warning
+fn add_entry(files: &mut Files, path: &str, name: String, mut reader: impl Read) -> Result<()> {
+    let mut content = vec![];
+    reader.read_to_end(&mut content)?;
+    match String::from_utf8(content) {
+        Ok(content) => {
+            files.insert(name, content);
+        }
+        Err(_) => warn!("Skipping archive entry {path} which is not UTF-8"),
+    }
+    Ok(())
+}
+
+/// Parses the contract folders of an archive one at a time, so that only the
+/// files of a single folder are held in memory
+struct FolderParser<'a> {
+    path: &'a Path,
+    ignore_errors: bool,
+    skip: &'a HashSet<String>,
+    contracts: Vec<(String, PlainContract)>,
+}
+
+impl FolderParser<'_> {
+    fn parse(&mut self, folder: &str, files: Files) -> Result<()> {
+        let key = self.path.join(folder).to_string_lossy().into_owned();
+        if !files.contains_key("metadata.json") || self.skip.contains(&key) {
+            return Ok(());
+        }
+        match PlainContract::from_files(files) {
+            Ok(c) => self.contracts.push((key, c)),
+            Err(error) if self.ignore_errors => {
+                debug!("Process folder failed with error {error} {folder}")
+            }
+            Err(error) => return Err(eyre!("Process folder failed with error {error} {folder}")),
+        }
+        Ok(())
+    }
+}
+
+/// Zip archives are read folder by folder from the entry index
+fn read_zip(parser: &mut FolderParser) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(File::open(parser.path)?)?;
+    let mut folders: BTreeMap<String, Vec<(usize, String)>> = BTreeMap::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i)?;
+        if !entry.is_file() {
+            continue;
+        }
+        if let Some((folder, name)) = entry_folder(entry.name()) {
+            folders.entry(folder).or_default().push((i, name));
+        }
+    }
+    for (folder, entries) in folders {
+        let mut files = Files::new();
+        for (i, name) in entries {
+            let entry = archive.by_index(i)?;
+            let path = entry.name().to_string();
+            add_entry(&mut files, &path, name, entry)?;
+        }
+        parser.parse(&folder, files)?;
+    }
+    Ok(())
+}
+
+/// Tar archives can only be read in order, a folder is parsed once the next
+/// folder starts. Files of a folder listed again after another folder are
+/// skipped with a warning.
+fn read_tar(reader: impl Read, parser: &mut FolderParser) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    let mut parsed = HashSet::new();
+    let mut current: Option<(String, Files)> = None;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let Some((folder, name)) = entry_folder(&path) else {
+            continue;
+        };
+        if parsed.contains(&folder) {
+            warn!("Skipping archive entry {path} listed apart from its folder");
+            continue;
+        }
+        if let Some((previous, files)) = current.take_if(|(f, _)| *f != folder) {
+            parser.parse(&previous, files)?;
+            parsed.insert(previous);
+        }
+        let (_, files) = current.get_or_insert_with(|| (folder, Files::new()));
+        add_entry(files, &path, name, entry)?;
+    }
+    if let Some((folder, files)) = current {
+        parser.parse(&folder, files)?;
+    }
+    Ok(())
+}
+
+/// Parse every contract folder, i.e. every folder with a `metadata.json`, of a
+/// zip or tar archive without extracting it. Contracts are returned with their
+/// folder below the archive path, e.g. `contracts.zip/a`, folders in `skip` are
+/// not parsed.
+pub fn read_archive_contracts(
+    path: &Path,
+    ignore_errors: bool,
+    skip: &HashSet<String>,
+) -> Result<Vec<(String, PlainContract)>> {
+    let mut parser = FolderParser {
+        path,
+        ignore_errors,
+        skip,
+        contracts: vec![],
+    };
+    let name = path.to_string_lossy();
+    if name.ends_with(".zip") {
+        read_zip(&mut parser)?;
+    } else if name.ends_with(".tar") {
+        read_tar(File::open(path)?, &mut parser)?;
+    } else {
+        read_tar(GzDecoder::new(File::open(path)?), &mut parser)?;
+    }
+    Ok(parser.contracts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    const FILES: [(&str, &str); 4] = [
+        (
+            "contracts/a/metadata.json",
+            r#"{"ContractName":"A","CompilerVersion":"v0.8.19+commit.7dd6d404","Runs":200,"OptimizationUsed":true,"BytecodeHash":""}"#,
+        ),
+        ("contracts/a/main.sol", "contract A {}"),
+        (
+            "contracts/b/metadata.json",
+            r#"{"ContractName":"B","CompilerVersion":"v0.8.19+commit.7dd6d404","Runs":200,"OptimizationUsed":false,"BytecodeHash":""}"#,
+        ),
+        ("contracts/b/B.sol", "contract B {}"),
+    ];
+
+    /// An entry which is not UTF-8, skipped without failing the folder
+    const BINARY: (&str, &[u8]) = ("contracts/b/Binary.sol", &[0xff, 0xfe, 0x00]);
+
+    fn names(contracts: &[(String, PlainContract)]) -> Vec<String> {
+        let mut names: Vec<_> = contracts
+            .iter()
+            .map(|(_, c)| c.metadata.contract_name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn read_contracts_from_archives() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let zip_path = dir.path().join("contracts.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path)?);
+        for (name, content) in FILES {
+            zip.start_file(name, SimpleFileOptions::default())?;
+            zip.write_all(content.as_bytes())?;
+        }
+        zip.start_file(BINARY.0, SimpleFileOptions::default())?;
+        zip.write_all(BINARY.1)?;
+        zip.finish()?;
+
+        let tar_path = dir.path().join("contracts.tar.gz");
+        let encoder = GzEncoder::new(File::create(&tar_path)?, Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, content) in FILES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, name, content.as_bytes())?;
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_size(BINARY.1.len() as u64);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, BINARY.0, BINARY.1)?;
+        tar.into_inner()?.finish()?;
+
+        for path in [zip_path, tar_path] {
+            assert!(is_archive(&path));
+            let contracts = read_archive_contracts(&path, false, &HashSet::new())?;
+            assert_eq!(names(&contracts), vec!["A", "B"]);
+
+            let skip = HashSet::from([path.join("contracts/a").to_string_lossy().into_owned()]);
+            let contracts = read_archive_contracts(&path, false, &skip)?;
+            assert_eq!(names(&contracts), vec!["B"]);
+        }
+        assert!(!is_archive(dir.path()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use eyre::Result;
+use foundry_compilers::{
+    artifacts::ConfigurableContractArtifact, ArtifactFile, Artifacts, ProjectCompileOutput,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::plain_contract::PlainContract;
+
+/// An artifact of a cached compilation output
+#[derive(Serialize, Deserialize)]
+struct CachedArtifact {
+    /// Source file declaring the contract
+    source: PathBuf,
+    contract_name: String,
+    /// Artifact file, the contract name of the output is read from its stem
+    file: PathBuf,
+    version: String,
+    build_id: String,
+    artifact: ConfigurableContractArtifact,
+}
+
+/// On-disk cache of successful compilation outputs, keyed by contract id and
+/// compiler, i.e. the compiler version and the settings changing the output.
+///
+/// Only the artifacts are kept, a cached output has no diagnostics. Entries are
+/// written to a temporary path and renamed, so a crashed run never leaves a
+/// partial entry behind.
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    root: PathBuf,
+}
+
+impl ArtifactCache {
+    pub fn new(root: &str) -> Result<Self> {
+        std::fs::create_dir_all(root)?;
+        Ok(Self { root: root.into() })
+    }
+
+    fn path(&self, contract_id: &str, compiler: &str) -> PathBuf {
+        let compiler: String = compiler
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-'))
+            .collect();
+        self.root.join(format!("{contract_id}-{compiler}.json"))
+    }
+
+    /// The cached output of the contract compiled by the compiler
+    pub async fn get(
+        &self,
+        contract_id: &str,
+        compiler: &str,
+    ) -> Result<Option<ProjectCompileOutput>> {
+        let path = self.path(contract_id, compiler);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let cached: Vec<CachedArtifact> =
+            serde_json::from_str(&tokio::fs::read_to_string(path).await?)?;
+
+        let mut artifacts: BTreeMap<PathBuf, BTreeMap<String, Vec<_>>> = BTreeMap::new();
+        for a in cached {
+            artifacts
+                .entry(a.source)
+                .or_default()
+                .entry(a.contract_name)
+                .or_default()
+                .push(ArtifactFile {
+                    artifact: a.artifact,
+                    file: a.file,
+                    version: Version::parse(&a.version)?,
+                    build_id: a.build_id,
+                });
+        }
+        let mut output = PlainContract::empty_output()?;
+        output.set_compiled_artifacts(Artifacts(artifacts));
+        Ok(Some(output))
+    }
+
+    /// Store the artifacts of a compilation output without errors
+    pub async fn put(
+        &self,
+        contract_id: &str,
+        compiler: &str,
+        output: &ProjectCompileOutput,
+    ) -> Result<()> {
+        if output.has_compiler_errors() {
+            return Ok(());
+        }
+        let cached = output
+            .compiled_artifacts()
+            .into_iter()
+            .flat_map(|(source, contracts)| {
+                contracts.iter().flat_map(move |(contract_name, files)| {
+                    files.iter().map(move |f| CachedArtifact {
+                        source: source.clone(),
+                        contract_name: contract_name.clone(),
+                        file: f.file.clone(),
+                        version: f.version.to_string(),
+                        build_id: f.build_id.clone(),
+                        artifact: f.artifact.clone(),
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        write_atomic(
+            &self.path(contract_id, compiler),
+            &serde_json::to_string(&cached)?,
+        )
+        .await
+    }
+}
+
+async fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension(format!("tmp{}", rand::random::<u32>()));
+    tokio::fs::write(&tmp, content).await?;
+    tokio::fs::rename(tmp, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+
+    #[tokio::test]
+    async fn restore_cached_artifacts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ArtifactCache::new(dir.path().to_str().unwrap())?;
+        assert!(cache.get("c1", "v0.8.20").await?.is_none());
+
+        let artifact = |name: &str| ArtifactFile {
+            artifact: ConfigurableContractArtifact::default(),
+            file: PathBuf::from(format!("out/main.sol/{name}.json")),
+            version: Version::new(0, 8, 20),
+            build_id: "b".into(),
+        };
+        let artifacts = BTreeMap::from([(
+            PathBuf::from("main.sol"),
+            BTreeMap::from([
+                ("Token".to_string(), vec![artifact("Token")]),
+                ("Vault".to_string(), vec![artifact("Vault")]),
+            ]),
+        )]);
+        let mut output = PlainContract::empty_output()?;
+        output.set_compiled_artifacts(Artifacts(artifacts));
+        cache.put("c1", "v0.8.20+commit.a1b79de6", &output).await?;
+
+        assert!(cache.get("c1", "v0.8.19").await?.is_none());
+        let cached = cache
+            .get("c1", "v0.8.20+commit.a1b79de6")
+            .await?
+            .expect("Cached output");
+        assert_eq!(
+            cached
+                .artifacts_with_files()
+                .map(|(file, name, _)| format!("{}:{name}", file.display()))
+                .collect_vec(),
+            ["main.sol:Token", "main.sol:Vault"]
+        );
+        assert_eq!(
+            cached.artifacts().map(|(name, _)| name).collect_vec(),
+            ["Token", "Vault"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use eyre::Result;
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::{
+    ast_query::{descendants, node_source, node_type, source_content},
+    literals::visit_implementations,
+    plain_contract::PlainContract,
+};
+
+/// An inline assembly block in the body of a function, constructor or modifier
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AssemblyBlock {
+    /// Id of the function in the `function` or `function_definition` table
+    pub function_id: String,
+    pub contract_id: String,
+    /// Position of the block in the function, in source order
+    pub position: u32,
+    /// Distinct opcodes called in the block, comma separated, e.g. `sload,delegatecall`
+    pub opcodes: String,
+    /// Yul source of the block
+    pub source: String,
+}
+
+/// Distinct builtins called in an `InlineAssembly` node, in order of first use.
+/// Solidity before 0.6 only provides the block as text in `operations`.
+pub fn assembly_opcodes(block: &Value) -> Vec<String> {
+    let Some(yul) = block.get("AST") else {
+        let call = Regex::new(r"\b([a-z][a-z0-9]*)\s*\(").unwrap();
+        let operations = block
+            .get("operations")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let defined: HashSet<_> = Regex::new(r"\bfunction\s+([A-Za-z_$][\w$]*)")
+            .unwrap()
+            .captures_iter(operations)
+            .map(|c| c[1].to_string())
+            .collect();
+        return call
+            .captures_iter(operations)
+            .map(|c| c[1].to_string())
+            .filter(|name| name != "function" && !defined.contains(name))
+            .unique()
+            .collect();
+    };
+
+    let mut nodes = vec![];
+    descendants(yul, &mut nodes);
+    // calls of functions defined in the block are not opcodes
+    let defined: HashSet<_> = nodes
+        .iter()
+        .filter(|n| node_type(n) == Some("YulFunctionDefinition"))
+        .filter_map(|n| n.get("name").and_then(Value::as_str))
+        .collect();
+    nodes
+        .iter()
+        .filter(|n| node_type(n) == Some("YulFunctionCall"))
+        .filter_map(|n| n.pointer("/functionName/name").and_then(Value::as_str))
+        .filter(|name| !defined.contains(name))
+        .unique()
+        .map(String::from)
+        .collect()
+}
+
+/// The inline assembly blocks of a function definition, in source order
+pub fn function_assembly(function: &Value) -> Vec<&Value> {
+    let mut nodes = vec![];
+    descendants(function, &mut nodes);
+    nodes
+        .into_iter()
+        .filter(|n| node_type(n) == Some("InlineAssembly"))
+        .collect()
+}
+
+/// Return the inline assembly blocks of every function, constructor and
+/// modifier of every compiled contract, see [`visit_implementations`]
+pub fn extract_assembly(contract: &PlainContract) -> Result<Vec<AssemblyBlock>> {
+    let contract_id = contract.id();
+    let mut blocks = vec![];
+    visit_implementations(contract, |function_id, path, function| {
+        let content = source_content(contract, path);
+        for (position, block) in function_assembly(function).into_iter().enumerate() {
+            let source = match node_source(&content, block) {
+                "" => block
+                    .get("operations")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                source => source,
+            };
+            blocks.push(AssemblyBlock {
+                function_id: function_id.into(),
+                contract_id: contract_id.clone(),
+                position: position as u32,
+                opcodes: assembly_opcodes(block).join(","),
+                source: source.into(),
+            });
+        }
+    })?;
+
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn list_assembly_opcodes() {
+        let function = serde_json::json!({
+            "nodeType": "FunctionDefinition",
+            "body": {
+                "nodeType": "Block",
+                "statements": [{
+                    "nodeType": "InlineAssembly",
+                    "AST": {
+                        "nodeType": "YulBlock",
+                        "statements": [{
+                            "nodeType": "YulFunctionDefinition",
+                            "name": "impl",
+                            "body": {
+                                "nodeType": "YulBlock",
+                                "statements": [{
+                                    "nodeType": "YulFunctionCall",
+                                    "functionName": {"nodeType": "YulIdentifier", "name": "sload"},
+                                    "arguments": [{"nodeType": "YulLiteral", "value": "0"}]
+                                }]
+                            }
+                        }, {
+                            "nodeType": "YulExpressionStatement",
+                            "expression": {
+                                "nodeType": "YulFunctionCall",
+                                "functionName": {"nodeType": "YulIdentifier", "name": "delegatecall"},
+                                "arguments": [
+                                    {"nodeType": "YulFunctionCall", "functionName": {"nodeType": "YulIdentifier", "name": "gas"}, "arguments": []},
+                                    {"nodeType": "YulFunctionCall", "functionName": {"nodeType": "YulIdentifier", "name": "impl"}, "arguments": []}
+                                ]
+                            }
+                        }]
+                    }
+                }, {
+                    "nodeType": "InlineAssembly",
+                    "operations": "{\n    function f() -> r { r := caller() }\n    let size := extcodesize(f())\n    mstore(0x40, size)\n}"
+                }]
+            }
+        });
+
+        let blocks = function_assembly(&function);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            assembly_opcodes(blocks[0]),
+            vec!["sload", "delegatecall", "gas"]
+        );
+        assert_eq!(
+            assembly_opcodes(blocks[1]),
+            vec!["caller", "extcodesize", "mstore"]
+        );
+    }
+}
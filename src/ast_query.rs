@@ -0,0 +1,551 @@
+use eyre::{eyre, ContextCompat, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashSet, str::FromStr};
+
+use crate::{finding::Finding, plain_contract::PlainContract};
+
+/// Prefix of the kind of findings stored for a named AST query
+pub const AST_QUERY_FINDING: &str = "ast-query";
+
+/// Maximum number of characters of the matched source kept in a finding
+const SNIPPET_LENGTH: usize = 200;
+
+/// A condition on an attribute of a node, e.g. `[expression.name=selfdestruct]`
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    /// Keys, or array indices, leading from the node to the attribute
+    path: Vec<String>,
+    /// The attribute only has to exist when missing
+    value: Option<String>,
+    negated: bool,
+}
+
+/// A node type, or `*` for any node, with conditions on its attributes
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    node_type: Option<String>,
+    filters: Vec<Filter>,
+}
+
+/// A query selecting nodes of a solidity AST. Steps separated by whitespace
+/// select descendants of the nodes matched by the previous step, e.g.
+/// `ContractDefinition[contractKind=library] FunctionCall[expression.name=selfdestruct]`.
+/// Filters compare the attribute with `=` or `!=`, strings without quotes, or
+/// only require the attribute to exist, e.g. `FunctionDefinition[modifiers.0]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AstQuery {
+    steps: Vec<Step>,
+}
+
+impl FromStr for Filter {
+    type Err = eyre::Report;
+
+    fn from_str(filter: &str) -> Result<Self> {
+        let (path, value, negated) = match filter.split_once("!=") {
+            Some((path, value)) => (path, Some(value), true),
+            None => match filter.split_once('=') {
+                Some((path, value)) => (path, Some(value), false),
+                None => (filter, None, false),
+            },
+        };
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(eyre!("Empty attribute in filter [{filter}]"));
+        }
+        Ok(Self {
+            path: path.split('.').map(String::from).collect(),
+            value: value.map(|v| v.trim().trim_matches('"').to_string()),
+            negated,
+        })
+    }
+}
+
+impl FromStr for Step {
+    type Err = eyre::Report;
+
+    fn from_str(step: &str) -> Result<Self> {
+        let (node_type, mut rest) = step.split_at(step.find('[').unwrap_or(step.len()));
+        let mut filters = vec![];
+        while !rest.is_empty() {
+            let end = rest
+                .find(']')
+                .with_context(|| format!("Unclosed filter in {step}"))?;
+            filters.push(rest[1..end].parse()?);
+            rest = &rest[end + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(eyre!("Unexpected {rest} in {step}"));
+            }
+        }
+        let node_type = match node_type {
+            "" => return Err(eyre!("Missing node type in {step}, use * for any node")),
+            "*" => None,
+            node_type => Some(node_type.to_string()),
+        };
+        Ok(Self { node_type, filters })
+    }
+}
+
+impl FromStr for AstQuery {
+    type Err = eyre::Report;
+
+    fn from_str(query: &str) -> Result<Self> {
+        // split at whitespace outside of filters
+        let mut steps = vec![];
+        let mut step = String::new();
+        let mut in_filter = false;
+        for c in query.trim().chars() {
+            match c {
+                '[' => in_filter = true,
+                ']' => in_filter = false,
+                c if c.is_whitespace() && !in_filter => {
+                    if !step.is_empty() {
+                        steps.push(step.parse()?);
+                        step.clear();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            step.push(c);
+        }
+        if !step.is_empty() {
+            steps.push(step.parse()?);
+        }
+        if steps.is_empty() {
+            return Err(eyre!("Empty AST query"));
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// The attribute at the path, array elements are selected by index
+fn attribute<'a>(node: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(node, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        value => value.get(key),
+    })
+}
+
+impl Filter {
+    fn matches(&self, node: &Value) -> bool {
+        let attribute = attribute(node, &self.path).filter(|v| !v.is_null());
+        let matches = match (&self.value, attribute) {
+            (None, attribute) => attribute.is_some(),
+            (Some(_), None) => false,
+            (Some(expected), Some(Value::String(s))) => s == expected,
+            (Some(expected), Some(value)) => {
+                serde_json::from_str::<Value>(expected).is_ok_and(|e| e == *value)
+            }
+        };
+        matches != self.negated
+    }
+}
+
+impl Step {
+    fn matches(&self, node: &Value) -> bool {
+        let Some(node_type) = node.get("nodeType").and_then(Value::as_str) else {
+            return false;
+        };
+        self.node_type.as_ref().is_none_or(|t| t == node_type)
+            && self.filters.iter().all(|f| f.matches(node))
+    }
+}
+
+/// All nodes nested in the value, the value itself excluded
+pub fn descendants<'a>(value: &'a Value, nodes: &mut Vec<&'a Value>) {
+    let children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Object(map) => Box::new(map.values()),
+        Value::Array(items) => Box::new(items.iter()),
+        _ => return,
+    };
+    for child in children {
+        if child.get("nodeType").is_some() {
+            nodes.push(child);
+        }
+        descendants(child, nodes);
+    }
+}
+
+/// The `nodeType` of an AST node
+pub fn node_type(node: &Value) -> Option<&str> {
+    node.get("nodeType").and_then(Value::as_str)
+}
+
+impl AstQuery {
+    /// Nodes of the AST matching the query, in source order
+    pub fn evaluate<'a>(&self, ast: &'a Value) -> Vec<&'a Value> {
+        let mut matched = vec![ast];
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut seen = HashSet::new();
+            let mut next = vec![];
+            for node in matched {
+                let mut nodes = vec![];
+                if i == 0 {
+                    nodes.push(node);
+                }
+                descendants(node, &mut nodes);
+                for node in nodes {
+                    if step.matches(node) && seen.insert(node as *const Value) {
+                        next.push(node);
+                    }
+                }
+            }
+            matched = next;
+        }
+        matched
+    }
+}
+
+/// Start and length of a `start:length:file` source location
+pub fn source_range(node: &Value) -> Option<(usize, usize)> {
+    let src = node.get("src")?.as_str()?;
+    let mut parts = src.split(':').map(|p| p.parse::<usize>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// Content of a compiled source file as seen by the compiler, offsets of the
+/// AST are relative to the normalized content, see
+/// `source_code_by_contract_and_function_name`
+pub fn source_content(contract: &PlainContract, path: &str) -> String {
+    contract
+        .source_files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .find(|f| path.ends_with(&f.name))
+        .map(|f| f.content.replace("\r\n", "\n"))
+        .unwrap_or_default()
+}
+
+/// The source code of a node, empty when the content does not match the AST
+pub fn node_source<'a>(content: &'a str, node: &Value) -> &'a str {
+    let (start, length) = source_range(node).unwrap_or_default();
+    content.get(start..start + length).unwrap_or_default()
+}
+
+/// The AST of every compiled source file as json, by the path of the file
+pub fn source_asts(contract: &PlainContract) -> Result<Vec<(String, Value)>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output, did you forget to call compile()?")?;
+
+    let mut files = HashSet::new();
+    let mut asts = vec![];
+    for (_, artifact) in compilation_output.artifacts() {
+        let Some(ast) = artifact.source_file().and_then(|f| f.ast) else {
+            continue;
+        };
+        if files.insert(ast.absolute_path.clone()) {
+            asts.push((ast.absolute_path.clone(), serde_json::to_value(&ast)?));
+        }
+    }
+    Ok(asts)
+}
+
+/// The AST of a compiled source file, stored by `index-functions` so that
+/// `ast-query` reads it instead of compiling the contract again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourceAst {
+    pub contract_id: String,
+    pub source_path: String,
+    pub ast_json: String,
+}
+
+/// Return the AST of every compiled source file of the contract
+pub fn extract_source_asts(contract: &PlainContract) -> Result<Vec<SourceAst>> {
+    let contract_id = contract.id();
+    source_asts(contract)?
+        .into_iter()
+        .map(|(source_path, ast)| {
+            Ok(SourceAst {
+                contract_id: contract_id.clone(),
+                source_path,
+                ast_json: serde_json::to_string(&ast)?,
+            })
+        })
+        .collect()
+}
+
+/// Source code of the event or error definitions of the compiled sources,
+/// to store with the events and errors of the ABI
+pub struct DefinitionSources {
+    /// Name, selector without `0x` when the AST has it, and source code
+    definitions: Vec<(String, Option<String>, String)>,
+}
+
+impl DefinitionSources {
+    /// The definitions of `node_type`, e.g. `EventDefinition`, with their
+    /// selector in `selector_attribute`, e.g. `eventSelector`
+    pub fn new(
+        contract: &PlainContract,
+        node_type: &str,
+        selector_attribute: &str,
+    ) -> Result<Self> {
+        let asts = source_asts(contract)?;
+        Ok(Self::from_asts(
+            &asts,
+            |path| source_content(contract, path),
+            node_type,
+            selector_attribute,
+        ))
+    }
+
+    fn from_asts(
+        asts: &[(String, Value)],
+        content: impl Fn(&str) -> String,
+        node_type: &str,
+        selector_attribute: &str,
+    ) -> Self {
+        let mut definitions = vec![];
+        for (path, ast) in asts.iter() {
+            let content = content(path);
+            let mut nodes = vec![];
+            descendants(ast, &mut nodes);
+            for node in nodes.into_iter().filter(|n| n["nodeType"] == node_type) {
+                let source = node_source(&content, node);
+                if source.is_empty() {
+                    continue;
+                }
+                definitions.push((
+                    node["name"].as_str().unwrap_or_default().to_string(),
+                    node[selector_attribute].as_str().map(String::from),
+                    source.to_string(),
+                ));
+            }
+        }
+        Self { definitions }
+    }
+
+    /// The source of the definition with the `0x` prefixed selector. Older
+    /// compilers do not record selectors in the AST, the definition is then
+    /// found by name when no other definition has the same name and the same
+    /// source.
+    pub fn get(&self, name: &str, selector: &str) -> Option<String> {
+        let selector = selector.trim_start_matches("0x");
+        if let Some((_, _, source)) = self
+            .definitions
+            .iter()
+            .find(|(_, s, _)| s.as_deref() == Some(selector))
+        {
+            return Some(source.clone());
+        }
+        let mut named = self
+            .definitions
+            .iter()
+            .filter(|(n, s, _)| n == name && s.is_none())
+            .map(|(_, _, source)| source)
+            .unique();
+        match (named.next(), named.next()) {
+            (Some(source), None) => Some(source.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The AST of the function with the selector of a contract: its definition
+/// in the contract or the nearest base contract, i.e. the override which is
+/// called, or the public state variable it is the getter of
+pub fn function_ast<'a>(
+    asts: &'a [(String, Value)],
+    contract_name: &str,
+    selector: &str,
+) -> Option<&'a Value> {
+    let mut nodes = vec![];
+    for (_, ast) in asts.iter() {
+        descendants(ast, &mut nodes);
+    }
+    let contracts = nodes
+        .into_iter()
+        .filter(|n| n["nodeType"] == "ContractDefinition")
+        .collect::<Vec<_>>();
+    let contract = contracts
+        .iter()
+        .copied()
+        .find(|c| c["name"] == contract_name)?;
+    let bases = match contract["linearizedBaseContracts"].as_array() {
+        Some(ids) => ids.clone(),
+        None => vec![contract["id"].clone()],
+    };
+
+    let selector = selector.trim_start_matches("0x");
+    bases.iter().find_map(|id| {
+        let base = contracts.iter().copied().find(|c| c["id"] == *id)?;
+        base["nodes"].as_array()?.iter().find(|n| {
+            matches!(
+                n["nodeType"].as_str(),
+                Some("FunctionDefinition" | "VariableDeclaration")
+            ) && n["functionSelector"] == selector
+        })
+    })
+}
+
+/// Findings for all nodes of the ASTs of the contract matching the query, named
+/// by the source file and offset of the node and detailed with its source code
+pub fn query_contract(
+    contract: &PlainContract,
+    asts: &[(String, Value)],
+    query: &AstQuery,
+    kind: &str,
+    severity: &str,
+) -> Result<Vec<Finding>> {
+    let contract_id = contract.id();
+
+    let mut findings = vec![];
+    for (path, ast) in asts.iter() {
+        let content = source_content(contract, path);
+        for node in query.evaluate(ast) {
+            let (start, _) = source_range(node).unwrap_or_default();
+            let snippet = node_source(&content, node)
+                .chars()
+                .take(SNIPPET_LENGTH)
+                .collect();
+            findings.push(Finding {
+                contract_id: contract_id.clone(),
+                kind: kind.into(),
+                name: format!("{path}:{start}"),
+                severity: severity.into(),
+                detail: snippet,
+            });
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn evaluate_ast_queries() -> Result<()> {
+        let ast = serde_json::json!({
+            "nodeType": "SourceUnit",
+            "src": "0:300:0",
+            "nodes": [{
+                "nodeType": "ContractDefinition",
+                "name": "Vault",
+                "contractKind": "contract",
+                "src": "0:200:0",
+                "nodes": [{
+                    "nodeType": "FunctionDefinition",
+                    "name": "kill",
+                    "visibility": "external",
+                    "src": "10:50:0",
+                    "body": {
+                        "nodeType": "Block",
+                        "src": "30:30:0",
+                        "statements": [{
+                            "nodeType": "ExpressionStatement",
+                            "src": "32:20:0",
+                            "expression": {
+                                "nodeType": "FunctionCall",
+                                "src": "32:19:0",
+                                "arguments": [{"nodeType": "Identifier", "name": "owner", "src": "45:5:0"}],
+                                "expression": {"nodeType": "Identifier", "name": "selfdestruct", "src": "32:12:0"}
+                            }
+                        }]
+                    }
+                }]
+            }, {
+                "nodeType": "ContractDefinition",
+                "name": "Math",
+                "contractKind": "library",
+                "src": "200:100:0",
+                "nodes": []
+            }]
+        });
+
+        let query: AstQuery = "FunctionCall[expression.name=selfdestruct]".parse()?;
+        let matched = query.evaluate(&ast);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(source_range(matched[0]), Some((32, 19)));
+
+        let query: AstQuery = "ContractDefinition[contractKind!=library] Identifier".parse()?;
+        assert_eq!(query.evaluate(&ast).len(), 2);
+
+        let query: AstQuery = "ContractDefinition[contractKind=library] FunctionCall".parse()?;
+        assert!(query.evaluate(&ast).is_empty());
+
+        let query: AstQuery = "*[arguments.0.name = owner]".parse()?;
+        assert_eq!(query.evaluate(&ast).len(), 1);
+
+        assert!("FunctionCall[name".parse::<AstQuery>().is_err());
+        assert!("[name=x]".parse::<AstQuery>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_function_ast() {
+        let asts = vec![(
+            "Token.sol".to_string(),
+            serde_json::json!({
+                "nodeType": "SourceUnit",
+                "nodes": [{
+                    "nodeType": "ContractDefinition",
+                    "id": 1,
+                    "name": "ERC20",
+                    "linearizedBaseContracts": [1],
+                    "nodes": [
+                        {"nodeType": "VariableDeclaration", "name": "totalSupply", "functionSelector": "18160ddd"},
+                        {"nodeType": "FunctionDefinition", "name": "transfer", "functionSelector": "a9059cbb", "body": {"nodeType": "Block", "statements": []}}
+                    ]
+                }, {
+                    "nodeType": "ContractDefinition",
+                    "id": 2,
+                    "name": "Token",
+                    "linearizedBaseContracts": [2, 1],
+                    "nodes": [
+                        {"nodeType": "FunctionDefinition", "name": "transfer", "functionSelector": "a9059cbb", "id": 3}
+                    ]
+                }]
+            }),
+        )];
+
+        let transfer = function_ast(&asts, "Token", "0xa9059cbb").expect("Override");
+        assert_eq!(transfer["id"], 3);
+        let transfer = function_ast(&asts, "ERC20", "0xa9059cbb").expect("Base");
+        assert_eq!(transfer["body"]["nodeType"], "Block");
+        let getter = function_ast(&asts, "Token", "0x18160ddd").expect("Inherited getter");
+        assert_eq!(getter["name"], "totalSupply");
+        assert!(function_ast(&asts, "Token", "0x00000000").is_none());
+        assert!(function_ast(&asts, "Vault", "0xa9059cbb").is_none());
+    }
+
+    #[test]
+    fn find_definition_sources() {
+        let content = "event Paused();\nevent Moved(uint256 a);\nevent Moved(address b);";
+        let asts = vec![(
+            "Token.sol".to_string(),
+            serde_json::json!({
+                "nodeType": "SourceUnit",
+                "nodes": [
+                    {"nodeType": "EventDefinition", "name": "Paused", "src": "0:15:0"},
+                    {"nodeType": "EventDefinition", "name": "Moved", "src": "16:23:0", "eventSelector": "aa"},
+                    {"nodeType": "EventDefinition", "name": "Moved", "src": "40:23:0", "eventSelector": "bb"},
+                    {"nodeType": "ErrorDefinition", "name": "Paused", "src": "0:15:0"}
+                ]
+            }),
+        )];
+        let events = DefinitionSources::from_asts(
+            &asts,
+            |_| content.into(),
+            "EventDefinition",
+            "eventSelector",
+        );
+
+        assert_eq!(
+            events.get("Paused", "0x01").as_deref(),
+            Some("event Paused();")
+        );
+        assert_eq!(
+            events.get("Moved", "0xbb").as_deref(),
+            Some("event Moved(address b);")
+        );
+        // definitions with a selector in the AST are only found by it
+        assert_eq!(events.get("Moved", "0xcc"), None);
+        assert_eq!(events.definitions.len(), 3);
+    }
+}
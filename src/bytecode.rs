@@ -0,0 +1,333 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::{ContextCompat, Result};
+use foundry_compilers::artifacts::BytecodeObject;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::plain_contract::PlainContract;
+
+/// Maximum runtime bytecode size of a contract, introduced by EIP-170
+pub const EIP170_LIMIT: usize = 24576;
+
+/// The raw bytes of a bytecode object. Library placeholders of unlinked
+/// bytecode are replaced with zero addresses.
+pub fn object_bytes(object: &BytecodeObject) -> Vec<u8> {
+    match object {
+        BytecodeObject::Bytecode(bytes) => bytes.to_vec(),
+        BytecodeObject::Unlinked(code) => {
+            let code: String = code
+                .trim_start_matches("0x")
+                .chars()
+                .map(|c| if c.is_ascii_hexdigit() { c } else { '0' })
+                .collect();
+            hex::decode(code).unwrap_or_default()
+        }
+    }
+}
+
+/// The `0x` prefixed hex of a bytecode object. Library placeholders of
+/// unlinked bytecode are kept, so that it can be linked later.
+pub fn object_hex(object: &BytecodeObject) -> String {
+    match object {
+        BytecodeObject::Bytecode(bytes) => hex::encode_prefixed(bytes),
+        BytecodeObject::Unlinked(code) => format!("0x{}", code.trim_start_matches("0x")),
+    }
+}
+
+/// Creation and runtime bytecode of a compiled contract, for bytecode
+/// similarity search and on-chain verification
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactBytecode {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    /// `0x` prefixed hex, see [`object_hex`]
+    pub creation_code: String,
+    pub runtime_code: String,
+}
+
+/// Return the bytecodes of every artifact with runtime bytecode
+pub fn extract_bytecodes(contract: &PlainContract) -> Result<Vec<ArtifactBytecode>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut bytecodes = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        let Some(deployed) = artifact
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|b| b.bytecode.as_ref())
+        else {
+            continue;
+        };
+        if object_bytes(&deployed.object).is_empty() {
+            // interfaces and abstract contracts
+            continue;
+        }
+        bytecodes.push(ArtifactBytecode {
+            contract_id: contract_id.clone(),
+            source_path: file.display().to_string(),
+            contract_name: contract_name.clone(),
+            creation_code: artifact
+                .bytecode
+                .as_ref()
+                .map(|b| object_hex(&b.object))
+                .unwrap_or_else(|| "0x".into()),
+            runtime_code: object_hex(&deployed.object),
+        });
+    }
+    Ok(bytecodes)
+}
+
+/// Split the bytecode into instructions, returns the program counter and size
+/// of every instruction
+pub fn instructions(code: &[u8]) -> Vec<(usize, usize)> {
+    let mut instructions = Vec::with_capacity(code.len());
+    let mut pc = 0;
+    while pc < code.len() {
+        let size = match code[pc] {
+            // PUSH1..PUSH32 carry their immediate value
+            op @ 0x60..=0x7f => 1 + (op - 0x5f) as usize,
+            _ => 1,
+        };
+        instructions.push((pc, size));
+        pc += size;
+    }
+    instructions
+}
+
+/// Runtime code of an EIP-1167 minimal proxy before the implementation address
+const EIP1167_PREFIX: [u8; 10] = hex!("363d3d373d3d3d363d73");
+/// Runtime code of an EIP-1167 minimal proxy after the implementation address
+const EIP1167_SUFFIX: [u8; 15] = hex!("5af43d82803e903d91602b57fd5bf3");
+
+/// The implementation address of an EIP-1167 minimal proxy, `None` if the
+/// runtime code is not a minimal proxy
+pub fn eip1167_implementation(code: &[u8]) -> Option<String> {
+    let address = code
+        .strip_prefix(&EIP1167_PREFIX)?
+        .strip_suffix(&EIP1167_SUFFIX)?;
+    (address.len() == 20).then(|| hex::encode_prefixed(address))
+}
+
+/// Whether the bytecode embeds the EIP-1167 proxy code, as factories creating
+/// clones with e.g. OpenZeppelin `Clones` do
+pub fn creates_clones(code: &[u8]) -> bool {
+    code.windows(EIP1167_PREFIX.len())
+        .any(|window| window == EIP1167_PREFIX)
+}
+
+/// The metadata appended by solc to the runtime bytecode, a CBOR map with the
+/// hash of the metadata JSON and the compiler version
+#[derive(Debug, PartialEq)]
+pub struct CborMetadata {
+    /// The metadata hash prefixed with its kind, e.g. `ipfs:0x1220...` or `bzzr1:0x...`
+    pub hash: Option<String>,
+    /// The compiler version, e.g. `0.8.19`
+    pub solc: Option<String>,
+}
+
+/// Decode the CBOR metadata at the end of the runtime bytecode. The last two
+/// bytes hold the length of the CBOR encoded map preceding them.
+pub fn cbor_metadata(code: &[u8]) -> Option<CborMetadata> {
+    let len = code.len();
+    if len < 2 {
+        return None;
+    }
+    let cbor_len = u16::from_be_bytes([code[len - 2], code[len - 1]]) as usize;
+    let start = (len - 2).checked_sub(cbor_len)?;
+    let mut cbor = Cbor {
+        data: &code[start..len - 2],
+        pos: 0,
+    };
+
+    let (major, entries) = cbor.header()?;
+    if major != 5 {
+        return None;
+    }
+    let mut metadata = CborMetadata {
+        hash: None,
+        solc: None,
+    };
+    for _ in 0..entries {
+        let key = cbor.text()?;
+        let (major, value) = cbor.value()?;
+        match (key.as_str(), major) {
+            ("ipfs" | "bzzr0" | "bzzr1", 2) => {
+                metadata.hash = Some(format!("{key}:{}", hex::encode_prefixed(value)))
+            }
+            // released compilers store the version as three bytes, nightly builds as text
+            ("solc", 2) => {
+                metadata.solc = Some(value.iter().map(|v| v.to_string()).join("."));
+            }
+            ("solc", 3) => metadata.solc = Some(String::from_utf8_lossy(value).into()),
+            _ => {}
+        }
+    }
+    Some(metadata)
+}
+
+/// The runtime code without what differs between deployments of the same
+/// compiled contract: the CBOR metadata is stripped and the values of the
+/// `PUSH32` solc reserves for immutables are zeroed, with the other `PUSH32`
+/// values
+pub fn code_skeleton(code: &[u8]) -> Vec<u8> {
+    let mut skeleton = code.to_vec();
+    if cbor_metadata(code).is_some() {
+        let cbor_len = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+        skeleton.truncate(code.len() - 2 - cbor_len);
+    }
+    for (pc, size) in instructions(&skeleton) {
+        if skeleton[pc] == 0x7f {
+            let end = (pc + size).min(skeleton.len());
+            skeleton[pc + 1..end].fill(0);
+        }
+    }
+    skeleton
+}
+
+/// `0x` prefixed keccak256 of the [`code_skeleton`], shared by the compiled
+/// runtime code of a contract and its deployments
+pub fn skeleton_hash(code: &[u8]) -> String {
+    keccak256(code_skeleton(code)).to_string()
+}
+
+/// Minimal reader of the CBOR subset used in solc metadata: maps with text
+/// keys and byte string, text string or boolean values
+struct Cbor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cbor<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// Major type and argument of the next item
+    fn header(&mut self) -> Option<(u8, usize)> {
+        let byte = self.byte()?;
+        let argument = match byte & 0x1f {
+            n @ 0..=23 => n as usize,
+            24 => self.byte()? as usize,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().ok()?) as usize,
+            _ => return None,
+        };
+        Some((byte >> 5, argument))
+    }
+
+    fn text(&mut self) -> Option<String> {
+        match self.value()? {
+            (3, bytes) => Some(String::from_utf8_lossy(bytes).into()),
+            _ => None,
+        }
+    }
+
+    /// Major type and content of the next byte string, text string or simple value
+    fn value(&mut self) -> Option<(u8, &'a [u8])> {
+        match self.header()? {
+            (major @ (2 | 3), len) => Some((major, self.take(len)?)),
+            (7, _) => Some((7, &[])),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_instructions() {
+        // PUSH1 0x80 PUSH1 0x40 MSTORE PUSH2 0x0102 STOP
+        let code = [0x60, 0x80, 0x60, 0x40, 0x52, 0x61, 0x01, 0x02, 0x00];
+        assert_eq!(
+            instructions(&code),
+            vec![(0, 2), (2, 2), (4, 1), (5, 3), (8, 1)]
+        );
+    }
+
+    #[test]
+    fn unlinked_placeholders_are_zeroed() {
+        let object = BytecodeObject::Unlinked(format!(
+            "0x73{}5b",
+            "__$0123456789abcdef0123456789abcdef01$__"
+        ));
+        let bytes = object_bytes(&object);
+        assert_eq!(bytes.len(), 22);
+        assert_eq!(bytes[0], 0x73);
+        assert_eq!(bytes[21], 0x5b);
+        assert_eq!(
+            object_hex(&object),
+            "0x73__$0123456789abcdef0123456789abcdef01$__5b"
+        );
+        assert_eq!(
+            object_hex(&BytecodeObject::Bytecode(vec![0x60, 0x80].into())),
+            "0x6080"
+        );
+    }
+
+    #[test]
+    fn decode_cbor_metadata() {
+        // tail of a runtime bytecode compiled with solc 0.8.19
+        let code = hex::decode(
+            "fe\
+             a2646970667358221220f0e3e5a3f0b6a4f9bfa1c3b42ad3e01a0a9d3c5b09fb7e2c5d8d6e2b7c4a9a1b64736f6c63430008130033",
+        )
+        .unwrap();
+        let metadata = cbor_metadata(&code).unwrap();
+        assert_eq!(
+            metadata.hash.as_deref(),
+            Some("ipfs:0x1220f0e3e5a3f0b6a4f9bfa1c3b42ad3e01a0a9d3c5b09fb7e2c5d8d6e2b7c4a9a1b")
+        );
+        assert_eq!(metadata.solc.as_deref(), Some("0.8.19"));
+
+        assert_eq!(cbor_metadata(&[0x60, 0x80]), None);
+    }
+
+    #[test]
+    fn detect_minimal_proxy() {
+        let code = hex::decode(
+            "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3",
+        )
+        .unwrap();
+        assert_eq!(
+            eip1167_implementation(&code).as_deref(),
+            Some("0xbebebebebebebebebebebebebebebebebebebebe")
+        );
+        assert!(creates_clones(&code));
+        assert_eq!(eip1167_implementation(&code[1..]), None);
+    }
+
+    #[test]
+    fn skeleton_ignores_metadata_and_immutables() {
+        let compiled = hex::decode(format!(
+            "7f{}5b00a264697066735822{}64736f6c63430008130033",
+            "00".repeat(32),
+            "11".repeat(34)
+        ))
+        .unwrap();
+        let deployed = hex::decode(format!(
+            "7f{}5b00a264697066735822{}64736f6c63430008130033",
+            "ab".repeat(32),
+            "22".repeat(34)
+        ))
+        .unwrap();
+        assert_eq!(code_skeleton(&deployed), code_skeleton(&compiled));
+        assert_eq!(code_skeleton(&deployed).len(), 35);
+        assert_eq!(skeleton_hash(&deployed), skeleton_hash(&compiled));
+        assert_ne!(skeleton_hash(&deployed), skeleton_hash(&[0x00]));
+    }
+}
@@ -0,0 +1,100 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::Result;
+use std::path::PathBuf;
+
+/// Content addressed on-disk cache of HTTP responses.
+///
+/// Bodies are stored once under `content/<keccak256 of body>`, and every
+/// request key maps to the hash of its body in `keys/<md5 of key>`, so the same
+/// response fetched through different URLs is only stored once. Files are
+/// written to a temporary path and renamed, so a crashed run never leaves a
+/// partial entry behind.
+pub struct ResponseCache {
+    root: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(root: &str) -> Result<Self> {
+        let root = PathBuf::from(root);
+        std::fs::create_dir_all(root.join("keys"))?;
+        std::fs::create_dir_all(root.join("content"))?;
+        Ok(Self { root })
+    }
+
+    fn key_path(&self, key: &str) -> PathBuf {
+        self.root
+            .join("keys")
+            .join(format!("{:x}", md5::compute(key)))
+    }
+
+    fn content_path(&self, hash: &str) -> PathBuf {
+        self.root.join("content").join(hash)
+    }
+
+    /// The cached body of `key`
+    pub async fn get(&self, key: &str) -> Result<Option<String>> {
+        let key_path = self.key_path(key);
+        if !key_path.exists() {
+            return Ok(None);
+        }
+        let hash = tokio::fs::read_to_string(key_path).await?;
+        let content_path = self.content_path(hash.trim());
+        if !content_path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(tokio::fs::read_to_string(content_path).await?))
+    }
+
+    /// Store the body of `key`, returns the content hash
+    pub async fn put(&self, key: &str, body: &str) -> Result<String> {
+        let hash = hex::encode(keccak256(body.as_bytes()));
+        let content_path = self.content_path(&hash);
+        if !content_path.exists() {
+            write_atomic(content_path, body).await?;
+        }
+        write_atomic(self.key_path(key), &hash).await?;
+        Ok(hash)
+    }
+}
+
+async fn write_atomic(path: PathBuf, content: &str) -> Result<()> {
+    let tmp = path.with_extension(format!("tmp{}", rand::random::<u32>()));
+    tokio::fs::write(&tmp, content).await?;
+    tokio::fs::rename(tmp, path).await?;
+    Ok(())
+}
+
+/// Explorer APIs such as Etherscan and Blockscout report errors like rate
+/// limits with a successful status code and `"status": "0"`, which must not be
+/// cached
+pub fn is_cacheable(body: &str) -> bool {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(value) => value.get("status").and_then(|s| s.as_str()) != Some("0"),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn cache_by_content() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache = ResponseCache::new(dir.path().to_str().unwrap())?;
+
+        assert_eq!(cache.get("https://a").await?, None);
+        let a = cache.put("https://a", "{}").await?;
+        let b = cache.put("https://b", "{}").await?;
+        assert_eq!(a, b);
+        assert_eq!(cache.get("https://b").await?.as_deref(), Some("{}"));
+        assert_eq!(std::fs::read_dir(dir.path().join("content"))?.count(), 1);
+
+        assert!(!is_cacheable(
+            r#"{"status":"0","message":"NOTOK","result":"Max rate limit reached"}"#
+        ));
+        assert!(is_cacheable(r#"{"status":"1","message":"OK","result":[]}"#));
+
+        Ok(())
+    }
+}
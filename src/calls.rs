@@ -0,0 +1,294 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast_query::{descendants, node_source, node_type, source_asts, source_content, source_range},
+    literals::visit_implementations,
+    plain_contract::PlainContract,
+};
+
+/// Members of `address` performing an external call
+const ADDRESS_CALLS: [&str; 5] = ["call", "delegatecall", "staticcall", "send", "transfer"];
+
+/// An external call site in the body of a function, constructor or modifier
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ExternalCall {
+    /// Id of the function in the `function` or `function_definition` table
+    pub function_id: String,
+    pub contract_id: String,
+    /// Position of the call in the function, in source order
+    pub position: u32,
+    /// One of `call`, `delegatecall`, `staticcall`, `send`, `transfer` or
+    /// `interface` for calls of a function of a contract or interface type
+    pub kind: String,
+    /// Source of the target expression, e.g. `IERC20(token)` or `implementation`
+    pub target: String,
+    /// What the target refers to, one of `state`, `immutable`, `constant`,
+    /// `parameter`, `local`, `msg_sender`, `this`, `literal`, `call_result` or
+    /// `expression`
+    pub target_kind: String,
+    /// The called member, e.g. `transferFrom`
+    pub member: String,
+}
+
+fn type_string(node: &Value) -> &str {
+    node.pointer("/typeDescriptions/typeString")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// Kind, target and member of an external call, `None` for any other node.
+/// Call options, `{value: 1}` or the legacy `.value(1)`, are skipped.
+pub fn external_call(call: &Value) -> Option<(&'static str, &Value, &str)> {
+    if node_type(call) != Some("FunctionCall") || call.get("kind")?.as_str()? != "functionCall" {
+        return None;
+    }
+    let mut callee = call.get("expression")?;
+    loop {
+        match node_type(callee) {
+            Some("FunctionCallOptions") => callee = callee.get("expression")?,
+            Some("FunctionCall")
+                if matches!(
+                    callee
+                        .pointer("/expression/memberName")
+                        .and_then(Value::as_str),
+                    Some("value" | "gas")
+                ) =>
+            {
+                callee = callee.pointer("/expression/expression")?
+            }
+            _ => break,
+        }
+    }
+    if node_type(callee) != Some("MemberAccess") {
+        return None;
+    }
+    let member = callee.get("memberName")?.as_str()?;
+    let target = callee.get("expression")?;
+    let target_type = type_string(target);
+
+    if target_type.starts_with("address") {
+        let kind = ADDRESS_CALLS.into_iter().find(|c| *c == member)?;
+        return Some((kind, target, member));
+    }
+    let function_type = type_string(callee);
+    if target_type.starts_with("contract ")
+        && function_type.starts_with("function ")
+        && function_type.contains(" external")
+    {
+        return Some(("interface", target, member));
+    }
+    None
+}
+
+/// What the target expression refers to, looking through type conversions,
+/// index and member accesses, e.g. `IVault(vaults[id])` is a `state` target
+pub fn target_kind(
+    target: &Value,
+    declarations: &HashMap<i64, &Value>,
+    parameters: &HashSet<i64>,
+) -> &'static str {
+    match node_type(target) {
+        Some("Identifier") => {
+            if target.get("name").and_then(Value::as_str) == Some("this") {
+                return "this";
+            }
+            let Some(id) = target.get("referencedDeclaration").and_then(Value::as_i64) else {
+                return "expression";
+            };
+            let Some(declaration) = declarations.get(&id) else {
+                return "expression";
+            };
+            if declaration.get("stateVariable").and_then(Value::as_bool) != Some(true) {
+                return match parameters.contains(&id) {
+                    true => "parameter",
+                    false => "local",
+                };
+            }
+            match declaration.get("mutability").and_then(Value::as_str) {
+                Some("immutable") => "immutable",
+                Some("constant") => "constant",
+                _ if declaration.get("constant").and_then(Value::as_bool) == Some(true) => {
+                    "constant"
+                }
+                _ => "state",
+            }
+        }
+        Some("MemberAccess") => {
+            let base = &target["expression"];
+            if base.get("name").and_then(Value::as_str) == Some("msg")
+                && target.get("memberName").and_then(Value::as_str) == Some("sender")
+            {
+                return "msg_sender";
+            }
+            target_kind(base, declarations, parameters)
+        }
+        Some("IndexAccess") => target_kind(&target["baseExpression"], declarations, parameters),
+        Some("TupleExpression") => match target["components"].as_array().map(Vec::as_slice) {
+            Some([component]) => target_kind(component, declarations, parameters),
+            _ => "expression",
+        },
+        Some("FunctionCall") => match target.get("kind").and_then(Value::as_str) {
+            Some("typeConversion") => match target["arguments"].as_array().map(Vec::as_slice) {
+                Some([argument]) => target_kind(argument, declarations, parameters),
+                _ => "expression",
+            },
+            _ => "call_result",
+        },
+        Some("Literal") => "literal",
+        _ => "expression",
+    }
+}
+
+/// Ids of the parameters of a function definition
+fn function_parameters(function: &Value) -> HashSet<i64> {
+    function
+        .pointer("/parameters/parameters")
+        .and_then(Value::as_array)
+        .map(|p| p.iter().filter_map(|p| p.get("id")?.as_i64()).collect())
+        .unwrap_or_default()
+}
+
+/// Return the external calls of every function, constructor and modifier of
+/// every compiled contract, see [`visit_implementations`]
+pub fn extract_external_calls(contract: &PlainContract) -> Result<Vec<ExternalCall>> {
+    let contract_id = contract.id();
+    let asts = source_asts(contract)?;
+    let mut declarations = HashMap::new();
+    for (_, ast) in asts.iter() {
+        let mut nodes = vec![];
+        descendants(ast, &mut nodes);
+        for node in nodes {
+            if node_type(node) == Some("VariableDeclaration") {
+                if let Some(id) = node.get("id").and_then(Value::as_i64) {
+                    declarations.insert(id, node);
+                }
+            }
+        }
+    }
+
+    let mut calls = vec![];
+    visit_implementations(contract, |function_id, path, function| {
+        let content = source_content(contract, path);
+        let parameters = function_parameters(function);
+        let mut nodes = vec![];
+        descendants(function, &mut nodes);
+        nodes.sort_by_key(|n| source_range(n).unwrap_or_default().0);
+        let sites = nodes.into_iter().filter_map(external_call);
+        for (position, (kind, target, member)) in sites.enumerate() {
+            calls.push(ExternalCall {
+                function_id: function_id.into(),
+                contract_id: contract_id.clone(),
+                position: position as u32,
+                kind: kind.into(),
+                target: node_source(&content, target).into(),
+                target_kind: target_kind(target, &declarations, &parameters).into(),
+                member: member.into(),
+            });
+        }
+    })?;
+
+    Ok(calls)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn identifier(name: &str, declaration: i64, ty: &str) -> Value {
+        json!({
+            "nodeType": "Identifier",
+            "name": name,
+            "referencedDeclaration": declaration,
+            "typeDescriptions": {"typeString": ty}
+        })
+    }
+
+    #[test]
+    fn classify_call_targets() {
+        let implementation = json!({"nodeType": "VariableDeclaration", "id": 1, "stateVariable": true, "mutability": "mutable"});
+        let token = json!({"nodeType": "VariableDeclaration", "id": 2, "stateVariable": false});
+        let declarations = HashMap::from([(1, &implementation), (2, &token)]);
+        let parameters = HashSet::from([2]);
+
+        // implementation.delegatecall(data)
+        let delegatecall = json!({
+            "nodeType": "FunctionCall",
+            "kind": "functionCall",
+            "expression": {
+                "nodeType": "MemberAccess",
+                "memberName": "delegatecall",
+                "expression": identifier("implementation", 1, "address"),
+                "typeDescriptions": {"typeString": "function (bytes memory) returns (bool,bytes memory)"}
+            }
+        });
+        let (kind, target, member) = external_call(&delegatecall).unwrap();
+        assert_eq!((kind, member), ("delegatecall", "delegatecall"));
+        assert_eq!(target_kind(target, &declarations, &parameters), "state");
+
+        // IERC20(token).transfer{gas: 50000}(to, amount)
+        let transfer = json!({
+            "nodeType": "FunctionCall",
+            "kind": "functionCall",
+            "expression": {
+                "nodeType": "FunctionCallOptions",
+                "expression": {
+                    "nodeType": "MemberAccess",
+                    "memberName": "transfer",
+                    "expression": {
+                        "nodeType": "FunctionCall",
+                        "kind": "typeConversion",
+                        "arguments": [identifier("token", 2, "address")],
+                        "typeDescriptions": {"typeString": "contract IERC20"}
+                    },
+                    "typeDescriptions": {"typeString": "function (address,uint256) external returns (bool)"}
+                }
+            }
+        });
+        let (kind, target, member) = external_call(&transfer).unwrap();
+        assert_eq!((kind, member), ("interface", "transfer"));
+        assert_eq!(target_kind(target, &declarations, &parameters), "parameter");
+
+        // msg.sender.call.value(1)("")
+        let call = json!({
+            "nodeType": "FunctionCall",
+            "kind": "functionCall",
+            "expression": {
+                "nodeType": "FunctionCall",
+                "kind": "functionCall",
+                "expression": {
+                    "nodeType": "MemberAccess",
+                    "memberName": "value",
+                    "expression": {
+                        "nodeType": "MemberAccess",
+                        "memberName": "call",
+                        "expression": {
+                            "nodeType": "MemberAccess",
+                            "memberName": "sender",
+                            "expression": identifier("msg", -15, "msg"),
+                            "typeDescriptions": {"typeString": "address payable"}
+                        }
+                    }
+                }
+            }
+        });
+        let (kind, target, _) = external_call(&call).unwrap();
+        assert_eq!(kind, "call");
+        assert_eq!(
+            target_kind(target, &declarations, &parameters),
+            "msg_sender"
+        );
+
+        // internal calls and `.value(1)` itself are not external calls
+        assert!(external_call(&call["expression"]).is_none());
+        let internal = json!({
+            "nodeType": "FunctionCall",
+            "kind": "functionCall",
+            "expression": identifier("_transfer", 3, "function (address,uint256)")
+        });
+        assert!(external_call(&internal).is_none());
+    }
+}
@@ -0,0 +1,188 @@
+use eyre::Result;
+use semver::Version;
+use serde::Deserialize;
+
+use crate::{
+    compiler_version::solc_version,
+    finding::Finding,
+    plain_contract::{ContractSource, PlainContract, StandardJson},
+};
+
+/// The official solidity bug list up to 0.8.30, in the format of
+/// https://github.com/ethereum/solidity/blob/develop/docs/bugs.json
+const BUNDLED_BUGS: &str = include_str!("../data/solidity-bugs.json");
+
+/// Kind of the findings stored for compiler bugs
+pub const COMPILER_BUG_FINDING: &str = "compiler-bug";
+
+/// A known bug of the solidity compiler
+#[derive(Debug, Deserialize)]
+pub struct CompilerBug {
+    pub name: String,
+    pub summary: String,
+    pub severity: String,
+    /// First affected version, all versions before `fixed` are affected when missing
+    pub introduced: Option<String>,
+    /// First version with the bug fixed
+    pub fixed: Option<String>,
+    #[serde(default)]
+    pub conditions: BugConditions,
+}
+
+/// Compiler settings required to trigger a bug
+#[derive(Debug, Default, Deserialize)]
+pub struct BugConditions {
+    optimizer: Option<bool>,
+    #[serde(rename = "yulOptimizer")]
+    yul_optimizer: Option<bool>,
+    #[serde(rename = "ABIEncoderV2")]
+    abi_encoder_v2: Option<bool>,
+    #[serde(rename = "viaIR")]
+    via_ir: Option<bool>,
+}
+
+/// Compiler settings of a contract as far as they are recorded
+#[derive(Debug)]
+pub struct CompilerSettings {
+    pub version: Version,
+    pub optimizer: bool,
+    pub abi_encoder_v2: bool,
+}
+
+impl CompilerSettings {
+    pub fn from_contract(contract: &PlainContract) -> Result<Self> {
+        let version = solc_version(&contract.metadata.compiler_version)?;
+
+        let mut optimizer = contract.metadata.optimization_used;
+        if let ContractSource::Json(source) = &contract.source {
+            let json: StandardJson = serde_json::from_str(&source.content)?;
+            if let Some(enabled) = json.settings.and_then(|s| s.optimizer.enabled) {
+                optimizer = enabled;
+            }
+        }
+
+        // ABI coder v2 is the default since 0.8.0
+        let sources = contract.get_source_files()?;
+        let pragma = |p: &str| sources.iter().any(|s| s.content.contains(p));
+        let abi_encoder_v2 = if version >= Version::new(0, 8, 0) {
+            !pragma("pragma abicoder v1")
+        } else {
+            pragma("pragma experimental ABIEncoderV2") || pragma("pragma abicoder v2")
+        };
+
+        Ok(Self {
+            version,
+            optimizer,
+            abi_encoder_v2,
+        })
+    }
+}
+
+impl CompilerBug {
+    /// Whether a contract compiled with the settings is susceptible to the bug.
+    /// Conditions on settings which are not recorded are assumed to be the
+    /// compiler defaults, e.g. no via-IR, and EVM version conditions are
+    /// assumed to hold.
+    pub fn affects(&self, settings: &CompilerSettings) -> bool {
+        let parse = |v: &Option<String>| v.as_deref().and_then(|v| Version::parse(v).ok());
+        if parse(&self.introduced).is_some_and(|v| settings.version < v)
+            || parse(&self.fixed).is_some_and(|v| settings.version >= v)
+        {
+            return false;
+        }
+
+        // the yul optimizer runs with the optimizer since 0.6.0
+        let yul_optimizer = settings.optimizer && settings.version >= Version::new(0, 6, 0);
+        let conditions = &self.conditions;
+        conditions.optimizer.is_none_or(|c| c == settings.optimizer)
+            && conditions.yul_optimizer.is_none_or(|c| c == yul_optimizer)
+            && conditions
+                .abi_encoder_v2
+                .is_none_or(|c| c == settings.abi_encoder_v2)
+            && conditions.via_ir.is_none_or(|c| !c)
+    }
+}
+
+/// Load the bug list from `path`, or the bundled list
+pub fn load_bugs(path: Option<&str>) -> Result<Vec<CompilerBug>> {
+    let content = match path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => BUNDLED_BUGS.to_string(),
+    };
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Findings for every bug the contract is susceptible to
+pub fn find_compiler_bugs(contract: &PlainContract, bugs: &[CompilerBug]) -> Result<Vec<Finding>> {
+    let settings = CompilerSettings::from_contract(contract)?;
+    let contract_id = contract.id();
+    let findings = bugs
+        .iter()
+        .filter(|bug| bug.affects(&settings))
+        .map(|bug| Finding {
+            contract_id: contract_id.clone(),
+            kind: COMPILER_BUG_FINDING.into(),
+            name: bug.name.clone(),
+            severity: bug.severity.clone(),
+            detail: bug.summary.clone(),
+        })
+        .collect();
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn match_bug_conditions() -> Result<()> {
+        let bugs = load_bugs(None)?;
+        let names = |settings: &CompilerSettings| {
+            bugs.iter()
+                .filter(|bug| bug.affects(settings))
+                .map(|bug| bug.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        let settings = CompilerSettings {
+            version: Version::new(0, 8, 13),
+            optimizer: true,
+            abi_encoder_v2: true,
+        };
+        let affected = names(&settings);
+        assert!(affected.contains(&"StorageWriteRemovalBeforeConditionalTermination"));
+        assert!(affected.contains(&"InlineAssemblyMemorySideEffects"));
+        assert!(!affected.contains(&"KeccakCaching"));
+
+        let settings = CompilerSettings {
+            optimizer: false,
+            ..settings
+        };
+        assert!(!names(&settings).contains(&"InlineAssemblyMemorySideEffects"));
+
+        let settings = CompilerSettings {
+            version: Version::new(0, 8, 23),
+            ..settings
+        };
+        assert!(names(&settings).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundled_bugs_are_fixed_in_released_versions() -> Result<()> {
+        let releases = crate::timeline::load_releases(None)?;
+        let bugs = load_bugs(None)?;
+        assert!(bugs.iter().any(|bug| bug.name == "AncientCompiler"));
+        for bug in bugs.iter() {
+            let fixed = bug.fixed.as_deref().unwrap_or_default();
+            assert!(
+                releases.contains_key(fixed),
+                "{} fixed in {fixed}",
+                bug.name
+            );
+        }
+
+        Ok(())
+    }
+}
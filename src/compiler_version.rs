@@ -0,0 +1,50 @@
+use eyre::{eyre, Result};
+use regex::Regex;
+use semver::Version;
+
+/// The solc release of a recorded compiler version, e.g. `0.4.24` for
+/// `v0.4.24+commit.e67f0147`, `soljson-v0.4.24+commit.e67f0147.js` or
+/// `0.4.24`.
+///
+/// A nightly or other prerelease build, e.g.
+/// `v0.8.20-nightly.2023.3.20+commit.e3ecd7c5`, is built from the development
+/// branch of its version and is compiled with the release of that version.
+pub fn solc_version(compiler_version: &str) -> Result<Version> {
+    let pattern = Regex::new(
+        r"^(?:soljson-)?v?(\d+)\.(\d+)\.(\d+)(?:-[0-9A-Za-z.\-]+?)?(?:\+commit\.[0-9a-fA-F]+)?(?:\.js)?$",
+    )
+    .unwrap();
+    let captures = pattern
+        .captures(compiler_version.trim())
+        .ok_or_else(|| eyre!("Invalid solc version {compiler_version}"))?;
+    let number = |i: usize| captures[i].parse::<u64>();
+    Ok(Version::new(number(1)?, number(2)?, number(3)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_solc_versions() {
+        let parse = |v: &str| solc_version(v).map(|v| v.to_string()).ok();
+        assert_eq!(parse("v0.4.24+commit.e67f0147").as_deref(), Some("0.4.24"));
+        assert_eq!(parse("0.8.19").as_deref(), Some("0.8.19"));
+        assert_eq!(parse(" v0.8.19 ").as_deref(), Some("0.8.19"));
+        assert_eq!(
+            parse("v0.8.20-nightly.2023.3.20+commit.e3ecd7c5").as_deref(),
+            Some("0.8.20")
+        );
+        assert_eq!(parse("0.8.20-nightly.2023.3.20").as_deref(), Some("0.8.20"));
+        assert_eq!(
+            parse("soljson-v0.5.17+commit.d19bba13.js").as_deref(),
+            Some("0.5.17")
+        );
+        assert_eq!(
+            parse("v0.4.11-develop.2017.6.6+commit.e1e9b8ba").as_deref(),
+            Some("0.4.11")
+        );
+        assert_eq!(parse("vyper:0.3.10"), None);
+        assert_eq!(parse("latest"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use eyre::{ContextCompat, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path};
+
+/// Config file looked up in the current directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "smart-contract-database-builder.toml";
+
+/// Profile used when `--profile` is not given
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Connection settings for one chain, selected with `--profile`
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Profile {
+    /// Path of the duckdb database
+    pub duckdb_path: Option<String>,
+    /// Base URL of the block explorer API, e.g. `https://api-optimistic.etherscan.io/api`
+    pub explorer_url: Option<String>,
+    /// API key of the block explorer
+    pub api_key: Option<String>,
+    pub chain_id: Option<u64>,
+    /// JSON-RPC endpoint of the chain
+    pub rpc_url: Option<String>,
+    /// Shell command run by `pre-process` for every stored contract, with the
+    /// contract json on stdin
+    pub contract_hook: Option<String>,
+    /// Shell command run by `pre-process` for every stored chunk, with the json
+    /// array of its contracts on stdin
+    pub chunk_hook: Option<String>,
+}
+
+/// The config file, e.g.
+///
+/// ```toml
+/// [profile.optimism]
+/// explorer_url = "https://api-optimistic.etherscan.io/api"
+/// chain_id = 10
+/// api_key = "..."
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub profile: HashMap<String, Profile>,
+}
+
+impl Config {
+    /// Load the config file at `path`, or the default config file if it exists
+    pub fn load(path: Option<&str>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => DEFAULT_CONFIG_FILE,
+            None => return Ok(Config::default()),
+        };
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// The profile with the given name, falls back to the `default` profile or
+    /// an empty profile when no name is given
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self
+                .profile
+                .get(name)
+                .cloned()
+                .with_context(|| format!("Profile {name} not found in config file")),
+            None => Ok(self
+                .profile
+                .get(DEFAULT_PROFILE)
+                .cloned()
+                .unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select_profile() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+[profile.default]
+duckdb_path = "contracts.duckdb"
+
+[profile.optimism]
+explorer_url = "https://api-optimistic.etherscan.io/api"
+chain_id = 10
+"#,
+        )?;
+
+        assert_eq!(
+            config.profile(None)?.duckdb_path.as_deref(),
+            Some("contracts.duckdb")
+        );
+        assert_eq!(config.profile(Some("optimism"))?.chain_id, Some(10));
+        assert!(config.profile(Some("base")).is_err());
+
+        Ok(())
+    }
+}
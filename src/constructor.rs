@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// A decoded constructor argument of a deployed contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConstructorArgument {
+    pub contract_id: String,
+    pub contract_name: String,
+    /// Position of the argument in the constructor parameter list
+    pub position: u32,
+    pub name: String,
+    pub ty: String,
+    pub value: String,
+}
+
+/// The value of an immutable variable read from deployed bytecode
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImmutableValue {
+    pub contract_id: String,
+    pub contract_name: String,
+    /// Deployment address the bytecode was read from, if known
+    pub address: Option<String>,
+    pub name: String,
+    pub ty: String,
+    pub value: String,
+}
@@ -0,0 +1,145 @@
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::Error;
+use alloy_primitives::{hex, U256};
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{ast_query::DefinitionSources, plain_contract::PlainContract, utils::simple_hash};
+
+/// Selector of the `Error(string)` revert reason of `require` and `revert`
+pub const ERROR_STRING_SELECTOR: &str = "0x08c379a0";
+
+/// Selector of the `Panic(uint256)` revert data of failed assertions and
+/// arithmetic errors since solidity 0.8.0
+pub const PANIC_SELECTOR: &str = "0x4e487b71";
+
+/// The `0x` prefixed selector of an error as stored in the `custom_error` table
+pub fn error_selector_hex(e: &Error) -> String {
+    hex::encode_prefixed(e.selector())
+}
+
+/// A custom error from the ABI of a compiled contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContractError {
+    pub id: String,
+    pub contract_id: String,
+    pub contract_name: String,
+    pub error_name: String,
+    pub signature: String,
+    pub selector: String,
+    /// Source code of the error definition, missing when it is not found in
+    /// the AST and for errors indexed before it was stored
+    pub source_code: Option<String>,
+}
+
+impl ContractError {
+    pub fn from_abi(contract_id: String, contract_name: String, e: &Error) -> Self {
+        let signature = e.signature();
+        let id = simple_hash(&format!("{}{}{}", contract_id, contract_name, signature));
+        Self {
+            id,
+            contract_id,
+            contract_name,
+            error_name: e.name.clone(),
+            signature,
+            selector: error_selector_hex(e),
+            source_code: None,
+        }
+    }
+}
+
+/// Return a list of custom errors from the ABI of every compiled contract,
+/// with the source code of their definition
+pub fn extract_errors(contract: &PlainContract) -> Result<Vec<ContractError>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+    let sources = DefinitionSources::new(contract, "ErrorDefinition", "errorSelector")?;
+
+    let errors = compilation_output
+        .artifacts()
+        .filter_map(|(contract_name, artifact)| {
+            artifact.abi.as_ref().map(|abi| {
+                abi.errors()
+                    .map(|e| {
+                        let mut error =
+                            ContractError::from_abi(contract_id.clone(), contract_name.clone(), e);
+                        error.source_code = sources.get(&error.error_name, &error.selector);
+                        error
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .flatten()
+        .collect();
+
+    Ok(errors)
+}
+
+/// An error from its text signature, e.g. `InsufficientBalance(uint256,uint256)`
+pub fn parse_error_signature(signature: &str) -> Result<Error> {
+    Error::parse(signature).map_err(|e| eyre!("Invalid signature {signature}: {e}"))
+}
+
+/// Arguments of the revert data of the error, `None` when the data has another
+/// selector or its arguments are not a valid encoding of the parameters
+pub fn decode_error(error: &Error, data: &[u8]) -> Option<Vec<DynSolValue>> {
+    if data.len() < 4 || data[..4] != error.selector()[..] {
+        return None;
+    }
+    error.abi_decode_input(&data[4..], true).ok()
+}
+
+/// Meaning of a `Panic(uint256)` code as documented by solidity
+pub fn panic_reason(code: U256) -> &'static str {
+    match code.saturating_to::<u64>() {
+        0x00 => "generic compiler inserted panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "conversion into an invalid enum value",
+        0x22 => "access to an incorrectly encoded storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to a zero-initialized internal function",
+        _ => "unknown panic code",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_builtin_and_custom_errors() -> Result<()> {
+        let error_string = parse_error_signature("Error(string)")?;
+        assert_eq!(error_selector_hex(&error_string), ERROR_STRING_SELECTOR);
+        let panic_error = parse_error_signature("Panic(uint256)")?;
+        assert_eq!(error_selector_hex(&panic_error), PANIC_SELECTOR);
+
+        let data = hex::decode("08c379a0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000124e6f7420656e6f7567682062616c616e63650000000000000000000000000000")?;
+        assert_eq!(
+            decode_error(&error_string, &data),
+            Some(vec![DynSolValue::String("Not enough balance".into())])
+        );
+        assert_eq!(decode_error(&panic_error, &data), None);
+
+        let data = hex::decode(
+            "4e487b710000000000000000000000000000000000000000000000000000000000000011",
+        )?;
+        let values = decode_error(&panic_error, &data).expect("Valid panic data");
+        let [DynSolValue::Uint(code, 256)] = values[..] else {
+            panic!("Unexpected panic arguments {values:?}");
+        };
+        assert_eq!(panic_reason(code), "arithmetic overflow or underflow");
+        assert_eq!(panic_reason(U256::MAX), "unknown panic code");
+
+        let insufficient = parse_error_signature("InsufficientBalance(uint256,uint256)")?;
+        assert!(decode_error(&insufficient, &data[..4]).is_none());
+
+        Ok(())
+    }
+}
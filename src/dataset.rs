@@ -0,0 +1,531 @@
+use clap::ValueEnum;
+use eyre::{eyre, Result};
+use futures::{future::BoxFuture, FutureExt};
+use glob::Pattern;
+use itertools::Itertools;
+use log::debug;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+use walkdir::WalkDir;
+
+use crate::plain_contract::{etherscan_file_address, PlainContract};
+
+/// Layout of the contracts below the root of a dataset
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DatasetFormat {
+    /// Probe every folder and file with the adapters of the other formats
+    Auto,
+    /// Folders with `metadata.json` and the sources, as in the fiesta dataset
+    Fiesta,
+    /// Etherscan API responses, `<address>_<name>.json` files in a folder
+    /// named like them
+    EtherscanJson,
+    /// Folders of a Sourcify repository with `metadata.json` and a `sources`
+    /// tree
+    Sourcify,
+    /// Build info files of Hardhat and Foundry projects
+    Hardhat,
+    /// Paths matching `--custom-pattern`, read by `--custom-command`
+    Custom,
+}
+
+impl Display for DatasetFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DatasetFormat::Auto => "auto",
+            DatasetFormat::Fiesta => "fiesta",
+            DatasetFormat::EtherscanJson => "etherscan-json",
+            DatasetFormat::Sourcify => "sourcify",
+            DatasetFormat::Hardhat => "hardhat",
+            DatasetFormat::Custom => "custom",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Reads the contracts of a dataset layout. Supporting a new layout only
+/// needs an adapter registered in [`DatasetRegistry::new`].
+pub trait DatasetAdapter: Send + Sync {
+    fn format(&self) -> DatasetFormat;
+    /// Whether the folder or file is a contract of the layout
+    fn probe(&self, path: &Path) -> bool;
+    /// Read the contract of a path accepted by [`DatasetAdapter::probe`]
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>>;
+}
+
+pub struct Fiesta;
+
+impl DatasetAdapter for Fiesta {
+    fn format(&self) -> DatasetFormat {
+        DatasetFormat::Fiesta
+    }
+
+    fn probe(&self, path: &Path) -> bool {
+        path.join("metadata.json").is_file()
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>> {
+        async move { PlainContract::from_folder(&path.to_string_lossy()).await }.boxed()
+    }
+}
+
+/// Contracts of the Etherscan layout, with the address of their file name on
+/// the chain
+pub struct EtherscanJson {
+    pub chain_id: u64,
+}
+
+impl DatasetAdapter for EtherscanJson {
+    fn format(&self) -> DatasetFormat {
+        DatasetFormat::EtherscanJson
+    }
+
+    fn probe(&self, path: &Path) -> bool {
+        let Some(folder) = path.parent().and_then(Path::file_name) else {
+            return false;
+        };
+        let filename = path.file_name().unwrap_or_default().to_string_lossy();
+        filename.starts_with(&*folder.to_string_lossy())
+            && filename.to_lowercase().ends_with(".json")
+            && path.is_file()
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>> {
+        async move {
+            let mut contract = PlainContract::from_etherscan_json(&path.to_string_lossy()).await?;
+            if let Some(address) = etherscan_file_address(path) {
+                contract.metadata.chain_id = Some(self.chain_id);
+                contract.metadata.address = Some(address);
+            }
+            Ok(contract)
+        }
+        .boxed()
+    }
+}
+
+pub struct Sourcify;
+
+impl DatasetAdapter for Sourcify {
+    fn format(&self) -> DatasetFormat {
+        DatasetFormat::Sourcify
+    }
+
+    fn probe(&self, path: &Path) -> bool {
+        path.join("metadata.json").is_file() && path.join("sources").is_dir()
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>> {
+        async move { PlainContract::from_sourcify_folder(&path.to_string_lossy()).await }.boxed()
+    }
+}
+
+/// Build info files of Hardhat and Foundry projects, json files in a
+/// `build-info` folder outside of `node_modules`
+pub struct Hardhat;
+
+impl DatasetAdapter for Hardhat {
+    fn format(&self) -> DatasetFormat {
+        DatasetFormat::Hardhat
+    }
+
+    fn probe(&self, path: &Path) -> bool {
+        path.parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "build-info")
+            && path.extension().is_some_and(|ext| ext == "json")
+            && !path.components().any(|c| c.as_os_str() == "node_modules")
+            && path.is_file()
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>> {
+        async move { PlainContract::from_build_info(&path.to_string_lossy()).await }.boxed()
+    }
+}
+
+/// A layout without built-in support: paths matching a glob pattern are read
+/// by a shell command, run with `CONTRACT_PATH` in its environment, printing
+/// the contract json with its `metadata` and `source` on stdout
+pub struct Custom {
+    pattern: Pattern,
+    command: String,
+}
+
+impl Custom {
+    /// The pattern is matched against the whole path, e.g. `*/contract.yaml`
+    pub fn new(pattern: &str, command: &str) -> Result<Self> {
+        Ok(Self {
+            pattern: Pattern::new(pattern)?,
+            command: command.into(),
+        })
+    }
+}
+
+impl DatasetAdapter for Custom {
+    fn format(&self) -> DatasetFormat {
+        DatasetFormat::Custom
+    }
+
+    fn probe(&self, path: &Path) -> bool {
+        self.pattern.matches_path(path)
+    }
+
+    fn read<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<PlainContract>> {
+        async move {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(&self.command)
+                .env("CONTRACT_PATH", path)
+                .output()
+                .await?;
+            if !output.status.success() {
+                return Err(eyre!(
+                    "Command `{}` failed with {}: {}",
+                    self.command,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(serde_json::from_slice(&output.stdout)?)
+        }
+        .boxed()
+    }
+}
+
+/// The adapters of every dataset format, in probing order: the custom layout
+/// first, then the most specific built-in layouts
+pub struct DatasetRegistry {
+    adapters: Vec<Box<dyn DatasetAdapter>>,
+}
+
+impl DatasetRegistry {
+    /// Etherscan contracts are recorded on `chain_id`, the custom adapter is
+    /// only registered when given
+    pub fn new(chain_id: u64, custom: Option<Custom>) -> Self {
+        let mut adapters: Vec<Box<dyn DatasetAdapter>> = vec![];
+        if let Some(custom) = custom {
+            adapters.push(Box::new(custom));
+        }
+        adapters.push(Box::new(Sourcify));
+        adapters.push(Box::new(Fiesta));
+        adapters.push(Box::new(Hardhat));
+        adapters.push(Box::new(EtherscanJson { chain_id }));
+        Self { adapters }
+    }
+
+    /// The adapters probed for a format, all of them for `auto`
+    pub fn select(&self, format: DatasetFormat) -> Result<Vec<&dyn DatasetAdapter>> {
+        let adapters = self
+            .adapters
+            .iter()
+            .map(Box::as_ref)
+            .filter(|a| format == DatasetFormat::Auto || a.format() == format)
+            .collect_vec();
+        if adapters.is_empty() {
+            return Err(eyre!(
+                "The {format} dataset format needs --custom-pattern and --custom-command"
+            ));
+        }
+        Ok(adapters)
+    }
+}
+
+/// The folders and files below the root accepted by an adapter, with the
+/// first adapter accepting them
+fn accepted<'a>(
+    root: &Path,
+    adapters: &'a [&'a dyn DatasetAdapter],
+) -> impl Iterator<Item = (PathBuf, &'a dyn DatasetAdapter)> + 'a {
+    WalkDir::new(root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let adapter = adapters.iter().find(|a| a.probe(entry.path()))?;
+            Some((entry.into_path(), *adapter))
+        })
+}
+
+/// Read the contracts of the paths with their adapter, paths in `skip` are
+/// not read
+async fn read_paths(
+    paths: impl IntoIterator<Item = (PathBuf, &dyn DatasetAdapter)>,
+    ignore_errors: bool,
+    skip: &HashSet<String>,
+) -> Vec<(String, PlainContract)> {
+    let mut contracts = vec![];
+    for (path, adapter) in paths {
+        let key = path.to_string_lossy().into_owned();
+        if skip.contains(&key) {
+            continue;
+        }
+
+        match adapter.read(&path).await {
+            Ok(c) => contracts.push((key, c)),
+            Err(error) => {
+                if ignore_errors {
+                    debug!(
+                        "Process {} failed with error {error} {path:?}",
+                        adapter.format()
+                    )
+                } else {
+                    panic!(
+                        "Process {} failed with error {error} {path:?}",
+                        adapter.format()
+                    )
+                }
+            }
+        }
+    }
+    contracts
+}
+
+/// Read the contracts below the root with the first adapter accepting each
+/// folder and file, paths in `skip` are not read. Contracts are returned with
+/// their path.
+pub async fn read_dataset(
+    root: &str,
+    adapters: &[&dyn DatasetAdapter],
+    ignore_errors: bool,
+    skip: &HashSet<String>,
+) -> Vec<(String, PlainContract)> {
+    read_paths(accepted(Path::new(root), adapters), ignore_errors, skip).await
+}
+
+/// Read the contracts of the `changed` paths below the root, as reported by a
+/// watcher: the closest folder or file around each path accepted by an
+/// adapter, or the contracts below a changed folder accepted by none. Paths
+/// are keyed like [`read_dataset`] keys them.
+pub async fn read_changed(
+    root: &str,
+    changed: &[String],
+    adapters: &[&dyn DatasetAdapter],
+    ignore_errors: bool,
+    skip: &HashSet<String>,
+) -> Vec<(String, PlainContract)> {
+    let root = Path::new(root);
+    let canonical_root = root.canonicalize().ok();
+    let mut paths = BTreeMap::new();
+    for changed in changed {
+        let changed = Path::new(changed);
+        let Some(relative) = changed
+            .strip_prefix(root)
+            .ok()
+            .or_else(|| changed.strip_prefix(canonical_root.as_ref()?).ok())
+        else {
+            continue;
+        };
+        let path = root.join(relative);
+        let closest = path
+            .ancestors()
+            .take(relative.components().count() + 1)
+            .find_map(|p| Some((p, *adapters.iter().find(|a| a.probe(p))?)));
+        match closest {
+            Some((path, adapter)) => {
+                paths.insert(path.to_path_buf(), adapter);
+            }
+            None if path.is_dir() => paths.extend(accepted(&path, adapters)),
+            None => {}
+        }
+    }
+    read_paths(paths, ignore_errors, skip).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe_dataset_layouts() -> Result<()> {
+        let registry = DatasetRegistry::new(1, None);
+        let contracts = read_dataset(
+            "./contracts",
+            &registry.select(DatasetFormat::Auto)?,
+            false,
+            &HashSet::new(),
+        )
+        .await;
+        let mut formats = contracts
+            .iter()
+            .map(|(path, _)| {
+                let path = Path::new(path);
+                let adapter = registry.adapters.iter().find(|a| a.probe(path)).unwrap();
+                adapter.format()
+            })
+            .collect_vec();
+        formats.sort_by_key(|f| f.to_string());
+        assert_eq!(
+            formats,
+            [
+                DatasetFormat::Fiesta,
+                DatasetFormat::Hardhat,
+                DatasetFormat::Sourcify
+            ]
+        );
+
+        let sourcify = read_dataset(
+            "./contracts",
+            &registry.select(DatasetFormat::Sourcify)?,
+            false,
+            &HashSet::new(),
+        )
+        .await;
+        assert_eq!(sourcify.len(), 1);
+        assert!(registry.select(DatasetFormat::Custom).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_fiesta_layout() -> Result<()> {
+        let folder = Path::new("./contracts/demo");
+        assert!(Fiesta.probe(folder));
+        assert!(!Fiesta.probe(Path::new("./contracts/foundry")));
+        let contract = Fiesta.read(folder).await?;
+        assert_eq!(
+            contract.id(),
+            PlainContract::from_folder("./contracts/demo").await?.id()
+        );
+
+        // as before the registry, fiesta folders are recognized by their
+        // metadata only
+        let sourcify = Path::new(
+            "./contracts/sourcify/contracts/full_match/1/0x00000000000000000000000000000000000000c0",
+        );
+        assert!(Fiesta.probe(sourcify));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_sourcify_layout() -> Result<()> {
+        let folder = Path::new(
+            "./contracts/sourcify/contracts/full_match/1/0x00000000000000000000000000000000000000c0",
+        );
+        assert!(Sourcify.probe(folder));
+        assert!(!Sourcify.probe(Path::new("./contracts/demo")));
+        assert!(!Sourcify.probe(&folder.join("metadata.json")));
+        let contract = Sourcify.read(folder).await?;
+        assert_eq!(
+            contract.id(),
+            PlainContract::from_sourcify_folder(&folder.to_string_lossy())
+                .await?
+                .id()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_hardhat_layout() -> Result<()> {
+        let path = Path::new(
+            "./contracts/hardhat/artifacts/build-info/0c6f7a2e5e4a3b2c1d0e9f8a7b6c5d4e.json",
+        );
+        assert!(Hardhat.probe(path));
+        assert!(!Hardhat.probe(path.parent().unwrap()));
+        Hardhat.read(path).await?;
+
+        // build info files of dependencies are skipped
+        let dir = tempfile::tempdir()?;
+        let dependency = dir.path().join("node_modules/lib/artifacts/build-info");
+        std::fs::create_dir_all(&dependency)?;
+        std::fs::copy(path, dependency.join("a.json"))?;
+        assert!(!Hardhat.probe(&dependency.join("a.json")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_etherscan_layout() -> Result<()> {
+        let address = "0x9ca84eacf0d0775782ab5b34d01187b37f1ceea4";
+        let dir = tempfile::tempdir()?;
+        let folder = dir.path().join(address);
+        std::fs::create_dir(&folder)?;
+        let path = folder.join(format!("{address}_Bueno721Drop.json"));
+        std::fs::copy(format!("./contracts/{address}_Bueno721Drop.json"), &path)?;
+
+        let adapter = EtherscanJson { chain_id: 10 };
+        assert!(adapter.probe(&path));
+        assert!(!adapter.probe(&folder));
+        // the file name does not start with the name of its folder
+        assert!(!adapter.probe(Path::new(&format!(
+            "./contracts/{address}_Bueno721Drop.json"
+        ))));
+
+        let contract = adapter.read(&path).await?;
+        assert_eq!(contract.metadata.chain_id, Some(10));
+        assert_eq!(contract.metadata.address.as_deref(), Some(address));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_custom_layout() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let contract = PlainContract::from_folder("./contracts/demo").await?;
+        let folder = dir.path().join("a");
+        std::fs::create_dir(&folder)?;
+        std::fs::write(folder.join("contract.dump"), serde_json::to_vec(&contract)?)?;
+        std::fs::write(folder.join("notes.txt"), "")?;
+
+        let custom = Custom::new("*.dump", "cat \"$CONTRACT_PATH\"")?;
+        let registry = DatasetRegistry::new(1, Some(custom));
+        let contracts = read_dataset(
+            &dir.path().to_string_lossy(),
+            &registry.select(DatasetFormat::Custom)?,
+            false,
+            &HashSet::new(),
+        )
+        .await;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].1.id(), contract.id());
+
+        let failing = Custom::new("*.dump", "exit 1")?;
+        assert!(failing.read(&folder.join("contract.dump")).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_changed_paths() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("root");
+        for name in ["a", "b"] {
+            std::fs::create_dir_all(root.join(name))?;
+            for file in std::fs::read_dir("./contracts/demo")? {
+                let file = file?;
+                std::fs::copy(file.path(), root.join(name).join(file.file_name()))?;
+            }
+        }
+        let root = root.to_string_lossy().into_owned();
+        let registry = DatasetRegistry::new(1, None);
+        let adapters = registry.select(DatasetFormat::Fiesta)?;
+
+        // a file changed inside a contract folder reads the folder only
+        let changed = [format!("{root}/a/Counter.sol"), "/elsewhere/c".to_string()];
+        let contracts = read_changed(&root, &changed, &adapters, false, &HashSet::new()).await;
+        assert_eq!(
+            contracts.iter().map(|(p, _)| p.clone()).collect_vec(),
+            [format!("{root}/a")]
+        );
+
+        // a changed folder of contracts reads the contracts below it
+        let contracts = read_changed(
+            &root,
+            std::slice::from_ref(&root),
+            &adapters,
+            false,
+            &HashSet::new(),
+        )
+        .await
+        .into_iter()
+        .map(|(p, _)| p)
+        .collect_vec();
+        assert_eq!(contracts, [format!("{root}/a"), format!("{root}/b")]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Statistics of the stored contracts described by a dataset card
+#[derive(Debug, Default, Serialize)]
+pub struct DatasetStats {
+    pub snapshot_id: String,
+    pub contracts: u64,
+    pub functions: u64,
+    /// Contracts with at least one indexed function, i.e. compiled successfully
+    pub indexed_contracts: u64,
+    /// Paths pre-process read a contract from, before deduplication
+    pub occurrences: u64,
+    /// Contracts by `single_sol`, `multi_sol`, `vyper` or `json`
+    pub source_types: Vec<(String, u64)>,
+    /// Contracts by their SPDX license expressions, `none` when they declare none
+    pub licenses: Vec<(String, u64)>,
+    /// Contracts by compiler and minor version, e.g. `solc 0.8`
+    pub compilers: Vec<(String, u64)>,
+    /// Contracts by the languages of their source files, a contract mixing
+    /// languages counts for each of them
+    pub languages: Vec<(String, u64)>,
+    /// Contracts with source files in more than one language, e.g. a vyper
+    /// contract with solidity interfaces
+    pub mixed_language_contracts: u64,
+    /// Contracts by the chain they were fetched from, `None` when unknown
+    pub chains: Vec<(Option<u64>, u64)>,
+}
+
+/// Limitations of the dataset which hold whatever its content
+const KNOWN_LIMITATIONS: [&str; 5] = [
+    "Licenses are read from the `SPDX-License-Identifier` comments of the sources, contracts without one are listed as `none` and may still be licensed otherwise.",
+    "Compiler settings are the ones recorded by the source of a contract, e.g. an explorer, which are occasionally wrong.",
+    "Functions are only indexed for contracts which compile with the recorded settings.",
+    "A contract deployed on several chains is stored once, with the chain and address it was first read from.",
+    "Identical sources with different compiler settings are stored once.",
+];
+
+/// The `top` largest groups, the others summed up as `other`
+fn top_groups(groups: &[(String, u64)], top: usize) -> Vec<(String, u64)> {
+    let mut shown = groups.iter().take(top).cloned().collect::<Vec<_>>();
+    let other: u64 = groups.iter().skip(top).map(|(_, count)| count).sum();
+    if other > 0 {
+        shown.push(("other".into(), other));
+    }
+    shown
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f64 * 100.0 / total as f64
+}
+
+impl DatasetStats {
+    /// A markdown table of the groups with their share of the contracts
+    fn table(&self, out: &mut String, title: &str, groups: &[(String, u64)], top: usize) {
+        let _ = writeln!(out, "## {title}\n");
+        let _ = writeln!(out, "| {title} | Contracts | Share |");
+        let _ = writeln!(out, "| --- | ---: | ---: |");
+        for (name, count) in top_groups(groups, top) {
+            let _ = writeln!(
+                out,
+                "| {name} | {count} | {:.1}% |",
+                percent(count, self.contracts)
+            );
+        }
+        out.push('\n');
+    }
+
+    /// The dataset card in markdown, with the `top` largest groups of every
+    /// distribution
+    pub fn to_markdown(&self, title: &str, top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {title}\n");
+        let _ = writeln!(
+            out,
+            "Verified smart contract sources with their indexed functions, generated by smart-contract-database-builder {} from the database snapshot `{}`.\n",
+            env!("CARGO_PKG_VERSION"),
+            self.snapshot_id
+        );
+
+        let _ = writeln!(out, "## Counts\n");
+        let _ = writeln!(out, "| | Count |");
+        let _ = writeln!(out, "| --- | ---: |");
+        let _ = writeln!(out, "| Contracts | {} |", self.contracts);
+        let _ = writeln!(
+            out,
+            "| Contracts with indexed functions | {} |",
+            self.indexed_contracts
+        );
+        let _ = writeln!(out, "| Functions | {} |", self.functions);
+        let _ = writeln!(
+            out,
+            "| Contracts mixing languages | {} |",
+            self.mixed_language_contracts
+        );
+        let _ = writeln!(out, "| Files and folders read | {} |\n", self.occurrences);
+
+        self.table(&mut out, "Source type", &self.source_types, top);
+        self.table(&mut out, "License", &self.licenses, top);
+        self.table(&mut out, "Compiler", &self.compilers, top);
+        self.table(&mut out, "Language", &self.languages, top);
+        let chains = self
+            .chains
+            .iter()
+            .map(|(chain_id, count)| {
+                let name = chain_id.map_or("unknown".into(), |id| id.to_string());
+                (name, *count)
+            })
+            .collect::<Vec<_>>();
+        self.table(&mut out, "Chain", &chains, top);
+
+        let _ = writeln!(out, "## Deduplication\n");
+        let _ = writeln!(
+            out,
+            "Contracts are identified by a hash of their sources: the content of a single file, or the sorted hashes of the files of a multi-file contract, so that the same sources read from several datasets, chains or addresses are stored once. {} files and folders were read for {} distinct contracts. Functions are identified by their contract, file and selector.\n",
+            self.occurrences, self.contracts
+        );
+
+        let _ = writeln!(out, "## Known limitations\n");
+        for limitation in KNOWN_LIMITATIONS {
+            let _ = writeln!(out, "- {limitation}");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_dataset_card() {
+        let stats = DatasetStats {
+            snapshot_id: "3-abc".into(),
+            contracts: 4,
+            functions: 10,
+            indexed_contracts: 3,
+            occurrences: 6,
+            source_types: vec![("single_sol".into(), 3), ("json".into(), 1)],
+            licenses: vec![("MIT".into(), 2), ("none".into(), 1), ("GPL-3.0".into(), 1)],
+            compilers: vec![("solc 0.8".into(), 4)],
+            languages: vec![("solidity".into(), 4), ("yul".into(), 1)],
+            mixed_language_contracts: 1,
+            chains: vec![(Some(1), 3), (None, 1)],
+        };
+        let card = stats.to_markdown("Contracts", 2);
+
+        assert!(card.starts_with("# Contracts\n"));
+        assert!(card.contains("`3-abc`"));
+        assert!(card.contains("| MIT | 2 | 50.0% |"));
+        assert!(card.contains("| other | 1 | 25.0% |"));
+        assert!(!card.contains("GPL-3.0"));
+        assert!(card.contains("| unknown | 1 | 25.0% |"));
+        assert!(card.contains("| Contracts mixing languages | 1 |"));
+        assert!(card.contains("| yul | 1 | 25.0% |"));
+        assert!(card.contains("6 files and folders were read for 4 distinct contracts"));
+        assert!(card.contains("## Known limitations"));
+    }
+}
@@ -1,17 +1,87 @@
-use std::fs::create_dir_all;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::create_dir_all,
+};
 
 use crate::{
+    abi::ContractAbi,
+    assembly::AssemblyBlock,
+    ast_query::SourceAst,
+    bytecode::{object_bytes, skeleton_hash, ArtifactBytecode},
+    calls::ExternalCall,
+    compiler_version::solc_version,
+    constructor::{ConstructorArgument, ImmutableValue},
+    custom_errors::ContractError,
+    dataset_card::DatasetStats,
+    definitions::TypeDefinition,
+    deployment::{CloneFactory, CloneGroup, Deployment},
+    events::ContractEvent,
+    features::{FeatureAdoption, FeatureUsage},
+    finding::Finding,
     functions::ContractFunction,
+    import_graph::ImportEdge,
+    index::{CompileError, CompilerFallback, IndexOutput},
+    layout::{StorageLayoutJson, StorageSlot},
+    license::contract_license,
+    literals::{AddressReference, FunctionDefinition, FunctionLiteral},
+    metadata_hash::MetadataHash,
+    modifiers::{ContractModifier, FunctionModifier},
+    package::PackageRelease,
     plain_contract::{ContractSource, ContractSourceType, Metadata, PlainContract},
+    plugins::PluginRow,
+    rpc::DeployedBytecode,
+    signatures::{normalize_signature, selector, KnownSignature, SignatureKind},
+    similarity::OpcodeSignature,
+    size::{ArtifactSize, FunctionSize},
+    source_map::{ArtifactSourceMap, SourceFileId},
+    state_variables::{Slot, StateVariable},
+    versions::{VersionedArtifact, VersionedGasEstimate},
 };
-use duckdb::{params, types::FromSql, Connection};
+use duckdb::{params, params_from_iter, types::FromSql, Connection};
 use eyre::Result;
+use foundry_compilers::artifacts::BytecodeObject;
+use itertools::Itertools;
+use log::warn;
 use rand::Rng;
+use serde_json::Value;
 
 pub struct Storage {
     pub conn: Connection,
 }
 
+/// Rows copied by [`Storage::merge`]
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeStats {
+    pub contracts: usize,
+    pub functions: usize,
+    /// Rows of the other tables
+    pub rows: usize,
+    /// Contracts of the merged database whose id is already stored with a
+    /// different source, the stored row is kept
+    pub conflicts: usize,
+}
+
+/// A path a contract was read from by pre-process. The same source stored from
+/// several datasets or addresses is one contract with several occurrences.
+#[derive(Debug, PartialEq)]
+pub struct ContractOccurrence {
+    pub contract_id: String,
+    /// The root folder, archive, project or dataset the contract was read from
+    pub dataset: String,
+    pub path: String,
+    pub chain_id: Option<u64>,
+    pub address: Option<String>,
+}
+
+/// A contract deployed at an address
+#[derive(Debug, PartialEq)]
+pub struct ContractAddress {
+    pub chain_id: u64,
+    /// Lowercase `0x` prefixed address
+    pub address: String,
+    pub contract_id: String,
+}
+
 enum SourceType {
     SingleSolidity,
     MultiSolidity,
@@ -32,32 +102,9 @@ impl FromSql for SourceType {
     }
 }
 
-pub fn row_to_contract(row: &duckdb::Row) -> Result<PlainContract> {
-    let source: String = row.get(0)?;
-    let source_type: SourceType = row.get(1)?;
-    let metadata: String = row.get(2)?;
-
-    let source: ContractSource = match source_type {
-        SourceType::SingleSolidity => serde_json::from_str(&source)?,
-        SourceType::MultiSolidity => serde_json::from_str(&source)?,
-        SourceType::Vyper => serde_json::from_str(&source)?,
-        SourceType::Json => serde_json::from_str(&source)?,
-    };
-
-    let metadata: Metadata = serde_json::from_str(&metadata)?;
-    Ok(PlainContract::new(metadata, source))
-}
-
-impl Storage {
-    pub fn new(db_file: &str) -> Result<Storage> {
-        let parent = std::path::Path::new(db_file).parent();
-        if let Some(parent) = parent {
-            create_dir_all(parent)?;
-        }
-
-        let conn = Connection::open(db_file)?;
-        let _ = conn.execute_batch(
-            r"
+/// Tables of the first version of the schema, created once, with the columns
+/// of [`COLUMN_UPGRADES`]
+const INITIAL_SCHEMA: &str = r"
 -- Create ENUM type for source_type
 CREATE TYPE source_type_enum AS ENUM ('json', 'vyper', 'single_sol', 'multi_sol');
 
@@ -67,7 +114,29 @@ CREATE TABLE contract (
     name STRING,
     metadata STRING,
     source STRING,
-    source_type source_type_enum
+    source_type source_type_enum,
+    -- Chain and address the contract was first fetched from, also kept in
+    -- the metadata. A source deployed on several chains is stored once, every
+    -- chain and address it was read from is in `contract_occurrence`.
+    chain_id UBIGINT,
+    address STRING,
+    -- Distinct SPDX license expressions declared by the sources, joined with
+    -- commas, empty when there is none. Filled in for older rows by
+    -- `backfill_licenses`.
+    license STRING,
+    -- Release of the solc compiler of the contract, e.g. `0.8.20` for
+    -- `v0.8.20-nightly.2023.3.20+commit.e3ecd7c5`, NULL for vyper contracts and
+    -- unknown versions. Filled in for older rows by `backfill_solc_versions`.
+    solc_version STRING,
+    -- Keccak identity of the sources, to join against Sourcify and other
+    -- corpora identifying sources by the keccak256 of the solc metadata, see
+    -- `ContractSource::keccak_hash`. Filled in for older rows when the column
+    -- is added. Not indexed, DuckDB cannot update an indexed column.
+    keccak_id STRING,
+    -- Number of source files by language, e.g. `solidity:3,yul:1`, standard
+    -- json inputs may mix languages. Filled in for older rows by
+    -- `backfill_languages`.
+    languages STRING
 );
 
 -- Create function table with foreign key
@@ -84,151 +153,4563 @@ CREATE TABLE function (
 );
 
 CREATE INDEX idx_function_composite ON function(contract_id, selector, signature);
-",
-        );
+";
 
-        Ok(Storage { conn })
-    }
+/// Tables and columns added after the initial schema
+const SCHEMA_UPGRADES: &str = r"
+-- Decoded constructor arguments of the main contract
+CREATE TABLE IF NOT EXISTS constructor_argument (
+    contract_id STRING,
+    contract_name STRING,
+    position INTEGER,
+    name STRING,
+    type STRING,
+    value STRING,
+    PRIMARY KEY (contract_id, position)
+);
 
-    /// Disables checkpoint on shutdown
-    pub fn disable_checkpoint(&self) -> Result<()> {
-        self.conn
-            .execute("PRAGMA disable_checkpoint_on_shutdown;", [])?;
-        Ok(())
-    }
+-- Compiled contracts of a stored contract
+CREATE TABLE IF NOT EXISTS artifact (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    runtime_size INTEGER,
+    creation_size INTEGER,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
 
-    /// Enable checkpoint on shutdown
-    pub fn enable_checkpoint(&self) -> Result<()> {
-        self.conn
-            .execute("PRAGMA enable_checkpoint_on_shutdown;", [])?;
-        Ok(())
-    }
+-- Creation and runtime bytecode of compiled contracts, `0x` prefixed hex with
+-- the library placeholders of unlinked bytecode
+CREATE TABLE IF NOT EXISTS artifact_bytecode (
+    contract_id STRING,
+    -- Source file defining the contract, same named contracts of different
+    -- files are different artifacts
+    source_path STRING,
+    contract_name STRING,
+    creation_code STRING,
+    runtime_code STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
 
-    /// Get contract by id
-    #[allow(dead_code)]
-    pub fn get_contract(&self, id: &str) -> Result<Option<PlainContract>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT source, source_type::varchar, metadata FROM contract WHERE id = ? limit 1",
-        )?;
-        let mut rows = stmt.query([id])?;
-        let row = match rows.next()? {
-            Some(row) => row,
-            None => return Ok(None),
-        };
+-- Functions whose signature is not valid or does not hash to their selector,
+-- kept out of the `function` table so selector joins stay sound
+CREATE TABLE IF NOT EXISTS function_quarantine (
+    id STRING PRIMARY KEY,
+    contract_id STRING,
+    contract_name STRING,
+    function_name STRING,
+    filename STRING,
+    signature STRING,
+    selector STRING,
+    source_code STRING,
+    reason STRING
+);
 
-        Ok(Some(row_to_contract(row)?))
-    }
+-- Source maps of compiled contracts, mapping program counters to source
+-- ranges by file id, resolved with `source_file_id`
+CREATE TABLE IF NOT EXISTS source_map (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    creation_source_map STRING,
+    runtime_source_map STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
 
-    #[allow(dead_code)]
-    pub fn get_random_contract(
-        &self,
-        source_type: &ContractSourceType,
-        offset: Option<u32>,
-    ) -> Result<Option<PlainContract>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT source, source_type::varchar, metadata FROM contract where source_type::varchar=? OFFSET ? LIMIT 1",
-        )?;
-        let source_type: String = source_type.to_string();
-        let mut rows = stmt.query(params![
-            &source_type,
-            offset.unwrap_or_else(|| {
-                let mut rng = rand::thread_rng();
-                rng.gen_range(0..1000)
-            })
-        ])?;
-        let row = match rows.next()? {
-            Some(row) => row,
-            None => return Err(eyre::eyre!("No contract found")),
-        };
+-- Paths of every source file of a compiled contract by the id the compiler
+-- gave them
+CREATE TABLE IF NOT EXISTS source_file_id (
+    contract_id STRING,
+    file_id INTEGER,
+    path STRING,
+    PRIMARY KEY (contract_id, file_id)
+);
 
-        Ok(Some(row_to_contract(row)?))
-    }
+-- Complete ABI of compiled contracts, with constructors, events and errors
+CREATE TABLE IF NOT EXISTS abi (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    abi_json STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
 
-    /// Store a single contract
-    #[allow(dead_code)]
-    pub fn store_contract(&self, contract: &PlainContract, id: Option<String>) -> Result<()> {
-        let PlainContract {
-            metadata, source, ..
-        } = contract;
-        let id = id.unwrap_or_else(|| contract.hash());
-        let name = &metadata.contract_name.clone();
-        let source_type = match source {
-            ContractSource::SingleSolidity(_) => "single_sol",
-            ContractSource::MultiSolidity(_) => "multi_sol",
-            ContractSource::Vyper(_) => "vyper",
-            ContractSource::Json(_) => "json",
-        };
-        let source = serde_json::to_string(source)?;
-        let metadata = serde_json::to_string(metadata)?;
-        self.conn.execute(
-            "INSERT INTO contract (id, name, metadata, source, source_type) VALUES (?, ?, ?, ?, ?)",
-            [id, name.into(), metadata, source, source_type.into()],
-        )?;
+-- Hashes of the metadata JSON of compiled contracts. `metadata_match` tells
+-- whether the metadata hash of the recompilation equals the one embedded in the
+-- deployed bytecode, NULL until verified
+CREATE TABLE IF NOT EXISTS artifact_metadata (
+    contract_id STRING,
+    contract_name STRING,
+    metadata_keccak STRING,
+    metadata_hash STRING,
+    deployed_metadata_hash STRING,
+    metadata_match BOOLEAN,
+    PRIMARY KEY (contract_id, contract_name)
+);
 
-        Ok(())
-    }
+-- Runtime bytecode bytes attributed to each function through the source map
+CREATE TABLE IF NOT EXISTS function_size (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    function_name STRING,
+    size INTEGER,
+    PRIMARY KEY (contract_id, source_path, contract_name, function_name)
+);
 
-    /// Store multiple contracts in batch mode
-    pub fn store_contracts(&self, contracts: Vec<PlainContract>) -> Result<()> {
-        let mut stmt = self.conn.prepare(
-            "INSERT INTO contract (id, name, metadata, source, source_type) VALUES (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
-        )?;
+-- State variables in the storage layout of compiled contracts
+CREATE TABLE IF NOT EXISTS storage_slot (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    label STRING,
+    slot STRING,
+    byte_offset INTEGER,
+    type STRING,
+    -- Key of the type in `storage_layout.types_json`
+    type_id STRING,
+    encoding STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name, label)
+);
 
-        for c in contracts {
-            let PlainContract {
-                metadata, source, ..
-            } = &c;
-            let id: String = c.hash();
-            let name: String = metadata.contract_name.clone();
-            let source_type = match &source {
-                ContractSource::SingleSolidity(_) => "single_sol",
-                ContractSource::MultiSolidity(_) => "multi_sol",
-                ContractSource::Vyper(_) => "vyper",
-                ContractSource::Json(_) => "json",
-            };
-            let source = serde_json::to_string(&source)?;
-            let metadata = serde_json::to_string(&metadata)?;
-            // allow error
-            let _ = stmt.insert([id, name, metadata, source, source_type.into()]);
-        }
+-- Types of the storage layout of compiled contracts as json, with the members
+-- of structs, completing the variables of `storage_slot` for proxy safety
+-- checks and diffs of upgrades
+CREATE TABLE IF NOT EXISTS storage_layout (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    types_json STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
 
-        Ok(())
-    }
+-- AST of every compiled solidity source file as json, queried by `ast-query`
+-- without compiling the contract again
+CREATE TABLE IF NOT EXISTS source_ast (
+    contract_id STRING,
+    source_path STRING,
+    ast_json STRING,
+    PRIMARY KEY (contract_id, source_path)
+);
 
-    pub fn count_contracts(&self) -> Result<u32> {
-        let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM contract")?;
-        let mut rows = stmt.query([])?;
-        let row = rows.next()?.unwrap();
-        let count: u32 = row.get(0)?;
-        Ok(count)
-    }
+-- Events from the ABI of compiled contracts
+CREATE TABLE IF NOT EXISTS event (
+    id STRING PRIMARY KEY,
+    contract_id STRING,
+    contract_name STRING,
+    event_name STRING,
+    signature STRING,
+    topic0 STRING,
+    anonymous BOOLEAN,
+    -- Signature with the indexed parameters, needed to decode its logs
+    full_signature STRING,
+    -- Source code of the event definition, from the AST of the compilation
+    source_code STRING
+);
 
-    pub fn store_functions(&self, functions: &[ContractFunction]) -> Result<()> {
-        let mut stmt = self.conn.prepare(
-            "INSERT OR IGNORE INTO function (id, contract_id, contract_name, function_name, filename, signature, selector, source_code) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-        )?;
+CREATE INDEX IF NOT EXISTS idx_event_topic0 ON event(topic0);
 
-        for f in functions.iter() {
-            let id = f.id.clone();
-            let contract_id = f.contract_id.clone();
-            let contract_name = f.contract_name.clone();
-            let function_name = f.function_name.clone();
-            let filename = f.filename.clone();
-            let signature = f.signature.clone();
-            let selector = f.selector.clone();
-            let source_code = f.source_code.clone();
-            // allow error
-            let _ = stmt.insert([
-                id,
-                contract_id,
-                contract_name,
-                function_name,
-                filename,
-                signature,
-                selector,
-                source_code,
-            ]);
-        }
+-- Custom errors from the ABI of compiled contracts
+CREATE TABLE IF NOT EXISTS custom_error (
+    id STRING PRIMARY KEY,
+    contract_id STRING,
+    contract_name STRING,
+    error_name STRING,
+    signature STRING,
+    selector STRING,
+    -- Source code of the error definition, from the AST of the compilation
+    source_code STRING
+);
+
+CREATE INDEX IF NOT EXISTS idx_custom_error_selector ON custom_error(selector);
+
+-- Text signatures imported from external signature databases
+CREATE TABLE IF NOT EXISTS known_signature (
+    kind STRING,
+    signature STRING,
+    hash STRING,
+    PRIMARY KEY (kind, signature)
+);
+
+-- Creation transactions of deployed contracts
+CREATE TABLE IF NOT EXISTS deployment (
+    chain_id UBIGINT,
+    address STRING,
+    contract_id STRING,
+    deployer STRING,
+    tx_hash STRING,
+    block_number UBIGINT,
+    implementation STRING,
+    PRIMARY KEY (chain_id, address)
+);
+CREATE INDEX IF NOT EXISTS idx_deployment_deployer ON deployment (deployer);
+
+-- Folders, files and projects stored by pre-process, by the path they were read
+-- from, skipped by `pre-process --resume`
+CREATE TABLE IF NOT EXISTS ingest_manifest (
+    path STRING PRIMARY KEY,
+    contract_id STRING,
+    ingested_at TIMESTAMP DEFAULT current_timestamp
+);
+
+-- Every path pre-process read a contract from, including the paths whose
+-- source was already stored under the same id
+CREATE TABLE IF NOT EXISTS contract_occurrence (
+    contract_id STRING,
+    dataset STRING,
+    path STRING,
+    chain_id UBIGINT,
+    address STRING,
+    PRIMARY KEY (contract_id, dataset, path)
+);
+CREATE INDEX IF NOT EXISTS idx_contract_occurrence_contract_id ON contract_occurrence (contract_id);
+
+-- Deployed addresses of the stored contracts, from the metadata of fetched
+-- contracts and the file names of the Etherscan layout. The contract table
+-- keeps one address per source, this table every address of the source.
+CREATE TABLE IF NOT EXISTS address (
+    chain_id UBIGINT,
+    address STRING,
+    contract_id STRING,
+    PRIMARY KEY (chain_id, address)
+);
+
+-- Runtime code fetched from a node, verified or not. `contract_id` is set once
+-- a contract with the same chain and address is stored
+CREATE TABLE IF NOT EXISTS bytecode (
+    chain_id UBIGINT,
+    address STRING,
+    code_hash STRING,
+    code STRING,
+    contract_id STRING,
+    -- Block the code was read at, NULL when unknown
+    block_number UBIGINT,
+    PRIMARY KEY (chain_id, address)
+);
+CREATE INDEX IF NOT EXISTS idx_bytecode_code_hash ON bytecode (code_hash);
+
+-- Compiled contracts embedding the EIP-1167 proxy code
+CREATE TABLE IF NOT EXISTS clone_factory (
+    contract_id STRING,
+    contract_name STRING,
+    PRIMARY KEY (contract_id, contract_name)
+);
+
+-- Problems found in contracts by the analyze passes
+CREATE TABLE IF NOT EXISTS finding (
+    contract_id STRING,
+    kind STRING,
+    name STRING,
+    severity STRING,
+    detail STRING,
+    PRIMARY KEY (contract_id, kind, name)
+);
+
+-- String, address and numeric literals in the functions, constructors and
+-- modifiers of compiled contracts
+CREATE TABLE IF NOT EXISTS literal (
+    function_id STRING,
+    contract_id STRING,
+    kind STRING,
+    value STRING,
+    PRIMARY KEY (function_id, kind, value)
+);
+CREATE INDEX IF NOT EXISTS idx_literal_value ON literal (value);
+
+-- Structs, enums, user defined value types and interfaces declared in the
+-- source files of compiled contracts
+CREATE TABLE IF NOT EXISTS type_definition (
+    contract_id STRING,
+    filename STRING,
+    name STRING,
+    kind STRING,
+    members STRING,
+    source STRING,
+    PRIMARY KEY (contract_id, filename, name)
+);
+CREATE INDEX IF NOT EXISTS idx_type_definition_name ON type_definition (name);
+
+-- Inline assembly blocks in the functions, constructors and modifiers,
+-- `opcodes` lists the distinct opcodes called in the block, comma separated
+CREATE TABLE IF NOT EXISTS assembly_block (
+    function_id STRING,
+    contract_id STRING,
+    position INTEGER,
+    opcodes STRING,
+    source STRING,
+    PRIMARY KEY (function_id, position)
+);
+
+-- External call sites in the functions, constructors and modifiers, with the
+-- source of the called target and what it refers to, see `calls::ExternalCall`
+CREATE TABLE IF NOT EXISTS external_call (
+    function_id STRING,
+    contract_id STRING,
+    position INTEGER,
+    kind STRING,
+    target STRING,
+    target_kind STRING,
+    member STRING,
+    PRIMARY KEY (function_id, position)
+);
+
+-- Releases of libraries fetched from npm or GitHub by `fetch-package`, each
+-- stored as one standard json contract
+CREATE TABLE IF NOT EXISTS package_release (
+    contract_id STRING,
+    registry STRING,
+    package STRING,
+    version STRING,
+    PRIMARY KEY (registry, package, version)
+);
+
+-- MinHash signatures of the opcodes of the runtime bytecode of compiled
+-- contracts, compared by `match-bytecode`, see `similarity::minhash`
+CREATE TABLE IF NOT EXISTS opcode_signature (
+    contract_id STRING,
+    contract_name STRING,
+    opcodes INTEGER,
+    signature STRING,
+    PRIMARY KEY (contract_id, contract_name)
+);
+
+-- Rows returned by the extractor plugins of `index-functions`, `row` is the
+-- json object of the row in the table chosen by the plugin
+CREATE TABLE IF NOT EXISTS plugin_row (
+    plugin STRING,
+    contract_id STRING,
+    table_name STRING,
+    row STRING,
+    PRIMARY KEY (plugin, contract_id, table_name, row)
+);
+
+-- `import` statements of the solidity sources of a contract, see
+-- `import_graph::find_imports`
+CREATE TABLE IF NOT EXISTS import_edge (
+    contract_id STRING,
+    from_file STRING,
+    position INTEGER,
+    to_file STRING,
+    symbols STRING,
+    PRIMARY KEY (contract_id, from_file, position)
+);
+
+-- Uses of language features in the sources of a contract, see `features::FEATURES`
+CREATE TABLE IF NOT EXISTS feature_usage (
+    contract_id STRING,
+    feature STRING,
+    uses INTEGER,
+    PRIMARY KEY (contract_id, feature)
+);
+
+-- Values of immutable variables read from deployed bytecode
+CREATE TABLE IF NOT EXISTS immutable_value (
+    contract_id STRING,
+    contract_name STRING,
+    address STRING,
+    name STRING,
+    type STRING,
+    value STRING
+);
+
+-- Latest failure of index-functions to compile a contract with a compiler
+-- version, cleared once it compiles
+CREATE TABLE IF NOT EXISTS compile_error (
+    contract_id STRING,
+    compiler_version STRING,
+    error STRING,
+    failed_at TIMESTAMP DEFAULT current_timestamp,
+    PRIMARY KEY (contract_id, compiler_version)
+);
+
+-- Compiler version a contract failing with its recorded version compiled with
+CREATE TABLE IF NOT EXISTS compiler_fallback (
+    contract_id STRING PRIMARY KEY,
+    recorded_version STRING,
+    compiled_version STRING
+);
+
+-- Contract and function ids exported by a release, by the snapshot id of the
+-- database at that time, the base of later delta releases. A delta release
+-- only records its own ids, the ids released before are in the releases it
+-- was exported since.
+CREATE TABLE IF NOT EXISTS release_entry (
+    snapshot_id STRING,
+    kind STRING,
+    id STRING,
+    PRIMARY KEY (snapshot_id, kind, id)
+);
+
+-- Exported releases by snapshot id, with the snapshot of the release they
+-- were exported since, NULL for full releases. Releases of older databases
+-- have no row and record every id in `release_entry`.
+CREATE TABLE IF NOT EXISTS release (
+    snapshot_id STRING PRIMARY KEY,
+    since STRING,
+    released_at TIMESTAMP DEFAULT current_timestamp
+);
+
+CREATE INDEX IF NOT EXISTS idx_contract_chain_address ON contract (chain_id, address);
+
+-- Artifacts of contracts compiled by `index-versions` with each solc version
+-- their pragmas allow, the `artifact` table with a version dimension
+CREATE TABLE IF NOT EXISTS versioned_artifact (
+    contract_id STRING,
+    contract_name STRING,
+    solc_version STRING,
+    runtime_size INTEGER,
+    creation_size INTEGER,
+    runtime_code_hash STRING,
+    deployment_gas UBIGINT,
+    PRIMARY KEY (contract_id, contract_name, solc_version)
+);
+
+-- Gas estimates of the functions of the versioned artifacts, NULL when
+-- estimated as infinite
+CREATE TABLE IF NOT EXISTS versioned_gas_estimate (
+    contract_id STRING,
+    contract_name STRING,
+    solc_version STRING,
+    signature STRING,
+    gas UBIGINT,
+    PRIMARY KEY (contract_id, contract_name, solc_version, signature)
+);
+
+-- Modifiers defined in the contracts of the source files, see
+-- `modifiers::ContractModifier`
+CREATE TABLE IF NOT EXISTS modifier (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    modifier_name STRING,
+    -- Id of the definition in the AST, which invocations refer to
+    ast_id BIGINT,
+    source_code STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name, modifier_name)
+);
+CREATE INDEX IF NOT EXISTS idx_modifier_name ON modifier (modifier_name);
+
+-- Modifiers invoked by the functions and constructors, in the order of the
+-- function header, see `modifiers::FunctionModifier`
+CREATE TABLE IF NOT EXISTS function_modifier (
+    function_id STRING,
+    contract_id STRING,
+    position INTEGER,
+    modifier_name STRING,
+    invocation STRING,
+    declaration_id BIGINT,
+    PRIMARY KEY (function_id, position)
+);
+CREATE INDEX IF NOT EXISTS idx_function_modifier_name ON function_modifier (modifier_name);
+
+-- State variables declared in the contracts of the source files, see
+-- `state_variables::StateVariable`. The slot of a mutable variable is its
+-- `storage_slot` in the layout of the declaring contract.
+CREATE TABLE IF NOT EXISTS state_variable (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    name STRING,
+    type STRING,
+    visibility STRING,
+    mutability STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name, name)
+);
+CREATE INDEX IF NOT EXISTS idx_state_variable_name ON state_variable (name);
+
+-- Functions, constructors and modifiers defined with a body outside of the
+-- ABI, e.g. internal and private functions. With the `function` table, the
+-- functions of the `literal`, `assembly_block`, `external_call` and
+-- `function_modifier` rows, see `literals::FunctionDefinition`
+CREATE TABLE IF NOT EXISTS function_definition (
+    id STRING PRIMARY KEY,
+    contract_id STRING,
+    contract_name STRING,
+    kind STRING,
+    name STRING,
+    filename STRING,
+    signature STRING
+);
+-- Hash of the runtime code of compiled contracts without metadata and
+-- immutable values, see `bytecode::skeleton_hash`, to find the source of
+-- deployed code. Filled in for older artifacts by `backfill_skeleton_hashes`.
+CREATE TABLE IF NOT EXISTS artifact_code_hash (
+    contract_id STRING,
+    source_path STRING,
+    contract_name STRING,
+    skeleton_hash STRING,
+    PRIMARY KEY (contract_id, source_path, contract_name)
+);
+CREATE INDEX IF NOT EXISTS idx_artifact_code_hash_skeleton ON artifact_code_hash (skeleton_hash);
+";
+
+/// Every chain and address a contract was read from: its occurrences, and the
+/// `chain_id` and `address` of the contract row for contracts stored before
+/// occurrences had them
+const CONTRACT_LOCATIONS: &str =
+    "SELECT contract_id, chain_id, address FROM contract_occurrence WHERE chain_id IS NOT NULL
+    UNION SELECT id, chain_id, address FROM contract WHERE chain_id IS NOT NULL";
+
+/// Columns added to tables after they were first released, as table, column
+/// and type. New databases have them in the table definitions, older ones get
+/// them from [`add_missing_columns`].
+const COLUMN_UPGRADES: &[(&str, &str, &str)] = &[
+    ("contract", "chain_id", "UBIGINT"),
+    ("contract", "address", "STRING"),
+    ("event", "full_signature", "STRING"),
+    ("contract", "license", "STRING"),
+    ("contract", "solc_version", "STRING"),
+    ("contract", "keccak_id", "STRING"),
+    ("event", "source_code", "STRING"),
+    ("custom_error", "source_code", "STRING"),
+    ("contract", "languages", "STRING"),
+    ("bytecode", "block_number", "UBIGINT"),
+    ("function_modifier", "declaration_id", "BIGINT"),
+];
+
+/// Add the columns of [`COLUMN_UPGRADES`] missing from the existing tables of
+/// an older database, returns the added columns. DuckDB cannot alter a table
+/// with indexes, they are dropped before and created again after.
+fn add_missing_columns(conn: &Connection) -> Result<Vec<(&'static str, &'static str)>> {
+    let mut added = vec![];
+    for &(table, column, ty) in COLUMN_UPGRADES {
+        let missing: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM duckdb_tables() WHERE table_name = $1)
+                AND NOT EXISTS (SELECT 1 FROM duckdb_columns() WHERE table_name = $1 AND column_name = $2)",
+            [table, column],
+            |row| row.get(0),
+        )?;
+        if !missing {
+            continue;
+        }
+
+        let mut stmt =
+            conn.prepare("SELECT index_name, sql FROM duckdb_indexes() WHERE table_name = ?")?;
+        let indexes = stmt
+            .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        for (name, _) in indexes.iter() {
+            conn.execute_batch(&format!("DROP INDEX {name};"))?;
+        }
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {ty};"))?;
+        for (_, sql) in indexes.iter() {
+            conn.execute_batch(sql)?;
+        }
+        added.push((table, column));
+    }
+    Ok(added)
+}
+
+/// Subquery selecting the row `t` of the kind if it was released at the
+/// snapshot given as parameter, or in one of the releases it was exported since
+fn released(kind: &str) -> String {
+    format!(
+        "WITH RECURSIVE lineage (snapshot_id) AS (
+            SELECT ?::VARCHAR
+            UNION SELECT r.since FROM release r JOIN lineage l ON r.snapshot_id = l.snapshot_id WHERE r.since IS NOT NULL
+        )
+        SELECT 1 FROM release_entry e WHERE e.kind = '{kind}' AND e.id = t.id AND e.snapshot_id IN (SELECT snapshot_id FROM lineage)"
+    )
+}
+
+/// The functions of the ABI and the other functions, constructors and
+/// modifiers with a body, which the `literal`, `assembly_block`,
+/// `external_call` and `function_modifier` rows refer to. Definitions outside
+/// of the ABI have no selector.
+const INDEXED_FUNCTIONS: &str = "SELECT id, contract_id, contract_name, function_name, filename, signature, selector FROM function
+    UNION ALL SELECT id, contract_id, contract_name, name, filename, signature, '' FROM function_definition";
+
+/// Tables whose primary key changed after they were first released, with the
+/// columns to copy from the rows of older databases. Rows of artifacts read
+/// before the key had the source path get an empty one.
+const KEY_UPGRADES: &[(&str, &str, &str)] = &[
+    ("artifact", "source_path", "*, '' AS source_path"),
+    ("function_size", "source_path", "*, '' AS source_path"),
+    ("artifact_bytecode", "source_path", "*, '' AS source_path"),
+    ("source_map", "source_path", "*, '' AS source_path"),
+    ("abi", "source_path", "*, '' AS source_path"),
+    ("storage_slot", "source_path", "*, '' AS source_path"),
+    ("modifier", "source_path", "*, '' AS source_path"),
+    (
+        "import_edge",
+        "position",
+        "*, (row_number() OVER (PARTITION BY contract_id, from_file ORDER BY to_file) - 1)::INTEGER AS position",
+    ),
+    (
+        "storage_layout",
+        "source_path",
+        "contract_id, contract_name, '' AS source_path, json_extract(layout_json, '$.types')::VARCHAR AS types_json",
+    ),
+    (
+        "state_variable",
+        "source_path",
+        "contract_id, '' AS source_path, contract_name, name, type, visibility, mutability",
+    ),
+    // deployments were fetched from mainnet before their chain was recorded
+    ("deployment", "chain_id", "*, 1::UBIGINT AS chain_id"),
+];
+
+/// Move the tables of [`KEY_UPGRADES`] without their key column aside, to be
+/// created again by the schema upgrades, returns the moved tables
+fn move_rekeyed_tables(conn: &Connection) -> Result<Vec<&'static str>> {
+    let mut moved = vec![];
+    for &(table, column, _) in KEY_UPGRADES {
+        let missing: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM duckdb_tables() WHERE table_name = $1)
+                AND NOT EXISTS (SELECT 1 FROM duckdb_columns() WHERE table_name = $1 AND column_name = $2)",
+            [table, column],
+            |row| row.get(0),
+        )?;
+        if missing {
+            conn.execute_batch(&format!(
+                "CREATE TABLE {table}_rekey AS SELECT * FROM {table}; DROP TABLE {table};"
+            ))?;
+            moved.push(table);
+        }
+    }
+    Ok(moved)
+}
+
+/// Copy the rows of the tables moved by [`move_rekeyed_tables`] into their
+/// upgraded definition
+fn restore_rekeyed_tables(conn: &Connection, moved: &[&str]) -> Result<()> {
+    for &(table, _, columns) in KEY_UPGRADES {
+        if moved.contains(&table) {
+            conn.execute_batch(&format!(
+                "INSERT OR IGNORE INTO {table} BY NAME SELECT {columns} FROM {table}_rekey; DROP TABLE {table}_rekey;"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// The SQL creating the schema of a database, as run by [`Storage::new`]
+pub fn schema_sql() -> String {
+    format!("{INITIAL_SCHEMA}{SCHEMA_UPGRADES}")
+}
+
+/// The normalized solc version of a solidity contract, see [`solc_version`],
+/// given the number of its source files by language
+fn contract_solc_version(
+    contract: &PlainContract,
+    languages: Option<&BTreeMap<&str, usize>>,
+) -> Option<String> {
+    match languages.is_some_and(|languages| languages.contains_key("vyper")) {
+        true => None,
+        false => solc_version(&contract.metadata.compiler_version)
+            .ok()
+            .map(|v| v.to_string()),
+    }
+}
+
+/// The number of source files by language of a contract, e.g.
+/// `solidity:3,yul:1`, see [`ContractSource::languages`]
+fn format_languages(languages: &BTreeMap<&str, usize>) -> String {
+    languages
+        .iter()
+        .map(|(language, count)| format!("{language}:{count}"))
+        .join(",")
+}
+
+pub fn row_to_contract(row: &duckdb::Row) -> Result<PlainContract> {
+    let source: String = row.get(0)?;
+    let source_type: SourceType = row.get(1)?;
+    let metadata: String = row.get(2)?;
+
+    let source: ContractSource = match source_type {
+        SourceType::SingleSolidity => serde_json::from_str(&source)?,
+        SourceType::MultiSolidity => serde_json::from_str(&source)?,
+        SourceType::Vyper => serde_json::from_str(&source)?,
+        SourceType::Json => serde_json::from_str(&source)?,
+    };
+
+    let metadata: Metadata = serde_json::from_str(&metadata)?;
+    Ok(PlainContract::new(metadata, source))
+}
+
+impl Storage {
+    pub fn new(db_file: &str) -> Result<Storage> {
+        let parent = std::path::Path::new(db_file).parent();
+        if let Some(parent) = parent {
+            create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_file)?;
+        let _ = conn.execute_batch(INITIAL_SCHEMA);
+
+        // Columns and tables added after the initial schema, created
+        // separately so that existing databases are upgraded as well
+        let added = add_missing_columns(&conn)?;
+        let moved = move_rekeyed_tables(&conn)?;
+        conn.execute_batch(SCHEMA_UPGRADES)?;
+        restore_rekeyed_tables(&conn, &moved)?;
+
+        let storage = Storage { conn };
+        if added.contains(&("contract", "keccak_id")) {
+            storage.backfill_keccak_ids()?;
+        }
+        Ok(storage)
+    }
+
+    /// Disables checkpoint on shutdown
+    pub fn disable_checkpoint(&self) -> Result<()> {
+        self.conn
+            .execute("PRAGMA disable_checkpoint_on_shutdown;", [])?;
+        Ok(())
+    }
+
+    /// Enable checkpoint on shutdown
+    pub fn enable_checkpoint(&self) -> Result<()> {
+        self.conn
+            .execute("PRAGMA enable_checkpoint_on_shutdown;", [])?;
+        Ok(())
+    }
+
+    /// Get contract by id
+    #[allow(dead_code)]
+    pub fn get_contract(&self, id: &str) -> Result<Option<PlainContract>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata FROM contract WHERE id = ? limit 1",
+        )?;
+        let mut rows = stmt.query([id])?;
+        let row = match rows.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        Ok(Some(row_to_contract(row)?))
+    }
+
+    #[allow(dead_code)]
+    pub fn get_random_contract(
+        &self,
+        source_type: &ContractSourceType,
+        offset: Option<u32>,
+    ) -> Result<Option<PlainContract>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata FROM contract where source_type::varchar=? OFFSET ? LIMIT 1",
+        )?;
+        let source_type: String = source_type.to_string();
+        let mut rows = stmt.query(params![
+            &source_type,
+            offset.unwrap_or_else(|| {
+                let mut rng = rand::thread_rng();
+                rng.gen_range(0..1000)
+            })
+        ])?;
+        let row = match rows.next()? {
+            Some(row) => row,
+            None => return Err(eyre::eyre!("No contract found")),
+        };
+
+        Ok(Some(row_to_contract(row)?))
+    }
+
+    /// A page of stored contracts
+    pub fn get_contracts(&self, offset: u64, limit: u64) -> Result<Vec<PlainContract>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata FROM contract ORDER BY id LIMIT ? OFFSET ?",
+        )?;
+        let mut rows = stmt.query(params![limit, offset])?;
+
+        let mut contracts = Vec::new();
+        while let Some(row) = rows.next()? {
+            contracts.push(row_to_contract(row)?);
+        }
+        Ok(contracts)
+    }
+
+    /// A random sample of stored contracts, the same seed gives the same sample
+    pub fn sample_contracts(&self, size: usize, seed: u64) -> Result<Vec<PlainContract>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT source, source_type::varchar, metadata FROM contract USING SAMPLE reservoir({size} ROWS) REPEATABLE ({seed})",
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        let mut contracts = Vec::new();
+        while let Some(row) = rows.next()? {
+            contracts.push(row_to_contract(row)?);
+        }
+        Ok(contracts)
+    }
+
+    /// A random sample of the indexed functions, with the license of their
+    /// contract, repeatable with the same seed
+    pub fn sample_functions(
+        &self,
+        size: usize,
+        seed: u64,
+    ) -> Result<Vec<(ContractFunction, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector, f.source_code, coalesce(c.license, '') FROM function f LEFT JOIN contract c ON c.id = f.contract_id USING SAMPLE reservoir({size} ROWS) REPEATABLE ({seed})",
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            let function = ContractFunction {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                filename: row.get(4)?,
+                signature: row.get(5)?,
+                selector: row.get(6)?,
+                source_code: row.get(7)?,
+            };
+            Ok((function, row.get(8)?))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store a single contract
+    #[allow(dead_code)]
+    pub fn store_contract(&self, contract: &PlainContract, id: Option<String>) -> Result<()> {
+        let PlainContract {
+            metadata, source, ..
+        } = contract;
+        let id = id.unwrap_or_else(|| contract.hash());
+        let name = &metadata.contract_name.clone();
+        let source_type = match source {
+            ContractSource::SingleSolidity(_) => "single_sol",
+            ContractSource::MultiSolidity(_) => "multi_sol",
+            ContractSource::Vyper(_) => "vyper",
+            ContractSource::Json(_) => "json",
+        };
+        let source = serde_json::to_string(source)?;
+        let metadata = serde_json::to_string(metadata)?;
+        self.conn.execute(
+            "INSERT INTO contract (id, name, metadata, source, source_type) VALUES (?, ?, ?, ?, ?)",
+            [id, name.into(), metadata, source, source_type.into()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Store multiple contracts in batch mode
+    pub fn store_contracts(&self, contracts: Vec<PlainContract>) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO contract (id, name, metadata, source, source_type, chain_id, address, license, solc_version, keccak_id, languages) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+        )?;
+
+        for c in contracts {
+            let PlainContract {
+                metadata, source, ..
+            } = &c;
+            let id: String = c.hash();
+            let name: String = metadata.contract_name.clone();
+            let license = contract_license(&c);
+            let languages = source.languages().ok();
+            let solc_version = contract_solc_version(&c, languages.as_ref());
+            let keccak_id = c.keccak_id().ok();
+            let languages = languages.as_ref().map(format_languages);
+            let source_type = source.source_type().to_string();
+            let source = serde_json::to_string(&source)?;
+            let chain_id = metadata.chain_id;
+            let address = metadata.address.clone();
+            let metadata = serde_json::to_string(&metadata)?;
+            // allow error
+            let _ = stmt.insert(params![
+                id,
+                name,
+                metadata,
+                source,
+                source_type,
+                chain_id,
+                address,
+                license,
+                solc_version,
+                keccak_id,
+                languages
+            ]);
+        }
+        self.link_bytecodes()?;
+
+        Ok(())
+    }
+
+    /// Copy the contracts, functions and every other table of another database
+    /// file, e.g. a shard built on another machine. Ids are hashes of the
+    /// source, so rows with an id already stored are skipped.
+    pub fn merge(&self, db_file: &str) -> Result<MergeStats> {
+        self.conn.execute_batch(&format!(
+            "ATTACH '{}' AS other (READ_ONLY)",
+            db_file.replace('\'', "''")
+        ))?;
+        let stats = self.merge_attached();
+        self.conn.execute_batch("DETACH other")?;
+        stats
+    }
+
+    /// The columns of a table in the database, with whether their type is an
+    /// enum, empty when the database has no such table
+    fn table_columns(&self, database: &str, table: &str) -> Result<Vec<(String, bool)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT column_name, data_type LIKE 'ENUM%' FROM duckdb_columns() WHERE database_name = ? AND schema_name = 'main' AND table_name = ? ORDER BY column_index",
+        )?;
+        let rows = stmt.query_map([database, table], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Copy the rows of a table of the attached database, with the columns
+    /// both databases have. Rows with a stored key are skipped, as are rows
+    /// already stored in tables without a key. Returns the number of copied
+    /// rows.
+    fn merge_table(&self, table: &str, derived: &[(&str, &str)]) -> Result<usize> {
+        let database: String = self
+            .conn
+            .query_row("SELECT current_database()", [], |row| row.get(0))?;
+        let other = self
+            .table_columns("other", table)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        if other.is_empty() {
+            return Ok(0);
+        }
+        let columns = self
+            .table_columns(&database, table)?
+            .into_iter()
+            .filter_map(|(column, _)| match other.get(&column) {
+                // enums of two databases are different types
+                Some(true) => Some(format!("\"{column}\"::VARCHAR AS \"{column}\"")),
+                Some(false) => Some(format!("\"{column}\"")),
+                None => derived
+                    .iter()
+                    .find(|(name, _)| *name == column)
+                    .map(|(_, expr)| format!("{expr} AS \"{column}\"")),
+            })
+            .join(", ");
+        let keyed: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM duckdb_constraints() WHERE database_name = ? AND schema_name = 'main' AND table_name = ? AND constraint_type IN ('PRIMARY KEY', 'UNIQUE'))",
+            [&database, table],
+            |row| row.get(0),
+        )?;
+        let sql = match keyed {
+            true => format!("INSERT OR IGNORE INTO {table} BY NAME SELECT {columns} FROM other.{table}"),
+            false => format!(
+                "INSERT INTO {table} BY NAME (SELECT {columns} FROM other.{table} EXCEPT SELECT {columns} FROM {table})"
+            ),
+        };
+        Ok(self.conn.execute(&sql, [])?)
+    }
+
+    fn merge_attached(&self) -> Result<MergeStats> {
+        let conflicts = self.conn.query_row(
+            "SELECT count(*) FROM other.contract o JOIN contract c ON c.id = o.id WHERE c.source IS DISTINCT FROM o.source",
+            [],
+            |row| row.get(0),
+        )?;
+        // databases of releases before the chain and address columns have
+        // them in the metadata only
+        let contracts = self.merge_table(
+            "contract",
+            &[
+                (
+                    "chain_id",
+                    "TRY_CAST(json_extract_string(metadata, '$.ChainId') AS UBIGINT)",
+                ),
+                ("address", "json_extract_string(metadata, '$.Address')"),
+            ],
+        )?;
+        let functions = self.merge_table("function", &[])?;
+
+        let tables: Vec<String> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT table_name FROM duckdb_tables() WHERE database_name = current_database() AND schema_name = 'main' AND table_name NOT IN ('contract', 'function') ORDER BY table_name",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        let mut rows = 0;
+        for table in tables.iter() {
+            rows += self.merge_table(table, &[])?;
+        }
+
+        // columns the merged database did not have yet
+        self.backfill_licenses()?;
+        self.backfill_solc_versions()?;
+        self.backfill_keccak_ids()?;
+        self.backfill_languages()?;
+        self.link_bytecodes()?;
+
+        Ok(MergeStats {
+            contracts,
+            functions,
+            rows,
+            conflicts,
+        })
+    }
+
+    /// Identifies the set of stored contracts: the number of contracts and the
+    /// md5 of their sorted ids
+    pub fn snapshot_id(&self) -> Result<String> {
+        Ok(self.conn.query_row(
+            "SELECT count(*) || '-' || md5(coalesce(string_agg(id, ',' ORDER BY id), '')) FROM contract",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Whether a release was exported at the snapshot
+    pub fn has_release(&self, snapshot_id: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM release WHERE snapshot_id = $1) OR EXISTS (SELECT 1 FROM release_entry WHERE snapshot_id = $1)",
+            [snapshot_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record the exported contracts and functions as released at the
+    /// snapshot, following the release at `since`. Rows left out of the
+    /// release, e.g. by a license filter, are exported by the next one.
+    pub fn record_release(
+        &self,
+        snapshot_id: &str,
+        since: Option<&str>,
+        contract_ids: &[String],
+        function_ids: &[String],
+    ) -> Result<()> {
+        // releasing an unchanged database again keeps the existing release
+        self.conn.execute(
+            "INSERT OR IGNORE INTO release (snapshot_id, since) VALUES (?, ?)",
+            params![snapshot_id, since.filter(|since| *since != snapshot_id)],
+        )?;
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO release_entry (snapshot_id, kind, id) VALUES (?, ?, ?)",
+        )?;
+        for (kind, ids) in [("contract", contract_ids), ("function", function_ids)] {
+            for id in ids.iter() {
+                stmt.execute([snapshot_id, kind, id])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A page of the contracts stored after the release at the snapshot, all
+    /// contracts without a snapshot, with their id and license. Pages are read
+    /// by id, after the last id of the previous page.
+    pub fn contracts_since(
+        &self,
+        snapshot_id: Option<&str>,
+        after: &str,
+        limit: u64,
+    ) -> Result<Vec<(String, PlainContract, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT source, source_type::varchar, metadata, coalesce(license, ''), id FROM contract t WHERE NOT EXISTS ({}) AND id > ? ORDER BY id LIMIT ?",
+            released("contract")
+        ))?;
+        let mut rows = stmt.query(params![snapshot_id.unwrap_or_default(), after, limit])?;
+
+        let mut contracts = Vec::new();
+        while let Some(row) = rows.next()? {
+            contracts.push((row.get(4)?, row_to_contract(row)?, row.get(3)?));
+        }
+        Ok(contracts)
+    }
+
+    /// A page of the functions indexed after the release at the snapshot, with
+    /// the license of their contract, see [`Self::contracts_since`]
+    pub fn functions_since(
+        &self,
+        snapshot_id: Option<&str>,
+        after: &str,
+        limit: u64,
+    ) -> Result<Vec<(ContractFunction, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT t.id, t.contract_id, t.contract_name, t.function_name, t.filename, t.signature, t.selector, t.source_code, coalesce(c.license, '') FROM function t LEFT JOIN contract c ON c.id = t.contract_id WHERE NOT EXISTS ({}) AND t.id > ? ORDER BY t.id LIMIT ?",
+            released("function")
+        ))?;
+        let rows = stmt.query_map(
+            params![snapshot_id.unwrap_or_default(), after, limit],
+            |row| {
+                let function = ContractFunction {
+                    id: row.get(0)?,
+                    contract_id: row.get(1)?,
+                    contract_name: row.get(2)?,
+                    function_name: row.get(3)?,
+                    filename: row.get(4)?,
+                    signature: row.get(5)?,
+                    selector: row.get(6)?,
+                    source_code: row.get(7)?,
+                };
+                Ok((function, row.get(8)?))
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Fill in the license of contracts stored before the `license` column
+    /// existed or merged from another database
+    pub fn backfill_licenses(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata, id FROM contract WHERE license IS NULL",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut licenses = vec![];
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(3)?;
+            licenses.push((contract_license(&row_to_contract(row)?), id));
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE contract SET license = ? WHERE id = ?")?;
+        for (license, id) in licenses.iter() {
+            stmt.execute([license, id])?;
+        }
+        Ok(licenses.len())
+    }
+
+    /// Fill in the solc version of the contracts stored before it was recorded,
+    /// returns the number of updated contracts
+    pub fn backfill_solc_versions(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata, id FROM contract WHERE solc_version IS NULL AND source_type != 'vyper'",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut versions = vec![];
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(3)?;
+            let contract = row_to_contract(row)?;
+            let languages = contract.source.languages().ok();
+            if let Some(version) = contract_solc_version(&contract, languages.as_ref()) {
+                versions.push((version, id));
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE contract SET solc_version = ? WHERE id = ?")?;
+        for (version, id) in versions.iter() {
+            stmt.execute([version, id])?;
+        }
+        Ok(versions.len())
+    }
+
+    /// Fill in the keccak id of the contracts stored before it was recorded,
+    /// returns the number of updated contracts
+    pub fn backfill_keccak_ids(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata, id FROM contract WHERE keccak_id IS NULL",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut ids = vec![];
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(3)?;
+            if let Ok(keccak_id) = row_to_contract(row)?.keccak_id() {
+                ids.push((keccak_id, id));
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE contract SET keccak_id = ? WHERE id = ?")?;
+        for (keccak_id, id) in ids.iter() {
+            stmt.execute([keccak_id, id])?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Fill in the languages of the contracts stored before they were
+    /// recorded, returns the number of updated contracts
+    pub fn backfill_languages(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source, source_type::varchar, metadata, id FROM contract WHERE languages IS NULL",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut languages = vec![];
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(3)?;
+            if let Ok(l) = row_to_contract(row)?.source.languages() {
+                languages.push((format_languages(&l), id));
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE contract SET languages = ? WHERE id = ?")?;
+        for (l, id) in languages.iter() {
+            stmt.execute([l, id])?;
+        }
+        Ok(languages.len())
+    }
+
+    /// Ids of the contracts with the keccak id
+    pub fn contract_ids_by_keccak_id(&self, keccak_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM contract WHERE keccak_id = ? ORDER BY id")?;
+        let rows = stmt.query_map([keccak_id.to_lowercase()], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The stored license of a contract, see [`crate::license::contract_license`]
+    pub fn contract_license(&self, id: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT coalesce(license, '') FROM contract WHERE id = ?")?;
+        let mut rows = stmt.query([id])?;
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get(0)?),
+            None => None,
+        })
+    }
+
+    pub fn count_contracts(&self) -> Result<u32> {
+        let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM contract")?;
+        let mut rows = stmt.query([])?;
+        let row = rows.next()?.unwrap();
+        let count: u32 = row.get(0)?;
+        Ok(count)
+    }
+
+    /// Paths of the ingest manifest, i.e. already stored by pre-process
+    pub fn ingested_paths(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT path FROM ingest_manifest")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Record the paths of stored contracts in the ingest manifest, with the
+    /// id of the contract read from them
+    pub fn record_ingested(&self, entries: &[(String, String)]) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("INSERT OR REPLACE INTO ingest_manifest (path, contract_id) VALUES (?, ?)")?;
+        for (path, contract_id) in entries.iter() {
+            stmt.execute([path, contract_id])?;
+        }
+
+        Ok(())
+    }
+
+    /// Store where contracts were read from, and link the runtime code stored
+    /// for their chain and address
+    pub fn store_occurrences(&self, occurrences: &[ContractOccurrence]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO contract_occurrence (contract_id, dataset, path, chain_id, address) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for o in occurrences.iter() {
+            stmt.execute(params![
+                o.contract_id,
+                o.dataset,
+                o.path,
+                o.chain_id,
+                o.address
+            ])?;
+        }
+        self.link_bytecodes()?;
+
+        Ok(())
+    }
+
+    /// Datasets, paths and addresses a contract was read from
+    pub fn occurrences(&self, contract_id: &str) -> Result<Vec<ContractOccurrence>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, dataset, path, chain_id, address FROM contract_occurrence WHERE contract_id = ? ORDER BY dataset, path",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| {
+            Ok(ContractOccurrence {
+                contract_id: row.get(0)?,
+                dataset: row.get(1)?,
+                path: row.get(2)?,
+                chain_id: row.get(3)?,
+                address: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_addresses(&self, addresses: &[ContractAddress]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO address (chain_id, address, contract_id) VALUES (?, ?, ?)",
+        )?;
+        for a in addresses.iter() {
+            stmt.execute(params![a.chain_id, a.address, a.contract_id])?;
+        }
+
+        Ok(())
+    }
+
+    /// Contracts deployed at the address on any chain
+    pub fn contracts_at(&self, address: &str) -> Result<Vec<ContractAddress>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, address, contract_id FROM address WHERE address = ? ORDER BY chain_id",
+        )?;
+        let rows = stmt.query_map([address], |row| {
+            Ok(ContractAddress {
+                chain_id: row.get(0)?,
+                address: row.get(1)?,
+                contract_id: row.get(2)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_functions(&self, functions: &[ContractFunction]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO function (id, contract_id, contract_name, function_name, filename, signature, selector, source_code) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+
+        for f in functions.iter() {
+            let id = f.id.clone();
+            let contract_id = f.contract_id.clone();
+            let contract_name = f.contract_name.clone();
+            let function_name = f.function_name.clone();
+            let filename = f.filename.clone();
+            let signature = f.signature.clone();
+            let selector = f.selector.clone();
+            let source_code = f.source_code.clone();
+            // allow error
+            let _ = stmt.insert([
+                id,
+                contract_id,
+                contract_name,
+                function_name,
+                filename,
+                signature,
+                selector,
+                source_code,
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// Underlying types of the user defined value types declared in the
+    /// sources of a contract, by canonical name
+    pub fn user_value_types(&self, contract_id: &str) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, members FROM type_definition WHERE contract_id = ? AND kind = 'type'",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store the functions with their signature in canonical form, see
+    /// [`normalize_signature`]. Functions with an invalid signature or a
+    /// selector other than the one of their signature are stored in the
+    /// `function_quarantine` table instead, returns their number.
+    pub fn store_checked_functions(&self, functions: &[ContractFunction]) -> Result<usize> {
+        let mut user_types = HashMap::new();
+        let mut checked = vec![];
+        let mut quarantined = vec![];
+        for f in functions.iter() {
+            if !user_types.contains_key(&f.contract_id) {
+                let types = self.user_value_types(&f.contract_id)?;
+                user_types.insert(f.contract_id.clone(), types);
+            }
+            let signature = normalize_signature(&f.signature, &user_types[&f.contract_id]);
+            let reason = match &signature {
+                Err(e) => Some(e.to_string()),
+                Ok(signature) if selector(signature) != f.selector.to_lowercase() => Some(format!(
+                    "Selector {} of {signature} is not {}",
+                    selector(signature),
+                    f.selector
+                )),
+                Ok(_) => None,
+            };
+            match (signature, reason) {
+                (Ok(signature), None) => checked.push(ContractFunction {
+                    signature,
+                    selector: f.selector.to_lowercase(),
+                    ..f.clone()
+                }),
+                (_, reason) => quarantined.push((f, reason.unwrap_or_default())),
+            }
+        }
+        self.store_functions(&checked)?;
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO function_quarantine (id, contract_id, contract_name, function_name, filename, signature, selector, source_code, reason) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for (f, reason) in quarantined.iter() {
+            stmt.execute([
+                &f.id,
+                &f.contract_id,
+                &f.contract_name,
+                &f.function_name,
+                &f.filename,
+                &f.signature,
+                &f.selector,
+                &f.source_code,
+                reason,
+            ])?;
+        }
+        Ok(quarantined.len())
+    }
+
+    /// Distinct signatures of the indexed functions with the selector, with the
+    /// number of contracts implementing them, most implemented first
+    pub fn function_signatures(&self, selector: &str) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT signature, count(DISTINCT contract_id) AS contracts FROM function WHERE selector = ? GROUP BY signature ORDER BY contracts DESC, signature",
+        )?;
+        let rows = stmt.query_map([selector], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Find contracts which implement all of the given function selectors,
+    /// emit all of the given event topics and define all of the given error
+    /// selectors, returns pairs of contract id and contract name
+    pub fn contracts_conforming_to(
+        &self,
+        selectors: &[String],
+        topics: &[String],
+        errors: &[String],
+    ) -> Result<Vec<(String, String)>> {
+        let subquery = |table: &str, column: &str, values: &[String]| {
+            format!(
+                "SELECT contract_id, contract_name FROM {table} WHERE {column} IN ({}) GROUP BY contract_id, contract_name HAVING count(DISTINCT {column}) = {}",
+                values.iter().map(|_| "?").join(", "),
+                values.len()
+            )
+        };
+        let mut subqueries = vec![];
+        if !selectors.is_empty() {
+            subqueries.push(subquery("function", "selector", selectors));
+        }
+        if !topics.is_empty() {
+            subqueries.push(subquery("event", "topic0", topics));
+        }
+        if !errors.is_empty() {
+            subqueries.push(subquery("custom_error", "selector", errors));
+        }
+
+        let query = format!(
+            "{} ORDER BY contract_id, contract_name",
+            subqueries.join(" INTERSECT ")
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let params = selectors.iter().chain(topics).chain(errors);
+        let rows = stmt.query_map(params_from_iter(params), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_constructor_arguments(&self, arguments: &[ConstructorArgument]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO constructor_argument (contract_id, contract_name, position, name, type, value) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+
+        for a in arguments.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                a.contract_id,
+                a.contract_name,
+                a.position,
+                a.name,
+                a.ty,
+                a.value
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// Store the immutable values read from deployed bytecode, replacing the
+    /// value read before for the same variable of the contract at the address
+    pub fn store_immutables(&self, immutables: &[ImmutableValue]) -> Result<()> {
+        let mut delete = self.conn.prepare(
+            "DELETE FROM immutable_value WHERE contract_id = ? AND contract_name = ? AND address IS NOT DISTINCT FROM ? AND name = ?",
+        )?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO immutable_value (contract_id, contract_name, address, name, type, value) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+
+        for i in immutables.iter() {
+            delete.execute(params![i.contract_id, i.contract_name, i.address, i.name])?;
+            stmt.execute(params![
+                i.contract_id,
+                i.contract_name,
+                i.address,
+                i.name,
+                i.ty,
+                i.value
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn store_sizes(
+        &self,
+        artifact_sizes: &[ArtifactSize],
+        function_sizes: &[FunctionSize],
+    ) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO artifact (contract_id, source_path, contract_name, runtime_size, creation_size) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for a in artifact_sizes.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                a.contract_id,
+                a.source_path,
+                a.contract_name,
+                a.runtime_size,
+                a.creation_size
+            ]);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO function_size (contract_id, source_path, contract_name, function_name, size) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for f in function_sizes.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                f.contract_id,
+                f.source_path,
+                f.contract_name,
+                f.function_name,
+                f.size
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_artifact_bytecodes(&self, bytecodes: &[ArtifactBytecode]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO artifact_bytecode (contract_id, source_path, contract_name, creation_code, runtime_code) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for b in bytecodes.iter() {
+            stmt.execute([
+                &b.contract_id,
+                &b.source_path,
+                &b.contract_name,
+                &b.creation_code,
+                &b.runtime_code,
+            ])?;
+        }
+        self.store_skeleton_hashes(bytecodes.iter().map(|b| {
+            (
+                &b.contract_id,
+                &b.source_path,
+                &b.contract_name,
+                &b.runtime_code,
+            )
+        }))?;
+        Ok(())
+    }
+
+    /// Record the [`skeleton_hash`] of runtime codes of artifacts, known
+    /// artifacts are kept as DuckDB cannot replace the indexed hash
+    fn store_skeleton_hashes<'a>(
+        &self,
+        codes: impl Iterator<Item = (&'a String, &'a String, &'a String, &'a String)>,
+    ) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO artifact_code_hash (contract_id, source_path, contract_name, skeleton_hash) VALUES (?, ?, ?, ?)",
+        )?;
+        let mut count = 0;
+        for (contract_id, source_path, contract_name, runtime_code) in codes {
+            let code = object_bytes(&BytecodeObject::Unlinked(runtime_code.clone()));
+            count += stmt.execute([
+                contract_id,
+                source_path,
+                contract_name,
+                &skeleton_hash(&code),
+            ])?;
+        }
+        Ok(count)
+    }
+
+    /// Hash the runtime code of the artifacts stored before their skeleton
+    /// hash was recorded, returns the number of hashed artifacts
+    pub fn backfill_skeleton_hashes(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, source_path, contract_name, runtime_code FROM artifact_bytecode a
+            WHERE NOT EXISTS (SELECT 1 FROM artifact_code_hash h WHERE h.contract_id = a.contract_id AND h.source_path = a.source_path AND h.contract_name = a.contract_name)",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<(String, String, String, String)>, _>>()?;
+        self.store_skeleton_hashes(rows.iter().map(|(a, b, c, d)| (a, b, c, d)))
+    }
+
+    pub fn store_source_maps(
+        &self,
+        source_maps: &[ArtifactSourceMap],
+        file_ids: &[SourceFileId],
+    ) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO source_map (contract_id, source_path, contract_name, creation_source_map, runtime_source_map) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for m in source_maps.iter() {
+            stmt.execute(params![
+                m.contract_id,
+                m.source_path,
+                m.contract_name,
+                m.creation_source_map,
+                m.runtime_source_map
+            ])?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO source_file_id (contract_id, file_id, path) VALUES (?, ?, ?)",
+        )?;
+        for f in file_ids.iter() {
+            stmt.execute(params![f.contract_id, f.file_id, f.path])?;
+        }
+        Ok(())
+    }
+
+    pub fn store_abis(&self, abis: &[ContractAbi]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO abi (contract_id, source_path, contract_name, abi_json) VALUES (?, ?, ?, ?)",
+        )?;
+        for a in abis.iter() {
+            stmt.execute([
+                &a.contract_id,
+                &a.source_path,
+                &a.contract_name,
+                &a.abi_json,
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn store_versioned(
+        &self,
+        artifacts: &[VersionedArtifact],
+        estimates: &[VersionedGasEstimate],
+    ) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO versioned_artifact (contract_id, contract_name, solc_version, runtime_size, creation_size, runtime_code_hash, deployment_gas) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for a in artifacts.iter() {
+            stmt.execute(params![
+                a.contract_id,
+                a.contract_name,
+                a.solc_version,
+                a.runtime_size,
+                a.creation_size,
+                a.runtime_code_hash,
+                a.deployment_gas
+            ])?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO versioned_gas_estimate (contract_id, contract_name, solc_version, signature, gas) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for e in estimates.iter() {
+            stmt.execute(params![
+                e.contract_id,
+                e.contract_name,
+                e.solc_version,
+                e.signature,
+                e.gas
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// The solc versions the contract is already stored with by `index-versions`
+    pub fn versioned_solc_versions(&self, contract_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT solc_version FROM versioned_artifact WHERE contract_id = ? ORDER BY solc_version",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Artifacts with a runtime bytecode of at least `min_size` bytes, largest first
+    pub fn artifacts_larger_than(&self, min_size: usize) -> Result<Vec<ArtifactSize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, source_path, contract_name, runtime_size, creation_size FROM artifact WHERE runtime_size >= ? ORDER BY runtime_size DESC",
+        )?;
+        let rows = stmt.query_map([min_size], |row| {
+            Ok(ArtifactSize {
+                contract_id: row.get(0)?,
+                source_path: row.get(1)?,
+                contract_name: row.get(2)?,
+                runtime_size: row.get(3)?,
+                creation_size: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_metadata_hashes(&self, hashes: &[MetadataHash]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO artifact_metadata (contract_id, contract_name, metadata_keccak, metadata_hash) VALUES (?, ?, ?, ?)",
+        )?;
+        for h in hashes.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                h.contract_id,
+                h.contract_name,
+                h.metadata_keccak,
+                h.metadata_hash
+            ]);
+        }
+        Ok(())
+    }
+
+    /// Record the metadata hash found in the deployed bytecode of an artifact
+    /// and whether it matches the recompilation
+    pub fn store_metadata_verification(
+        &self,
+        hash: &MetadataHash,
+        deployed_metadata_hash: Option<&str>,
+    ) -> Result<bool> {
+        let metadata_match =
+            hash.metadata_hash.is_some() && hash.metadata_hash.as_deref() == deployed_metadata_hash;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO artifact_metadata (contract_id, contract_name, metadata_keccak, metadata_hash, deployed_metadata_hash, metadata_match) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                hash.contract_id,
+                hash.contract_name,
+                hash.metadata_keccak,
+                hash.metadata_hash,
+                deployed_metadata_hash,
+                metadata_match
+            ],
+        )?;
+        Ok(metadata_match)
+    }
+
+    /// Number of matching and of all verified artifacts
+    pub fn metadata_match_count(&self) -> Result<(usize, usize)> {
+        Ok(self.conn.query_row(
+            "SELECT count(*) FILTER (WHERE metadata_match), count(metadata_match) FROM artifact_metadata",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
+
+    /// Functions contributing most to the runtime bytecode of an artifact
+    pub fn largest_functions(
+        &self,
+        artifact: &ArtifactSize,
+        limit: usize,
+    ) -> Result<Vec<FunctionSize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, source_path, contract_name, function_name, size FROM function_size WHERE contract_id = ? AND source_path = ? AND contract_name = ? ORDER BY size DESC LIMIT ?",
+        )?;
+        let params = params![
+            artifact.contract_id,
+            artifact.source_path,
+            artifact.contract_name,
+            limit
+        ];
+        let rows = stmt.query_map(params, |row| {
+            Ok(FunctionSize {
+                contract_id: row.get(0)?,
+                source_path: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                size: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_storage_slots(&self, slots: &[StorageSlot]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO storage_slot (contract_id, source_path, contract_name, label, slot, byte_offset, type, type_id, encoding) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+
+        for s in slots.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                s.contract_id,
+                s.source_path,
+                s.contract_name,
+                s.label,
+                s.slot,
+                s.offset,
+                s.ty,
+                s.type_id,
+                s.encoding
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_storage_layouts(&self, layouts: &[StorageLayoutJson]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO storage_layout (contract_id, source_path, contract_name, types_json) VALUES (?, ?, ?, ?)",
+        )?;
+        for l in layouts.iter() {
+            stmt.execute([
+                &l.contract_id,
+                &l.source_path,
+                &l.contract_name,
+                &l.types_json,
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn store_source_asts(&self, asts: &[SourceAst]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO source_ast (contract_id, source_path, ast_json) VALUES (?, ?, ?)",
+        )?;
+        for a in asts.iter() {
+            stmt.execute([&a.contract_id, &a.source_path, &a.ast_json])?;
+        }
+        Ok(())
+    }
+
+    /// The stored AST of every source file of a contract by the path of the
+    /// file, empty when the contract was indexed before the ASTs were stored
+    pub fn contract_source_asts(&self, contract_id: &str) -> Result<Vec<(String, Value)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_path, ast_json FROM source_ast WHERE contract_id = ? ORDER BY source_path",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.map(|row| {
+            let (path, ast) = row?;
+            Ok((path, serde_json::from_str(&ast)?))
+        })
+        .collect()
+    }
+
+    /// The storage slots of the state variables of a contract
+    pub fn contract_storage_slots(&self, contract_id: &str) -> Result<Vec<StorageSlot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, source_path, contract_name, label, slot, byte_offset, type, type_id, encoding FROM storage_slot WHERE contract_id = ? ORDER BY source_path, contract_name, length(slot), slot, byte_offset",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| {
+            Ok(StorageSlot {
+                contract_id: row.get(0)?,
+                source_path: row.get(1)?,
+                contract_name: row.get(2)?,
+                label: row.get(3)?,
+                slot: row.get(4)?,
+                offset: row.get(5)?,
+                ty: row.get(6)?,
+                type_id: row.get(7)?,
+                encoding: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Search state variables by solidity type, slot number (in decimal) and
+    /// storage encoding, all filters are optional
+    pub fn search_storage_slots(
+        &self,
+        ty: Option<&str>,
+        slot: Option<&str>,
+        encodings: &[&str],
+    ) -> Result<Vec<StorageSlot>> {
+        let mut conditions = vec!["true".to_string()];
+        let mut values: Vec<&str> = vec![];
+        if let Some(ty) = ty {
+            conditions.push("type = ?".into());
+            values.push(ty);
+        }
+        if let Some(slot) = slot {
+            conditions.push("slot = ?".into());
+            values.push(slot);
+        }
+        if !encodings.is_empty() {
+            conditions.push(format!(
+                "encoding IN ({})",
+                encodings.iter().map(|_| "?").join(", ")
+            ));
+            values.extend(encodings);
+        }
+
+        let query = format!(
+            "SELECT contract_id, source_path, contract_name, label, slot, byte_offset, type, type_id, encoding FROM storage_slot WHERE {} ORDER BY contract_id, source_path, contract_name, label",
+            conditions.join(" AND ")
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok(StorageSlot {
+                contract_id: row.get(0)?,
+                source_path: row.get(1)?,
+                contract_name: row.get(2)?,
+                label: row.get(3)?,
+                slot: row.get(4)?,
+                offset: row.get(5)?,
+                ty: row.get(6)?,
+                type_id: row.get(7)?,
+                encoding: row.get(8)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store events, filling the signature and source code of events indexed
+    /// before they were stored
+    pub fn store_events(&self, events: &[ContractEvent]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO event (id, contract_id, contract_name, event_name, signature, topic0, anonymous, full_signature, source_code) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT (id) DO UPDATE SET full_signature = coalesce(event.full_signature, excluded.full_signature), source_code = coalesce(event.source_code, excluded.source_code)",
+        )?;
+
+        for e in events.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                e.id,
+                e.contract_id,
+                e.contract_name,
+                e.event_name,
+                e.signature,
+                e.topic0,
+                e.anonymous,
+                e.full_signature,
+                e.source_code
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// Indexed events with the given `0x` prefixed topic0
+    pub fn events_by_topic0(&self, topic0: &str) -> Result<Vec<ContractEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, contract_id, contract_name, event_name, signature, topic0, anonymous, full_signature, source_code FROM event WHERE topic0 = ? ORDER BY contract_id, contract_name",
+        )?;
+        let rows = stmt.query_map([topic0], |row| {
+            Ok(ContractEvent {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                event_name: row.get(3)?,
+                signature: row.get(4)?,
+                topic0: row.get(5)?,
+                anonymous: row.get(6)?,
+                full_signature: row.get(7)?,
+                source_code: row.get(8)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store custom errors, filling the source code of errors indexed before
+    /// it was stored
+    pub fn store_errors(&self, errors: &[ContractError]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO custom_error (id, contract_id, contract_name, error_name, signature, selector, source_code) VALUES (?, ?, ?, ?, ?, ?, ?) ON CONFLICT (id) DO UPDATE SET source_code = coalesce(custom_error.source_code, excluded.source_code)",
+        )?;
+
+        for e in errors.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                e.id,
+                e.contract_id,
+                e.contract_name,
+                e.error_name,
+                e.signature,
+                e.selector,
+                e.source_code
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// Indexed custom errors with the given `0x` prefixed selector
+    pub fn errors_by_selector(&self, selector: &str) -> Result<Vec<ContractError>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, contract_id, contract_name, error_name, signature, selector, source_code FROM custom_error WHERE selector = ? ORDER BY contract_id, contract_name",
+        )?;
+        let rows = stmt.query_map([selector], |row| {
+            Ok(ContractError {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                error_name: row.get(3)?,
+                signature: row.get(4)?,
+                selector: row.get(5)?,
+                source_code: row.get(6)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store fetched runtime code, replacing the code stored for the same chain
+    /// and address but keeping its known contract and block, and link it to the
+    /// stored contracts. DuckDB cannot upsert the indexed `code_hash`, the old
+    /// row is deleted first.
+    pub fn store_bytecodes(&self, bytecodes: &[DeployedBytecode]) -> Result<()> {
+        let mut delete = self.conn.prepare(
+            "DELETE FROM bytecode WHERE chain_id = ? AND address = ? RETURNING contract_id, block_number",
+        )?;
+        let mut insert = self.conn.prepare(
+            "INSERT INTO bytecode (chain_id, address, code_hash, code, contract_id, block_number) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for b in bytecodes.iter() {
+            let known: Option<(Option<String>, Option<u64>)> = delete
+                .query_map(params![b.chain_id, b.address], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .next()
+                .transpose()?;
+            let (contract_id, block_number) = known.unwrap_or_default();
+            insert.execute(params![
+                b.chain_id,
+                b.address,
+                b.code_hash,
+                b.code,
+                b.contract_id.clone().or(contract_id),
+                b.block_number.or(block_number)
+            ])?;
+        }
+        self.link_bytecodes()?;
+        Ok(())
+    }
+
+    /// Link the stored runtime code without a contract to the contract stored
+    /// for the same chain and address, returns the number of linked rows
+    pub fn link_bytecodes(&self) -> Result<usize> {
+        Ok(self.conn.execute(
+            &format!("UPDATE bytecode SET contract_id = c.contract_id FROM ({CONTRACT_LOCATIONS}) c WHERE bytecode.contract_id IS NULL AND c.chain_id = bytecode.chain_id AND c.address = bytecode.address"),
+            [],
+        )?)
+    }
+
+    /// The indexed contract of a runtime code: a verified deployment with the
+    /// same code hash, else a compiled artifact with the same
+    /// [`skeleton_hash`]
+    pub fn exact_code_match(
+        &self,
+        code_hash: &str,
+        skeleton_hash: &str,
+    ) -> Result<Option<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            r"SELECT b.contract_id, c.name, 0 AS source FROM bytecode b JOIN contract c ON c.id = b.contract_id WHERE b.code_hash = ?
+            UNION ALL
+            SELECT contract_id, contract_name, 1 AS source FROM artifact_code_hash WHERE skeleton_hash = ?
+            ORDER BY source, contract_id LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map([code_hash, skeleton_hash], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Number of stored runtime codes, and of those linked to a contract
+    pub fn count_bytecodes(&self) -> Result<(usize, usize)> {
+        Ok(self.conn.query_row(
+            "SELECT count(*), count(contract_id) FROM bytecode",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
+
+    /// Stored runtime codes without a verified contract, optionally only on one chain
+    pub fn unlinked_bytecodes(&self, chain_id: Option<u64>) -> Result<Vec<DeployedBytecode>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, address, code_hash, code, block_number FROM bytecode WHERE contract_id IS NULL AND (? IS NULL OR chain_id = ?) ORDER BY chain_id, address",
+        )?;
+        let rows = stmt.query_map(params![chain_id, chain_id], |row| {
+            Ok(DeployedBytecode {
+                chain_id: row.get(0)?,
+                address: row.get(1)?,
+                code_hash: row.get(2)?,
+                code: row.get(3)?,
+                contract_id: None,
+                block_number: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_deployments(&self, deployments: &[Deployment]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO deployment (chain_id, address, contract_id, deployer, tx_hash, block_number, implementation) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for d in deployments.iter() {
+            stmt.execute(params![
+                d.chain_id,
+                d.address,
+                d.contract_id,
+                d.deployer,
+                d.tx_hash,
+                d.block_number,
+                d.implementation
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Clones grouped by chain and implementation, most cloned first. The
+    /// source of a clone is the source of the deployment at its implementation
+    /// address on the same chain.
+    pub fn clone_groups(&self, limit: usize) -> Result<Vec<CloneGroup>> {
+        let mut stmt = self.conn.prepare(
+            r"SELECT c.chain_id, c.implementation, i.contract_id, count(*) AS clones
+            FROM deployment c LEFT JOIN deployment i ON i.chain_id = c.chain_id AND i.address = c.implementation
+            WHERE c.implementation IS NOT NULL
+            GROUP BY c.chain_id, c.implementation, i.contract_id
+            ORDER BY clones DESC, c.chain_id, c.implementation
+            LIMIT ?",
+        )?;
+        let rows = stmt.query_map([limit], |row| {
+            Ok(CloneGroup {
+                chain_id: row.get(0)?,
+                implementation: row.get(1)?,
+                contract_id: row.get(2)?,
+                clones: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_clone_factories(&self, factories: &[CloneFactory]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO clone_factory (contract_id, contract_name) VALUES (?, ?)",
+        )?;
+        for f in factories.iter() {
+            // allow error
+            let _ = stmt.insert(params![f.contract_id, f.contract_name]);
+        }
+        Ok(())
+    }
+
+    /// Id, name and address of the contracts fetched from the chain, optionally
+    /// only the one at the lowercase `0x` prefixed address
+    pub fn contracts_on_chain(
+        &self,
+        chain_id: u64,
+        address: Option<&str>,
+    ) -> Result<Vec<(String, String, Option<String>)>> {
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT DISTINCT c.id, c.name, l.address FROM ({CONTRACT_LOCATIONS}) l JOIN contract c ON c.id = l.contract_id WHERE l.chain_id = ? AND (? IS NULL OR l.address = ?) ORDER BY l.address, c.id"),
+        )?;
+        let rows = stmt.query_map(params![chain_id, address, address], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Ids of the contracts whose name matches the glob pattern, e.g. `Uniswap*`
+    pub fn contract_ids_by_name(&self, pattern: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM contract WHERE name GLOB ? ORDER BY name, id")?;
+        let rows = stmt.query_map([pattern], |row| row.get(0))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// An indexed function by id
+    pub fn get_function(&self, id: &str) -> Result<Option<ContractFunction>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, contract_id, contract_name, function_name, filename, signature, selector, source_code FROM function WHERE id = ?",
+        )?;
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(ContractFunction {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                filename: row.get(4)?,
+                signature: row.get(5)?,
+                selector: row.get(6)?,
+                source_code: row.get(7)?,
+            })
+        })?;
+
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Indexed functions of a contract, with their source code
+    pub fn contract_functions(&self, contract_id: &str) -> Result<Vec<ContractFunction>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, contract_id, contract_name, function_name, filename, signature, selector, source_code FROM function WHERE contract_id = ? ORDER BY contract_name, filename, signature",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| {
+            Ok(ContractFunction {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                filename: row.get(4)?,
+                signature: row.get(5)?,
+                selector: row.get(6)?,
+                source_code: row.get(7)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Contracts deployed by the lowercase `0x` prefixed address on any chain
+    pub fn deployments_by(&self, deployer: &str) -> Result<Vec<Deployment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, address, contract_id, deployer, tx_hash, block_number, implementation FROM deployment WHERE deployer = ? ORDER BY chain_id, block_number",
+        )?;
+        let rows = stmt.query_map([deployer], |row| {
+            Ok(Deployment {
+                chain_id: row.get(0)?,
+                address: row.get(1)?,
+                contract_id: row.get(2)?,
+                deployer: row.get(3)?,
+                tx_hash: row.get(4)?,
+                block_number: row.get(5)?,
+                implementation: row.get(6)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// All deployments, ordered by chain and address
+    pub fn deployments(&self) -> Result<Vec<Deployment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, address, contract_id, deployer, tx_hash, block_number, implementation FROM deployment ORDER BY chain_id, address",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Deployment {
+                chain_id: row.get(0)?,
+                address: row.get(1)?,
+                contract_id: row.get(2)?,
+                deployer: row.get(3)?,
+                tx_hash: row.get(4)?,
+                block_number: row.get(5)?,
+                implementation: row.get(6)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Ids of the contracts using a feature found by `analyze`, e.g. `create2`
+    pub fn contract_ids_using(&self, feature: &str) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT contract_id FROM feature_usage WHERE feature = ? AND uses > 0")?;
+        let rows = stmt.query_map([feature], |row| row.get(0))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Replace the findings of a kind for the given contracts
+    pub fn store_findings(
+        &self,
+        kind: &str,
+        contract_ids: &[String],
+        findings: &[Finding],
+    ) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM finding WHERE kind = ? AND contract_id = ?")?;
+        for contract_id in contract_ids.iter() {
+            stmt.execute(params![kind, contract_id])?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO finding (contract_id, kind, name, severity, detail) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for f in findings.iter() {
+            stmt.execute(params![f.contract_id, f.kind, f.name, f.severity, f.detail])?;
+        }
+        Ok(())
+    }
+
+    /// Replace the import edges of the given contracts
+    pub fn store_import_edges(&self, contract_ids: &[String], edges: &[ImportEdge]) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM import_edge WHERE contract_id = ?")?;
+        for contract_id in contract_ids.iter() {
+            stmt.execute([contract_id])?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO import_edge (contract_id, from_file, position, to_file, symbols) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for e in edges.iter() {
+            stmt.execute(params![
+                e.contract_id,
+                e.from_file,
+                e.position,
+                e.to_file,
+                e.symbols
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Import edges of a contract, or of the contracts importing a file whose
+    /// path matches the glob pattern, optionally importing the symbol by name
+    pub fn import_edges(
+        &self,
+        contract_id: Option<&str>,
+        to_file: Option<&str>,
+        symbol: Option<&str>,
+    ) -> Result<Vec<ImportEdge>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, from_file, position, to_file, symbols FROM import_edge WHERE (? IS NULL OR contract_id = ?) AND (? IS NULL OR to_file GLOB ?) AND (? IS NULL OR list_contains(string_split(symbols, ','), ?)) ORDER BY contract_id, from_file, position",
+        )?;
+        let rows = stmt.query_map(
+            params![contract_id, contract_id, to_file, to_file, symbol, symbol],
+            |row| {
+                Ok(ImportEdge {
+                    contract_id: row.get(0)?,
+                    from_file: row.get(1)?,
+                    position: row.get(2)?,
+                    to_file: row.get(3)?,
+                    symbols: row.get(4)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Replace the feature usage of the given contracts
+    pub fn store_feature_usages(
+        &self,
+        contract_ids: &[String],
+        usages: &[FeatureUsage],
+    ) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM feature_usage WHERE contract_id = ?")?;
+        for contract_id in contract_ids.iter() {
+            stmt.execute([contract_id])?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO feature_usage (contract_id, feature, uses) VALUES (?, ?, ?)",
+        )?;
+        for u in usages.iter() {
+            stmt.execute(params![u.contract_id, u.feature, u.uses])?;
+        }
+        Ok(())
+    }
+
+    /// Share of solidity contracts using each feature by minor compiler version
+    pub fn feature_adoption(&self) -> Result<Vec<FeatureAdoption>> {
+        let mut stmt = self.conn.prepare(
+            r"WITH versions AS (
+                SELECT id, regexp_extract(json_extract_string(metadata, '$.CompilerVersion'), '(\d+\.\d+)', 1) AS version
+                FROM contract WHERE source_type != 'vyper'
+            ), totals AS (
+                SELECT version, count(*) AS total FROM versions GROUP BY version
+            )
+            SELECT v.version, f.feature, count(DISTINCT f.contract_id), any_value(t.total)
+            FROM feature_usage f JOIN versions v ON v.id = f.contract_id JOIN totals t ON t.version = v.version
+            GROUP BY v.version, f.feature
+            ORDER BY string_split(v.version, '.')::INTEGER[], f.feature",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(FeatureAdoption {
+                version: row.get(0)?,
+                feature: row.get(1)?,
+                contracts: row.get(2)?,
+                total: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Number of solidity contracts by compiler version, e.g. `0.8.19`, solc
+    /// versions need to be backfilled beforehand
+    pub fn contracts_by_compiler_version(&self) -> Result<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            r"SELECT coalesce(solc_version, '') AS version, count(*)
+            FROM contract WHERE source_type != 'vyper'
+            GROUP BY version ORDER BY version",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Counts and distributions of the stored contracts for a dataset card,
+    /// licenses need to be backfilled beforehand
+    pub fn dataset_stats(&self) -> Result<DatasetStats> {
+        let count =
+            |sql: &str| -> Result<u64> { Ok(self.conn.query_row(sql, [], |row| row.get(0))?) };
+        let groups = |sql: &str| -> Result<Vec<(String, u64)>> {
+            let mut stmt = self.conn.prepare(sql)?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        };
+
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT l.chain_id, count(DISTINCT c.id) AS contracts FROM contract c LEFT JOIN ({CONTRACT_LOCATIONS}) l ON l.contract_id = c.id GROUP BY l.chain_id ORDER BY contracts DESC, l.chain_id"),
+        )?;
+        let chains = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(DatasetStats {
+            snapshot_id: self.snapshot_id()?,
+            contracts: count("SELECT count(*) FROM contract")?,
+            functions: count("SELECT count(*) FROM function")?,
+            indexed_contracts: count("SELECT count(DISTINCT contract_id) FROM function")?,
+            occurrences: count("SELECT count(*) FROM contract_occurrence")?,
+            source_types: groups(
+                "SELECT source_type::varchar AS source_type, count(*) AS contracts FROM contract GROUP BY source_type ORDER BY contracts DESC, source_type",
+            )?,
+            licenses: groups(
+                "SELECT coalesce(nullif(license, ''), 'none') AS license, count(*) AS contracts FROM contract GROUP BY license ORDER BY contracts DESC, license",
+            )?,
+            compilers: groups(
+                r"SELECT CASE WHEN source_type = 'vyper' THEN 'vyper ' ELSE 'solc ' END || coalesce(regexp_extract(json_extract_string(metadata, '$.CompilerVersion'), '(\d+\.\d+)\.\d+', 1), '') AS compiler, count(*) AS contracts
+                FROM contract GROUP BY compiler ORDER BY contracts DESC, compiler",
+            )?,
+            languages: groups(
+                "SELECT split_part(entry, ':', 1) AS language, count(*) AS contracts FROM (SELECT unnest(string_split(languages, ',')) AS entry FROM contract) GROUP BY language ORDER BY contracts DESC, language",
+            )?,
+            mixed_language_contracts: count(
+                "SELECT count(*) FROM contract WHERE languages LIKE '%,%'",
+            )?,
+            chains,
+        })
+    }
+
+    pub fn findings(&self, kind: &str) -> Result<Vec<Finding>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, kind, name, severity, detail FROM finding WHERE kind = ? ORDER BY contract_id, name",
+        )?;
+        let rows = stmt.query_map([kind], |row| {
+            Ok(Finding {
+                contract_id: row.get(0)?,
+                kind: row.get(1)?,
+                name: row.get(2)?,
+                severity: row.get(3)?,
+                detail: row.get(4)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Number of contracts with findings of a kind, by finding name
+    pub fn count_findings(&self, kind: &str) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, count(*) AS contracts FROM finding WHERE kind = ? GROUP BY name ORDER BY contracts DESC, name",
+        )?;
+        let rows = stmt.query_map([kind], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_known_signatures(&self, signatures: &[KnownSignature]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO known_signature (kind, signature, hash) VALUES (?, ?, ?)",
+        )?;
+
+        for s in signatures.iter() {
+            stmt.execute(params![s.kind.to_string(), s.signature, s.hash])?;
+        }
+
+        Ok(())
+    }
+
+    /// Imported text signatures of the given kind with the given hash
+    pub fn known_signatures(&self, kind: SignatureKind, hash: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT signature FROM known_signature WHERE kind = ? AND hash = ? ORDER BY signature",
+        )?;
+        let rows = stmt.query_map(params![kind.to_string(), hash], |row| row.get(0))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_literals(&self, literals: &[FunctionLiteral]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO literal (function_id, contract_id, kind, value) VALUES (?, ?, ?, ?)",
+        )?;
+        for l in literals.iter() {
+            // allow error
+            let _ = stmt.insert(params![l.function_id, l.contract_id, l.kind, l.value]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_function_definitions(&self, definitions: &[FunctionDefinition]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO function_definition (id, contract_id, contract_name, kind, name, filename, signature) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for d in definitions.iter() {
+            stmt.execute(params![
+                d.id,
+                d.contract_id,
+                d.contract_name,
+                d.kind,
+                d.name,
+                d.filename,
+                d.signature
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn store_type_definitions(&self, definitions: &[TypeDefinition]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO type_definition (contract_id, filename, name, kind, members, source) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for d in definitions.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                d.contract_id,
+                d.filename,
+                d.name,
+                d.kind,
+                d.members,
+                d.source
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_assembly_blocks(&self, blocks: &[AssemblyBlock]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO assembly_block (function_id, contract_id, position, opcodes, source) VALUES (?, ?, ?, ?, ?)",
+        )?;
+        for b in blocks.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                b.function_id,
+                b.contract_id,
+                b.position,
+                b.opcodes,
+                b.source
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_external_calls(&self, calls: &[ExternalCall]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO external_call (function_id, contract_id, position, kind, target, target_kind, member) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for c in calls.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                c.function_id,
+                c.contract_id,
+                c.position,
+                c.kind,
+                c.target,
+                c.target_kind,
+                c.member
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_modifiers(&self, modifiers: &[ContractModifier]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO modifier (contract_id, source_path, contract_name, modifier_name, ast_id, source_code) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for m in modifiers.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                m.contract_id,
+                m.source_path,
+                m.contract_name,
+                m.modifier_name,
+                m.ast_id,
+                m.source_code
+            ]);
+        }
+
+        Ok(())
+    }
+
+    pub fn store_function_modifiers(&self, modifiers: &[FunctionModifier]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO function_modifier (function_id, contract_id, position, modifier_name, invocation, declaration_id) VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for m in modifiers.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                m.function_id,
+                m.contract_id,
+                m.position,
+                m.modifier_name,
+                m.invocation,
+                m.declaration_id
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// Indexed functions invoking the modifier, with the invocation and the
+    /// source code of the invoked definition, empty when it is unknown.
+    /// Invocations indexed before their declaration was stored are resolved by
+    /// the modifier name.
+    pub fn functions_with_modifier(
+        &self,
+        modifier_name: &str,
+    ) -> Result<Vec<(ContractFunction, FunctionModifier, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r"SELECT f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector, m.position, m.modifier_name, m.invocation, m.declaration_id,
+                coalesce((SELECT first(d.source_code ORDER BY d.source_path, d.contract_name) FROM modifier d WHERE d.contract_id = m.contract_id AND CASE WHEN m.declaration_id IS NULL THEN d.modifier_name = m.modifier_name ELSE d.ast_id = m.declaration_id END), '')
+            FROM function_modifier m JOIN ({INDEXED_FUNCTIONS}) f ON f.id = m.function_id
+            WHERE m.modifier_name = ?
+            ORDER BY f.contract_id, f.contract_name, f.signature, m.function_id"
+        ))?;
+        let rows = stmt.query_map([modifier_name], |row| {
+            let function = ContractFunction {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                filename: row.get(4)?,
+                signature: row.get(5)?,
+                selector: row.get(6)?,
+                source_code: "".into(),
+            };
+            let modifier = FunctionModifier {
+                function_id: function.id.clone(),
+                contract_id: function.contract_id.clone(),
+                position: row.get(7)?,
+                modifier_name: row.get(8)?,
+                invocation: row.get(9)?,
+                declaration_id: row.get(10)?,
+            };
+            Ok((function, modifier, row.get(11)?))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_state_variables(&self, variables: &[StateVariable]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO state_variable (contract_id, source_path, contract_name, name, type, visibility, mutability) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for v in variables.iter() {
+            // allow error
+            let _ = stmt.insert(params![
+                v.contract_id,
+                v.source_path,
+                v.contract_name,
+                v.name,
+                v.ty,
+                v.visibility,
+                v.mutability
+            ]);
+        }
+
+        Ok(())
+    }
+
+    /// State variables with the name, optionally of a type, e.g. `address`,
+    /// with their slot and offset in the storage layout of the declaring
+    /// contract, `None` for constants, immutables and contracts without layout
+    pub fn state_variables(
+        &self,
+        name: &str,
+        ty: Option<&str>,
+    ) -> Result<Vec<(StateVariable, Option<Slot>)>> {
+        let mut stmt = self.conn.prepare(
+            r"SELECT v.contract_id, v.source_path, v.contract_name, v.name, v.type, v.visibility, v.mutability, s.slot, s.byte_offset
+            FROM state_variable v LEFT JOIN storage_slot s
+                ON s.contract_id = v.contract_id AND s.source_path = v.source_path AND s.contract_name = v.contract_name AND s.label = v.name AND v.mutability = 'mutable'
+            WHERE v.name = ? AND (? IS NULL OR v.type = ?)
+            ORDER BY v.contract_id, v.source_path, v.contract_name",
+        )?;
+        let rows = stmt.query_map(params![name, ty, ty], |row| {
+            let variable = StateVariable {
+                contract_id: row.get(0)?,
+                source_path: row.get(1)?,
+                contract_name: row.get(2)?,
+                name: row.get(3)?,
+                ty: row.get(4)?,
+                visibility: row.get(5)?,
+                mutability: row.get(6)?,
+            };
+            let slot: Option<String> = row.get(7)?;
+            let offset: Option<i64> = row.get(8)?;
+            Ok((variable, slot.zip(offset)))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_opcode_signatures(&self, signatures: &[OpcodeSignature]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO opcode_signature (contract_id, contract_name, opcodes, signature) VALUES (?, ?, ?, ?)",
+        )?;
+        for s in signatures.iter() {
+            stmt.execute(params![
+                s.contract_id,
+                s.contract_name,
+                s.opcodes,
+                s.signature
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn opcode_signatures(&self) -> Result<Vec<OpcodeSignature>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, contract_name, opcodes, signature FROM opcode_signature",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(OpcodeSignature {
+                contract_id: row.get(0)?,
+                contract_name: row.get(1)?,
+                opcodes: row.get(2)?,
+                signature: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_package_releases(&self, releases: &[PackageRelease]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO package_release (contract_id, registry, package, version) VALUES (?, ?, ?, ?)",
+        )?;
+        for r in releases.iter() {
+            stmt.execute(params![r.contract_id, r.registry, r.package, r.version])?;
+        }
+
+        Ok(())
+    }
+
+    /// Stored versions of a package, e.g. `@openzeppelin/contracts` on `npm`
+    pub fn package_versions(&self, registry: &str, package: &str) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT version FROM package_release WHERE registry = ? AND package = ?")?;
+        let rows = stmt.query_map([registry, package], |row| row.get(0))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn store_plugin_rows(&self, rows: &[PluginRow]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO plugin_row (plugin, contract_id, table_name, row) VALUES (?, ?, ?, ?)",
+        )?;
+        for r in rows.iter() {
+            stmt.execute(params![r.plugin, r.contract_id, r.table_name, r.row])?;
+        }
+
+        Ok(())
+    }
+
+    /// The external calls of the functions of a contract, in source order
+    pub fn contract_external_calls(&self, contract_id: &str) -> Result<Vec<ExternalCall>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT function_id, contract_id, position, kind, target, target_kind, member FROM external_call WHERE contract_id = ? ORDER BY function_id, position",
+        )?;
+        let rows = stmt.query_map([contract_id], |row| {
+            Ok(ExternalCall {
+                function_id: row.get(0)?,
+                contract_id: row.get(1)?,
+                position: row.get(2)?,
+                kind: row.get(3)?,
+                target: row.get(4)?,
+                target_kind: row.get(5)?,
+                member: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// External calls of the kind, e.g. `delegatecall`, optionally restricted
+    /// to a kind of target, e.g. `state`, with the indexed function making them
+    pub fn external_calls(
+        &self,
+        kind: &str,
+        target_kind: Option<&str>,
+    ) -> Result<Vec<(ContractFunction, ExternalCall)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r"SELECT f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector, c.position, c.kind, c.target, c.target_kind, c.member
+            FROM external_call c JOIN ({INDEXED_FUNCTIONS}) f ON f.id = c.function_id
+            WHERE c.kind = ? AND (? IS NULL OR c.target_kind = ?)
+            ORDER BY f.contract_id, f.contract_name, f.signature, c.position"
+        ))?;
+        let rows = stmt.query_map(params![kind, target_kind, target_kind], |row| {
+            let function = ContractFunction {
+                id: row.get(0)?,
+                contract_id: row.get(1)?,
+                contract_name: row.get(2)?,
+                function_name: row.get(3)?,
+                filename: row.get(4)?,
+                signature: row.get(5)?,
+                selector: row.get(6)?,
+                source_code: "".into(),
+            };
+            let call = ExternalCall {
+                function_id: function.id.clone(),
+                contract_id: function.contract_id.clone(),
+                position: row.get(7)?,
+                kind: row.get(8)?,
+                target: row.get(9)?,
+                target_kind: row.get(10)?,
+                member: row.get(11)?,
+            };
+            Ok((function, call))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Indexed functions with an assembly block calling the lowercase opcode,
+    /// with the opcodes of all their assembly blocks
+    pub fn functions_with_assembly(&self, opcode: &str) -> Result<Vec<(ContractFunction, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r"SELECT f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector, string_agg(a.opcodes, ',' ORDER BY a.position)
+            FROM assembly_block a JOIN ({INDEXED_FUNCTIONS}) f ON f.id = a.function_id
+            WHERE f.id IN (SELECT function_id FROM assembly_block WHERE list_contains(string_split(opcodes, ','), ?))
+            GROUP BY f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector
+            ORDER BY f.contract_id, f.contract_name, f.signature"
+        ))?;
+        let rows = stmt.query_map([opcode], |row| {
+            Ok((
+                ContractFunction {
+                    id: row.get(0)?,
+                    contract_id: row.get(1)?,
+                    contract_name: row.get(2)?,
+                    function_name: row.get(3)?,
+                    filename: row.get(4)?,
+                    signature: row.get(5)?,
+                    selector: row.get(6)?,
+                    source_code: "".into(),
+                },
+                row.get(7)?,
+            ))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Type definitions by canonical name, or by name within any contract. The
+    /// name is matched literally, `%` and `_` are no wildcards.
+    pub fn type_definitions(&self, name: &str) -> Result<Vec<TypeDefinition>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, filename, name, kind, members, source FROM type_definition WHERE name = ? OR suffix(name, '.' || ?) ORDER BY name, contract_id, filename",
+        )?;
+        let rows = stmt.query_map([name, name], |row| {
+            Ok(TypeDefinition {
+                contract_id: row.get(0)?,
+                filename: row.get(1)?,
+                name: row.get(2)?,
+                kind: row.get(3)?,
+                members: row.get(4)?,
+                source: row.get(5)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Indexed functions using the literal, addresses are matched case insensitively
+    pub fn functions_with_literal(&self, value: &str) -> Result<Vec<(ContractFunction, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT DISTINCT f.id, f.contract_id, f.contract_name, f.function_name, f.filename, f.signature, f.selector, l.kind FROM literal l JOIN ({INDEXED_FUNCTIONS}) f ON f.id = l.function_id WHERE l.value = ? OR (l.kind = 'address' AND l.value = lower(?)) ORDER BY f.contract_id, f.contract_name, f.signature"
+        ))?;
+        let rows = stmt.query_map([value, value], |row| {
+            Ok((
+                ContractFunction {
+                    id: row.get(0)?,
+                    contract_id: row.get(1)?,
+                    contract_name: row.get(2)?,
+                    function_name: row.get(3)?,
+                    filename: row.get(4)?,
+                    signature: row.get(5)?,
+                    selector: row.get(6)?,
+                    source_code: "".into(),
+                },
+                row.get(7)?,
+            ))
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Contracts embedding the lowercase `0x` prefixed address, as a literal of
+    /// an indexed function, an immutable value or a constructor argument
+    pub fn address_references(&self, address: &str) -> Result<Vec<AddressReference>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r"SELECT f.contract_id, f.contract_name, 'literal' AS origin, f.signature AS name
+            FROM literal l JOIN ({INDEXED_FUNCTIONS}) f ON f.id = l.function_id
+            WHERE l.kind = 'address' AND l.value = ?
+            UNION
+            SELECT contract_id, contract_name, 'immutable', name
+            FROM immutable_value WHERE lower(value) = ?
+            UNION
+            SELECT contract_id, contract_name, 'constructor', name
+            FROM constructor_argument WHERE type = 'address' AND lower(value) = ?
+            ORDER BY contract_id, contract_name, origin, name"
+        ))?;
+        let rows = stmt.query_map([address, address, address], |row| {
+            Ok(AddressReference {
+                contract_id: row.get(0)?,
+                contract_name: row.get(1)?,
+                origin: row.get(2)?,
+                name: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Store everything extracted from a chunk of compiled contracts
+    pub fn store_index_output(&self, output: &IndexOutput) -> Result<()> {
+        // user defined value types are needed to check the signatures
+        self.store_type_definitions(&output.type_definitions)?;
+        let quarantined = self.store_checked_functions(&output.functions)?;
+        if quarantined > 0 {
+            warn!("Quarantined {quarantined} functions with an invalid signature or selector");
+        }
+        self.store_constructor_arguments(&output.constructor_arguments)?;
+        self.store_sizes(&output.artifact_sizes, &output.function_sizes)?;
+        self.store_artifact_bytecodes(&output.bytecodes)?;
+        self.store_abis(&output.abis)?;
+        self.store_source_maps(&output.source_maps, &output.source_file_ids)?;
+        self.store_metadata_hashes(&output.metadata_hashes)?;
+        self.store_storage_slots(&output.storage_slots)?;
+        self.store_storage_layouts(&output.storage_layouts)?;
+        self.store_events(&output.events)?;
+        self.store_errors(&output.errors)?;
+        self.store_clone_factories(&output.clone_factories)?;
+        self.store_literals(&output.literals)?;
+        self.store_function_definitions(&output.function_definitions)?;
+        self.store_assembly_blocks(&output.assembly_blocks)?;
+        self.store_external_calls(&output.external_calls)?;
+        self.store_modifiers(&output.modifiers)?;
+        self.store_function_modifiers(&output.function_modifiers)?;
+        self.store_state_variables(&output.state_variables)?;
+        self.store_opcode_signatures(&output.opcode_signatures)?;
+        self.store_source_asts(&output.source_asts)?;
+        self.store_plugin_rows(&output.plugin_rows)?;
+        self.clear_compile_errors(&output.compiled)?;
+        self.store_compile_errors(&output.compile_errors)?;
+        self.store_compiler_fallbacks(&output.compiler_fallbacks)
+    }
+
+    pub fn store_compiler_fallbacks(&self, fallbacks: &[CompilerFallback]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO compiler_fallback (contract_id, recorded_version, compiled_version) VALUES (?, ?, ?)",
+        )?;
+        for f in fallbacks.iter() {
+            stmt.execute([&f.contract_id, &f.recorded_version, &f.compiled_version])?;
+        }
+
+        Ok(())
+    }
+
+    /// Solc version a contract failing with its recorded version compiled with
+    pub fn compiler_fallback(&self, contract_id: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT compiled_version FROM compiler_fallback WHERE contract_id = ?")?;
+        let mut rows = stmt.query([contract_id])?;
+
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get(0)?),
+            None => None,
+        })
+    }
+
+    /// Record failed compilations, replacing the previous failure of the
+    /// contract with the same compiler version
+    pub fn store_compile_errors(&self, errors: &[CompileError]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR REPLACE INTO compile_error (contract_id, compiler_version, error, failed_at) VALUES (?, ?, ?, current_timestamp)",
+        )?;
+        for e in errors.iter() {
+            stmt.execute([&e.contract_id, &e.compiler_version, &e.error])?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the compile errors of contracts which compiled since
+    pub fn clear_compile_errors(&self, contract_ids: &[String]) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM compile_error WHERE contract_id = ?")?;
+        for id in contract_ids.iter() {
+            stmt.execute([id])?;
+        }
+
+        Ok(())
+    }
+
+    /// The recorded compile errors, most recent first
+    pub fn compile_errors(&self) -> Result<Vec<CompileError>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id, compiler_version, error FROM compile_error ORDER BY failed_at DESC, contract_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(CompileError {
+                contract_id: row.get(0)?,
+                compiler_version: row.get(1)?,
+                error: row.get(2)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::abi::decode_hex;
+
+    fn function(contract_id: &str, contract_name: &str, selector: &str) -> ContractFunction {
+        ContractFunction {
+            id: format!("{contract_id}{contract_name}{selector}"),
+            contract_id: contract_id.into(),
+            contract_name: contract_name.into(),
+            function_name: "f".into(),
+            filename: "main.sol".into(),
+            signature: "f()".into(),
+            selector: selector.into(),
+            source_code: "".into(),
+        }
+    }
+
+    #[test]
+    fn find_contracts_by_selectors() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a'), ('b');")?;
+        storage.store_functions(&[
+            function("a", "Token", "0x70a08231"),
+            function("a", "Token", "0xa9059cbb"),
+            function("b", "Other", "0x70a08231"),
+        ])?;
+
+        let found = storage.contracts_conforming_to(
+            &["0x70a08231".into(), "0xa9059cbb".into()],
+            &[],
+            &[],
+        )?;
+        assert_eq!(found, vec![("a".to_string(), "Token".to_string())]);
+
+        // contracts without the custom error of the interface do not conform
+        let error = |contract_id: &str, contract_name: &str| ContractError {
+            id: format!("{contract_id}-InsufficientBalance"),
+            contract_id: contract_id.into(),
+            contract_name: contract_name.into(),
+            error_name: "InsufficientBalance".into(),
+            signature: "InsufficientBalance(uint256)".into(),
+            selector: "0xcf479181".into(),
+            source_code: None,
+        };
+        storage.store_errors(&[error("b", "Other")])?;
+        let found =
+            storage.contracts_conforming_to(&["0x70a08231".into()], &[], &["0xcf479181".into()])?;
+        assert_eq!(found, vec![("b".to_string(), "Other".to_string())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_storage_slots_by_type_and_slot() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let slot = |label: &str, slot: &str, ty: &str, encoding: &str| StorageSlot {
+            contract_id: "a".into(),
+            source_path: "main.sol".into(),
+            contract_name: "Token".into(),
+            label: label.into(),
+            slot: slot.into(),
+            offset: 0,
+            ty: ty.into(),
+            type_id: "t_uint256".into(),
+            encoding: encoding.into(),
+        };
+        storage.store_storage_slots(&[
+            slot("balances", "0", "mapping(address => uint256)", "mapping"),
+            slot("owner", "1", "address", "inplace"),
+        ])?;
+
+        let found =
+            storage.search_storage_slots(Some("mapping(address => uint256)"), Some("0"), &[])?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].label, "balances");
+
+        let found = storage.search_storage_slots(None, Some("1"), &["mapping"])?;
+        assert!(found.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_metadata_hashes() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let hash = |name: &str| MetadataHash {
+            contract_id: "a".into(),
+            contract_name: name.into(),
+            metadata_keccak: "0x00".into(),
+            metadata_hash: Some("ipfs:0x1220aa".into()),
+        };
+        storage.store_metadata_hashes(&[hash("A"), hash("B")])?;
+        assert_eq!(storage.metadata_match_count()?, (0, 0));
+
+        assert!(storage.store_metadata_verification(&hash("A"), Some("ipfs:0x1220aa"))?);
+        assert!(!storage.store_metadata_verification(&hash("B"), Some("ipfs:0x1220bb"))?);
+        assert_eq!(storage.metadata_match_count()?, (1, 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_contracts_by_chain() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let deployed = |content: &str, chain_id, address: &str| {
+            let mut contract = PlainContract::single_file(content);
+            contract.metadata.chain_id = chain_id;
+            contract.metadata.address = Some(address.into());
+            contract
+        };
+        storage.store_contracts(vec![
+            deployed("contract A {}", Some(1), "0x01"),
+            deployed("contract B {}", Some(137), "0x01"),
+            deployed("contract C {}", Some(137), "0x02"),
+            deployed("contract D {}", None, "0x03"),
+        ])?;
+
+        let polygon = storage.contracts_on_chain(137, None)?;
+        assert_eq!(
+            polygon.iter().map(|c| c.2.as_deref()).collect_vec(),
+            vec![Some("0x01"), Some("0x02")]
+        );
+        let found = storage.contracts_on_chain(1, Some("0x01"))?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, PlainContract::single_file("contract A {}").id());
+        assert!(storage.contracts_on_chain(10, None)?.is_empty());
+
+        // the same source read again on another chain keeps the first one
+        let a = deployed("contract A {}", Some(10), "0x0a");
+        storage.store_contracts(vec![a.clone()])?;
+        storage.store_occurrences(&[ContractOccurrence {
+            contract_id: a.id(),
+            dataset: "optimism".into(),
+            path: "10/0x0a".into(),
+            chain_id: Some(10),
+            address: Some("0x0a".into()),
+        }])?;
+        let optimism = storage.contracts_on_chain(10, None)?;
+        assert_eq!(optimism.len(), 1);
+        assert_eq!(optimism[0].0, a.id());
+        assert_eq!(optimism[0].2.as_deref(), Some("0x0a"));
+        assert_eq!(storage.contracts_on_chain(1, Some("0x01"))?, found);
+
+        Ok(())
+    }
+
+    #[test]
+    fn match_code_without_metadata_and_immutables() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let code = |immutable: &str, hash: &str| {
+            format!(
+                "0x7f{}5b00a264697066735822{}64736f6c63430008130033",
+                immutable.repeat(32),
+                hash.repeat(34)
+            )
+        };
+        storage.store_artifact_bytecodes(&[ArtifactBytecode {
+            contract_id: "a".into(),
+            source_path: "src/Token.sol".into(),
+            contract_name: "Token".into(),
+            creation_code: "0x".into(),
+            runtime_code: code("00", "11"),
+        }])?;
+
+        let deployed = decode_hex(&code("ab", "22"))?;
+        let found = storage.exact_code_match("0x", &skeleton_hash(&deployed))?;
+        assert_eq!(found, Some(("a".to_string(), "Token".to_string())));
+        assert_eq!(
+            storage.exact_code_match("0x", &skeleton_hash(&[0x00]))?,
+            None
+        );
+
+        // artifacts stored before their hash was recorded
+        storage.conn.execute_batch(
+            "DELETE FROM artifact_code_hash; UPDATE artifact_bytecode SET contract_id = 'b'",
+        )?;
+        assert_eq!(
+            storage.exact_code_match("0x", &skeleton_hash(&deployed))?,
+            None
+        );
+        assert_eq!(storage.backfill_skeleton_hashes()?, 1);
+        assert_eq!(storage.backfill_skeleton_hashes()?, 0);
+        let found = storage.exact_code_match("0x", &skeleton_hash(&deployed))?;
+        assert_eq!(found, Some(("b".to_string(), "Token".to_string())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn link_bytecode_to_verified_contract() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage.store_bytecodes(&[
+            DeployedBytecode::new(1, "0x01", &[0x60, 0x80], None),
+            DeployedBytecode::new(1, "0x02", &[0x60, 0x80], None),
+            DeployedBytecode::new(10, "0x02", &[0x00], None),
+        ])?;
+        assert_eq!(storage.count_bytecodes()?, (3, 0));
+
+        let mut contract = PlainContract::single_file("contract A {}");
+        contract.metadata.chain_id = Some(1);
+        contract.metadata.address = Some("0x02".into());
+        let id = contract.id();
+        storage.store_contracts(vec![contract])?;
+        assert_eq!(storage.count_bytecodes()?, (3, 1));
+        let linked: String = storage.conn.query_row(
+            "SELECT contract_id FROM bytecode WHERE chain_id = 1 AND address = '0x02'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(linked, id);
+
+        // fetching the code again keeps its contract
+        storage.store_bytecodes(&[DeployedBytecode::new(1, "0x02", &[0x60], None)])?;
+        assert_eq!(storage.count_bytecodes()?, (3, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_ingested_paths() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage.record_ingested(&[
+            ("contracts/a".into(), "1".into()),
+            ("contracts/b".into(), "2".into()),
+        ])?;
+        // a path read again is recorded once
+        storage.record_ingested(&[("contracts/a".into(), "3".into())])?;
+
+        assert_eq!(
+            storage.ingested_paths()?,
+            HashSet::from(["contracts/a".to_string(), "contracts/b".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_and_clear_compile_errors() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let error = |contract_id: &str, error: &str| CompileError {
+            contract_id: contract_id.into(),
+            compiler_version: "v0.8.19+commit.7dd6d404".into(),
+            error: error.into(),
+        };
+        storage
+            .store_compile_errors(&[error("a", "stack too deep"), error("b", "parser error")])?;
+        // a contract failing again keeps its latest error
+        storage.store_compile_errors(&[error("a", "missing import")])?;
+        assert_eq!(storage.compile_errors()?.len(), 2);
+        assert!(storage
+            .compile_errors()?
+            .contains(&error("a", "missing import")));
+
+        storage.clear_compile_errors(&["b".into()])?;
+        assert_eq!(
+            storage.compile_errors()?,
+            vec![error("a", "missing import")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn select_rows_since_release() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        storage.store_functions(&[function("a", "Token", "0x70a08231")])?;
+        let first = storage.snapshot_id()?;
+        assert!(!storage.has_release(&first)?);
+        let ids = |rows: Vec<(ContractFunction, String)>| {
+            rows.into_iter().map(|(f, _)| f.id).collect_vec()
+        };
+        let released = ids(storage.functions_since(None, "", 10)?);
+        storage.record_release(&first, None, &["a".into()], &released)?;
+        assert!(storage.has_release(&first)?);
+
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('b');")?;
+        storage.store_functions(&[
+            function("a", "Token", "0xa9059cbb"),
+            function("b", "Other", "0x70a08231"),
+        ])?;
+        let since = storage.functions_since(Some(&first), "", 10)?;
+        assert_eq!(
+            since.iter().map(|(f, _)| f.id.as_str()).collect_vec(),
+            ["aToken0xa9059cbb", "bOther0x70a08231"]
+        );
+        let page = storage.functions_since(Some(&first), "aToken0xa9059cbb", 10)?;
+        assert_eq!(page.len(), 1);
+        assert_eq!(storage.functions_since(None, "", 10)?.len(), 3);
+        assert!(storage
+            .functions_since(Some(&first), "bOther0x70a08231", 10)?
+            .is_empty());
+
+        // a delta release only records its own rows, the rows of the release
+        // it was exported since are found through it
+        let second = storage.snapshot_id()?;
+        let released = ids(storage.functions_since(Some(&first), "", 10)?);
+        storage.record_release(&second, Some(&first), &["b".into()], &released)?;
+        let entries: u64 = storage.conn.query_row(
+            "SELECT count(*) FROM release_entry WHERE snapshot_id = ?",
+            [&second],
+            |row| row.get(0),
+        )?;
+        assert_eq!(entries, 3);
+        assert!(storage.functions_since(Some(&second), "", 10)?.is_empty());
+        assert!(storage.contracts_since(Some(&second), "", 10)?.is_empty());
+
+        let c = PlainContract::single_file("contract C {}");
+        storage.store_contracts(vec![c.clone()])?;
+        let third = storage.snapshot_id()?;
+        assert_eq!(
+            storage
+                .contracts_since(Some(&second), "", 10)?
+                .into_iter()
+                .map(|(id, _, _)| id)
+                .collect_vec(),
+            [c.id()]
+        );
+        // a contract left out by the license filter is not released
+        storage.record_release(&third, Some(&second), &[], &[])?;
+        assert!(storage.has_release(&third)?);
+        assert_eq!(storage.contracts_since(Some(&third), "", 10)?.len(), 1);
+        storage.record_release(&third, Some(&second), &[c.id()], &[])?;
+        assert!(storage.contracts_since(Some(&third), "", 10)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_contract_occurrences() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let occurrence = |dataset: &str, path: &str, address: Option<&str>| ContractOccurrence {
+            contract_id: "1".into(),
+            dataset: dataset.into(),
+            path: path.into(),
+            chain_id: address.map(|_| 1),
+            address: address.map(String::from),
+        };
+        storage.store_occurrences(&[
+            occurrence("etherscan", "etherscan/0x01", Some("0x01")),
+            occurrence("etherscan", "etherscan/0x02", Some("0x02")),
+            occurrence("contracts", "contracts/A", None),
+        ])?;
+        // a path read again is recorded once
+        storage.store_occurrences(&[occurrence("contracts", "contracts/A", None)])?;
+
+        let occurrences = storage.occurrences("1")?;
+        assert_eq!(occurrences.len(), 3);
+        assert_eq!(occurrences[0], occurrence("contracts", "contracts/A", None));
+        assert_eq!(occurrences[2].address.as_deref(), Some("0x02"));
+        assert!(storage.occurrences("2")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_addresses_to_contracts() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let address = |chain_id, contract_id: &str| ContractAddress {
+            chain_id,
+            address: "0x01".into(),
+            contract_id: contract_id.into(),
+        };
+        storage.store_addresses(&[address(137, "b"), address(1, "a")])?;
+        // an address is mapped to the contract stored first
+        storage.store_addresses(&[address(1, "c")])?;
+
+        assert_eq!(
+            storage.contracts_at("0x01")?,
+            vec![address(1, "a"), address(137, "b")]
+        );
+        assert!(storage.contracts_at("0x02")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_deployments_by_deployer() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let deployment = |address: &str, block_number| Deployment {
+            chain_id: 1,
+            address: address.into(),
+            contract_id: None,
+            deployer: "0xde".into(),
+            tx_hash: "0x00".into(),
+            block_number,
+            implementation: None,
+        };
+        storage.store_deployments(&[
+            deployment("0x02", Some(2)),
+            deployment("0x01", Some(1)),
+            // the same address on another chain is another deployment
+            Deployment {
+                chain_id: 10,
+                ..deployment("0x01", Some(1))
+            },
+        ])?;
+
+        let deployments = storage.deployments_by("0xde")?;
+        assert_eq!(
+            deployments
+                .iter()
+                .map(|d| (d.chain_id, d.address.as_str()))
+                .collect_vec(),
+            vec![(1, "0x01"), (1, "0x02"), (10, "0x01")]
+        );
+        assert!(storage.deployments_by("0xff")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_clones_by_implementation() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let deployment =
+            |address: &str, contract_id: Option<&str>, implementation: Option<&str>| Deployment {
+                chain_id: 1,
+                address: address.into(),
+                contract_id: contract_id.map(String::from),
+                deployer: "0xde".into(),
+                tx_hash: "0x00".into(),
+                block_number: None,
+                implementation: implementation.map(String::from),
+            };
+        storage.store_deployments(&[
+            deployment("0x01", Some("a"), None),
+            deployment("0x02", None, Some("0x01")),
+            deployment("0x03", None, Some("0x01")),
+            deployment("0x04", None, Some("0x09")),
+            // a clone of the same address on another chain
+            Deployment {
+                chain_id: 10,
+                ..deployment("0x05", None, Some("0x01"))
+            },
+        ])?;
+
+        let groups = storage.clone_groups(10)?;
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].implementation, "0x01");
+        assert_eq!(groups[0].contract_id.as_deref(), Some("a"));
+        assert_eq!(groups[0].clones, 2);
+        assert_eq!(groups[1].contract_id, None);
+        assert_eq!(
+            (groups[2].chain_id, groups[2].contract_id.as_deref()),
+            (10, None)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn page_contracts() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage.store_contracts(vec![
+            PlainContract::single_file("contract A {}"),
+            PlainContract::single_file("contract A { uint x; }"),
+            PlainContract::single_file("contract A { uint y; }"),
+        ])?;
+
+        assert_eq!(storage.get_contracts(0, 2)?.len(), 2);
+        assert_eq!(storage.get_contracts(2, 2)?.len(), 1);
+        assert!(storage.get_contracts(3, 2)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn quarantine_mismatching_selectors() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        storage.store_type_definitions(&[TypeDefinition {
+            contract_id: "a".into(),
+            filename: "main.sol".into(),
+            name: "Vault.Price".into(),
+            kind: "type".into(),
+            members: "uint128".into(),
+            source: "type Price is uint128;".into(),
+        }])?;
+        let function = |id: &str, signature: &str, selector: &str| ContractFunction {
+            id: id.into(),
+            contract_id: "a".into(),
+            contract_name: "Vault".into(),
+            function_name: signature.split('(').next().unwrap_or_default().into(),
+            filename: "main.sol".into(),
+            signature: signature.into(),
+            selector: selector.into(),
+            source_code: "".into(),
+        };
+        let quarantined = storage.store_checked_functions(&[
+            function("1", "transfer(address to, uint amount)", "0xA9059CBB"),
+            function("2", "setPrice(Price)", &selector("setPrice(uint128)")),
+            function("3", "approve(address,uint256)", "0xa9059cbb"),
+            function("4", "broken(uint256", "0x00000000"),
+        ])?;
+        assert_eq!(quarantined, 2);
+
+        let stored = storage
+            .conn
+            .prepare("SELECT signature, selector FROM function ORDER BY id")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        assert_eq!(
+            stored,
+            [
+                ("transfer(address,uint256)".into(), "0xa9059cbb".into()),
+                ("setPrice(uint128)".into(), selector("setPrice(uint128)")),
+            ]
+        );
+        let reasons = storage
+            .conn
+            .prepare("SELECT id, reason FROM function_quarantine ORDER BY id")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(reasons[0].0, "3");
+        assert!(reasons[0].1.contains("0x095ea7b3"));
+        assert_eq!(reasons[1].0, "4");
+
+        Ok(())
+    }
+
+    #[test]
+    fn store_versioned_artifacts() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let artifact = |solc_version: &str, deployment_gas| VersionedArtifact {
+            contract_id: "a".into(),
+            contract_name: "Vault".into(),
+            solc_version: solc_version.into(),
+            runtime_size: 100,
+            creation_size: 120,
+            runtime_code_hash: "0x01".into(),
+            deployment_gas,
+        };
+        let estimate = |solc_version: &str, gas| VersionedGasEstimate {
+            contract_id: "a".into(),
+            contract_name: "Vault".into(),
+            solc_version: solc_version.into(),
+            signature: "deposit()".into(),
+            gas,
+        };
+        storage.store_versioned(
+            &[artifact("0.7.6", Some(90000)), artifact("0.8.26", None)],
+            &[estimate("0.7.6", Some(2415)), estimate("0.8.26", None)],
+        )?;
+        // compiling again replaces the rows of the version
+        storage.store_versioned(&[artifact("0.8.26", Some(80000))], &[])?;
+
+        assert_eq!(storage.versioned_solc_versions("a")?, ["0.7.6", "0.8.26"]);
+        let gas = storage
+            .conn
+            .prepare("SELECT deployment_gas FROM versioned_artifact ORDER BY solc_version")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<Option<u64>>, _>>()?;
+        assert_eq!(gas, [Some(90000), Some(80000)]);
+        let gas = storage
+            .conn
+            .prepare("SELECT gas FROM versioned_gas_estimate ORDER BY solc_version")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<Option<u64>>, _>>()?;
+        assert_eq!(gas, [Some(2415), None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_solc_versions() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let compiled_with = |compiler_version: &str, content: &str| {
+            let mut contract = PlainContract::single_file(content);
+            contract.metadata.compiler_version = compiler_version.into();
+            contract
+        };
+        storage.store_contracts(vec![
+            compiled_with("v0.8.20-nightly.2023.3.20+commit.e3ecd7c5", "contract A {}"),
+            compiled_with("v0.8.20+commit.a1b79de6", "contract A { uint x; }"),
+            compiled_with("v0.4.24+commit.e67f0147", "contract A { uint y; }"),
+        ])?;
+        assert_eq!(
+            storage.contracts_by_compiler_version()?,
+            [("0.4.24".to_string(), 1), ("0.8.20".to_string(), 2)]
+        );
+
+        storage
+            .conn
+            .execute_batch("UPDATE contract SET solc_version = NULL")?;
+        assert_eq!(storage.backfill_solc_versions()?, 3);
+        assert_eq!(storage.backfill_solc_versions()?, 0);
+        assert_eq!(storage.contracts_by_compiler_version()?.len(), 2);
+
+        let keccak_id = compiled_with("v0.8.20+commit.a1b79de6", "contract A {}").keccak_id()?;
+        assert_eq!(storage.contract_ids_by_keccak_id(&keccak_id)?.len(), 1);
+        storage
+            .conn
+            .execute_batch("UPDATE contract SET keccak_id = NULL")?;
+        assert!(storage.contract_ids_by_keccak_id(&keccak_id)?.is_empty());
+        assert_eq!(storage.backfill_keccak_ids()?, 3);
+        assert_eq!(
+            storage.contract_ids_by_keccak_id(&keccak_id)?,
+            [compiled_with("v0.4.24+commit.e67f0147", "contract A {}").id()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn store_artifacts_of_same_named_contracts() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let bytecode = |source_path: &str, code: &str| ArtifactBytecode {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            creation_code: code.into(),
+            runtime_code: code.into(),
+        };
+        storage.store_artifact_bytecodes(&[
+            bytecode("src/v1/Token.sol", "0x01"),
+            bytecode("src/v2/Token.sol", "0x02"),
+        ])?;
+        let source_map = |source_path: &str| ArtifactSourceMap {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            creation_source_map: None,
+            runtime_source_map: Some("0:1:0".into()),
+        };
+        storage.store_source_maps(
+            &[
+                source_map("src/v1/Token.sol"),
+                source_map("src/v2/Token.sol"),
+            ],
+            &[],
+        )?;
+        let abi = |source_path: &str| ContractAbi {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            abi_json: "[]".into(),
+        };
+        storage.store_abis(&[abi("src/v1/Token.sol"), abi("src/v2/Token.sol")])?;
+        let layout = |source_path: &str| StorageLayoutJson {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            types_json: "{}".into(),
+        };
+        storage.store_storage_layouts(&[layout("src/v1/Token.sol"), layout("src/v2/Token.sol")])?;
+        let size = |source_path: &str, runtime_size| ArtifactSize {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            runtime_size,
+            creation_size: runtime_size,
+        };
+        let function_size = |source_path: &str, size| FunctionSize {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Token".into(),
+            function_name: "Token.transfer".into(),
+            size,
+        };
+        storage.store_sizes(
+            &[size("src/v1/Token.sol", 100), size("src/v2/Token.sol", 200)],
+            &[
+                function_size("src/v1/Token.sol", 10),
+                function_size("src/v2/Token.sol", 20),
+            ],
+        )?;
+        let sizes = storage.artifacts_larger_than(0)?;
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(
+            (sizes[0].source_path.as_str(), sizes[0].runtime_size),
+            ("src/v2/Token.sol", 200)
+        );
+        let functions = storage.largest_functions(&sizes[1], 10)?;
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].size, 10);
+
+        for table in [
+            "artifact_bytecode",
+            "source_map",
+            "abi",
+            "storage_layout",
+            "artifact",
+            "function_size",
+        ] {
+            let count: u64 = storage.conn.query_row(
+                &format!(
+                    "SELECT count(DISTINCT source_path) FROM {table} WHERE contract_name = 'Token'"
+                ),
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(count, 2, "{table}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_schema() -> Result<()> {
+        let indexes = |storage: &Storage| -> Result<Vec<String>> {
+            let mut stmt = storage.conn.prepare(
+                "SELECT index_name FROM duckdb_indexes() WHERE table_name IN ('bytecode', 'contract', 'event') ORDER BY index_name",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        };
+        let expected = [
+            "idx_bytecode_code_hash",
+            "idx_contract_chain_address",
+            "idx_event_topic0",
+        ];
+
+        let fresh = Storage::new(":memory:")?;
+        assert_eq!(indexes(&fresh)?, expected);
+
+        // a database of a release with the chain of contracts but neither their
+        // keccak id nor the source code of events
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("old.duckdb");
+        let contract = PlainContract::single_file("contract A {}");
+        {
+            let conn = Connection::open(&path)?;
+            conn.execute_batch(
+                r#"
+                CREATE TYPE source_type_enum AS ENUM ('json', 'vyper', 'single_sol', 'multi_sol');
+                CREATE TABLE contract (
+                    id STRING PRIMARY KEY, name STRING, metadata STRING, source STRING,
+                    source_type source_type_enum, chain_id UBIGINT, address STRING
+                );
+                CREATE INDEX idx_contract_chain_address ON contract (chain_id, address);
+                CREATE TABLE event (
+                    id STRING PRIMARY KEY, contract_id STRING, contract_name STRING,
+                    event_name STRING, signature STRING, topic0 STRING, anonymous BOOLEAN
+                );
+                CREATE INDEX idx_event_topic0 ON event(topic0);
+                CREATE TABLE abi (
+                    contract_id STRING, contract_name STRING, abi_json STRING,
+                    PRIMARY KEY (contract_id, contract_name)
+                );
+                INSERT INTO abi VALUES ('a', 'Token', '[]');
+                CREATE TABLE storage_layout (
+                    contract_id STRING, contract_name STRING, layout_json STRING,
+                    PRIMARY KEY (contract_id, contract_name)
+                );
+                INSERT INTO storage_layout VALUES ('a', 'Token', '{"storage": [], "types": {"t_bool": {}}}');
+                CREATE TABLE bytecode (
+                    chain_id UBIGINT, address STRING, code_hash STRING, code STRING,
+                    contract_id STRING, PRIMARY KEY (chain_id, address)
+                );
+                CREATE INDEX idx_bytecode_code_hash ON bytecode (code_hash);
+                INSERT INTO bytecode VALUES (1, '0x01', '0xaa', '0x00', NULL);
+                CREATE TABLE modifier (
+                    contract_id STRING, contract_name STRING, modifier_name STRING,
+                    source_code STRING, PRIMARY KEY (contract_id, contract_name, modifier_name)
+                );
+                INSERT INTO modifier VALUES ('a', 'Ownable', 'onlyOwner', 'modifier onlyOwner() { _; }');
+                CREATE TABLE function_modifier (
+                    function_id STRING, contract_id STRING, position INTEGER,
+                    modifier_name STRING, invocation STRING, PRIMARY KEY (function_id, position)
+                );
+                CREATE INDEX idx_function_modifier_name ON function_modifier (modifier_name);
+                INSERT INTO function_modifier VALUES ('f', 'a', 0, 'onlyOwner', 'onlyOwner');
+                CREATE TABLE import_edge (
+                    contract_id STRING, from_file STRING, to_file STRING, symbols STRING,
+                    PRIMARY KEY (contract_id, from_file, to_file)
+                );
+                INSERT INTO import_edge VALUES ('a', 'A.sol', 'B.sol', ''), ('a', 'A.sol', 'C.sol', 'C');
+                CREATE TABLE deployment (
+                    address STRING PRIMARY KEY, contract_id STRING, deployer STRING,
+                    tx_hash STRING, block_number UBIGINT, implementation STRING
+                );
+                CREATE INDEX idx_deployment_deployer ON deployment (deployer);
+                INSERT INTO deployment VALUES ('0x01', 'a', '0xde', '0x00', 1, NULL);
+                CREATE TABLE artifact (
+                    contract_id STRING, contract_name STRING, runtime_size INTEGER,
+                    creation_size INTEGER, PRIMARY KEY (contract_id, contract_name)
+                );
+                INSERT INTO artifact VALUES ('a', 'Token', 30000, 31000);
+                CREATE TABLE function_size (
+                    contract_id STRING, contract_name STRING, function_name STRING,
+                    size INTEGER, PRIMARY KEY (contract_id, contract_name, function_name)
+                );
+                INSERT INTO function_size VALUES ('a', 'Token', 'Token.transfer', 500);
+                CREATE TABLE state_variable (
+                    contract_id STRING, contract_name STRING, name STRING, type STRING,
+                    visibility STRING, mutability STRING, slot STRING, slot_offset INTEGER,
+                    PRIMARY KEY (contract_id, contract_name, name)
+                );
+                CREATE INDEX idx_state_variable_name ON state_variable (name);
+                INSERT INTO state_variable VALUES ('a', 'Vault', 'owner', 'address', 'public', 'mutable', '0', 0);
+                "#,
+            )?;
+            conn.execute(
+                "INSERT INTO contract (id, name, metadata, source, source_type, chain_id, address) VALUES (?, 'A', ?, ?, 'single_sol', 1, '0x01')",
+                params![
+                    contract.id(),
+                    serde_json::to_string(&contract.metadata)?,
+                    serde_json::to_string(&contract.source)?
+                ],
+            )?;
+        }
+
+        let storage = Storage::new(&path.to_string_lossy())?;
+        assert_eq!(indexes(&storage)?, expected);
+        assert_eq!(
+            storage.contract_ids_by_keccak_id(&contract.keccak_id()?)?,
+            [contract.id()]
+        );
+        let (chain_id, languages): (u64, Option<String>) =
+            storage
+                .conn
+                .query_row("SELECT chain_id, languages FROM contract", [], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+        assert_eq!((chain_id, languages), (1, None));
+        // artifacts keyed by their contract name only keep their rows
+        let abi: (String, String) = storage.conn.query_row(
+            "SELECT source_path, abi_json FROM abi WHERE contract_id = 'a' AND contract_name = 'Token'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(abi, ("".to_string(), "[]".to_string()));
+        let types: String = storage.conn.query_row(
+            "SELECT types_json FROM storage_layout WHERE contract_id = 'a'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(types, r#"{"t_bool":{}}"#);
+        // modifiers and their invocations are kept without the source path and
+        // the declaration
+        let modifier: (String, Option<i64>) = storage.conn.query_row(
+            "SELECT source_path, ast_id FROM modifier WHERE modifier_name = 'onlyOwner'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(modifier, ("".to_string(), None));
+        let declaration_id: Option<i64> = storage.conn.query_row(
+            "SELECT declaration_id FROM function_modifier WHERE function_id = 'f'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(declaration_id, None);
+        // imports get their position in the order of the imported files
+        let positions = storage
+            .import_edges(Some("a"), None, None)?
+            .into_iter()
+            .map(|e| (e.position, e.to_file))
+            .collect_vec();
+        assert_eq!(positions, [(0, "B.sol".into()), (1, "C.sol".into())]);
+        // fetched code gets the block it was read at
+        let mut code = DeployedBytecode::new(1, "0x01", &[0x60], None);
+        code.block_number = Some(7);
+        storage.store_bytecodes(&[code])?;
+        let block_number: u64 = storage.conn.query_row(
+            "SELECT block_number FROM bytecode WHERE address = '0x01'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(block_number, 7);
+        // sizes are kept without the source path
+        let sizes = storage.artifacts_larger_than(0)?;
+        assert_eq!(sizes.len(), 1);
+        assert_eq!(sizes[0].source_path, "");
+        assert_eq!(storage.largest_functions(&sizes[0], 10)?[0].size, 500);
+        // state variables are kept without their source path and slot
+        let variables = storage.state_variables("owner", None)?;
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].0.source_path, "");
+        // deployments fetched before their chain was recorded are on mainnet
+        let deployments = storage.deployments_by("0xde")?;
+        assert_eq!(
+            deployments
+                .iter()
+                .map(|d| (d.chain_id, d.address.as_str()))
+                .collect_vec(),
+            [(1, "0x01")]
+        );
+        storage.conn.execute(
+            "INSERT INTO event (id, full_signature, source_code) VALUES ('e', 'E(uint256)', 'event E(uint256);')",
+            [],
+        )?;
+        drop(storage);
+
+        // upgraded databases are opened as is
+        let storage = Storage::new(&path.to_string_lossy())?;
+        assert_eq!(indexes(&storage)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_functions_by_literal() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a'), ('b');")?;
+        let transfer = function("a", "Token", "0xa9059cbb");
+        let balance = function("b", "Other", "0x70a08231");
+        let literal = |f: &ContractFunction, kind: &str, value: &str| FunctionLiteral {
+            function_id: f.id.clone(),
+            contract_id: f.contract_id.clone(),
+            kind: kind.into(),
+            value: value.into(),
+        };
+        storage.store_literals(&[
+            literal(&transfer, "string", "not owner"),
+            literal(
+                &transfer,
+                "address",
+                "0xdac17f958d2ee523a2206206994597c13d831ec7",
+            ),
+            literal(&balance, "string", "not owner"),
+        ])?;
+        storage.store_functions(&[transfer, balance])?;
+
+        let found = storage.functions_with_literal("not owner")?;
+        assert_eq!(found.len(), 2);
+        let found = storage.functions_with_literal("0xdAC17F958D2ee523a2206206994597C13D831ec7")?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.contract_name, "Token");
+        assert_eq!(found[0].1, "address");
+
+        // literals of internal functions are found through their definition
+        storage.store_function_definitions(&[FunctionDefinition {
+            id: "internal".into(),
+            contract_id: "a".into(),
+            contract_name: "Token".into(),
+            kind: "function".into(),
+            name: "_checkOwner".into(),
+            filename: "main.sol".into(),
+            signature: "_checkOwner()".into(),
+        }])?;
+        storage.store_literals(&[FunctionLiteral {
+            function_id: "internal".into(),
+            contract_id: "a".into(),
+            kind: "string".into(),
+            value: "Ownable: caller is not the owner".into(),
+        }])?;
+        let found = storage.functions_with_literal("Ownable: caller is not the owner")?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.signature, "_checkOwner()");
+        assert_eq!(found[0].0.selector, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn cross_reference_address() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        let usdt = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+        let transfer = function("a", "Token", "0xa9059cbb");
+        storage.store_literals(&[FunctionLiteral {
+            function_id: transfer.id.clone(),
+            contract_id: "a".into(),
+            kind: "address".into(),
+            value: usdt.into(),
+        }])?;
+        storage.store_functions(&[transfer])?;
+        storage.store_immutables(&[ImmutableValue {
+            contract_id: "b".into(),
+            contract_name: "Vault".into(),
+            address: None,
+            name: "asset".into(),
+            ty: "contract IERC20".into(),
+            value: "0xdAC17F958D2ee523a2206206994597C13D831ec7".into(),
+        }])?;
+        storage.store_constructor_arguments(&[ConstructorArgument {
+            contract_id: "c".into(),
+            contract_name: "Pool".into(),
+            position: 0,
+            name: "token".into(),
+            ty: "address".into(),
+            value: "0xdAC17F958D2ee523a2206206994597C13D831ec7".into(),
+        }])?;
+
+        let references = storage.address_references(usdt)?;
+        let origins: Vec<_> = references
+            .iter()
+            .map(|r| (r.contract_id.as_str(), r.origin.as_str(), r.name.as_str()))
+            .collect();
+        assert_eq!(
+            origins,
+            vec![
+                ("a", "literal", "f()"),
+                ("b", "immutable", "asset"),
+                ("c", "constructor", "token"),
+            ]
+        );
+        assert!(storage
+            .address_references("0x0000000000000000000000000000000000000001")?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn replace_immutable_values() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let immutable = |address: Option<&str>, value: &str| ImmutableValue {
+            contract_id: "b".into(),
+            contract_name: "Vault".into(),
+            address: address.map(Into::into),
+            name: "asset".into(),
+            ty: "contract IERC20".into(),
+            value: value.into(),
+        };
+        storage.store_immutables(&[immutable(None, "0x01"), immutable(Some("0x0b"), "0x01")])?;
+        // extracting the values again replaces them
+        storage.store_immutables(&[immutable(None, "0x02"), immutable(Some("0x0b"), "0x02")])?;
+        storage.store_immutables(&[immutable(Some("0x0c"), "0x03")])?;
+
+        let mut stmt = storage
+            .conn
+            .prepare("SELECT address, value FROM immutable_value ORDER BY address NULLS FIRST")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(Option<String>, String)>, _>>()?;
+        assert_eq!(
+            rows,
+            [
+                (None, "0x02".to_string()),
+                (Some("0x0b".to_string()), "0x02".to_string()),
+                (Some("0x0c".to_string()), "0x03".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_type_definitions_by_name() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let definition = |contract_id: &str, name: &str| TypeDefinition {
+            contract_id: contract_id.into(),
+            filename: "main.sol".into(),
+            name: name.into(),
+            kind: "struct".into(),
+            members: "address owner, uint256 amount".into(),
+            source: "struct Position { address owner; uint256 amount; }".into(),
+        };
+        storage.store_type_definitions(&[
+            definition("a", "Vault.Position"),
+            definition("b", "Position"),
+            definition("c", "Vault.PositionInfo"),
+            definition("d", "Vault.Pool_Info"),
+        ])?;
+
+        let found = storage.type_definitions("Position")?;
+        assert_eq!(found.len(), 2);
+        let found = storage.type_definitions("Vault.Position")?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].contract_id, "a");
+        // wildcards of LIKE patterns are matched literally
+        assert!(storage.type_definitions("Position%")?.is_empty());
+        assert!(storage.type_definitions("Position_nfo")?.is_empty());
+        let found = storage.type_definitions("Pool_Info")?;
+        assert_eq!(found.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_functions_by_assembly_opcode() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        let fallback = function("a", "Proxy", "0x5c60da1b");
+        let other = function("a", "Proxy", "0x8da5cb5b");
+        let block = |f: &ContractFunction, position: u32, opcodes: &str| AssemblyBlock {
+            function_id: f.id.clone(),
+            contract_id: f.contract_id.clone(),
+            position,
+            opcodes: opcodes.into(),
+            source: "assembly { }".into(),
+        };
+        storage.store_assembly_blocks(&[
+            block(&fallback, 0, "sload"),
+            block(&fallback, 1, "calldatacopy,delegatecall,returndatacopy"),
+            block(&other, 0, "sload,call"),
+        ])?;
+        storage.store_functions(&[fallback, other])?;
+
+        let found = storage.functions_with_assembly("delegatecall")?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.selector, "0x5c60da1b");
+        assert_eq!(found[0].1, "sload,calldatacopy,delegatecall,returndatacopy");
+        assert_eq!(storage.functions_with_assembly("sload")?.len(), 2);
+        assert_eq!(storage.functions_with_assembly("call")?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_external_calls_by_target_kind() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        let upgrade = function("a", "Proxy", "0x3659cfe6");
+        let execute = function("a", "Proxy", "0x1cff79cd");
+        let call =
+            |f: &ContractFunction, position: u32, kind: &str, target: &str, target_kind: &str| {
+                ExternalCall {
+                    function_id: f.id.clone(),
+                    contract_id: f.contract_id.clone(),
+                    position,
+                    kind: kind.into(),
+                    target: target.into(),
+                    target_kind: target_kind.into(),
+                    member: kind.into(),
+                }
+            };
+        storage.store_external_calls(&[
+            call(&upgrade, 0, "delegatecall", "implementation", "state"),
+            call(&execute, 0, "delegatecall", "target", "parameter"),
+            call(&execute, 1, "call", "msg.sender", "msg_sender"),
+        ])?;
+        storage.store_functions(&[upgrade, execute])?;
+
+        let found = storage.external_calls("delegatecall", Some("state"))?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.selector, "0x3659cfe6");
+        assert_eq!(found[0].1.target, "implementation");
+        assert_eq!(storage.external_calls("delegatecall", None)?.len(), 2);
+        assert!(storage.external_calls("staticcall", None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_functions_by_modifier() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage
+            .conn
+            .execute_batch("INSERT INTO contract (id) VALUES ('a');")?;
+        let withdraw = function("a", "Vault", "0x3ccfd60b");
+        let pause = function("a", "Vault", "0x8456cb59");
+        let unpause = function("a", "Vault", "0x3f4ba83a");
+        // two contracts named `Ownable` in different files
+        let definition = |source_path: &str, ast_id: i64, source_code: &str| ContractModifier {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: "Ownable".into(),
+            modifier_name: "onlyOwner".into(),
+            ast_id: Some(ast_id),
+            source_code: source_code.into(),
+        };
+        storage.store_modifiers(&[
+            definition(
+                "a/Ownable.sol",
+                10,
+                "modifier onlyOwner() { _checkOwner(); _; }",
+            ),
+            definition(
+                "b/Ownable.sol",
+                20,
+                "modifier onlyOwner() { require(owner == msg.sender); _; }",
+            ),
+        ])?;
+        let invocation =
+            |f: &ContractFunction, position: u32, name: &str, declaration_id| FunctionModifier {
+                function_id: f.id.clone(),
+                contract_id: f.contract_id.clone(),
+                position,
+                modifier_name: name.into(),
+                invocation: name.into(),
+                declaration_id,
+            };
+        storage.store_function_modifiers(&[
+            invocation(&withdraw, 0, "nonReentrant", Some(30)),
+            invocation(&withdraw, 1, "onlyOwner", Some(10)),
+            invocation(&pause, 0, "onlyOwner", Some(20)),
+            // indexed before the declaration was stored
+            invocation(&unpause, 0, "onlyOwner", None),
+        ])?;
+        storage.store_functions(&[withdraw, pause, unpause])?;
+
+        let found = storage.functions_with_modifier("onlyOwner")?;
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].0.selector, "0x3ccfd60b");
+        assert_eq!(found[0].1.position, 1);
+        assert_eq!(found[0].2, "modifier onlyOwner() { _checkOwner(); _; }");
+        assert_eq!(found[1].0.selector, "0x3f4ba83a");
+        assert_eq!(found[1].2, "modifier onlyOwner() { _checkOwner(); _; }");
+        assert_eq!(found[2].0.selector, "0x8456cb59");
+        assert_eq!(
+            found[2].2,
+            "modifier onlyOwner() { require(owner == msg.sender); _; }"
+        );
+        let found = storage.functions_with_modifier("nonReentrant")?;
+        assert_eq!(found.len(), 1);
+        assert!(found[0].2.is_empty());
+        assert!(storage.functions_with_modifier("onlyRole")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_state_variables_by_name() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let variable =
+            |contract_id: &str, source_path: &str, ty: &str, mutability: &str| StateVariable {
+                contract_id: contract_id.into(),
+                source_path: source_path.into(),
+                contract_name: "Vault".into(),
+                name: "owner".into(),
+                ty: ty.into(),
+                visibility: "public".into(),
+                mutability: mutability.into(),
+            };
+        storage.store_state_variables(&[
+            variable("a", "Vault.sol", "address", "mutable"),
+            // a contract with the same name in another file
+            variable("a", "Other.sol", "address", "mutable"),
+            variable("b", "Vault.sol", "address", "immutable"),
+            variable("c", "Vault.sol", "contract IOwner", "mutable"),
+        ])?;
+        let slot = |source_path: &str, contract_name: &str, slot: &str| StorageSlot {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            contract_name: contract_name.into(),
+            label: "owner".into(),
+            slot: slot.into(),
+            offset: 0,
+            ty: "address".into(),
+            type_id: "t_address".into(),
+            encoding: "inplace".into(),
+        };
+        // the slot of the variable in a derived contract is another one
+        storage.store_storage_slots(&[
+            slot("Vault.sol", "Vault", "0"),
+            slot("Vault.sol", "Derived", "3"),
+            slot("Other.sol", "Vault", "7"),
+        ])?;
+
+        let found = storage.state_variables("owner", Some("address"))?;
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].0.source_path, "Other.sol");
+        assert_eq!(found[0].1, Some(("7".into(), 0)));
+        assert_eq!(found[1].1, Some(("0".into(), 0)));
+        assert_eq!(found[2].0.mutability, "immutable");
+        assert_eq!(found[2].1, None);
+        assert_eq!(storage.state_variables("owner", None)?.len(), 4);
+        assert!(storage.state_variables("admin", None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fill_source_code_of_indexed_events() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let event = |source_code: Option<&str>| ContractEvent {
+            id: "e".into(),
+            contract_id: "c".into(),
+            contract_name: "A".into(),
+            event_name: "E".into(),
+            signature: "E(uint256)".into(),
+            topic0: "0x01".into(),
+            anonymous: false,
+            full_signature: source_code.map(|_| "event E(uint256 a)".into()),
+            source_code: source_code.map(Into::into),
+        };
+
+        storage.store_events(&[event(None)])?;
+        storage.store_events(&[event(Some("event E(uint256 a);"))])?;
+        storage.store_events(&[event(None)])?;
+        let stored = storage.events_by_topic0("0x01")?;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(
+            stored[0].source_code.as_deref(),
+            Some("event E(uint256 a);")
+        );
+        assert_eq!(
+            stored[0].full_signature.as_deref(),
+            Some("event E(uint256 a)")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fill_source_code_of_indexed_errors() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let error = |source_code: Option<&str>| ContractError {
+            id: "e".into(),
+            contract_id: "c".into(),
+            contract_name: "A".into(),
+            error_name: "E".into(),
+            signature: "E(uint256)".into(),
+            selector: "0x01".into(),
+            source_code: source_code.map(Into::into),
+        };
+
+        storage.store_errors(&[error(None)])?;
+        storage.store_errors(&[error(Some("error E(uint256 a);"))])?;
+        storage.store_errors(&[error(None)])?;
+        let stored = storage.errors_by_selector("0x01")?;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(
+            stored[0].source_code.as_deref(),
+            Some("error E(uint256 a);")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_databases() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let named = |name: &str, content: &str| {
+            let mut contract = PlainContract::single_file(content);
+            contract.metadata.contract_name = name.into();
+            contract
+        };
+        let shared = named("Shared", "contract Shared {}");
+        let only_shard = named("Shard", "contract Shard {}");
+
+        let shard_path = dir
+            .path()
+            .join("shard.duckdb")
+            .to_string_lossy()
+            .into_owned();
+        {
+            let shard = Storage::new(&shard_path)?;
+            shard.store_contracts(vec![shared.clone(), only_shard.clone()])?;
+            shard.store_functions(&[
+                function(&shared.id(), "Shared", "0x70a08231"),
+                function(&only_shard.id(), "Shard", "0xa9059cbb"),
+            ])?;
+        }
+
+        let target = Storage::new(&dir.path().join("target.duckdb").to_string_lossy())?;
+        target.store_contracts(vec![shared.clone()])?;
+        target.store_functions(&[function(&shared.id(), "Shared", "0x70a08231")])?;
+
+        let stats = target.merge(&shard_path)?;
+        assert_eq!(
+            stats,
+            MergeStats {
+                contracts: 1,
+                functions: 1,
+                rows: 0,
+                conflicts: 0
+            }
+        );
+        assert_eq!(target.count_contracts()?, 2);
+        // merging again is a no-op
+        assert_eq!(target.merge(&shard_path)?, MergeStats::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_every_table() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let shard_path = dir.path().join("shard.duckdb");
+        let shard = Storage::new(&shard_path.to_string_lossy())?;
+        let tables: Vec<String> = {
+            let mut stmt = shard.conn.prepare(
+                "SELECT table_name FROM duckdb_tables() WHERE schema_name = 'main' ORDER BY table_name",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        // a row in every table with a value in every column
+        for table in tables.iter() {
+            let mut stmt = shard.conn.prepare(
+                "SELECT data_type FROM duckdb_columns() WHERE schema_name = 'main' AND table_name = ? ORDER BY column_index",
+            )?;
+            let types = stmt
+                .query_map([table], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = types
+                .iter()
+                .map(|ty| match ty.as_str() {
+                    ty if ty.starts_with("ENUM") => "'json'",
+                    ty if ty.starts_with("TIMESTAMP") => "'2024-01-01 00:00:00'",
+                    _ => "'1'",
+                })
+                .join(", ");
+            shard
+                .conn
+                .execute_batch(&format!("INSERT INTO {table} VALUES ({values})"))?;
+        }
+        drop(shard);
+
+        let target = Storage::new(&dir.path().join("target.duckdb").to_string_lossy())?;
+        let stats = target.merge(&shard_path.to_string_lossy())?;
+        assert_eq!((stats.contracts, stats.functions), (1, 1));
+        assert_eq!(stats.rows, tables.len() - 2);
+
+        target.conn.execute_batch(&format!(
+            "ATTACH '{}' AS shard (READ_ONLY)",
+            shard_path.to_string_lossy()
+        ))?;
+        for table in tables.iter() {
+            let missing: usize = target.conn.query_row(
+                &format!("SELECT count(*) FROM (SELECT * FROM shard.{table} EXCEPT SELECT * FROM {table})"),
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(missing, 0, "{table}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn feature_adoption_by_compiler_version() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        storage.conn.execute_batch(
+            r#"INSERT INTO contract (id, metadata, source_type) VALUES
+                ('a', '{"CompilerVersion": "v0.8.19+commit.7dd6d404"}', 'single_sol'),
+                ('b', '{"CompilerVersion": "v0.8.4+commit.c7e474f2"}', 'single_sol'),
+                ('c', '{"CompilerVersion": "v0.6.12+commit.27d51765"}', 'json');"#,
+        )?;
+        let usage = |contract_id: &str, feature: &str| FeatureUsage {
+            contract_id: contract_id.into(),
+            feature: feature.into(),
+            uses: 1,
+        };
+        storage.store_feature_usages(
+            &[],
+            &[usage("a", "custom-error"), usage("a", "unchecked-block")],
+        )?;
+        // a new run replaces the usage of the analyzed contracts
+        storage.store_feature_usages(
+            &["a".into(), "b".into(), "c".into()],
+            &[
+                usage("a", "unchecked-block"),
+                usage("b", "unchecked-block"),
+                usage("c", "try-catch"),
+            ],
+        )?;
+
+        let adoption: Vec<_> = storage
+            .feature_adoption()?
+            .into_iter()
+            .map(|a| (a.version, a.feature, a.contracts, a.total))
+            .collect();
+        assert_eq!(
+            adoption,
+            vec![
+                ("0.6".to_string(), "try-catch".to_string(), 1, 1),
+                ("0.8".to_string(), "unchecked-block".to_string(), 2, 2),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn store_source_asts() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        assert!(storage.contract_source_asts("a")?.is_empty());
+
+        let ast = |source_path: &str, name: &str| SourceAst {
+            contract_id: "a".into(),
+            source_path: source_path.into(),
+            ast_json: format!(r#"{{"nodeType": "SourceUnit", "nodes": [{{"name": "{name}"}}]}}"#),
+        };
+        storage.store_source_asts(&[ast("src/Vault.sol", "Vault"), ast("src/Math.sol", "Math")])?;
+        // indexing the contract again replaces its ASTs
+        storage.store_source_asts(&[ast("src/Vault.sol", "VaultV2")])?;
+
+        let asts = storage.contract_source_asts("a")?;
+        assert_eq!(
+            asts.iter().map(|(path, _)| path.as_str()).collect_vec(),
+            ["src/Math.sol", "src/Vault.sol"]
+        );
+        assert_eq!(asts[1].1["nodes"][0]["name"], "VaultV2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_import_edges() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let edge = |contract_id: &str, position, to_file: &str, symbols: &str| ImportEdge {
+            contract_id: contract_id.into(),
+            from_file: "contracts/Vault.sol".into(),
+            position,
+            to_file: to_file.into(),
+            symbols: symbols.into(),
+        };
+        storage.store_import_edges(
+            &["a".into(), "b".into()],
+            &[
+                edge(
+                    "a",
+                    0,
+                    "@openzeppelin/contracts/access/AccessControl.sol",
+                    "",
+                ),
+                edge("a", 1, "contracts/Math.sol", "Math"),
+                // a second import of the same file
+                edge("a", 2, "contracts/Math.sol", "SafeMath"),
+                edge(
+                    "b",
+                    0,
+                    "lib/AccessControl.sol",
+                    "AccessControl,IAccessControl",
+                ),
+            ],
+        )?;
+
+        assert_eq!(storage.import_edges(Some("a"), None, None)?.len(), 3);
+        let importing = storage.import_edges(None, Some("*AccessControl.sol"), None)?;
+        assert_eq!(
+            importing
+                .iter()
+                .map(|e| e.contract_id.as_str())
+                .collect_vec(),
+            ["a", "b"]
+        );
+        let importing = storage.import_edges(None, None, Some("IAccessControl"))?;
+        assert_eq!(
+            importing,
+            [edge(
+                "b",
+                0,
+                "lib/AccessControl.sol",
+                "AccessControl,IAccessControl"
+            )]
+        );
+
+        // analyzing again replaces the edges of the contract
+        storage.store_import_edges(&["a".into()], &[])?;
+        assert!(storage.import_edges(Some("a"), None, None)?.is_empty());
 
         Ok(())
     }
@@ -0,0 +1,176 @@
+use eyre::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ast_query::{descendants, node_source, source_asts, source_content},
+    plain_contract::PlainContract,
+};
+
+/// A struct, enum, user defined value type or interface declared in a source
+/// file of a contract
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TypeDefinition {
+    pub contract_id: String,
+    pub filename: String,
+    /// Canonical name, prefixed with the enclosing contract for nested
+    /// definitions, e.g. `Vault.Position`
+    pub name: String,
+    /// One of `struct`, `enum`, `type` or `interface`
+    pub kind: String,
+    /// Typed members of a struct, values of an enum, underlying type of a user
+    /// defined value type or functions of an interface, comma separated
+    pub members: String,
+    /// Source code of the definition
+    pub source: String,
+}
+
+/// Type string of the type name under the key, e.g. `typeName`
+fn type_string<'a>(node: &'a Value, key: &str) -> &'a str {
+    node.pointer(&format!("/{key}/typeDescriptions/typeString"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn items<'a>(node: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    node.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn name(node: &Value) -> &str {
+    node.get("name").and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Kind and members of a definition node, `None` for other nodes
+fn definition(node: &Value) -> Option<(&'static str, String)> {
+    match node.get("nodeType")?.as_str()? {
+        "StructDefinition" => Some((
+            "struct",
+            items(node, "members")
+                .map(|m| format!("{} {}", type_string(m, "typeName"), name(m)))
+                .join(", "),
+        )),
+        "EnumDefinition" => Some(("enum", items(node, "members").map(name).join(", "))),
+        "UserDefinedValueTypeDefinition" => {
+            Some(("type", type_string(node, "underlyingType").to_string()))
+        }
+        "ContractDefinition"
+            if node.get("contractKind").and_then(Value::as_str) == Some("interface") =>
+        {
+            Some((
+                "interface",
+                items(node, "nodes")
+                    .filter(|n| {
+                        n.get("nodeType").and_then(Value::as_str) == Some("FunctionDefinition")
+                    })
+                    .map(name)
+                    .join(", "),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The type definitions of a single source file AST
+pub fn ast_definitions(ast: &Value) -> Vec<(&Value, &'static str, String)> {
+    let mut nodes = vec![];
+    descendants(ast, &mut nodes);
+    nodes
+        .into_iter()
+        .filter_map(|node| definition(node).map(|(kind, members)| (node, kind, members)))
+        .collect()
+}
+
+/// Return the type definitions of every compiled source file
+pub fn extract_definitions(contract: &PlainContract) -> Result<Vec<TypeDefinition>> {
+    let contract_id = contract.id();
+
+    let mut definitions = vec![];
+    for (path, ast) in source_asts(contract)? {
+        let content = source_content(contract, &path);
+        for (node, kind, members) in ast_definitions(&ast) {
+            let name = node
+                .get("canonicalName")
+                .and_then(Value::as_str)
+                .unwrap_or(name(node));
+            definitions.push(TypeDefinition {
+                contract_id: contract_id.clone(),
+                filename: path.clone(),
+                name: name.into(),
+                kind: kind.into(),
+                members,
+                source: node_source(&content, node).into(),
+            });
+        }
+    }
+
+    Ok(definitions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extract_type_definitions() {
+        let ast = serde_json::json!({
+            "nodeType": "SourceUnit",
+            "nodes": [{
+                "nodeType": "UserDefinedValueTypeDefinition",
+                "name": "Price",
+                "canonicalName": "Price",
+                "underlyingType": {"nodeType": "ElementaryTypeName", "typeDescriptions": {"typeString": "uint128"}}
+            }, {
+                "nodeType": "ContractDefinition",
+                "name": "IVault",
+                "contractKind": "interface",
+                "nodes": [
+                    {"nodeType": "FunctionDefinition", "name": "deposit"},
+                    {"nodeType": "FunctionDefinition", "name": "withdraw"}
+                ]
+            }, {
+                "nodeType": "ContractDefinition",
+                "name": "Vault",
+                "contractKind": "contract",
+                "nodes": [{
+                    "nodeType": "StructDefinition",
+                    "name": "Position",
+                    "canonicalName": "Vault.Position",
+                    "members": [
+                        {"nodeType": "VariableDeclaration", "name": "owner", "typeName": {"typeDescriptions": {"typeString": "address"}}},
+                        {"nodeType": "VariableDeclaration", "name": "amount", "typeName": {"typeDescriptions": {"typeString": "uint256"}}}
+                    ]
+                }, {
+                    "nodeType": "EnumDefinition",
+                    "name": "State",
+                    "canonicalName": "Vault.State",
+                    "members": [
+                        {"nodeType": "EnumValue", "name": "Open"},
+                        {"nodeType": "EnumValue", "name": "Closed"}
+                    ]
+                }]
+            }]
+        });
+
+        let definitions: Vec<_> = ast_definitions(&ast)
+            .into_iter()
+            .map(|(node, kind, members)| (name(node), kind, members))
+            .collect();
+        assert_eq!(
+            definitions,
+            vec![
+                ("Price", "type", "uint128".to_string()),
+                ("IVault", "interface", "deposit, withdraw".to_string()),
+                (
+                    "Position",
+                    "struct",
+                    "address owner, uint256 amount".to_string()
+                ),
+                ("State", "enum", "Open, Closed".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use eyre::{ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    bytecode::{creates_clones, object_bytes},
+    explorer::ContractCreation,
+    layout::parse_word,
+    plain_contract::PlainContract,
+};
+
+/// The creation of a contract on chain, linked to the indexed source when known
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Deployment {
+    pub chain_id: u64,
+    /// Lowercase `0x` prefixed address
+    pub address: String,
+    pub contract_id: Option<String>,
+    pub deployer: String,
+    pub tx_hash: String,
+    pub block_number: Option<u64>,
+    /// Implementation address when the deployed code is an EIP-1167 minimal proxy
+    pub implementation: Option<String>,
+}
+
+impl Deployment {
+    pub fn from_creation(
+        chain_id: u64,
+        creation: ContractCreation,
+        contract_id: Option<String>,
+    ) -> Result<Self> {
+        let block_number = creation
+            .block_number
+            .as_deref()
+            .map(|n| parse_word(n).map(|n| n.to::<u64>()))
+            .transpose()?;
+        Ok(Self {
+            chain_id,
+            address: creation.contract_address.to_lowercase(),
+            contract_id,
+            deployer: creation.contract_creator.to_lowercase(),
+            tx_hash: creation.tx_hash,
+            block_number,
+            implementation: None,
+        })
+    }
+}
+
+/// Read deployed addresses from a file with one `address[,contract_id]` per
+/// line, empty lines and lines starting with `#` are skipped
+pub async fn read_addresses(path: &str) -> Result<HashMap<String, Option<String>>> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let addresses = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let address = fields.next().unwrap_or_default().to_lowercase();
+            let contract_id = fields.next().filter(|id| !id.is_empty()).map(String::from);
+            (address, contract_id)
+        })
+        .collect();
+    Ok(addresses)
+}
+
+/// Deployments of EIP-1167 clones of the same implementation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneGroup {
+    pub chain_id: u64,
+    pub implementation: String,
+    /// The indexed source of the implementation, when its deployment on the
+    /// same chain is known
+    pub contract_id: Option<String>,
+    pub clones: usize,
+}
+
+/// A compiled contract embedding the EIP-1167 proxy code, i.e. a clone factory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneFactory {
+    pub contract_id: String,
+    pub contract_name: String,
+}
+
+/// Return the artifacts whose runtime bytecode creates EIP-1167 clones
+pub fn extract_clone_factories(contract: &PlainContract) -> Result<Vec<CloneFactory>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let factories = compilation_output
+        .artifacts()
+        .filter(|(_, artifact)| {
+            artifact
+                .deployed_bytecode
+                .as_ref()
+                .and_then(|b| b.bytecode.as_ref())
+                .is_some_and(|b| creates_clones(&object_bytes(&b.object)))
+        })
+        .map(|(contract_name, _)| CloneFactory {
+            contract_id: contract_id.clone(),
+            contract_name: contract_name.clone(),
+        })
+        .collect();
+
+    Ok(factories)
+}
+
+/// Read organization tags of deployers from a file with one `address,tag` per
+/// line, empty lines and lines starting with `#` are skipped
+pub async fn read_tags(path: &str) -> Result<HashMap<String, String>> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let tags = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (address, tag) = line.split_once(',')?;
+            let tag = tag.trim();
+            (!tag.is_empty()).then(|| (address.trim().to_lowercase(), tag.to_string()))
+        })
+        .collect();
+    Ok(tags)
+}
+
+/// Deployments of the same organization: the contracts deployed by an address
+/// and, transitively, by the factories among them, or by addresses with the
+/// same tag
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployerCluster {
+    /// The tag of the cluster, else its root deployer
+    pub name: String,
+    pub tagged: bool,
+    /// Addresses which deployed contracts of the cluster
+    pub deployers: Vec<String>,
+    /// Deployers whose indexed source uses create2, i.e. deploying to
+    /// addresses derived from a salt
+    pub create2_factories: Vec<String>,
+    /// Chain and address of the deployed contracts
+    pub addresses: Vec<(u64, String)>,
+    /// Distinct indexed sources of the deployed contracts
+    pub contract_ids: Vec<String>,
+}
+
+/// The cluster of a deployer on a chain: the nearest tag among it and the
+/// addresses which deployed it there, else the first deployer of the chain
+fn cluster_name(
+    chain_id: u64,
+    deployer: &str,
+    deployer_of: &HashMap<(u64, &str), &str>,
+    tags: &HashMap<String, String>,
+) -> (String, bool) {
+    let mut address = deployer;
+    let mut visited = HashSet::new();
+    loop {
+        if let Some(tag) = tags.get(address) {
+            return (tag.clone(), true);
+        }
+        match deployer_of.get(&(chain_id, address)) {
+            Some(parent) if visited.insert(address) => address = parent,
+            _ => return (address.to_string(), false),
+        }
+    }
+}
+
+/// Group the deployments by cluster, largest first by number of indexed
+/// sources then deployments
+pub fn deployer_clusters(
+    deployments: &[Deployment],
+    tags: &HashMap<String, String>,
+    create2_contract_ids: &HashSet<String>,
+) -> Vec<DeployerCluster> {
+    let deployer_of: HashMap<(u64, &str), &str> = deployments
+        .iter()
+        .map(|d| ((d.chain_id, d.address.as_str()), d.deployer.as_str()))
+        .collect();
+    let contract_of: HashMap<(u64, &str), &str> = deployments
+        .iter()
+        .filter_map(|d| Some(((d.chain_id, d.address.as_str()), d.contract_id.as_deref()?)))
+        .collect();
+
+    let mut clusters: BTreeMap<String, DeployerCluster> = BTreeMap::new();
+    for d in deployments.iter() {
+        let (name, tagged) = cluster_name(d.chain_id, &d.deployer, &deployer_of, tags);
+        let cluster = clusters
+            .entry(name.clone())
+            .or_insert_with(|| DeployerCluster {
+                name,
+                tagged,
+                deployers: vec![],
+                create2_factories: vec![],
+                addresses: vec![],
+                contract_ids: vec![],
+            });
+        cluster.deployers.push(d.deployer.clone());
+        if contract_of
+            .get(&(d.chain_id, d.deployer.as_str()))
+            .is_some_and(|id| create2_contract_ids.contains(*id))
+        {
+            cluster.create2_factories.push(d.deployer.clone());
+        }
+        cluster.addresses.push((d.chain_id, d.address.clone()));
+        cluster.contract_ids.extend(d.contract_id.clone());
+    }
+
+    let mut clusters = clusters
+        .into_values()
+        .map(|mut c| {
+            for values in [
+                &mut c.deployers,
+                &mut c.create2_factories,
+                &mut c.contract_ids,
+            ] {
+                values.sort();
+                values.dedup();
+            }
+            c.addresses.sort();
+            c.addresses.dedup();
+            c
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by(|a, b| {
+        (b.contract_ids.len(), b.addresses.len()).cmp(&(a.contract_ids.len(), a.addresses.len()))
+    });
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cluster_deployments_by_deployer() {
+        let deployment = |address: &str, deployer: &str, contract_id: Option<&str>| Deployment {
+            chain_id: 1,
+            address: address.into(),
+            contract_id: contract_id.map(String::from),
+            deployer: deployer.into(),
+            tx_hash: format!("tx{address}"),
+            block_number: None,
+            implementation: None,
+        };
+        let deployments = [
+            // an EOA deploying a create2 factory which deploys two pairs
+            deployment("0xf1", "0xe1", Some("factory")),
+            deployment("0xa1", "0xf1", Some("pair")),
+            deployment("0xa2", "0xf1", Some("pair")),
+            // tagged EOAs of the same organization
+            deployment("0xb1", "0xe2", Some("vault")),
+            deployment("0xb2", "0xe3", None),
+            deployment("0xc1", "0xe4", Some("token")),
+            // the address of the factory on another chain is another deployer
+            Deployment {
+                chain_id: 10,
+                ..deployment("0xa3", "0xf1", Some("pair"))
+            },
+        ];
+        let tags = HashMap::from([
+            ("0xe2".to_string(), "acme".to_string()),
+            ("0xe3".to_string(), "acme".to_string()),
+        ]);
+        let create2 = HashSet::from(["factory".to_string()]);
+
+        let clusters = deployer_clusters(&deployments, &tags, &create2);
+        assert_eq!(
+            clusters.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["0xe1", "acme", "0xe4", "0xf1"]
+        );
+        assert!(!clusters[0].tagged);
+        assert_eq!(clusters[0].deployers, ["0xe1", "0xf1"]);
+        assert_eq!(clusters[0].create2_factories, ["0xf1"]);
+        assert_eq!(
+            clusters[0].addresses,
+            [(1, "0xa1".into()), (1, "0xa2".into()), (1, "0xf1".into())]
+        );
+        assert_eq!(clusters[0].contract_ids, ["factory", "pair"]);
+        assert!(clusters[1].tagged);
+        assert_eq!(clusters[1].deployers, ["0xe2", "0xe3"]);
+        assert_eq!(clusters[1].contract_ids, ["vault"]);
+        assert!(clusters[3].create2_factories.is_empty());
+        assert_eq!(clusters[3].addresses, [(10, "0xa3".into())]);
+    }
+}
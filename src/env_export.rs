@@ -0,0 +1,152 @@
+use eyre::Result;
+use flate2::{write::GzEncoder, Compression};
+use semver::Version;
+use std::{collections::BTreeSet, fs::File};
+use tar::{Builder, Header};
+
+use crate::{
+    compiler_version::solc_version,
+    plain_contract::{ContractSource, PlainContract},
+    vyper::vyper_version,
+};
+
+/// Path of the binary of this tool in an environment
+pub const TOOL_ENTRY: &str = "bin/smart-contract-database-builder";
+
+/// Script of an environment creating the database from its schema, importing
+/// its contracts and indexing them with the bundled tool and compilers,
+/// without network access. The solc binaries are copied where svm looks for
+/// them: `~/.svm`, else the svm folder of the user data folder when it exists.
+pub const REPLICATE_SCRIPT: &str = r#"#!/bin/sh
+# Index the contracts of this environment with the bundled compilers, offline:
+#   DUCKDB_PATH=contracts.duckdb ./replicate.sh
+set -e
+cd "$(dirname "$0")"
+SVM_DIR="$HOME/.svm"
+if [ ! -d "$SVM_DIR" ]; then
+  case "$(uname)" in
+    Darwin) DATA_DIR="$HOME/Library/Application Support" ;;
+    *) DATA_DIR="${XDG_DATA_HOME:-$HOME/.local/share}" ;;
+  esac
+  if [ -d "$DATA_DIR" ]; then SVM_DIR="$DATA_DIR/svm"; fi
+fi
+if [ -d svm ]; then mkdir -p "$SVM_DIR" && cp -R svm/. "$SVM_DIR/"; fi
+if [ -d vvm ]; then mkdir -p "$HOME/.vvm" && cp -R vvm/. "$HOME/.vvm/"; fi
+export DUCKDB_PATH="${DUCKDB_PATH:-contracts.duckdb}"
+if [ ! -e "$DUCKDB_PATH" ]; then
+  if command -v duckdb >/dev/null; then
+    duckdb "$DUCKDB_PATH" < schema.sql
+  else
+    echo "duckdb not found, the schema is created by the tool" >&2
+  fi
+fi
+TOOL=./bin/smart-contract-database-builder
+if [ ! -x "$TOOL" ]; then TOOL=smart-contract-database-builder; fi
+"$TOOL" --offline import --contracts contracts.jsonl
+"$TOOL" --offline index-functions
+"#;
+
+/// The solc and vyper versions compiling the contracts, without their commit
+pub fn required_compilers(contracts: &[PlainContract]) -> (BTreeSet<Version>, BTreeSet<Version>) {
+    let mut solc = BTreeSet::new();
+    let mut vyper = BTreeSet::new();
+    for contract in contracts.iter() {
+        let compiler_version = &contract.metadata.compiler_version;
+        if matches!(contract.source, ContractSource::Vyper(_)) {
+            vyper.extend(vyper_version(compiler_version).ok());
+        } else if let Ok(version) = solc_version(compiler_version) {
+            solc.insert(version);
+        }
+    }
+    (solc, vyper)
+}
+
+/// Path of a solc binary in an environment, laid out as in the svm folder
+pub fn solc_entry(version: &Version) -> String {
+    format!("svm/{version}/solc-{version}")
+}
+
+/// Path of a vyper binary in an environment, laid out as in the vvm folder
+pub fn vyper_entry(version: &Version) -> String {
+    format!("vvm/vyper-{version}")
+}
+
+/// A gzipped tarball of the files of an environment
+pub struct EnvBundle {
+    builder: Builder<GzEncoder<File>>,
+}
+
+impl EnvBundle {
+    pub fn create(path: &str) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            builder: Builder::new(GzEncoder::new(file, Compression::default())),
+        })
+    }
+
+    /// Add a file, `executable` for binaries and scripts
+    pub fn add(&mut self, path: &str, content: &[u8], executable: bool) -> Result<()> {
+        let mut header = Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(if executable { 0o755 } else { 0o644 });
+        header.set_cksum();
+        self.builder.append_data(&mut header, path, content)?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        self.builder.into_inner()?.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn bundle_environment_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("env.tar.gz");
+        let path = path.to_string_lossy();
+
+        let version = Version::new(0, 8, 19);
+        assert_eq!(solc_entry(&version), "svm/0.8.19/solc-0.8.19");
+        assert_eq!(vyper_entry(&Version::new(0, 3, 10)), "vvm/vyper-0.3.10");
+
+        let mut bundle = EnvBundle::create(&path)?;
+        bundle.add("replicate.sh", REPLICATE_SCRIPT.as_bytes(), true)?;
+        bundle.add(&solc_entry(&version), b"solc", true)?;
+        bundle.add("schema.sql", b"CREATE TABLE contract (id STRING);", false)?;
+        bundle.finish()?;
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&*path)?));
+        let mut entries = vec![];
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            entries.push((
+                entry.path()?.to_string_lossy().into_owned(),
+                entry.header().mode()?,
+                content,
+            ));
+        }
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].0, "replicate.sh");
+        assert_eq!(entries[0].1, 0o755);
+        assert_eq!(entries[1].0, "svm/0.8.19/solc-0.8.19");
+        assert_eq!(entries[2].1, 0o644);
+        assert_eq!(entries[2].2, "CREATE TABLE contract (id STRING);");
+
+        // the script creates the database from the schema and runs the bundled
+        // tool offline
+        assert!(REPLICATE_SCRIPT.contains("< schema.sql"));
+        assert!(REPLICATE_SCRIPT.contains(&format!("TOOL=./{TOOL_ENTRY}")));
+        assert!(REPLICATE_SCRIPT.contains("\"$TOOL\" --offline index-functions"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use alloy_dyn_abi::{DynSolValue, EventExt};
+use alloy_json_abi::Event;
+use alloy_primitives::{hex, B256};
+use eyre::{eyre, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{ast_query::DefinitionSources, plain_contract::PlainContract, utils::simple_hash};
+
+/// The `0x` prefixed topic0 hash of an event as stored in the `event` table
+pub fn topic0_hex(e: &Event) -> String {
+    hex::encode_prefixed(e.selector())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContractEvent {
+    pub id: String,
+    pub contract_id: String,
+    pub contract_name: String,
+    pub event_name: String,
+    pub signature: String,
+    pub topic0: String,
+    pub anonymous: bool,
+    /// Signature with the parameter names and `indexed` modifiers, e.g.
+    /// `event Transfer(address indexed from, address indexed to, uint256 value)`,
+    /// missing for events indexed before it was stored
+    pub full_signature: Option<String>,
+    /// Source code of the event definition, missing when it is not found in
+    /// the AST and for events indexed before it was stored
+    pub source_code: Option<String>,
+}
+
+impl ContractEvent {
+    pub fn from_abi(contract_id: String, contract_name: String, e: &Event) -> Self {
+        let signature = e.signature();
+        let id = simple_hash(&format!("{}{}{}", contract_id, contract_name, signature));
+        Self {
+            id,
+            contract_id,
+            contract_name,
+            event_name: e.name.clone(),
+            signature,
+            topic0: topic0_hex(e),
+            anonymous: e.anonymous,
+            full_signature: Some(e.full_signature()),
+            source_code: None,
+        }
+    }
+}
+
+/// Return a list of events from the ABI of every compiled contract, with the
+/// source code of their definition
+pub fn extract_events(contract: &PlainContract) -> Result<Vec<ContractEvent>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+    let sources = DefinitionSources::new(contract, "EventDefinition", "eventSelector")?;
+
+    let events = compilation_output
+        .artifacts()
+        .filter_map(|(contract_name, artifact)| {
+            artifact.abi.as_ref().map(|abi| {
+                abi.events()
+                    .map(|e| {
+                        let mut event =
+                            ContractEvent::from_abi(contract_id.clone(), contract_name.clone(), e);
+                        event.source_code = sources.get(&event.event_name, &event.topic0);
+                        event
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .flatten()
+        .collect();
+
+    Ok(events)
+}
+
+/// An event from its signature, either a full signature with the `indexed`
+/// modifiers or a plain text signature, e.g. `Transfer(address,address,uint256)`.
+/// The parameters of a plain signature are not known to be indexed, the first
+/// `indexed` of them are assumed to be, as most events declare them first.
+pub fn parse_event_signature(signature: &str, indexed: usize) -> Result<Event> {
+    let mut event =
+        Event::parse(signature).map_err(|e| eyre!("Invalid signature {signature}: {e}"))?;
+    if !signature.starts_with("event ") {
+        for (i, param) in event.inputs.iter_mut().enumerate() {
+            param.indexed = i < indexed;
+        }
+    }
+    Ok(event)
+}
+
+/// Values of the parameters of the event in declaration order, `None` when
+/// the topics and data are not a valid encoding of the event. Indexed dynamic
+/// values are only known by their hash.
+pub fn decode_event(event: &Event, topics: &[B256], data: &[u8]) -> Option<Vec<DynSolValue>> {
+    let decoded = event
+        .decode_log_parts(topics.iter().copied(), data, true)
+        .ok()?;
+    let mut indexed = decoded.indexed.into_iter();
+    let mut body = decoded.body.into_iter();
+    event
+        .inputs
+        .iter()
+        .map(|p| match p.indexed {
+            true => indexed.next(),
+            false => body.next(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::abi::format_value;
+    use alloy_primitives::{Address, U256};
+    use itertools::Itertools;
+
+    #[test]
+    fn decode_transfer_logs() -> Result<()> {
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let full = "event Transfer(address indexed from, address indexed to, uint256 value)";
+        let event = parse_event_signature(full, 0)?;
+        let topics = [event.selector(), from.into_word(), to.into_word()];
+        let data = U256::from(100).to_be_bytes::<32>();
+
+        let values = decode_event(&event, &topics, &data).expect("Valid transfer log");
+        assert_eq!(
+            values.iter().map(format_value).collect_vec(),
+            [from.to_checksum(None), to.to_checksum(None), "100".into()]
+        );
+
+        // the same log with the indexed parameters guessed from the topics
+        let plain = parse_event_signature("Transfer(address,address,uint256)", 2)?;
+        assert_eq!(plain.inputs.iter().filter(|p| p.indexed).count(), 2);
+        assert_eq!(decode_event(&plain, &topics, &data), Some(values));
+
+        // a missing topic or data does not decode
+        assert_eq!(decode_event(&event, &topics[..2], &data), None);
+        assert_eq!(decode_event(&event, &topics, &[]), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use eyre::{eyre, ContextCompat, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    abi::decode_hex, config::Profile, plain_contract::EtherscanRawJson, utils::HttpFetcher,
+};
+
+/// Unified Etherscan API of all supported chains, selected with the `chainid`
+/// parameter, used when no explorer is configured
+pub const ETHERSCAN_V2_API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// Maximum number of addresses accepted by `getcontractcreation`
+const CREATION_BATCH_SIZE: usize = 5;
+
+/// The Etherscan compatible API of a Blockscout instance, given by its base URL
+pub fn blockscout_api_url(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    match url.ends_with("/api") {
+        true => url.into(),
+        false => format!("{url}/api"),
+    }
+}
+
+/// Whether the API is the Etherscan V2 API, which needs the chain of every request
+pub fn is_etherscan_v2(url: &str) -> bool {
+    url.trim_end_matches('/').ends_with("/v2/api")
+}
+
+/// Client of an Etherscan compatible block explorer API
+pub struct Explorer<'a> {
+    fetcher: &'a HttpFetcher,
+    url: String,
+    api_key: Option<String>,
+    /// Sent as `chainid` to the Etherscan V2 API
+    chain_id: Option<u64>,
+}
+
+/// Response envelope of the explorer API
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    status: String,
+    message: String,
+    result: Option<T>,
+}
+
+/// Result of `eth_*` calls proxied by the explorer
+#[derive(Deserialize)]
+struct ProxyResponse<T> {
+    result: Option<T>,
+}
+
+/// The transaction creating a contract
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractCreation {
+    pub contract_address: String,
+    pub contract_creator: String,
+    pub tx_hash: String,
+    /// Only returned by newer explorer versions
+    #[serde(default)]
+    pub block_number: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transaction {
+    block_number: Option<String>,
+}
+
+impl<'a> Explorer<'a> {
+    /// Explorer of the selected profile
+    pub fn new(fetcher: &'a HttpFetcher, profile: &Profile) -> Result<Self> {
+        let url = profile
+            .explorer_url
+            .clone()
+            .context("No explorer_url in the selected profile")?;
+        Ok(Self::with_url(fetcher, &url, profile.api_key.clone()).chain(profile.chain_id))
+    }
+
+    /// Explorer at `url`, e.g. a Blockscout instance or the default Etherscan API
+    pub fn with_url(fetcher: &'a HttpFetcher, url: &str, api_key: Option<String>) -> Self {
+        Self {
+            fetcher,
+            url: url.trim_end_matches('/').into(),
+            api_key,
+            chain_id: None,
+        }
+    }
+
+    /// Select the chain of the Etherscan V2 API, mainnet when `None`. Other
+    /// explorers serve a single chain and ignore it.
+    pub fn chain(mut self, chain_id: Option<u64>) -> Self {
+        if is_etherscan_v2(&self.url) {
+            self.chain_id = Some(chain_id.unwrap_or(1));
+        }
+        self
+    }
+
+    /// Chain sent to the Etherscan V2 API
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    fn url(&self, query: &str) -> String {
+        let chain = match self.chain_id {
+            Some(chain_id) => format!("chainid={chain_id}&"),
+            None => String::new(),
+        };
+        match &self.api_key {
+            Some(key) => format!("{}?{chain}{query}&apikey={key}", self.url),
+            None => format!("{}?{chain}{query}", self.url),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, query: &str) -> Result<T> {
+        let body = self.fetcher.get_text(&self.url(query)).await?;
+        let response: ApiResponse<T> = serde_json::from_str(&body)?;
+        match response.result {
+            Some(result) if response.status == "1" => Ok(result),
+            _ => Err(eyre!("Explorer error for {query}: {}", response.message)),
+        }
+    }
+
+    async fn proxy_call<T: DeserializeOwned>(&self, query: &str) -> Result<Option<T>> {
+        let body = self.fetcher.get_text(&self.url(query)).await?;
+        let response: ProxyResponse<T> = serde_json::from_str(&body)?;
+        Ok(response.result)
+    }
+
+    /// Deployed runtime code at the address
+    pub async fn code(&self, address: &str) -> Result<Vec<u8>> {
+        let query = format!("module=proxy&action=eth_getCode&address={address}&tag=latest");
+        let code: Option<String> = self.proxy_call(&query).await?;
+        decode_hex(&code.context("No code returned")?)
+    }
+
+    /// Verified source code of the contract at the address, the source code is
+    /// empty when the contract is not verified
+    pub async fn source_code(&self, address: &str) -> Result<Vec<EtherscanRawJson>> {
+        let query = format!("module=contract&action=getsourcecode&address={address}");
+        self.call(&query).await
+    }
+
+    /// Creation transactions of the given contracts, the block number is looked
+    /// up from the transaction when the explorer does not return it
+    pub async fn contract_creations(&self, addresses: &[String]) -> Result<Vec<ContractCreation>> {
+        let mut creations = vec![];
+        for batch in addresses.chunks(CREATION_BATCH_SIZE) {
+            let query = format!(
+                "module=contract&action=getcontractcreation&contractaddresses={}",
+                batch.join(",")
+            );
+            let batch: Vec<ContractCreation> = self.call(&query).await?;
+            creations.extend(batch);
+        }
+
+        for creation in creations.iter_mut() {
+            if creation.block_number.is_some() {
+                continue;
+            }
+            let query = format!(
+                "module=proxy&action=eth_getTransactionByHash&txhash={}",
+                creation.tx_hash
+            );
+            let tx: Option<Transaction> = self.proxy_call(&query).await?;
+            creation.block_number = tx.and_then(|tx| tx.block_number);
+        }
+
+        Ok(creations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::HttpOptions;
+
+    #[test]
+    fn select_chain_of_etherscan_v2() -> Result<()> {
+        let fetcher = HttpFetcher::new(HttpOptions {
+            requests_per_second: 0.0,
+            max_retries: 0,
+            http_cache_dir: None,
+            refresh: false,
+            proxy: None,
+        })?;
+        let query = "module=contract&action=getsourcecode&address=0xab";
+
+        let base = Explorer::with_url(&fetcher, ETHERSCAN_V2_API_URL, Some("KEY".into()))
+            .chain(Some(8453));
+        assert_eq!(
+            base.url(query),
+            "https://api.etherscan.io/v2/api?chainid=8453&module=contract&action=getsourcecode&address=0xab&apikey=KEY"
+        );
+        let mainnet = Explorer::with_url(&fetcher, ETHERSCAN_V2_API_URL, None).chain(None);
+        assert!(mainnet.url(query).contains("?chainid=1&"));
+
+        // single chain explorers do not take the chain
+        let blockscout = Explorer::with_url(
+            &fetcher,
+            &blockscout_api_url("https://gnosis.blockscout.com/"),
+            None,
+        )
+        .chain(Some(100));
+        assert_eq!(
+            blockscout.url(query),
+            format!("https://gnosis.blockscout.com/api?{query}")
+        );
+
+        Ok(())
+    }
+}
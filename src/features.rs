@@ -0,0 +1,115 @@
+use eyre::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{plain_contract::PlainContract, pragma::strip_comments};
+
+/// Language features tracked by the feature usage analysis, with the pattern
+/// of their use in comment free source code
+pub const FEATURES: [(&str, &str); 6] = [
+    ("unchecked-block", r"\bunchecked\s*\{"),
+    ("custom-error", r"\berror\s+[A-Za-z_$][\w$]*\s*\("),
+    ("try-catch", r"\bcatch\b"),
+    (
+        "create2",
+        r"\bnew\s+[A-Za-z_$][\w$.]*\s*\{[^}]*\bsalt\s*:|\bcreate2\s*\(",
+    ),
+    ("immutable", r"\bimmutable\b"),
+    (
+        "user-defined-operator",
+        r"\busing\s*\{[^}]*\bas\s*[-+*/%&|^~<>=!]",
+    ),
+];
+
+/// Number of uses of a language feature in the sources of a contract
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeatureUsage {
+    pub contract_id: String,
+    pub feature: String,
+    pub uses: usize,
+}
+
+/// Contracts using a feature among the contracts compiled with a minor
+/// compiler version, e.g. `0.8`
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeatureAdoption {
+    pub version: String,
+    pub feature: String,
+    pub contracts: usize,
+    /// All solidity contracts of the compiler version
+    pub total: usize,
+}
+
+/// Count the uses of each feature in the solidity sources of the contract,
+/// features which are not used are left out
+pub fn find_features(contract: &PlainContract) -> Result<Vec<FeatureUsage>> {
+    let sources: Vec<String> = contract
+        .get_source_files()?
+        .iter()
+        .filter(|f| f.name.ends_with(".sol"))
+        .map(|f| strip_comments(&f.content))
+        .collect();
+    let contract_id = contract.id();
+
+    let mut usages = vec![];
+    for (feature, pattern) in FEATURES {
+        let pattern = Regex::new(pattern).unwrap();
+        let uses = sources.iter().map(|s| pattern.find_iter(s).count()).sum();
+        if uses > 0 {
+            usages.push(FeatureUsage {
+                contract_id: contract_id.clone(),
+                feature: feature.into(),
+                uses,
+            });
+        }
+    }
+    Ok(usages)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_feature_usage() -> Result<()> {
+        let source = r#"
+pragma solidity ^0.8.19;
+
+type Amount is uint256;
+using {add as +} for Amount global;
+
+error Unauthorized(address caller);
+
+contract Factory {
+    address public immutable owner;
+    // unchecked { disabled }
+
+    function deploy(bytes32 salt) external returns (address) {
+        if (msg.sender != owner) revert Unauthorized(msg.sender);
+        unchecked { salt = bytes32(uint256(salt) + 1); }
+        try this.ping() {} catch {}
+        return address(new Child{salt: salt}());
+    }
+}
+"#;
+        let contract = PlainContract::single_file(source);
+
+        let usages: Vec<_> = find_features(&contract)?
+            .into_iter()
+            .map(|u| (u.feature, u.uses))
+            .collect();
+        assert_eq!(
+            usages,
+            vec![
+                ("unchecked-block".to_string(), 1),
+                ("custom-error".to_string(), 1),
+                ("try-catch".to_string(), 1),
+                ("create2".to_string(), 1),
+                ("immutable".to_string(), 1),
+                ("user-defined-operator".to_string(), 1),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// A problem found in a contract by one of the `analyze` passes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Finding {
+    pub contract_id: String,
+    /// The pass producing the finding, e.g. `compiler-bug`
+    pub kind: String,
+    pub name: String,
+    pub severity: String,
+    pub detail: String,
+}
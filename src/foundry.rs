@@ -0,0 +1,290 @@
+use eyre::{ContextCompat, Result};
+use log::warn;
+use regex::Regex;
+use semver::Version;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    plain_contract::{ContractSource, Metadata, PlainContract, SourceFile},
+    pragma::{minimum_version, solidity_pragmas, strip_comments},
+};
+
+/// The settings of `foundry.toml` needed to rebuild a project
+#[derive(Debug, Default, Deserialize)]
+struct FoundryConfig {
+    #[serde(default)]
+    profile: BTreeMap<String, FoundryProfile>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+struct FoundryProfile {
+    src: Option<String>,
+    libs: Option<Vec<String>>,
+    #[serde(default)]
+    remappings: Vec<String>,
+    /// A version or the path of a solc binary
+    solc: Option<String>,
+    solc_version: Option<String>,
+    optimizer: Option<bool>,
+    optimizer_runs: Option<u32>,
+    evm_version: Option<String>,
+    via_ir: Option<bool>,
+}
+
+/// A Foundry project on disk, i.e. a folder with `foundry.toml`
+pub struct FoundryProject {
+    root: PathBuf,
+    profile: FoundryProfile,
+    /// Remappings as `prefix=target`, longest prefix first
+    remappings: Vec<(String, String)>,
+}
+
+/// Join a relative path to a base path without touching the file system,
+/// `..` components remove the preceding component
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Paths imported by a solidity source, as written in the import directives
+pub fn imports(content: &str) -> Vec<String> {
+    let import = Regex::new(r#"import\s+(?:[^;'"]*?\s+from\s+)?["']([^"']+)["']"#).unwrap();
+    import
+        .captures_iter(&strip_comments(content))
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+impl FoundryProject {
+    /// Read the default profile of `foundry.toml` and the remappings of the
+    /// project. Remappings are taken from `foundry.toml`, `remappings.txt` and
+    /// the libraries, in this order of precedence.
+    pub fn load(root: &Path) -> Result<Self> {
+        let config = std::fs::read_to_string(root.join("foundry.toml"))?;
+        let config: FoundryConfig = toml::from_str(&config)?;
+        let profile = config.profile.get("default").cloned().unwrap_or_default();
+
+        let mut remappings: Vec<(String, String)> = vec![];
+        let mut add = |remapping: &str| {
+            if let Some((prefix, target)) = remapping.trim().split_once('=') {
+                if !remappings.iter().any(|(p, _)| p == prefix) {
+                    remappings.push((prefix.into(), target.into()));
+                }
+            }
+        };
+        profile.remappings.iter().for_each(|r| add(r));
+        if let Ok(content) = std::fs::read_to_string(root.join("remappings.txt")) {
+            content
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .for_each(&mut add);
+        }
+        let libs = profile.libs.clone().unwrap_or(vec!["lib".into()]);
+        for lib in libs.iter() {
+            let Ok(entries) = std::fs::read_dir(root.join(lib)) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok).filter(|e| e.path().is_dir()) {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let target = match entry.path().join("src").is_dir() {
+                    true => format!("{lib}/{name}/src/"),
+                    false => format!("{lib}/{name}/"),
+                };
+                add(&format!("{name}/={target}"));
+            }
+        }
+        remappings.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+
+        Ok(Self {
+            root: root.into(),
+            profile,
+            remappings,
+        })
+    }
+
+    /// Path of an imported file relative to the project root
+    fn resolve(&self, importer: &Path, import: &str) -> PathBuf {
+        if import.starts_with("./") || import.starts_with("../") {
+            let parent = importer.parent().unwrap_or(Path::new(""));
+            return normalize(&parent.join(import));
+        }
+        match self
+            .remappings
+            .iter()
+            .find(|(prefix, _)| import.starts_with(prefix.as_str()))
+        {
+            Some((prefix, target)) => normalize(Path::new(&import.replacen(prefix, target, 1))),
+            None => normalize(Path::new(import)),
+        }
+    }
+
+    /// All sources of the `src` folder and the files they import, by path
+    /// relative to the project root
+    pub fn sources(&self) -> Result<BTreeMap<String, String>> {
+        let src = self.profile.src.as_deref().unwrap_or("src");
+        let mut pending: VecDeque<PathBuf> = WalkDir::new(self.root.join(src))
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "sol"))
+            .filter_map(|e| e.path().strip_prefix(&self.root).ok().map(PathBuf::from))
+            .collect();
+
+        let mut sources = BTreeMap::new();
+        while let Some(path) = pending.pop_front() {
+            let name = path.to_string_lossy().into_owned();
+            if sources.contains_key(&name) {
+                continue;
+            }
+            let content = match std::fs::read_to_string(self.root.join(&path)) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Failed to read {name} in {:?}: {e}", self.root);
+                    continue;
+                }
+            };
+            pending.extend(imports(&content).iter().map(|i| self.resolve(&path, i)));
+            sources.insert(name, content);
+        }
+        Ok(sources)
+    }
+
+    /// The pinned compiler version, or the lowest version allowed by the pragmas
+    fn compiler_version(&self, sources: &BTreeMap<String, String>) -> Result<Version> {
+        let pinned = self
+            .profile
+            .solc_version
+            .as_deref()
+            .or(self.profile.solc.as_deref())
+            .and_then(|v| Version::parse(v).ok());
+        if let Some(version) = pinned {
+            return Ok(version);
+        }
+        let pragmas: Vec<String> = sources.values().flat_map(|c| solidity_pragmas(c)).collect();
+        minimum_version(&pragmas)?.context("No compiler version in foundry.toml or the pragmas")
+    }
+
+    /// The project as a standard json contract. The main contract is the one in
+    /// the largest file of `src`.
+    pub fn to_contract(&self) -> Result<PlainContract> {
+        let sources = self.sources()?;
+        let src = self.profile.src.as_deref().unwrap_or("src");
+        let contract_name = sources
+            .iter()
+            .filter(|(name, _)| Path::new(name).starts_with(src))
+            .max_by_key(|(_, content)| content.len())
+            .and_then(|(name, _)| Path::new(name).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .with_context(|| format!("No sources in {src} of {:?}", self.root))?;
+
+        let optimizer = self.profile.optimizer.unwrap_or(false);
+        let runs = self.profile.optimizer_runs.unwrap_or(200);
+        let mut settings = serde_json::json!({
+            "remappings": self
+                .remappings
+                .iter()
+                .map(|(prefix, target)| format!("{prefix}={target}"))
+                .collect::<Vec<_>>(),
+            "optimizer": {"enabled": optimizer, "runs": runs},
+        });
+        if let Some(evm_version) = &self.profile.evm_version {
+            settings["evmVersion"] = evm_version.as_str().into();
+        }
+        if let Some(via_ir) = self.profile.via_ir {
+            settings["viaIR"] = via_ir.into();
+        }
+        let input = serde_json::json!({
+            "language": "Solidity",
+            "sources": sources
+                .iter()
+                .map(|(name, content)| (name.clone(), serde_json::json!({ "content": content })))
+                .collect::<serde_json::Map<_, _>>(),
+            "settings": settings,
+        });
+
+        let metadata = Metadata {
+            contract_name,
+            compiler_version: self.compiler_version(&sources)?.to_string(),
+            runs,
+            optimization_used: optimizer,
+            bytecode_hash: "".into(),
+            constructor_arguments: None,
+            chain_id: None,
+            address: None,
+            evm_version: None,
+            via_ir: None,
+        };
+        let source = ContractSource::Json(SourceFile {
+            name: "contract.json".into(),
+            content: serde_json::to_string(&input)?,
+        });
+        Ok(PlainContract::new(metadata, source))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plain_contract::StandardJson;
+
+    #[test]
+    fn parse_import_directives() {
+        let source = r#"
+import "./A.sol";
+import {B, C} from "lib/B.sol";
+import * as D from '../D.sol';
+// import "ignored.sol";
+import "E.sol" as E;
+"#;
+        assert_eq!(
+            imports(source),
+            vec!["./A.sol", "lib/B.sol", "../D.sol", "E.sol"]
+        );
+    }
+
+    #[test]
+    fn load_foundry_project() -> Result<()> {
+        let project = FoundryProject::load(Path::new("./contracts/foundry"))?;
+        let contract = project.to_contract()?;
+
+        assert_eq!(contract.metadata.contract_name, "Vault");
+        assert_eq!(contract.metadata.compiler_version, "0.8.20");
+        assert!(contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 10000);
+
+        let ContractSource::Json(source) = &contract.source else {
+            panic!("Expected a standard json contract");
+        };
+        let json: StandardJson = serde_json::from_str(&source.content)?;
+        let mut names: Vec<_> = json.sources.keys().cloned().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "lib/solmate/src/auth/Owned.sol",
+                "lib/solmate/src/tokens/ERC20.sol",
+                "src/Vault.sol",
+                "src/interfaces/IVault.sol",
+            ]
+        );
+        let remappings = json.settings.context("Missing settings")?.remappings;
+        let remappings: Vec<_> = remappings.iter().map(|r| r.to_string()).collect();
+        assert!(remappings.contains(&"solmate/=lib/solmate/src/".to_string()));
+        assert!(remappings.contains(&"forge-std/=lib/forge-std/src/".to_string()));
+
+        Ok(())
+    }
+}
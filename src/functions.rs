@@ -2,7 +2,17 @@ use crate::utils::simple_hash;
 use alloy_json_abi::Function;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The `0x` prefixed function selector as stored in the `function` table
+pub fn selector_hex(f: &Function) -> String {
+    format!("0x{:04x}", f.selector())
+}
+
+/// Id of a function in the `function` table
+pub fn function_id(contract_id: &str, filename: &str, selector: &str) -> String {
+    simple_hash(&format!("{}{}{}", contract_id, filename, selector))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractFunction {
     pub id: String,
     /// The contract id. A compilation output can have multiple contracts, in this
@@ -25,10 +35,9 @@ impl ContractFunction {
         f: &Function,
         source_code: String,
     ) -> Self {
-        let selector = f.selector();
-        let selector = format!("0x{:04x}", selector);
+        let selector = selector_hex(f);
         let signature = f.signature();
-        let id = simple_hash(&format!("{}{}{}", contract_id, filename, selector));
+        let id = function_id(&contract_id, &filename, &selector);
         let function_name = f.name.clone();
         Self {
             id,
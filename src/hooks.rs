@@ -0,0 +1,132 @@
+use eyre::{eyre, Result};
+use serde::Serialize;
+use std::{
+    io::{ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+use crate::plain_contract::PlainContract;
+
+/// A stored contract as given to the hooks: its id and the path it was read
+/// from, with the metadata and source of the contract
+#[derive(Serialize)]
+pub struct HookContract<'a> {
+    pub id: String,
+    pub path: &'a str,
+    #[serde(flatten)]
+    pub contract: &'a PlainContract,
+}
+
+/// Shell commands run after contracts are stored by `pre-process`, e.g. to
+/// enrich them in another database
+#[derive(Debug, Default)]
+pub struct Hooks {
+    /// Run once per contract with the contract json on stdin
+    pub contract: Option<String>,
+    /// Run once per stored chunk with the json array of its contracts on stdin
+    pub chunk: Option<String>,
+}
+
+/// Run the command with `sh -c`, writing `input` to its stdin, fails when the
+/// command exits with an error
+fn run_hook(command: &str, input: &[u8], env: &[(&str, &str)]) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the hook does not have to read its input
+        match stdin.write_all(input) {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    let status = child.wait()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(eyre!("Hook `{command}` failed with {status}")),
+    }
+}
+
+impl Hooks {
+    /// Run the hooks for a stored chunk of contracts with the paths they were
+    /// read from. The contract hook gets `CONTRACT_ID` and `CONTRACT_PATH` in
+    /// its environment, the chunk hook `CHUNK_SIZE`.
+    pub fn run(&self, contracts: &[(String, PlainContract)]) -> Result<()> {
+        if self.contract.is_none() && self.chunk.is_none() {
+            return Ok(());
+        }
+        let contracts = contracts
+            .iter()
+            .map(|(path, contract)| HookContract {
+                id: contract.id(),
+                path,
+                contract,
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(command) = &self.contract {
+            for c in contracts.iter() {
+                let env = [("CONTRACT_ID", c.id.as_str()), ("CONTRACT_PATH", c.path)];
+                run_hook(command, &serde_json::to_vec(c)?, &env)?;
+            }
+        }
+        if let Some(command) = &self.chunk {
+            let size = contracts.len().to_string();
+            run_hook(
+                command,
+                &serde_json::to_vec(&contracts)?,
+                &[("CHUNK_SIZE", &size)],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn run_contract_and_chunk_hooks() -> Result<()> {
+        let contract = PlainContract::single_file("contract A {}");
+        let id = contract.id();
+        let contracts = vec![("contracts/A".to_string(), contract)];
+        let output = tempfile::tempdir()?;
+        let output = output.path().to_string_lossy();
+
+        let hooks = Hooks {
+            contract: Some(format!(
+                r#"cat > "{output}/$CONTRACT_ID.json"; echo "$CONTRACT_PATH" > {output}/path"#
+            )),
+            chunk: Some(format!(
+                r#"cat > {output}/chunk.json; echo $CHUNK_SIZE > {output}/size"#
+            )),
+        };
+        hooks.run(&contracts)?;
+
+        let json: Value =
+            serde_json::from_str(&std::fs::read_to_string(format!("{output}/{id}.json"))?)?;
+        assert_eq!(json["id"], id.as_str());
+        assert_eq!(json["metadata"]["ContractName"], "A");
+        assert_eq!(
+            std::fs::read_to_string(format!("{output}/path"))?,
+            "contracts/A\n"
+        );
+        let chunk: Value =
+            serde_json::from_str(&std::fs::read_to_string(format!("{output}/chunk.json"))?)?;
+        assert_eq!(chunk[0]["path"], "contracts/A");
+        assert_eq!(std::fs::read_to_string(format!("{output}/size"))?, "1\n");
+
+        let failing = Hooks {
+            contract: Some("exit 3".into()),
+            chunk: None,
+        };
+        assert!(failing.run(&contracts).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use eyre::{eyre, Result};
+use log::{debug, info};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    plain_contract::{is_contract_file, PlainContract},
+    utils::HttpFetcher,
+};
+
+/// The Hugging Face hub, hosting the datasets
+pub const HF_ENDPOINT: &str = "https://huggingface.co";
+
+/// A file or folder of a dataset repository, as listed by the tree API
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    path: String,
+}
+
+impl TreeEntry {
+    fn is_file(&self) -> bool {
+        self.kind == "file"
+    }
+}
+
+/// A dataset repository on the Hugging Face hub, laid out like
+/// `Zellic/smart-contract-fiesta`, i.e. one folder with `metadata.json` per
+/// contract. Files are downloaded one contract at a time, nothing is cloned.
+pub struct HfDataset<'a> {
+    fetcher: &'a HttpFetcher,
+    repo: String,
+    revision: String,
+}
+
+/// Files of the contract folders among the listed files, by folder and file
+/// path. Only contract files are kept.
+fn contract_folders(paths: &[String]) -> BTreeMap<String, Vec<String>> {
+    let mut folders: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in paths {
+        let (folder, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if is_contract_file(name) {
+            folders.entry(folder.into()).or_default().push(path.clone());
+        }
+    }
+    folders.retain(|_, files| {
+        files
+            .iter()
+            .any(|f| f.rsplit('/').next() == Some("metadata.json"))
+    });
+    folders
+}
+
+impl<'a> HfDataset<'a> {
+    pub fn new(fetcher: &'a HttpFetcher, repo: &str, revision: &str) -> Self {
+        Self {
+            fetcher,
+            repo: repo.into(),
+            revision: revision.into(),
+        }
+    }
+
+    /// All entries below the path, following the pages of the listing
+    async fn list(&self, path: &str, recursive: bool) -> Result<Vec<TreeEntry>> {
+        let mut url = Some(format!(
+            "{HF_ENDPOINT}/api/datasets/{}/tree/{}/{path}?recursive={recursive}",
+            self.repo, self.revision
+        ));
+        let mut entries = vec![];
+        while let Some(page) = url {
+            let (body, next) = self.fetcher.get_page(&page).await?;
+            let page: Vec<TreeEntry> = serde_json::from_str(&body)
+                .map_err(|e| eyre!("Unexpected listing of {path} in {}: {e}", self.repo))?;
+            entries.extend(page);
+            url = next;
+        }
+        Ok(entries)
+    }
+
+    /// URL of a folder of the dataset, identifying the contract read from it
+    pub fn folder_url(&self, folder: &str) -> String {
+        format!(
+            "{HF_ENDPOINT}/datasets/{}/tree/{}/{folder}",
+            self.repo, self.revision
+        )
+    }
+
+    async fn download(&self, path: &str) -> Result<String> {
+        let url = format!(
+            "{HF_ENDPOINT}/datasets/{}/resolve/{}/{path}",
+            self.repo, self.revision
+        );
+        self.fetcher.get_text(&url).await
+    }
+
+    async fn read_contract(&self, folder: &str, paths: &[String]) -> Result<PlainContract> {
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let name = path.strip_prefix(folder).unwrap_or(path);
+            files.insert(
+                name.trim_start_matches('/').into(),
+                self.download(path).await?,
+            );
+        }
+        PlainContract::from_files(files)
+    }
+
+    /// Download the contract folders among the paths and pass them to `process`
+    /// with their URL, folders whose URL is in `skip` are not downloaded
+    async fn process_folders(
+        &self,
+        paths: &[String],
+        ignore_errors: bool,
+        skip: &HashSet<String>,
+        process: &mut impl FnMut(String, PlainContract) -> Result<()>,
+    ) -> Result<()> {
+        for (folder, paths) in contract_folders(paths) {
+            let url = self.folder_url(&folder);
+            if skip.contains(&url) {
+                continue;
+            }
+            match self.read_contract(&folder, &paths).await {
+                Ok(contract) => process(url, contract)?,
+                Err(error) if ignore_errors => {
+                    debug!("Process folder failed with error {error} {folder}")
+                }
+                Err(error) => {
+                    return Err(eyre!("Process folder failed with error {error} {folder}"))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Download the contracts below the path and pass each of them to
+    /// `process` with the URL of its folder. The tree is listed one subfolder of
+    /// the path at a time, e.g. `organized_contracts` of the fiesta dataset is
+    /// split into 256 folders.
+    pub async fn stream_contracts(
+        &self,
+        path: &str,
+        ignore_errors: bool,
+        skip: &HashSet<String>,
+        mut process: impl FnMut(String, PlainContract) -> Result<()>,
+    ) -> Result<()> {
+        let path = path.trim_matches('/');
+        let (files, dirs): (Vec<_>, Vec<_>) = self
+            .list(path, false)
+            .await?
+            .into_iter()
+            .partition(TreeEntry::is_file);
+        let files: Vec<_> = files.into_iter().map(|e| e.path).collect();
+        self.process_folders(&files, ignore_errors, skip, &mut process)
+            .await?;
+
+        for dir in dirs {
+            info!("Listing {} in {}", dir.path, self.repo);
+            let files: Vec<_> = self
+                .list(&dir.path, true)
+                .await?
+                .into_iter()
+                .filter(TreeEntry::is_file)
+                .map(|e| e.path)
+                .collect();
+            self.process_folders(&files, ignore_errors, skip, &mut process)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn group_contract_folders() {
+        let paths: Vec<String> = [
+            "organized_contracts/00/00a1/main.sol",
+            "organized_contracts/00/00a1/metadata.json",
+            "organized_contracts/00/00b2/Token.sol",
+            "organized_contracts/00/00b2/Ownable.sol",
+            "organized_contracts/00/00b2/metadata.json",
+            "organized_contracts/00/00c3/main.sol",
+            "organized_contracts/00/README.md",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let folders = contract_folders(&paths);
+        assert_eq!(
+            folders.keys().collect::<Vec<_>>(),
+            vec!["organized_contracts/00/00a1", "organized_contracts/00/00b2"]
+        );
+        assert_eq!(folders["organized_contracts/00/00b2"].len(), 3);
+    }
+}
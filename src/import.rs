@@ -0,0 +1,388 @@
+use duckdb::Connection;
+use eyre::{eyre, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    abi::decode_hex,
+    functions::{function_id, ContractFunction},
+    plain_contract::{ContractSource, Metadata, PlainContract, SourceFile, StandardJson},
+    rpc::DeployedBytecode,
+};
+
+/// Columns of a contract dump, `None` for required columns or the SQL default
+/// of an optional column
+const CONTRACT_COLUMNS: [(&str, &str, Option<&str>); 11] = [
+    ("id", "VARCHAR", Some("NULL")),
+    ("contract_name", "VARCHAR", None),
+    ("compiler_version", "VARCHAR", None),
+    ("optimization_used", "BOOLEAN", Some("false")),
+    ("runs", "INTEGER", Some("200")),
+    ("source_type", "VARCHAR", Some("NULL")),
+    ("source", "VARCHAR", None),
+    ("constructor_arguments", "VARCHAR", Some("NULL")),
+    ("chain_id", "UBIGINT", Some("NULL")),
+    ("address", "VARCHAR", Some("NULL")),
+    ("evm_version", "VARCHAR", Some("NULL")),
+];
+
+/// Columns of a function dump, see [`CONTRACT_COLUMNS`]
+const FUNCTION_COLUMNS: [(&str, &str, Option<&str>); 7] = [
+    ("contract_id", "VARCHAR", None),
+    ("contract_name", "VARCHAR", None),
+    ("function_name", "VARCHAR", None),
+    ("filename", "VARCHAR", None),
+    ("signature", "VARCHAR", None),
+    ("selector", "VARCHAR", None),
+    ("source_code", "VARCHAR", Some("''")),
+];
+
+/// Columns of an export of the BigQuery `crypto_ethereum.contracts` table, see
+/// [`CONTRACT_COLUMNS`]. `bytecode` is the runtime code of the contract.
+const BIGQUERY_COLUMNS: [(&str, &str, Option<&str>); 3] = [
+    ("address", "VARCHAR", None),
+    ("bytecode", "VARCHAR", None),
+    ("block_number", "UBIGINT", Some("NULL")),
+];
+
+/// A row of a contract dump
+#[derive(Debug, Serialize)]
+pub struct ContractRow {
+    /// Id of the contract in the exporting pipeline, referenced by the
+    /// functions of the dump
+    pub id: Option<String>,
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub optimization_used: bool,
+    pub runs: u32,
+    /// One of `single_sol`, `multi_sol`, `vyper` or `json`, solidity or
+    /// standard json input when missing
+    pub source_type: Option<String>,
+    /// The source code, a json object of file names and contents for
+    /// `multi_sol` contracts
+    pub source: String,
+    pub constructor_arguments: Option<String>,
+    pub chain_id: Option<u64>,
+    pub address: Option<String>,
+    pub evm_version: Option<String>,
+}
+
+impl ContractRow {
+    /// The row of a stored contract, e.g. in a release, read back by
+    /// [`Self::to_contract`] under the same id
+    pub fn from_contract(contract: &PlainContract) -> Result<Self> {
+        let source = match &contract.source {
+            ContractSource::SingleSolidity(file)
+            | ContractSource::Vyper(file)
+            | ContractSource::Json(file) => file.content.clone(),
+            ContractSource::MultiSolidity(files) => serde_json::to_string(
+                &files
+                    .iter()
+                    .map(|f| (&f.name, &f.content))
+                    .collect::<BTreeMap<_, _>>(),
+            )?,
+        };
+        let metadata = &contract.metadata;
+        Ok(Self {
+            id: Some(contract.id()),
+            contract_name: metadata.contract_name.clone(),
+            compiler_version: metadata.compiler_version.clone(),
+            optimization_used: metadata.optimization_used,
+            runs: metadata.runs,
+            source_type: Some(contract.source.source_type().to_string()),
+            source,
+            constructor_arguments: metadata.constructor_arguments.clone(),
+            chain_id: metadata.chain_id,
+            address: metadata.address.clone(),
+            evm_version: metadata.evm_version.clone(),
+        })
+    }
+
+    /// The contract of the row, the files of `multi_sol` sources are kept
+    /// with their names
+    pub fn to_contract(&self) -> Result<PlainContract> {
+        let metadata = Metadata {
+            contract_name: self.contract_name.clone(),
+            compiler_version: self.compiler_version.clone(),
+            runs: self.runs,
+            optimization_used: self.optimization_used,
+            bytecode_hash: "".into(),
+            constructor_arguments: self.constructor_arguments.clone(),
+            chain_id: self.chain_id,
+            address: self.address.as_deref().map(str::to_lowercase),
+            evm_version: self.evm_version.clone(),
+            via_ir: None,
+        };
+        let source_type = match self.source_type.as_deref() {
+            Some(source_type) => source_type,
+            None if serde_json::from_str::<StandardJson>(&self.source).is_ok() => "json",
+            None => "single_sol",
+        };
+        let file = |name: &str| SourceFile {
+            name: name.into(),
+            content: self.source.clone(),
+        };
+        let source = match source_type {
+            "single_sol" => ContractSource::SingleSolidity(file("main.sol")),
+            "vyper" => ContractSource::Vyper(file("main.vy")),
+            "json" => ContractSource::Json(file("contract.json")),
+            "multi_sol" => {
+                let files: BTreeMap<String, String> =
+                    serde_json::from_str(&self.source).map_err(|e| {
+                        eyre!("Invalid multi_sol source of {}: {e}", self.contract_name)
+                    })?;
+                ContractSource::MultiSolidity(
+                    files
+                        .into_iter()
+                        .map(|(name, content)| SourceFile { name, content })
+                        .collect(),
+                )
+            }
+            source_type => return Err(eyre!("Unknown source type {source_type}")),
+        };
+        Ok(PlainContract::new(metadata, source))
+    }
+}
+
+/// The DuckDB table function reading the dump, by its extension. Globs are
+/// expanded by DuckDB, e.g. `dump/*.parquet`. CSV files need a header row,
+/// their values are read as text and cast to the column types.
+fn reader(path: &str) -> Result<String> {
+    let quoted = path.replace('\'', "''");
+    if path.ends_with(".parquet") {
+        Ok(format!("read_parquet('{quoted}')"))
+    } else if [".jsonl", ".ndjson", ".json"]
+        .iter()
+        .any(|ext| path.ends_with(ext))
+    {
+        Ok(format!(
+            "read_json_auto('{quoted}', format = 'newline_delimited')"
+        ))
+    } else if path.ends_with(".csv") {
+        Ok(format!(
+            "read_csv_auto('{quoted}', header = true, all_varchar = true)"
+        ))
+    } else {
+        Err(eyre!(
+            "Unsupported dump {path}, expected parquet, jsonl or csv"
+        ))
+    }
+}
+
+/// A query selecting the columns from the dump, missing optional columns and
+/// null values are filled with their default
+fn select(conn: &Connection, path: &str, columns: &[(&str, &str, Option<&str>)]) -> Result<String> {
+    let reader = reader(path)?;
+    let mut stmt = conn.prepare(&format!("DESCRIBE SELECT * FROM {reader}"))?;
+    let present = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let mut expressions = vec![];
+    for (name, ty, default) in columns {
+        let expression = match (present.contains(*name), default) {
+            (true, Some(default)) => format!("COALESCE(\"{name}\", {default})"),
+            (true, None) => format!("\"{name}\""),
+            (false, Some(default)) => default.to_string(),
+            (false, None) => return Err(eyre!("Missing column {name} in {path}")),
+        };
+        expressions.push(format!("CAST({expression} AS {ty})"));
+    }
+    Ok(format!("SELECT {} FROM {reader}", expressions.join(", ")))
+}
+
+/// Read a Parquet or JSONL dump of contracts, with the error of each row
+/// having a null required column in place of the row
+pub fn read_contracts(conn: &Connection, path: &str) -> Result<Vec<Result<ContractRow>>> {
+    let mut stmt = conn.prepare(&select(conn, path, &CONTRACT_COLUMNS)?)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ContractRow {
+            id: row.get(0)?,
+            contract_name: row.get(1)?,
+            compiler_version: row.get(2)?,
+            optimization_used: row.get(3)?,
+            runs: row.get(4)?,
+            source_type: row.get(5)?,
+            source: row.get(6)?,
+            constructor_arguments: row.get(7)?,
+            chain_id: row.get(8)?,
+            address: row.get(9)?,
+            evm_version: row.get(10)?,
+        })
+    })?;
+
+    Ok(rows.map(|row| Ok(row?)).collect())
+}
+
+/// Read a Parquet or JSONL dump of functions. Contract ids of the exporting
+/// pipeline are replaced by the ids of the imported contracts, other ids are
+/// kept as they are.
+pub fn read_functions(
+    conn: &Connection,
+    path: &str,
+    contract_ids: &HashMap<String, String>,
+) -> Result<Vec<ContractFunction>> {
+    let mut stmt = conn.prepare(&select(conn, path, &FUNCTION_COLUMNS)?)?;
+    let rows = stmt.query_map([], |row| {
+        let contract_id: String = row.get(0)?;
+        let contract_id = contract_ids
+            .get(&contract_id)
+            .cloned()
+            .unwrap_or(contract_id);
+        let filename: String = row.get(3)?;
+        let selector: String = row.get(5)?;
+        Ok(ContractFunction {
+            id: function_id(&contract_id, &filename, &selector),
+            contract_id,
+            contract_name: row.get(1)?,
+            function_name: row.get(2)?,
+            filename,
+            signature: row.get(4)?,
+            selector,
+            source_code: row.get(6)?,
+        })
+    })?;
+
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Read an export of the BigQuery `crypto_ethereum.contracts` table as the
+/// runtime code of contracts deployed on the chain
+pub fn read_bigquery_contracts(
+    conn: &Connection,
+    path: &str,
+    chain_id: u64,
+) -> Result<Vec<DeployedBytecode>> {
+    let mut stmt = conn.prepare(&select(conn, path, &BIGQUERY_COLUMNS)?)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<u64>>(2)?,
+        ))
+    })?;
+
+    let mut bytecodes = vec![];
+    for row in rows {
+        let (address, code, block_number) = row?;
+        let code = decode_hex(&code).map_err(|e| eyre!("Invalid bytecode of {address}: {e}"))?;
+        let mut bytecode = DeployedBytecode::new(chain_id, &address, &code, None);
+        bytecode.block_number = block_number;
+        bytecodes.push(bytecode);
+    }
+    Ok(bytecodes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_contract_dumps() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jsonl = dir.path().join("contracts.jsonl");
+        std::fs::write(
+            &jsonl,
+            [
+                r#"{"id": "x1", "contract_name": "A", "compiler_version": "v0.8.19+commit.7dd6d404", "source": "contract A {}"}"#,
+                r#"{"id": "x2", "contract_name": "B", "compiler_version": "v0.8.19+commit.7dd6d404", "optimization_used": true, "runs": 1000, "source_type": "multi_sol", "chain_id": 137, "address": "0xAbC0000000000000000000000000000000000001", "source": "{\"B.sol\": \"import './L.sol'; contract B {}\", \"L.sol\": \"library L {}\"}"}"#,
+                // a null required column fails this row only
+                r#"{"id": "x3", "contract_name": "C", "compiler_version": "v0.8.19+commit.7dd6d404", "source": null}"#,
+                // a main.sol of a multi source contract keeps the other files
+                r#"{"id": "x4", "contract_name": "D", "compiler_version": "v0.8.19+commit.7dd6d404", "source_type": "multi_sol", "source": "{\"main.sol\": \"import './L.sol'; contract D {}\", \"L.sol\": \"library L {}\"}"}"#,
+            ]
+            .join("\n"),
+        )?;
+        let conn = Connection::open_in_memory()?;
+        let mut rows = read_contracts(&conn, &jsonl.to_string_lossy())?;
+        assert_eq!(rows.len(), 4);
+        let d = rows.remove(3)?.to_contract()?;
+        let ContractSource::MultiSolidity(files) = &d.source else {
+            panic!("Expected a multi source contract");
+        };
+        assert_eq!(
+            files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+            ["L.sol", "main.sol"]
+        );
+        assert!(rows.remove(2).is_err());
+        let rows = rows.into_iter().collect::<Result<Vec<_>>>()?;
+
+        let a = rows[0].to_contract()?;
+        assert!(matches!(a.source, ContractSource::SingleSolidity(_)));
+        assert_eq!(a.metadata.runs, 200);
+        let b = rows[1].to_contract()?;
+        let ContractSource::MultiSolidity(files) = &b.source else {
+            panic!("Expected a multi source contract");
+        };
+        assert_eq!(files.len(), 2);
+        assert!(b.metadata.optimization_used);
+        assert_eq!(b.metadata.chain_id, Some(137));
+        assert_eq!(
+            b.metadata.address.as_deref(),
+            Some("0xabc0000000000000000000000000000000000001")
+        );
+        assert_eq!(a.metadata.chain_id, None);
+        for contract in [&a, &b] {
+            let row = ContractRow::from_contract(contract)?;
+            assert_eq!(row.id, Some(contract.id()));
+            assert_eq!(row.to_contract()?.id(), contract.id());
+        }
+
+        // the same dump as parquet, written by DuckDB
+        let parquet = dir.path().join("contracts.parquet");
+        conn.execute_batch(&format!(
+            "COPY (SELECT * FROM {}) TO '{}' (FORMAT PARQUET)",
+            reader(&jsonl.to_string_lossy())?,
+            parquet.to_string_lossy()
+        ))?;
+        assert_eq!(read_contracts(&conn, &parquet.to_string_lossy())?.len(), 4);
+
+        let functions = dir.path().join("functions.jsonl");
+        std::fs::write(
+            &functions,
+            r#"{"contract_id": "x1", "contract_name": "A", "function_name": "f", "filename": "main.sol", "signature": "f()", "selector": "0x26121ff0"}"#,
+        )?;
+        let ids = HashMap::from([("x1".to_string(), a.id())]);
+        let functions = read_functions(&conn, &functions.to_string_lossy(), &ids)?;
+        assert_eq!(functions[0].contract_id, a.id());
+
+        assert!(read_contracts(&conn, &dump_without_source(&dir)?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_bigquery_export() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let csv = dir.path().join("contracts-000000000000.csv");
+        std::fs::write(
+            &csv,
+            "address,bytecode,function_sighashes,is_erc20,is_erc721,block_timestamp,block_number,block_hash\n\
+             0xAB00000000000000000000000000000000000001,0x6080604052,,false,false,2015-08-07 03:30:33 UTC,48643,0x01\n",
+        )?;
+        let conn = Connection::open_in_memory()?;
+        let bytecodes = read_bigquery_contracts(&conn, &csv.to_string_lossy(), 1)?;
+        assert_eq!(
+            bytecodes,
+            vec![DeployedBytecode {
+                chain_id: 1,
+                address: "0xab00000000000000000000000000000000000001".into(),
+                code_hash: alloy_primitives::keccak256([0x60, 0x80, 0x60, 0x40, 0x52]).to_string(),
+                code: "0x6080604052".into(),
+                contract_id: None,
+                block_number: Some(48643),
+            }]
+        );
+
+        Ok(())
+    }
+
+    fn dump_without_source(dir: &tempfile::TempDir) -> Result<String> {
+        let path = dir.path().join("invalid.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"contract_name": "A", "compiler_version": "0.8.19"}"#,
+        )?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+}
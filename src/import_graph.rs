@@ -0,0 +1,203 @@
+use eyre::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Write, sync::OnceLock};
+
+use crate::{plain_contract::PlainContract, pragma::strip_comments};
+
+/// An `import` statement of a solidity source file of a contract
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ImportEdge {
+    pub contract_id: String,
+    pub from_file: String,
+    /// Position of the statement among the imports of the importing file
+    pub position: u32,
+    /// The imported file among the sources of the contract, else the path as
+    /// written, e.g. a remapped `@openzeppelin/...` path
+    pub to_file: String,
+    /// Comma separated names imported with `import {A, B as C} from`, `*` for
+    /// `import * as X from` and `import "x" as X`, empty when the whole file is
+    /// imported
+    pub symbols: String,
+}
+
+/// Imported paths and symbols of a comment free source
+fn parse_imports(source: &str) -> Vec<(String, String)> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"\bimport\s+(?:(\{[^}]*\})\s*from\s*|(\*)\s*as\s+[\w$]+\s+from\s*)?["']([^"']+)["'](\s*as\s+[\w$]+)?"#,
+        )
+        .unwrap()
+    });
+    pattern
+        .captures_iter(source)
+        .map(|c| {
+            let symbols = match (c.get(1), c.get(2).or(c.get(4))) {
+                (Some(names), _) => names
+                    .as_str()
+                    .trim_matches(|c| c == '{' || c == '}')
+                    .split(',')
+                    .filter_map(|name| name.split_whitespace().next())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                (None, Some(_)) => "*".into(),
+                (None, None) => "".into(),
+            };
+            (c[3].to_string(), symbols)
+        })
+        .collect()
+}
+
+/// Resolve `./` and `../` segments of a path relative to the importing file
+fn resolve_relative(from_file: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = from_file.split('/').collect();
+    parts.pop();
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                parts.pop();
+            }
+            segment => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+/// The source file an import refers to: the relative path resolved against
+/// the importing file, or the file whose path ends with the imported one,
+/// else the path as written
+fn resolve_import(from_file: &str, path: &str, files: &[&str]) -> String {
+    let resolved = match path.starts_with("./") || path.starts_with("../") {
+        true => resolve_relative(from_file, path),
+        false => path.to_string(),
+    };
+    files
+        .iter()
+        .find(|f| **f == resolved)
+        .or_else(|| {
+            files
+                .iter()
+                .find(|f| f.ends_with(&format!("/{}", resolved.trim_start_matches('/'))))
+        })
+        .map(|f| f.to_string())
+        .unwrap_or(resolved)
+}
+
+/// The `import` statements of the solidity sources of the contract
+pub fn find_imports(contract: &PlainContract) -> Result<Vec<ImportEdge>> {
+    let files = contract.get_source_files()?;
+    let names = files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+    let contract_id = contract.id();
+
+    let mut edges = vec![];
+    for file in files.iter().filter(|f| f.name.ends_with(".sol")) {
+        let imports = parse_imports(&strip_comments(&file.content));
+        for (position, (path, symbols)) in imports.into_iter().enumerate() {
+            edges.push(ImportEdge {
+                contract_id: contract_id.clone(),
+                from_file: file.name.clone(),
+                position: position as u32,
+                to_file: resolve_import(&file.name, &path, &names),
+                symbols,
+            });
+        }
+    }
+    Ok(edges)
+}
+
+/// The import graph of a contract in the DOT format of graphviz, edges are
+/// labelled with the imported symbols
+pub fn to_dot(contract_id: &str, edges: &[ImportEdge]) -> String {
+    let files: BTreeSet<&str> = edges
+        .iter()
+        .flat_map(|e| [e.from_file.as_str(), e.to_file.as_str()])
+        .collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph \"{contract_id}\" {{");
+    let _ = writeln!(out, "  rankdir=LR;");
+    for file in files {
+        let _ = writeln!(out, "  \"{file}\";");
+    }
+    for e in edges {
+        match e.symbols.is_empty() {
+            true => {
+                let _ = writeln!(out, "  \"{}\" -> \"{}\";", e.from_file, e.to_file);
+            }
+            false => {
+                let _ = writeln!(
+                    out,
+                    "  \"{}\" -> \"{}\" [label=\"{}\"];",
+                    e.from_file, e.to_file, e.symbols
+                );
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_import_statements() {
+        let source = r#"
+import "./IERC20.sol";
+import {Ownable, Context as Ctx} from "@openzeppelin/contracts/access/Ownable.sol";
+import * as Math from '../utils/Math.sol';
+import "./lib/Lib.sol" as Lib;
+"#;
+        assert_eq!(
+            parse_imports(source),
+            [
+                ("./IERC20.sol".to_string(), "".to_string()),
+                (
+                    "@openzeppelin/contracts/access/Ownable.sol".to_string(),
+                    "Ownable,Context".to_string()
+                ),
+                ("../utils/Math.sol".to_string(), "*".to_string()),
+                ("./lib/Lib.sol".to_string(), "*".to_string()),
+            ]
+        );
+
+        let files = [
+            "contracts/token/Token.sol",
+            "contracts/token/IERC20.sol",
+            "contracts/utils/Math.sol",
+            "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+        ];
+        let from = "contracts/token/Token.sol";
+        assert_eq!(
+            resolve_import(from, "./IERC20.sol", &files),
+            "contracts/token/IERC20.sol"
+        );
+        assert_eq!(
+            resolve_import(from, "../utils/Math.sol", &files),
+            "contracts/utils/Math.sol"
+        );
+        assert_eq!(
+            resolve_import(from, "@openzeppelin/contracts/access/Ownable.sol", &files),
+            "node_modules/@openzeppelin/contracts/access/Ownable.sol"
+        );
+        assert_eq!(
+            resolve_import(from, "@openzeppelin/contracts/utils/Address.sol", &files),
+            "@openzeppelin/contracts/utils/Address.sol"
+        );
+
+        let edges = [ImportEdge {
+            contract_id: "a".into(),
+            from_file: from.into(),
+            position: 0,
+            to_file: "contracts/utils/Math.sol".into(),
+            symbols: "*".into(),
+        }];
+        let dot = to_dot("a", &edges);
+        assert!(dot.starts_with("digraph \"a\" {\n"));
+        assert!(dot.contains(
+            "\"contracts/token/Token.sol\" -> \"contracts/utils/Math.sol\" [label=\"*\"];"
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use eyre::Result;
+use log::warn;
+
+use crate::{
+    abi::{extract_abis, ContractAbi},
+    assembly::{extract_assembly, AssemblyBlock},
+    ast_query::{extract_source_asts, SourceAst},
+    bytecode::{extract_bytecodes, ArtifactBytecode},
+    calls::{extract_external_calls, ExternalCall},
+    constructor::ConstructorArgument,
+    custom_errors::{extract_errors, ContractError},
+    definitions::{extract_definitions, TypeDefinition},
+    deployment::{extract_clone_factories, CloneFactory},
+    events::{extract_events, ContractEvent},
+    functions::ContractFunction,
+    layout::{extract_storage_layouts, extract_storage_slots, StorageLayoutJson, StorageSlot},
+    literals::{
+        extract_function_definitions, extract_literals, FunctionDefinition, FunctionLiteral,
+    },
+    metadata_hash::{extract_metadata_hashes, MetadataHash},
+    modifiers::{
+        extract_function_modifiers, extract_modifiers, ContractModifier, FunctionModifier,
+    },
+    plain_contract::PlainContract,
+    plugins::PluginRow,
+    similarity::{extract_opcode_signatures, OpcodeSignature},
+    size::{extract_sizes, ArtifactSize, FunctionSize},
+    source_map::{extract_source_maps, ArtifactSourceMap, SourceFileId},
+    state_variables::{extract_state_variables, StateVariable},
+};
+
+/// A contract failing to compile, with the compiler error or the diagnostics
+/// of the compiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub contract_id: String,
+    pub compiler_version: String,
+    pub error: String,
+}
+
+/// A contract which failed to compile with its recorded compiler version and
+/// compiled with another patch release of the same minor series
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompilerFallback {
+    pub contract_id: String,
+    pub recorded_version: String,
+    pub compiled_version: String,
+}
+
+/// Rows extracted from the compilation output of one or more contracts
+#[derive(Debug, Default)]
+pub struct IndexOutput {
+    pub functions: Vec<ContractFunction>,
+    pub constructor_arguments: Vec<ConstructorArgument>,
+    pub artifact_sizes: Vec<ArtifactSize>,
+    pub function_sizes: Vec<FunctionSize>,
+    pub bytecodes: Vec<ArtifactBytecode>,
+    pub abis: Vec<ContractAbi>,
+    pub source_maps: Vec<ArtifactSourceMap>,
+    pub source_file_ids: Vec<SourceFileId>,
+    pub metadata_hashes: Vec<MetadataHash>,
+    pub storage_slots: Vec<StorageSlot>,
+    pub storage_layouts: Vec<StorageLayoutJson>,
+    pub events: Vec<ContractEvent>,
+    pub errors: Vec<ContractError>,
+    pub clone_factories: Vec<CloneFactory>,
+    pub literals: Vec<FunctionLiteral>,
+    pub function_definitions: Vec<FunctionDefinition>,
+    pub type_definitions: Vec<TypeDefinition>,
+    pub assembly_blocks: Vec<AssemblyBlock>,
+    pub external_calls: Vec<ExternalCall>,
+    pub modifiers: Vec<ContractModifier>,
+    pub function_modifiers: Vec<FunctionModifier>,
+    pub state_variables: Vec<StateVariable>,
+    pub opcode_signatures: Vec<OpcodeSignature>,
+    pub source_asts: Vec<SourceAst>,
+    /// Rows of the extractor plugins, only filled by `index-functions --plugin`
+    pub plugin_rows: Vec<PluginRow>,
+    /// Contracts which failed to compile
+    pub compile_errors: Vec<CompileError>,
+    /// Contracts compiled with another compiler version than recorded
+    pub compiler_fallbacks: Vec<CompilerFallback>,
+    /// Ids of the contracts compiled without errors, whose previous compile
+    /// errors are cleared
+    pub compiled: Vec<String>,
+}
+
+impl IndexOutput {
+    /// Extract all indexed rows from a compiled contract
+    pub fn from_contract(contract: &PlainContract) -> Result<Self> {
+        let (artifact_sizes, function_sizes) = extract_sizes(contract)?;
+        let (source_maps, source_file_ids) = extract_source_maps(contract)?;
+        Ok(IndexOutput {
+            functions: contract.extract_functions()?,
+            constructor_arguments: contract
+                .extract_constructor_arguments()
+                .unwrap_or_else(|e| {
+                    warn!(
+                        "Failed to decode constructor arguments of contract {}: {e}",
+                        contract.id()
+                    );
+                    vec![]
+                }),
+            artifact_sizes,
+            function_sizes,
+            source_maps,
+            source_file_ids,
+            bytecodes: extract_bytecodes(contract)?,
+            abis: extract_abis(contract)?,
+            metadata_hashes: extract_metadata_hashes(contract)?,
+            storage_slots: extract_storage_slots(contract)?,
+            storage_layouts: extract_storage_layouts(contract)?,
+            events: extract_events(contract)?,
+            errors: extract_errors(contract)?,
+            clone_factories: extract_clone_factories(contract)?,
+            literals: extract_literals(contract)?,
+            function_definitions: extract_function_definitions(contract)?,
+            type_definitions: extract_definitions(contract)?,
+            assembly_blocks: extract_assembly(contract)?,
+            external_calls: extract_external_calls(contract)?,
+            modifiers: extract_modifiers(contract)?,
+            function_modifiers: extract_function_modifiers(contract)?,
+            state_variables: extract_state_variables(contract)?,
+            opcode_signatures: extract_opcode_signatures(contract)?,
+            source_asts: extract_source_asts(contract)?,
+            plugin_rows: vec![],
+            compile_errors: vec![],
+            compiler_fallbacks: vec![],
+            compiled: vec![contract.id()],
+        })
+    }
+
+    /// Extract the rows of a compiled vyper contract, the extractors reading
+    /// solidity ASTs are left out
+    pub fn from_vyper_contract(contract: &PlainContract) -> Result<Self> {
+        Ok(IndexOutput {
+            functions: contract.extract_functions()?,
+            events: extract_events(contract)?,
+            errors: extract_errors(contract)?,
+            opcode_signatures: extract_opcode_signatures(contract)?,
+            bytecodes: extract_bytecodes(contract)?,
+            abis: extract_abis(contract)?,
+            compiled: vec![contract.id()],
+            ..Default::default()
+        })
+    }
+
+    pub fn extend(&mut self, other: IndexOutput) {
+        self.functions.extend(other.functions);
+        self.constructor_arguments
+            .extend(other.constructor_arguments);
+        self.artifact_sizes.extend(other.artifact_sizes);
+        self.function_sizes.extend(other.function_sizes);
+        self.bytecodes.extend(other.bytecodes);
+        self.abis.extend(other.abis);
+        self.source_maps.extend(other.source_maps);
+        self.source_file_ids.extend(other.source_file_ids);
+        self.metadata_hashes.extend(other.metadata_hashes);
+        self.storage_slots.extend(other.storage_slots);
+        self.storage_layouts.extend(other.storage_layouts);
+        self.events.extend(other.events);
+        self.errors.extend(other.errors);
+        self.clone_factories.extend(other.clone_factories);
+        self.literals.extend(other.literals);
+        self.function_definitions.extend(other.function_definitions);
+        self.type_definitions.extend(other.type_definitions);
+        self.assembly_blocks.extend(other.assembly_blocks);
+        self.external_calls.extend(other.external_calls);
+        self.modifiers.extend(other.modifiers);
+        self.function_modifiers.extend(other.function_modifiers);
+        self.state_variables.extend(other.state_variables);
+        self.opcode_signatures.extend(other.opcode_signatures);
+        self.source_asts.extend(other.source_asts);
+        self.plugin_rows.extend(other.plugin_rows);
+        self.compile_errors.extend(other.compile_errors);
+        self.compiler_fallbacks.extend(other.compiler_fallbacks);
+        self.compiled.extend(other.compiled);
+    }
+}
@@ -0,0 +1,65 @@
+use alloy_json_abi::JsonAbi;
+use eyre::{ContextCompat, Result};
+use std::path::Path;
+
+use crate::plain_contract::{ContractSource, Metadata, PlainContract, SourceFile};
+
+/// Load an interface ABI from a json file or by compiling a solidity file.
+///
+/// The json file can either be a plain ABI array or a compiler artifact with
+/// an `abi` field. When the solidity file defines several contracts and no
+/// `name` is given, the ABIs of all of them are merged.
+pub async fn load_interface(
+    path: &str,
+    name: Option<&str>,
+    solc_version: Option<&str>,
+) -> Result<JsonAbi> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let file_name = Path::new(path)
+        .file_name()
+        .context("Interface path has no file name")?
+        .to_string_lossy()
+        .into_owned();
+
+    if !file_name.ends_with(".sol") {
+        if let Ok(abi) = serde_json::from_str::<JsonAbi>(&content) {
+            return Ok(abi);
+        }
+        let artifact: serde_json::Value = serde_json::from_str(&content)?;
+        let abi = artifact.get("abi").context("No abi field in json file")?;
+        return Ok(serde_json::from_value(abi.clone())?);
+    }
+
+    let compiler_version =
+        solc_version.context("A solc version is required to compile a solidity interface")?;
+    let metadata = Metadata {
+        contract_name: file_name.trim_end_matches(".sol").into(),
+        compiler_version: compiler_version.into(),
+        runs: 0,
+        optimization_used: false,
+        bytecode_hash: "".into(),
+        constructor_arguments: None,
+        chain_id: None,
+        address: None,
+        evm_version: None,
+        via_ir: None,
+    };
+    let source = ContractSource::SingleSolidity(SourceFile {
+        name: file_name,
+        content,
+    });
+    let mut contract = PlainContract::new(metadata, source);
+    let output = contract.compile().await?;
+    if output.has_compiler_errors() {
+        return Err(eyre::eyre!("Failed to compile interface: {output}"));
+    }
+
+    let merged = output
+        .artifacts()
+        .filter(|(contract_name, _)| name.is_none_or(|name| name == contract_name))
+        .filter_map(|(_, artifact)| artifact.abi.as_ref())
+        .flat_map(|abi| abi.items())
+        .collect();
+
+    Ok(merged)
+}
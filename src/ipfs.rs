@@ -0,0 +1,233 @@
+use alloy_primitives::keccak256;
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::{
+    abi::decode_hex, bytecode::cbor_metadata, plain_contract::PlainContract, utils::HttpFetcher,
+};
+
+/// Public gateway used when none is given
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io";
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Base58 (bitcoin alphabet) encoding of the bytes, as used by CIDv0
+fn base58(bytes: &[u8]) -> String {
+    // digits in base 58, least significant first
+    let mut digits: Vec<u8> = vec![];
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|d| BASE58_ALPHABET[*d as usize] as char),
+        )
+        .collect()
+}
+
+/// Size of the chunks files are split into when added to IPFS
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Protobuf varint encoding of the value
+fn varint(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Protobuf length delimited field
+fn bytes_field(tag: u8, bytes: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    varint(bytes.len(), out);
+    out.extend_from_slice(bytes);
+}
+
+/// sha2-256 multihash of the block
+fn multihash(block: &[u8]) -> Vec<u8> {
+    [&[0x12, 0x20][..], &Sha256::digest(block)].concat()
+}
+
+/// dag-pb block of a file chunk without links
+fn leaf_block(chunk: &[u8]) -> Vec<u8> {
+    // unixfs Data { Type: File, Data, filesize }
+    let mut data = vec![0x08, 0x02];
+    if !chunk.is_empty() {
+        bytes_field(0x12, chunk, &mut data);
+    }
+    data.push(0x18);
+    varint(chunk.len(), &mut data);
+
+    let mut block = vec![];
+    bytes_field(0x0a, &data, &mut block);
+    block
+}
+
+/// CIDv0 of the content added to IPFS as a file, the way solc hashes the
+/// metadata: 256 KiB chunks linked from a single root when it does not fit one
+pub fn content_cid(content: &[u8]) -> String {
+    if content.len() <= CHUNK_SIZE {
+        return base58(&multihash(&leaf_block(content)));
+    }
+
+    let mut block = vec![];
+    // unixfs Data { Type: File, filesize, blocksizes }
+    let mut data = vec![0x08, 0x02, 0x18];
+    varint(content.len(), &mut data);
+    for chunk in content.chunks(CHUNK_SIZE) {
+        let leaf = leaf_block(chunk);
+        // PBLink { Hash, Name: "", Tsize }
+        let mut link = vec![];
+        bytes_field(0x0a, &multihash(&leaf), &mut link);
+        bytes_field(0x12, &[], &mut link);
+        link.push(0x18);
+        varint(leaf.len(), &mut link);
+        bytes_field(0x12, &link, &mut block);
+
+        data.push(0x20);
+        varint(chunk.len(), &mut data);
+    }
+    bytes_field(0x0a, &data, &mut block);
+    base58(&multihash(&block))
+}
+
+/// CIDv0 of a metadata hash decoded from the bytecode, e.g. `ipfs:0x1220...`,
+/// `None` for Swarm hashes
+pub fn ipfs_cid(hash: &str) -> Option<String> {
+    let multihash = decode_hex(hash.strip_prefix("ipfs:")?).ok()?;
+    Some(base58(&multihash))
+}
+
+/// CID of a source from the `urls` of the metadata, e.g. `dweb:/ipfs/Qm...`
+fn source_cid(urls: &[String]) -> Option<&str> {
+    urls.iter().find_map(|url| url.strip_prefix("dweb:/ipfs/"))
+}
+
+/// Sources of the solidity metadata json, with what is needed to fetch them
+#[derive(Deserialize)]
+struct MetadataSources {
+    sources: BTreeMap<String, MetadataSource>,
+}
+
+#[derive(Deserialize)]
+struct MetadataSource {
+    /// `0x` prefixed keccak256 of the content
+    keccak256: Option<String>,
+    #[serde(default)]
+    urls: Vec<String>,
+    /// Embedded source, when compiled with `useLiteralContent`
+    content: Option<String>,
+}
+
+/// Client of an IPFS HTTP gateway, responses are content addressed and cached
+/// by the fetcher like any other response
+pub struct IpfsClient<'a> {
+    fetcher: &'a HttpFetcher,
+    gateway: String,
+}
+
+impl<'a> IpfsClient<'a> {
+    pub fn new(fetcher: &'a HttpFetcher, gateway: &str) -> Self {
+        Self {
+            fetcher,
+            gateway: gateway.trim_end_matches('/').into(),
+        }
+    }
+
+    pub async fn get(&self, cid: &str) -> Result<String> {
+        self.fetcher
+            .get_text(&format!("{}/ipfs/{cid}", self.gateway))
+            .await
+    }
+
+    /// The contract deployed with the runtime code, from the metadata whose
+    /// hash solc appended to the code and the sources it lists. `None` when the
+    /// code has no IPFS metadata hash.
+    pub async fn resolve(
+        &self,
+        code: &[u8],
+        chain_id: Option<u64>,
+        address: Option<String>,
+    ) -> Result<Option<PlainContract>> {
+        let Some(cid) = cbor_metadata(code)
+            .and_then(|m| m.hash)
+            .and_then(|hash| ipfs_cid(&hash))
+        else {
+            return Ok(None);
+        };
+        let metadata = self.get(&cid).await?;
+        if content_cid(metadata.as_bytes()) != cid {
+            return Err(eyre!("Metadata fetched from {cid} does not match its hash"));
+        }
+        let listed: MetadataSources =
+            serde_json::from_str(&metadata).map_err(|e| eyre!("Invalid metadata {cid}: {e}"))?;
+
+        let mut sources = BTreeMap::new();
+        for (name, source) in listed.sources {
+            if source.content.is_some() {
+                continue;
+            }
+            let cid = source_cid(&source.urls)
+                .with_context(|| format!("No IPFS url for source {name}"))?;
+            let content = self.get(cid).await?;
+            let verified = match &source.keccak256 {
+                Some(expected) => {
+                    keccak256(content.as_bytes()).to_string() == expected.to_lowercase()
+                }
+                None => content_cid(content.as_bytes()) == cid,
+            };
+            if !verified {
+                return Err(eyre!("Hash mismatch of source {name} fetched from {cid}"));
+            }
+            sources.insert(name, content);
+        }
+        PlainContract::from_solc_metadata(&metadata, sources, chain_id, address).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metadata_hash_to_cid() {
+        assert_eq!(base58(&[0, 0, 1]), "112");
+        assert_eq!(
+            ipfs_cid("ipfs:0x12202cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+                .as_deref(),
+            Some("QmRN6wdp1S2A5EtjW9A3M1vKSBuQQGcgvuhoMUoEz4iiT5")
+        );
+        assert_eq!(ipfs_cid("bzzr1:0x1234"), None);
+        assert_eq!(
+            content_cid(b""),
+            "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"
+        );
+        assert_eq!(
+            content_cid(b"hello world\n"),
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o"
+        );
+        assert_eq!(
+            source_cid(&[
+                "bzz-raw://abcd".into(),
+                "dweb:/ipfs/QmRN6wdp1S2A5EtjW9A3M1vKSBuQQGcgvuhoMUoEz4iiT5".into()
+            ]),
+            Some("QmRN6wdp1S2A5EtjW9A3M1vKSBuQQGcgvuhoMUoEz4iiT5")
+        );
+    }
+}
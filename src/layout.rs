@@ -0,0 +1,226 @@
+use alloy_primitives::{keccak256, U256};
+use eyre::{ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::plain_contract::PlainContract;
+
+/// A state variable in the storage layout of a compiled contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageSlot {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    pub label: String,
+    /// Slot number in decimal
+    pub slot: String,
+    /// Byte offset of the variable inside the slot
+    pub offset: i64,
+    /// Type as written in solidity, e.g. `mapping(address => uint256)`
+    pub ty: String,
+    /// Identifier of the type in the `types` of the storage layout, e.g.
+    /// `t_mapping(t_address,t_uint256)`
+    pub type_id: String,
+    /// Storage encoding of the type: `inplace`, `mapping`, `dynamic_array` or `bytes`
+    pub encoding: String,
+}
+
+/// The `types` of the storage layout of a compiled contract as returned by
+/// solc, with the members of structs and the key and value types of mappings.
+/// The variables of the layout are the [`StorageSlot`]s of the contract.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageLayoutJson {
+    pub contract_id: String,
+    pub source_path: String,
+    pub contract_name: String,
+    pub types_json: String,
+}
+
+/// Return the storage layout types of every artifact in the compilation
+/// output, empty for contracts without state variables
+pub fn extract_storage_layouts(contract: &PlainContract) -> Result<Vec<StorageLayoutJson>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut layouts = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        let Some(layout) = &artifact.storage_layout else {
+            continue;
+        };
+        layouts.push(StorageLayoutJson {
+            contract_id: contract_id.clone(),
+            source_path: file.display().to_string(),
+            contract_name: contract_name.clone(),
+            types_json: serde_json::to_string(&layout.types)?,
+        });
+    }
+
+    Ok(layouts)
+}
+
+/// Read the storage layout of every artifact in the compilation output
+pub fn extract_storage_slots(contract: &PlainContract) -> Result<Vec<StorageSlot>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut slots = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        let Some(layout) = &artifact.storage_layout else {
+            continue;
+        };
+        for storage in layout.storage.iter() {
+            let ty = layout.types.get(&storage.storage_type);
+            slots.push(StorageSlot {
+                contract_id: contract_id.clone(),
+                source_path: file.display().to_string(),
+                contract_name: contract_name.clone(),
+                label: storage.label.clone(),
+                slot: storage.slot.clone(),
+                offset: storage.offset,
+                ty: ty
+                    .map(|t| t.label.clone())
+                    .unwrap_or_else(|| storage.storage_type.clone()),
+                type_id: storage.storage_type.clone(),
+                encoding: ty.map(|t| t.encoding.clone()).unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(slots)
+}
+
+/// Parse a slot or key given in decimal or as `0x` prefixed hex
+pub fn parse_word(value: &str) -> Result<U256> {
+    Ok(U256::from_str(value.trim())?)
+}
+
+/// How an observed storage slot relates to a declared slot of a layout
+#[derive(Debug, PartialEq)]
+pub enum SlotMatch {
+    /// The observed slot is the declared slot itself
+    Direct(U256),
+    /// The observed slot is the entry of `key` in the mapping at the declared slot
+    MappingEntry(U256),
+    /// The observed slot is the first element of the dynamic array or long
+    /// `bytes`/`string` data at the declared slot
+    ArrayData(U256),
+}
+
+/// Candidate declared slots in `0..=max_base_slot` which could produce the
+/// observed slot, optionally given the mapping key seen on chain
+pub fn candidate_slots(observed: U256, key: Option<U256>, max_base_slot: u64) -> Vec<SlotMatch> {
+    let mut candidates = vec![];
+    if observed <= U256::from(max_base_slot) {
+        candidates.push(SlotMatch::Direct(observed));
+    }
+
+    for base in 0..=max_base_slot {
+        let base = U256::from(base);
+        let base_word = base.to_be_bytes::<32>();
+        if U256::from_be_bytes(keccak256(base_word).0) == observed {
+            candidates.push(SlotMatch::ArrayData(base));
+        }
+        if let Some(key) = key {
+            let mut preimage = key.to_be_bytes::<32>().to_vec();
+            preimage.extend(base_word);
+            if U256::from_be_bytes(keccak256(&preimage).0) == observed {
+                candidates.push(SlotMatch::MappingEntry(base));
+            }
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn match_mapping_entry() -> Result<()> {
+        // balances[0x...01] of a mapping declared at slot 3
+        let key = parse_word("0x1")?;
+        let mut preimage = key.to_be_bytes::<32>().to_vec();
+        preimage.extend(U256::from(3).to_be_bytes::<32>());
+        let observed = U256::from_be_bytes(keccak256(&preimage).0);
+
+        assert_eq!(
+            candidate_slots(observed, Some(key), 16),
+            vec![SlotMatch::MappingEntry(U256::from(3))]
+        );
+        assert_eq!(
+            candidate_slots(U256::from(2), None, 16),
+            vec![SlotMatch::Direct(U256::from(2))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn layouts_of_same_named_contracts() -> Result<()> {
+        let layout = |label: &str| {
+            serde_json::json!({
+                "storageLayout": {
+                    "storage": [{
+                        "astId": 3,
+                        "contract": "src/Vault.sol:Vault",
+                        "label": label,
+                        "offset": 0,
+                        "slot": "0",
+                        "type": "t_mapping(t_address,t_uint256)"
+                    }],
+                    "types": {
+                        "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+                        "t_mapping(t_address,t_uint256)": {
+                            "encoding": "mapping",
+                            "key": "t_address",
+                            "label": "mapping(address => uint256)",
+                            "numberOfBytes": "32",
+                            "value": "t_uint256"
+                        },
+                        "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"}
+                    }
+                }
+            })
+        };
+        let contract = PlainContract::with_artifacts(
+            vec![
+                ("src/Vault.sol", "Vault", layout("shares")),
+                ("test/Vault.sol", "Vault", layout("balances")),
+            ],
+            &[],
+        )?;
+
+        let slots = extract_storage_slots(&contract)?;
+        assert_eq!(
+            slots
+                .iter()
+                .map(|s| (s.source_path.as_str(), s.label.as_str(), s.ty.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("src/Vault.sol", "shares", "mapping(address => uint256)"),
+                ("test/Vault.sol", "balances", "mapping(address => uint256)")
+            ]
+        );
+        assert_eq!(slots[0].type_id, "t_mapping(t_address,t_uint256)");
+
+        // the layouts keep the types only, the variables are the slots
+        let layouts = extract_storage_layouts(&contract)?;
+        assert_eq!(layouts.len(), 2);
+        let types: serde_json::Value = serde_json::from_str(&layouts[1].types_json)?;
+        assert_eq!(
+            types[&slots[1].type_id]["value"],
+            serde_json::json!("t_uint256")
+        );
+        assert!(!layouts[1].types_json.contains("balances"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use itertools::Itertools;
+use std::collections::HashSet;
+
+use crate::plain_contract::PlainContract;
+
+/// SPDX identifiers of the licenses allowing redistribution without copyleft
+/// obligations, selected by `--permissive-only`
+pub const PERMISSIVE_LICENSES: [&str; 10] = [
+    "MIT",
+    "Apache-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "ISC",
+    "0BSD",
+    "Unlicense",
+    "CC0-1.0",
+    "Zlib",
+    "BSL-1.0",
+];
+
+/// The distinct SPDX license expressions declared by the
+/// `SPDX-License-Identifier` comments of the sources, sorted and joined with
+/// commas as stored in the `license` column, empty when there is none
+pub fn contract_license(contract: &PlainContract) -> String {
+    let Ok(files) = contract.get_source_files() else {
+        return "".into();
+    };
+    files
+        .iter()
+        .flat_map(|f| spdx_expressions(&f.content))
+        .sorted()
+        .dedup()
+        .join(",")
+}
+
+/// License expressions of the `SPDX-License-Identifier` comments of a source
+fn spdx_expressions(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once("SPDX-License-Identifier:"))
+        .map(|(_, expression)| {
+            expression
+                .trim()
+                .trim_end_matches("*/")
+                .trim()
+                .trim_matches(|c| c == '(' || c == ')')
+                .to_string()
+        })
+        .filter(|expression| !expression.is_empty())
+        .collect()
+}
+
+/// Licenses a contract must be distributed under to be exported
+#[derive(Debug, Clone)]
+pub struct LicenseFilter {
+    allowed: HashSet<String>,
+}
+
+impl LicenseFilter {
+    pub fn new(allowlist: &[String], permissive_only: bool) -> Option<Self> {
+        let mut allowed: HashSet<String> = allowlist.iter().map(|l| l.trim().to_string()).collect();
+        if permissive_only {
+            allowed.extend(PERMISSIVE_LICENSES.iter().map(|l| l.to_string()));
+        }
+        (!allowed.is_empty()).then_some(Self { allowed })
+    }
+
+    /// The allowed identifiers, sorted, as recorded in export manifests
+    pub fn allowlist(&self) -> String {
+        self.allowed.iter().sorted().join(",")
+    }
+
+    /// Whether a contract with the stored `license` can be exported: it
+    /// declares a license and each of its expressions is allowed. An `OR`
+    /// expression is allowed when one of its alternatives is, an `AND`
+    /// expression when all of its parts are.
+    pub fn allows(&self, license: &str) -> bool {
+        !license.is_empty()
+            && license.split(',').all(|expression| {
+                expression.split(" OR ").any(|alternative| {
+                    alternative
+                        .split(" AND ")
+                        .all(|part| self.allowed.contains(part.trim()))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filter_spdx_licenses() {
+        assert_eq!(
+            spdx_expressions(
+                "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n/* SPDX-License-Identifier: (MIT OR GPL-3.0) */"
+            ),
+            ["MIT", "MIT OR GPL-3.0"]
+        );
+        assert!(spdx_expressions("pragma solidity ^0.8.0;").is_empty());
+
+        let filter = LicenseFilter::new(&[], true).expect("Permissive preset");
+        assert!(filter.allows("MIT"));
+        assert!(filter.allows("Apache-2.0,MIT"));
+        assert!(filter.allows("GPL-3.0 OR MIT"));
+        assert!(!filter.allows("GPL-3.0,MIT"));
+        assert!(!filter.allows("MIT AND GPL-3.0"));
+        assert!(!filter.allows(""));
+
+        let filter = LicenseFilter::new(&["GPL-3.0".into()], false).expect("Allowlist");
+        assert!(filter.allows("GPL-3.0"));
+        assert!(!filter.allows("MIT"));
+        assert!(LicenseFilter::new(&[], false).is_none());
+    }
+}
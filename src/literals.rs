@@ -0,0 +1,430 @@
+use eyre::{ContextCompat, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast_query::{descendants, node_type, source_asts},
+    functions::{function_id, selector_hex},
+    plain_contract::PlainContract,
+};
+
+/// A string, address or numeric literal in the body of a function, constructor
+/// or modifier
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FunctionLiteral {
+    /// Id of the function in the `function` or `function_definition` table
+    pub function_id: String,
+    pub contract_id: String,
+    /// One of `string`, `hex`, `address` or `number`
+    pub kind: String,
+    pub value: String,
+}
+
+/// A contract embedding an address, in a function body, an immutable variable
+/// or a constructor argument
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AddressReference {
+    pub contract_id: String,
+    pub contract_name: String,
+    /// One of `literal`, `immutable` or `constructor`
+    pub origin: String,
+    /// Signature of the function using the literal, or the name of the
+    /// immutable variable or constructor parameter
+    pub name: String,
+}
+
+/// A function, constructor or modifier defined with a body outside of the ABI
+/// of the compiled contracts, e.g. an internal or private function, by the id
+/// its literals, assembly blocks and calls are stored with
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FunctionDefinition {
+    pub id: String,
+    pub contract_id: String,
+    /// The contract defining it, empty for free functions
+    pub contract_name: String,
+    /// `function`, `constructor`, `fallback`, `receive`, `freeFunction` or
+    /// `modifier`
+    pub kind: String,
+    pub name: String,
+    pub filename: String,
+    /// Name and parameter types, named by the kind for constructors, fallback
+    /// and receive functions, e.g. `_transfer(address,address,uint256)`
+    pub signature: String,
+}
+
+fn has_body(node: &Value) -> bool {
+    node.get("body").is_some_and(|body| !body.is_null())
+}
+
+pub fn is_address(value: &str) -> bool {
+    value.len() == 42
+        && value.starts_with("0x")
+        && value[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Kind and value of a `Literal` node, booleans are skipped and numbers keep
+/// their unit, e.g. `1 ether`
+fn literal(node: &Value) -> Option<(&'static str, String)> {
+    let value = node.get("value").and_then(Value::as_str);
+    match node.get("kind")?.as_str()? {
+        "number" => {
+            let value = value?;
+            if is_address(value) {
+                return Some(("address", value.to_lowercase()));
+            }
+            match node.get("subdenomination").and_then(Value::as_str) {
+                Some(unit) => Some(("number", format!("{value} {unit}"))),
+                None => Some(("number", value.into())),
+            }
+        }
+        "string" | "unicodeString" => Some(("string", value?.into())),
+        "hexString" => Some(("hex", node.get("hexValue")?.as_str()?.into())),
+        _ => None,
+    }
+}
+
+/// The distinct literals used in a function definition
+pub fn function_literals(function: &Value) -> Vec<(&'static str, String)> {
+    let mut nodes = vec![];
+    descendants(function, &mut nodes);
+    nodes
+        .into_iter()
+        .filter(|node| node_type(node) == Some("Literal"))
+        .filter_map(literal)
+        .unique()
+        .collect()
+}
+
+/// The implementation of the function with the selector, i.e. the definition
+/// in the most derived contract of the linearized base contracts
+fn implementation<'a>(bases: &[&'a Value], selector: &str) -> Option<&'a Value> {
+    bases.iter().find_map(|base| {
+        base.get("nodes")?.as_array()?.iter().find(|node| {
+            node_type(node) == Some("FunctionDefinition")
+                && node.get("functionSelector").and_then(Value::as_str) == Some(selector)
+                && has_body(node)
+        })
+    })
+}
+
+/// Kind of a function or modifier definition, see [`FunctionDefinition::kind`]
+fn definition_kind(node: &Value) -> &str {
+    match node_type(node) {
+        Some("ModifierDefinition") => "modifier",
+        _ => node
+            .get("kind")
+            .and_then(Value::as_str)
+            .unwrap_or("function"),
+    }
+}
+
+/// See [`FunctionDefinition::signature`]
+fn definition_signature(node: &Value) -> String {
+    let name = match node.get("name").and_then(Value::as_str) {
+        Some(name) if !name.is_empty() => name,
+        _ => definition_kind(node),
+    };
+    let types = node
+        .pointer("/parameters/parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p.pointer("/typeDescriptions/typeString")?.as_str())
+        .join(",");
+    format!("{name}({types})")
+}
+
+/// The function and modifier definitions with a body of a single source file
+/// AST, with the name of their contract, empty for free functions
+fn ast_definitions(ast: &Value) -> Vec<(&str, &Value)> {
+    let mut definitions = vec![];
+    for node in ast
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match node_type(node) {
+            Some("FunctionDefinition") if has_body(node) => definitions.push(("", node)),
+            Some("ContractDefinition") => {
+                let name = node.get("name").and_then(Value::as_str).unwrap_or_default();
+                definitions.extend(
+                    node.get("nodes")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter(|n| {
+                            matches!(
+                                node_type(n),
+                                Some("FunctionDefinition" | "ModifierDefinition")
+                            ) && has_body(n)
+                        })
+                        .map(|n| (name, n)),
+                );
+            }
+            _ => {}
+        }
+    }
+    definitions
+}
+
+/// Call the visitor with the id, source file and definition of the
+/// implementation of every function in the ABI of every compiled contract,
+/// including functions implemented in base contracts, then of every other
+/// function, constructor and modifier with a body, e.g. internal and private
+/// functions, see [`extract_function_definitions`]
+pub fn visit_implementations(
+    contract: &PlainContract,
+    mut visit: impl FnMut(&str, &str, &Value),
+) -> Result<()> {
+    visit_definitions(contract, |id, path, node, _| visit(id, path, node))
+}
+
+/// See [`visit_implementations`], definitions outside of the ABI come with the
+/// name of their contract
+fn visit_definitions(
+    contract: &PlainContract,
+    mut visit: impl FnMut(&str, &str, &Value, Option<&str>),
+) -> Result<()> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    // contract definitions of all source files by node id
+    let asts = source_asts(contract)?;
+    let mut definitions = HashMap::new();
+    for (path, ast) in asts.iter() {
+        let mut nodes = vec![];
+        descendants(ast, &mut nodes);
+        for node in nodes {
+            if node_type(node) != Some("ContractDefinition") {
+                continue;
+            }
+            if let Some(id) = node.get("id").and_then(Value::as_i64) {
+                definitions.insert(id, (path.as_str(), node));
+            }
+        }
+    }
+
+    let mut visited = HashSet::new();
+    for (contract_name, artifact) in compilation_output.artifacts() {
+        let Some(abi) = artifact.abi.as_ref() else {
+            continue;
+        };
+        let filename = artifact
+            .source_file()
+            .and_then(|f| f.ast)
+            .map(|ast| ast.absolute_path)
+            .unwrap_or_default();
+        let Some((_, definition)) = definitions.values().find(|(path, node)| {
+            *path == filename
+                && node.get("name").and_then(Value::as_str) == Some(contract_name.as_str())
+        }) else {
+            continue;
+        };
+        let bases = definition
+            .get("linearizedBaseContracts")
+            .and_then(Value::as_array)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| definitions.get(&id.as_i64()?))
+                    .collect_vec()
+            })
+            .unwrap_or_default();
+        let nodes = bases.iter().map(|(_, node)| *node).collect_vec();
+
+        for f in abi.functions() {
+            let selector = selector_hex(f);
+            let Some(function) = implementation(&nodes, &selector[2..]) else {
+                continue;
+            };
+            // the file of the base contract implementing the function
+            let path = bases
+                .iter()
+                .find(|(_, node)| {
+                    node.get("nodes")
+                        .and_then(Value::as_array)
+                        .is_some_and(|nodes| nodes.iter().any(|n| std::ptr::eq(n, function)))
+                })
+                .map(|(path, _)| *path)
+                .unwrap_or(filename.as_str());
+            visited.insert(function as *const Value);
+            visit(
+                &function_id(&contract_id, &filename, &selector),
+                path,
+                function,
+                None,
+            );
+        }
+    }
+
+    for (path, ast) in asts.iter() {
+        for (contract_name, node) in ast_definitions(ast) {
+            if visited.contains(&(node as *const Value)) {
+                continue;
+            }
+            let key = format!("{contract_name}.{}", definition_signature(node));
+            visit(
+                &function_id(&contract_id, path, &key),
+                path,
+                node,
+                Some(contract_name),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the functions, constructors and modifiers outside of the ABI whose
+/// rows are extracted by [`visit_implementations`]
+pub fn extract_function_definitions(contract: &PlainContract) -> Result<Vec<FunctionDefinition>> {
+    let contract_id = contract.id();
+    let mut definitions = vec![];
+    visit_definitions(contract, |id, path, node, contract_name| {
+        let Some(contract_name) = contract_name else {
+            return;
+        };
+        definitions.push(FunctionDefinition {
+            id: id.into(),
+            contract_id: contract_id.clone(),
+            contract_name: contract_name.into(),
+            kind: definition_kind(node).into(),
+            name: node
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .into(),
+            filename: path.into(),
+            signature: definition_signature(node),
+        });
+    })?;
+
+    Ok(definitions)
+}
+
+/// Return the literals of every function, constructor and modifier of every
+/// compiled contract, see [`visit_implementations`]
+pub fn extract_literals(contract: &PlainContract) -> Result<Vec<FunctionLiteral>> {
+    let contract_id = contract.id();
+    let mut literals = vec![];
+    visit_implementations(contract, |function_id, _, function| {
+        for (kind, value) in function_literals(function) {
+            literals.push(FunctionLiteral {
+                function_id: function_id.into(),
+                contract_id: contract_id.clone(),
+                kind: kind.into(),
+                value,
+            });
+        }
+    })?;
+
+    Ok(literals)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify_function_literals() {
+        let function = serde_json::json!({
+            "nodeType": "FunctionDefinition",
+            "functionSelector": "a9059cbb",
+            "body": {
+                "nodeType": "Block",
+                "statements": [
+                    {"nodeType": "Literal", "kind": "string", "value": "not owner"},
+                    {"nodeType": "Literal", "kind": "number", "value": "0xDAC17F958D2ee523a2206206994597C13D831ec7"},
+                    {"nodeType": "Literal", "kind": "number", "value": "1", "subdenomination": "ether"},
+                    {"nodeType": "Literal", "kind": "number", "value": "100"},
+                    {"nodeType": "Literal", "kind": "hexString", "value": null, "hexValue": "ff00"},
+                    {"nodeType": "Literal", "kind": "bool", "value": "true"},
+                    {"nodeType": "Literal", "kind": "string", "value": "not owner"}
+                ]
+            }
+        });
+
+        assert_eq!(
+            function_literals(&function),
+            vec![
+                ("string", "not owner".to_string()),
+                (
+                    "address",
+                    "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string()
+                ),
+                ("number", "1 ether".to_string()),
+                ("number", "100".to_string()),
+                ("hex", "ff00".to_string()),
+            ]
+        );
+
+        let base = serde_json::json!({"nodeType": "ContractDefinition", "nodes": [function]});
+        assert!(implementation(&[&base], "a9059cbb").is_some());
+        assert!(implementation(&[&base], "70a08231").is_none());
+    }
+
+    #[test]
+    fn list_definitions_outside_of_the_abi() {
+        let parameter = |ty: &str| serde_json::json!({"nodeType": "VariableDeclaration", "typeDescriptions": {"typeString": ty}});
+        let definition = |node_type: &str, kind: &str, name: &str, body: bool| {
+            serde_json::json!({
+                "nodeType": node_type,
+                "kind": kind,
+                "name": name,
+                "parameters": {"parameters": [parameter("address"), parameter("uint256")]},
+                "body": if body { serde_json::json!({"nodeType": "Block"}) } else { Value::Null }
+            })
+        };
+        let ast = serde_json::json!({
+            "nodeType": "SourceUnit",
+            "nodes": [
+                definition("FunctionDefinition", "freeFunction", "min", true),
+                {
+                    "nodeType": "ContractDefinition",
+                    "name": "Vault",
+                    "nodes": [
+                        definition("FunctionDefinition", "constructor", "", true),
+                        definition("FunctionDefinition", "function", "_transfer", true),
+                        definition("ModifierDefinition", "", "onlyOwner", true),
+                        definition("FunctionDefinition", "function", "_hook", false),
+                        {"nodeType": "VariableDeclaration", "name": "owner"}
+                    ]
+                }
+            ]
+        });
+
+        assert_eq!(
+            ast_definitions(&ast)
+                .into_iter()
+                .map(|(contract, node)| (
+                    contract,
+                    definition_kind(node),
+                    definition_signature(node)
+                ))
+                .collect_vec(),
+            [
+                ("", "freeFunction", "min(address,uint256)".to_string()),
+                (
+                    "Vault",
+                    "constructor",
+                    "constructor(address,uint256)".to_string()
+                ),
+                (
+                    "Vault",
+                    "function",
+                    "_transfer(address,uint256)".to_string()
+                ),
+                (
+                    "Vault",
+                    "modifier",
+                    "onlyOwner(address,uint256)".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use clap::ValueEnum;
+use log::{
+    kv::{self, Key, VisitSource},
+    Record,
+};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Format of the log lines written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// The default format of env_logger
+    Text,
+    /// One json object per line, with the key values of the record as fields,
+    /// e.g. `stage`, `contract_id`, `duration_ms` and `outcome`
+    Json,
+}
+
+/// Collects the key values of a record into a json object
+struct JsonFields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(b) = value.to_bool() {
+            Value::from(b)
+        } else if let Some(n) = value.to_u64() {
+            Value::from(n)
+        } else if let Some(n) = value.to_i64() {
+            Value::from(n)
+        } else if let Some(n) = value.to_f64() {
+            Value::from(n)
+        } else {
+            Value::from(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// The record as a json object with its timestamp, level, target, message and
+/// key values
+pub fn json_record(timestamp: &str, record: &Record) -> Value {
+    let mut fields = Map::new();
+    fields.insert("timestamp".into(), timestamp.into());
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+    Value::Object(fields)
+}
+
+/// Initialize env_logger, filtered by `RUST_LOG` as usual, in the format
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if let LogFormat::Json = format {
+        builder.format(|buf, record| {
+            let timestamp = buf.timestamp_millis().to_string();
+            writeln!(buf, "{}", json_record(&timestamp, record))
+        });
+    }
+    builder.init();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn record_to_json() {
+        let fields: &[(&str, kv::Value)] = &[
+            ("stage", "compile".into()),
+            ("contract_id", "abc".into()),
+            ("duration_ms", 12u64.into()),
+            ("outcome", "error".into()),
+        ];
+        let json = json_record(
+            "2024-01-01T00:00:00.000Z",
+            &Record::builder()
+                .args(format_args!("Failed to compile contract with id abc"))
+                .level(Level::Error)
+                .target("smart_contract_database_builder")
+                .key_values(&fields)
+                .build(),
+        );
+        assert_eq!(json["level"], "ERROR");
+        assert_eq!(json["message"], "Failed to compile contract with id abc");
+        assert_eq!(json["stage"], "compile");
+        assert_eq!(json["duration_ms"], 12);
+        assert_eq!(json["outcome"], "error");
+    }
+}
@@ -1,30 +1,195 @@
-use clap::{ArgAction, Parser, Subcommand};
-use db::{row_to_contract, Storage};
+use abi::{
+    decode_call, decode_hex, encode_call, format_value, input_types, parse_signature, random_value,
+};
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_primitives::{hex, B256};
+use archive::{is_archive, read_archive_contracts};
+use artifact_cache::ArtifactCache;
+use ast_query::{function_ast, query_contract, source_asts, AstQuery, AST_QUERY_FINDING};
+use bytecode::{cbor_metadata, eip1167_implementation, skeleton_hash, EIP170_LIMIT};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use compiler_bugs::{find_compiler_bugs, load_bugs, COMPILER_BUG_FINDING};
+use compiler_version::solc_version;
+use config::Config;
+use custom_errors::{
+    decode_error, error_selector_hex, panic_reason, parse_error_signature, ERROR_STRING_SELECTOR,
+    PANIC_SELECTOR,
+};
+use dataset::{read_changed, read_dataset, Custom, DatasetFormat, DatasetRegistry};
+use db::{row_to_contract, schema_sql, ContractAddress, ContractOccurrence, Storage};
+use deployment::{deployer_clusters, read_addresses, read_tags, Deployment};
+use env_export::{
+    required_compilers, solc_entry, vyper_entry, EnvBundle, REPLICATE_SCRIPT, TOOL_ENTRY,
+};
+use events::{decode_event, parse_event_signature, topic0_hex};
+use explorer::{blockscout_api_url, is_etherscan_v2, Explorer, ETHERSCAN_V2_API_URL};
 use eyre::Result;
-use futures::future::try_join_all;
+use features::find_features;
+use finding::Finding;
+use foundry::FoundryProject;
+use foundry_compilers::artifacts::EvmVersion;
+use functions::selector_hex;
+use futures::{future::try_join_all, stream, StreamExt};
+use glob::Pattern;
+use hooks::Hooks;
+use huggingface::HfDataset;
+use import::{read_bigquery_contracts, read_contracts, read_functions, ContractRow};
+use import_graph::{find_imports, to_dot};
+use index::{CompileError, CompilerFallback, IndexOutput};
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
-use log::{debug, error, info};
-use plain_contract::PlainContract;
-use std::{fmt::Write, sync::Arc};
-use tokio::{sync::Mutex, task};
-use utils::download_all_solc_versions;
-use walkdir::WalkDir;
+use interface::load_interface;
+use ipfs::{IpfsClient, DEFAULT_IPFS_GATEWAY};
+use itertools::Itertools;
+use layout::{candidate_slots, parse_word, SlotMatch};
+use license::LicenseFilter;
+use literals::is_address;
+use log::{debug, error, info, warn};
+use logging::LogFormat;
+use manifest::ExportManifest;
+use metadata_hash::extract_metadata_hashes;
+use overview::Overview;
+use package::{
+    PackageClient, PackageSpec, Registry, DEFAULT_PACKAGE_CDN_URL, DEFAULT_PACKAGE_DATA_URL,
+};
+use plain_contract::{Metadata, PlainContract};
+use pragma::{find_pragma_mismatches, PRAGMA_MISMATCH_FINDING};
+use rand::{rngs::StdRng, SeedableRng};
+use rpc::{DeployedBytecode, RpcClient};
+use screen::{decode_signatures, most_similar, ScreenResult};
+use settings_diff::EffectiveSettings;
+use signatures::{normalize_hash, read_signatures, SignatureKind};
+use similarity::{minhash, opcodes, signature_from_hex, similarity, OpcodeSignature};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    io::Write as _,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use sweep::{artifact_hashes, differing_artifacts, SweepReport, SweepStats};
+use timeline::{load_releases, CompilerTimeline};
+use tokio::{
+    sync::{Mutex, Semaphore},
+    task,
+};
+use triplets::build_triplets;
+use utils::{
+    download_all_solc_versions, expand_roots, find_solc, redact_url, set_offline, svm_home,
+    HttpFetcher, HttpOptions, SolcPlatform,
+};
+use vyper::{install_vyper, vyper_version};
+use watch::RootWatcher;
+use workspace::Workspace;
 
 use crate::plain_contract::ContractSource;
 
+mod abi;
+mod archive;
+mod artifact_cache;
+mod assembly;
+mod ast_query;
+mod bytecode;
+mod cache;
+mod calls;
+mod compiler_bugs;
+mod compiler_version;
+mod config;
+mod constructor;
+mod custom_errors;
+mod dataset;
+mod dataset_card;
 mod db;
+mod definitions;
+mod deployment;
+mod env_export;
+mod events;
+mod explorer;
+mod features;
+mod finding;
+mod foundry;
 mod functions;
+mod hooks;
+mod huggingface;
+mod import;
+mod import_graph;
+mod index;
+mod interface;
+mod ipfs;
+mod layout;
+mod license;
+mod literals;
+mod logging;
+mod manifest;
+mod metadata_hash;
+mod modifiers;
+mod overview;
+mod package;
 mod plain_contract;
+mod plugins;
+mod pragma;
+mod rpc;
+mod screen;
+mod settings_diff;
+mod signatures;
+mod similarity;
+mod site;
+mod size;
+mod source_map;
+mod state_variables;
+mod sweep;
+mod timeline;
+mod triplets;
+mod truffle;
 mod utils;
+mod versions;
+mod vyper;
+mod watch;
+mod workspace;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
-    /// Optionally duckdb path, if not provided will try to read from environment variable DUCKDB_PATH
+    /// Optionally duckdb path, if not provided will try to read from the
+    /// selected profile or environment variable DUCKDB_PATH
     #[arg(long)]
     duckdb_path: Option<String>,
+    /// Path of the config file, defaults to `smart-contract-database-builder.toml`
+    /// in the current directory
+    #[arg(long)]
+    config: Option<String>,
+    /// Name of the profile in the config file providing chain and connection
+    /// defaults
+    #[arg(long)]
+    profile: Option<String>,
+    #[command(flatten)]
+    http: HttpOptions,
+    /// Folder caching the artifacts of compiled contracts by contract id and
+    /// compiler settings, a cached contract is not compiled again by
+    /// `index-functions`, `compile`, `extract-immutables`, `verify-metadata`,
+    /// `ast-query` and `export-function-ast`
+    #[arg(long)]
+    artifact_cache_dir: Option<String>,
+    /// Folder of the scratch directories of the compilations instead of the
+    /// temporary directory of the system, e.g. when /tmp is a small tmpfs.
+    /// Directories left behind by crashed runs are removed on start
+    #[arg(long)]
+    workspace_dir: Option<String>,
+    /// Size in MB above which the workspace is cleaned up of stale
+    /// directories, with a warning if it remains larger
+    #[arg(long, requires = "workspace_dir")]
+    workspace_max_mb: Option<u64>,
+    /// Only compile with the solc and vyper binaries already installed, a
+    /// contract needing another version fails to compile and is recorded in the
+    /// `compile_error` table instead of downloading it
+    #[arg(long)]
+    offline: bool,
+    /// Format of the logs, `json` writes one object per line with the stage,
+    /// contract id, duration and outcome of the logged step when known
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
 }
 
 #[derive(Parser)]
@@ -33,231 +198,3681 @@ struct PreProcessArgs {
     /// contain contracts stored alongside `metadata.json`.
     ///
     /// Example https://huggingface.co/datasets/Zellic/smart-contract-fiesta/tree/main/organized_contracts
+    ///
+    /// A `.zip`, `.tar` or `.tar.gz` archive of such a folder is read without
+    /// extracting it.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `datasets/*/organized_contracts`
+    #[arg(long)]
+    metadata_contracts_root: Vec<String>,
+
+    /// Folder containing etherscan contracts. Each contract contains a json file
+    /// which contains both the metadata and the source code
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `etherscan/*`
+    #[arg(long)]
+    etherscan_contracts_root: Vec<String>,
+
+    /// Root of a Sourcify repository dump, e.g. `repository/contracts`. Every
+    /// folder below containing `metadata.json` and a `sources` tree, as in
+    /// `full_match/<chain id>/<address>` and `partial_match/<chain id>/<address>`,
+    /// is imported.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `repository/contracts/full_match/*`
+    #[arg(long)]
+    sourcify_root: Vec<String>,
+
+    /// Root of a Hardhat or Foundry project, or its `artifacts` or `out`
+    /// folder. Every build info file in a `build-info` folder below is imported
+    /// with its standard json input and compiler settings.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `projects/*`
+    #[arg(long)]
+    build_info_root: Vec<String>,
+
+    /// Root of a dataset in any supported layout, each folder and file below
+    /// is probed with the adapter of every format unless `--dataset-format`
+    /// selects one.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `dumps/*`
+    #[arg(long)]
+    dataset_root: Vec<String>,
+
+    /// Layout of the dataset roots
+    #[arg(long, value_enum, default_value_t = DatasetFormat::Auto)]
+    dataset_format: DatasetFormat,
+
+    /// Glob pattern of the paths of the custom layout, matched against the
+    /// whole path, e.g. `*/contract.yaml`
+    #[arg(long, requires = "custom_command")]
+    custom_pattern: Option<String>,
+
+    /// Shell command reading a contract of the custom layout, run with
+    /// `CONTRACT_PATH` in its environment and printing the contract json with
+    /// its `metadata` and `source` on stdout
+    #[arg(long, requires = "custom_pattern")]
+    custom_command: Option<String>,
+
+    /// Root of a Foundry project, i.e. a folder with `foundry.toml`. The
+    /// sources of `src` and the library files they import are imported as a
+    /// standard json contract, with the remappings of `foundry.toml`,
+    /// `remappings.txt` and `lib`.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `projects/*`
+    #[arg(long)]
+    foundry_project: Vec<String>,
+
+    /// Root of a Truffle or Brownie project, detected by its config file. The
+    /// sources embedded in the artifacts of `build/contracts` are imported as a
+    /// standard json contract, with the compiler version and optimizer
+    /// settings of the largest project contract.
+    ///
+    /// Can be repeated and accepts glob patterns, e.g. `projects/*`
+    #[arg(long)]
+    truffle_project: Vec<String>,
+
+    /// Dataset repository on the Hugging Face hub laid out like the fiesta
+    /// dataset, e.g. `Zellic/smart-contract-fiesta`. Contracts are downloaded
+    /// and stored one chunk at a time instead of cloning the dataset.
+    #[arg(long)]
+    hf_dataset: Vec<String>,
+
+    /// Folder of the Hugging Face datasets containing the contract folders
+    #[arg(long, default_value = "organized_contracts")]
+    hf_path: String,
+
+    /// Branch, tag or commit of the Hugging Face datasets
+    #[arg(long, default_value = "main")]
+    hf_revision: String,
+
+    /// Optionally ignore errors during processing (default: false)
+    #[arg(long, action = ArgAction::SetTrue, default_value_t = false)]
+    ignore_errors: bool,
+
+    /// Skip the folders, files and projects stored by previous runs, as
+    /// recorded in the `ingest_manifest` table, e.g. after an interrupted run
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+
+    /// Keep running after the import and store the contracts added later to
+    /// the metadata, etherscan, sourcify, build info and dataset folders, e.g.
+    /// by a scraper. Archives, projects and Hugging Face datasets are not
+    /// watched.
+    #[arg(long, default_value_t = false)]
+    watch: bool,
+
+    /// Seconds without changes to wait for before reading new files in watch
+    /// mode, so that files still being written are not read
+    #[arg(long, default_value_t = 2)]
+    settle_secs: u64,
+
+    /// Chain of the contracts of the etherscan roots, recorded with the address
+    /// of their file name. Defaults to the chain of the selected profile, or
+    /// 1 for Ethereum mainnet.
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// Shell command run for every stored contract with the contract json on
+    /// stdin, and `CONTRACT_ID` and `CONTRACT_PATH` in its environment.
+    /// Overrides the `contract_hook` of the selected profile.
+    #[arg(long)]
+    contract_hook: Option<String>,
+
+    /// Shell command run for every stored chunk with the json array of its
+    /// contracts on stdin, and `CHUNK_SIZE` in its environment. Overrides the
+    /// `chunk_hook` of the selected profile.
+    #[arg(long)]
+    chunk_hook: Option<String>,
+
+    /// Chunk size, for faster importing contracts
+    #[arg(long)]
+    chunk_size: usize,
+}
+
+#[derive(Parser)]
+struct IndexFunctionsArgs {
+    /// How many contracts to process in one go
+    #[arg(long)]
+    chunk_size: usize,
+    /// Maximum number of contracts compiled at the same time over the whole
+    /// run, each compilation forks a solc process. Defaults to the number of
+    /// CPUs.
+    #[arg(long, default_value_t = num_cpus::get())]
+    max_concurrent_compiles: usize,
+    /// Number of the nearest patch releases of the same minor series tried
+    /// when a contract fails to compile with its recorded compiler version,
+    /// before the version inferred from its pragmas, which is tried with 0 too
+    #[arg(long, default_value_t = 3)]
+    fallback_versions: usize,
+    /// WebAssembly extractor run on every compiled contract, `.wasm` or
+    /// `.wat`, can be repeated
+    #[cfg(feature = "wasm-plugins")]
+    #[arg(long)]
+    plugin: Vec<String>,
+}
+
+#[derive(Parser)]
+struct DownloadSolcArgs {
+    /// Root folder for storing solc binaries, laid out as by svm, defaults to
+    /// the folder of svm where the binaries are used when compiling, `~/.svm`
+    /// if it exists else `$XDG_DATA_HOME/svm`
+    #[arg(long)]
+    solc_folder: Option<String>,
+    /// Platform of the binaries, defaults to the platform of the host, e.g.
+    /// to prepare a solc folder for another machine
+    #[arg(long, value_enum)]
+    platform: Option<SolcPlatform>,
+}
+
+#[derive(Parser)]
+struct ExportSourceArgs {
+    /// The contract id to export
+    #[arg(long)]
+    contract_id: String,
+    /// Output folder to store the source code
+    #[arg(long, required_unless_present = "stdout")]
+    output_folder: Option<String>,
+    /// Write to stdout instead of the output folder, either the file given by
+    /// `--file` or the whole export in the json or zip format
+    #[arg(long, default_value_t = false)]
+    stdout: bool,
+    /// The path of a single source file to write to stdout, as in the exported tree
+    #[arg(long, requires = "stdout")]
+    file: Option<String>,
+    /// Optionally write a reproducibility manifest of the export to this file
+    #[arg(long)]
+    manifest: Option<String>,
+    /// Write a directory tree, a single zip archive or a single json file
+    #[arg(long, value_enum, default_value_t = ExportFormat::Dir)]
+    format: ExportFormat,
+    /// Only export the source files matching this glob pattern, e.g.
+    /// `contracts/Vault.sol` or `contracts/**/*.sol`. Can be repeated.
+    #[arg(long)]
+    only: Vec<Pattern>,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+/// Licenses of the contracts to export, from the SPDX identifiers declared in
+/// their sources. Contracts declaring no license are never exported when
+/// filtering.
+#[derive(Args)]
+struct LicenseFilterArgs {
+    /// Only export contracts whose licenses are all in this comma separated
+    /// list of SPDX identifiers, e.g. `MIT,Apache-2.0,GPL-3.0`
+    #[arg(long, value_delimiter = ',')]
+    license_allowlist: Vec<String>,
+    /// Only export contracts under permissive licenses such as MIT, Apache-2.0
+    /// or the BSD licenses, in addition to the allowlist
+    #[arg(long, default_value_t = false)]
+    permissive_only: bool,
+}
+
+impl LicenseFilterArgs {
+    fn new_filter(&self) -> Option<LicenseFilter> {
+        LicenseFilter::new(&self.license_allowlist, self.permissive_only)
+    }
+
+    /// The filter of the arguments, with the licenses of older contracts
+    /// filled in, `None` when exporting every contract
+    fn filter(&self, storage: &Storage) -> Result<Option<LicenseFilter>> {
+        let filter = self.new_filter();
+        if filter.is_some() {
+            let count = storage.backfill_licenses()?;
+            if count > 0 {
+                info!("Filled in the license of {count} contracts");
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum ExportFormat {
+    /// The source files below `<output folder>/<contract name>`
+    Dir,
+    /// `<output folder>/<contract name>.zip` with the source files and `metadata.json`
+    Zip,
+    /// `<output folder>/<contract name>.json` mapping the path of every source
+    /// file to its content
+    Json,
+}
+
+/// Compiler, optimizer and code generation settings replacing the ones
+/// recorded in the metadata of a contract, to experiment with contracts which
+/// fail to compile as recorded
+#[derive(Args)]
+struct CompilerOverrides {
+    /// Compile with the optimizer enabled or disabled, instead of as recorded
+    #[arg(long)]
+    optimizer: Option<bool>,
+    /// Compile with this number of optimizer runs, instead of as recorded
+    #[arg(long, alias = "override-optimizer-runs")]
+    optimizer_runs: Option<u32>,
+    /// Compile through the IR pipeline, e.g. contracts verified with it which
+    /// fail with stack too deep errors otherwise
+    #[arg(long, default_value_t = false)]
+    via_ir: bool,
+    /// Compile for this EVM version, e.g. `paris`, instead of as recorded
+    #[arg(long)]
+    override_evm_version: Option<String>,
+    /// Compile with this solc version, e.g. `0.8.19`, instead of as recorded
+    #[arg(long)]
+    override_solc: Option<String>,
+}
+
+impl CompilerOverrides {
+    fn apply(&self, metadata: &mut Metadata) -> Result<()> {
+        if let Some(enabled) = self.optimizer {
+            metadata.optimization_used = enabled;
+        }
+        if let Some(runs) = self.optimizer_runs {
+            metadata.runs = runs;
+        }
+        if self.via_ir {
+            metadata.via_ir = Some(true);
+        }
+        if let Some(evm_version) = &self.override_evm_version {
+            let evm_version = evm_version
+                .parse::<EvmVersion>()
+                .map_err(|e| eyre::eyre!("Invalid --override-evm-version {evm_version}: {e}"))?;
+            metadata.evm_version = Some(evm_version.to_string());
+        }
+        if let Some(solc) = &self.override_solc {
+            let version = solc_version(solc)
+                .map_err(|e| eyre::eyre!("Invalid --override-solc {solc}: {e}"))?;
+            metadata.compiler_version = format!("v{version}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser)]
+struct CompileArgs {
+    /// The contract id to compile
+    #[arg(long)]
+    contract_id: String,
+    /// Folder to write the artifacts to
+    #[arg(long, default_value = "artifacts")]
+    output_dir: String,
+    #[command(flatten)]
+    overrides: CompilerOverrides,
+}
+
+#[derive(Parser)]
+struct DiffSettingsArgs {
+    /// Id of the first contract
+    #[arg(long)]
+    a: String,
+    /// Id of the second contract
+    #[arg(long)]
+    b: String,
+    /// Print the settings of both contracts and their differences as json
+    #[arg(long)]
+    json: bool,
+    #[command(flatten)]
+    overrides: CompilerOverrides,
+}
+
+#[derive(Parser)]
+struct IndexVersionsArgs {
+    /// Ids of the contracts to compile, all contracts by default
+    #[arg(long)]
+    contract_id: Vec<String>,
+    /// Compile a random sample of this many contracts instead of all contracts
+    #[arg(long)]
+    sample: Option<usize>,
+    /// Seed of the random sample, the same seed selects the same contracts
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Number of the most recent minor series of solc allowed by the pragmas
+    /// to compile each contract with, with the latest release of each
+    #[arg(long, default_value_t = 3)]
+    max_versions: usize,
+    /// Compile contracts again with versions they are already stored with
+    #[arg(long, default_value_t = false)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct RetryFailedArgs {
+    /// Retry at most this many of the failed contracts, most recent failures
+    /// first
+    #[arg(long)]
+    limit: Option<usize>,
+    #[command(flatten)]
+    overrides: CompilerOverrides,
+}
+
+#[derive(Parser)]
+struct ExtractImmutablesArgs {
+    /// The contract id to compile
+    #[arg(long)]
+    contract_id: String,
+    /// File containing the hex encoded deployed (runtime) bytecode
+    #[arg(long)]
+    deployed_bytecode: String,
+    /// Optionally the address the bytecode was deployed at
+    #[arg(long)]
+    address: Option<String>,
+    #[command(flatten)]
+    overrides: CompilerOverrides,
+}
+
+#[derive(Parser)]
+struct VerifyMetadataArgs {
+    /// The contract id to recompile
+    #[arg(long)]
+    contract_id: String,
+    /// File containing the hex encoded deployed (runtime) bytecode
+    #[arg(long)]
+    deployed_bytecode: String,
+    #[command(flatten)]
+    overrides: CompilerOverrides,
+}
+
+#[derive(Parser)]
+struct ConformsToArgs {
+    /// The interface to match, either an ABI json file or a solidity file
+    #[arg(long)]
+    interface: String,
+    /// Only use the contract with this name when the interface file defines
+    /// more than one
+    #[arg(long)]
+    interface_name: Option<String>,
+    /// Compiler version used to compile a solidity interface file
+    #[arg(long)]
+    solc_version: Option<String>,
+}
+
+#[derive(Parser)]
+struct SizeReportArgs {
+    /// Also report contracts within this many bytes below the EIP-170 limit
+    #[arg(long, default_value_t = 1024)]
+    margin: usize,
+    /// Number of largest functions to list for each contract
+    #[arg(long, default_value_t = 5)]
+    top_functions: usize,
+}
+
+#[derive(Parser)]
+struct CompileSweepArgs {
+    /// Compile a random sample of this many contracts instead of all contracts
+    #[arg(long)]
+    sample: Option<usize>,
+    /// Seed of the random sample, the same seed selects the same contracts
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Number of contracts compiled concurrently
+    #[arg(long, default_value_t = 100)]
+    chunk_size: usize,
+    /// Print the report as json, e.g. to keep track of the corpus over time
+    #[arg(long, default_value_t = false)]
+    json: bool,
+    /// Compile every successful contract a second time and report the ones
+    /// whose artifacts differ, e.g. because of paths or remappings
+    #[arg(long, default_value_t = false)]
+    check_determinism: bool,
+}
+
+#[derive(Parser)]
+struct SearchArgs {
+    /// Solidity type of a state variable, e.g. "mapping(address => uint256)"
+    #[arg(long)]
+    storage_type: Option<String>,
+    /// Storage slot of a state variable, decimal or 0x prefixed hex
+    #[arg(long)]
+    slot: Option<String>,
+    /// Topic hash of an event, resolved against indexed events and imported
+    /// signatures
+    #[arg(long)]
+    topic0: Option<String>,
+    /// Address of a deployer, lists the contracts it deployed
+    #[arg(long)]
+    deployer: Option<String>,
+    /// A string, address or number literal, lists the functions using it,
+    /// e.g. a hardcoded address or an error message
+    #[arg(long)]
+    literal: Option<String>,
+    /// Name of a struct, enum, user defined value type or interface, either
+    /// canonical, e.g. `Vault.Position`, or unqualified
+    #[arg(long)]
+    type_name: Option<String>,
+    /// An opcode, lists the functions with inline assembly calling it, e.g.
+    /// `delegatecall`
+    #[arg(long)]
+    assembly_opcode: Option<String>,
+    /// A kind of external call, `call`, `delegatecall`, `staticcall`, `send`,
+    /// `transfer` or `interface`, lists the call sites
+    #[arg(long)]
+    external_call: Option<String>,
+    /// Restrict `--external-call` to targets of a kind, e.g. `state` for
+    /// delegatecalls to a storage variable
+    #[arg(long, requires = "external_call")]
+    target_kind: Option<String>,
+    /// Name of a modifier, e.g. `onlyOwner`, lists the functions invoking it
+    /// with the source code of its definition
+    #[arg(long)]
+    modifier: Option<String>,
+    /// Name of a state variable, e.g. `owner`, lists the contracts declaring
+    /// it with its type, visibility, mutability and slot
+    #[arg(long)]
+    state_variable: Option<String>,
+    /// Restrict `--state-variable` to a type, e.g. `address`
+    #[arg(long, requires = "state_variable")]
+    variable_type: Option<String>,
+    /// Id of a chain, lists the contracts fetched from it
+    #[arg(long)]
+    chain_id: Option<u64>,
+    /// Restrict `--chain-id` to the contract at an address. Without
+    /// `--chain-id`, lists the contracts deployed at the address on any chain.
+    #[arg(long)]
+    address: Option<String>,
+    /// Id of a contract, lists every dataset, path and address pre-process
+    /// read it from
+    #[arg(long)]
+    occurrences: Option<String>,
+    /// Keccak id of the sources of a contract, the keccak256 of the sorted
+    /// keccak256 of its source files, lists the ids of the matching contracts
+    #[arg(long)]
+    keccak_id: Option<String>,
+}
+
+#[derive(Parser)]
+struct FetchDeploymentsArgs {
+    /// File with one deployed address per line, optionally followed by a comma
+    /// and the id of the contract deployed at the address
+    #[arg(long)]
+    addresses: String,
+    /// Also fetch the deployed code to detect EIP-1167 minimal proxies
+    #[arg(long, default_value_t = false)]
+    detect_clones: bool,
+    /// Chain of the explorer, recorded with the deployments, overrides the
+    /// chain id of the selected profile, mainnet by default
+    #[arg(long)]
+    chain_id: Option<u64>,
+}
+
+#[derive(Parser)]
+struct FetchEtherscanArgs {
+    /// Address of a verified contract, can be repeated
+    #[arg(long)]
+    address: Vec<String>,
+    /// File with one address per line, in the format of `fetch-deployments`
+    #[arg(long)]
+    addresses: Option<String>,
+    /// API key of the explorer, overrides the key of the selected profile
+    #[arg(long, conflicts_with = "blockscout_url")]
+    api_key: Option<String>,
+    /// Fetch from a Blockscout instance instead, e.g. `https://gnosis.blockscout.com`
+    #[arg(long)]
+    blockscout_url: Option<String>,
+    /// API key of the Blockscout instance, the Etherscan key of the profile is
+    /// never sent to it
+    #[arg(long, requires = "blockscout_url")]
+    blockscout_api_key: Option<String>,
+    /// Chain of the explorer, recorded with the contracts, overrides the chain
+    /// id of the selected profile. Can be repeated with the Etherscan V2 API to
+    /// fetch every address on several chains, e.g. `--chain-id 1 --chain-id 8453`.
+    #[arg(long)]
+    chain_id: Vec<u64>,
+}
+
+#[derive(Parser)]
+struct FetchBytecodeArgs {
+    /// Address of a deployed contract, can be repeated
+    #[arg(long)]
+    address: Vec<String>,
+    /// File with one address per line, in the format of `fetch-deployments`
+    #[arg(long)]
+    addresses: Option<String>,
+    /// JSON-RPC endpoint of the chain, overrides the `rpc_url` of the selected profile
+    #[arg(long)]
+    rpc_url: Option<String>,
+    /// Chain of the endpoint, overrides the chain id of the selected profile.
+    /// Asked to the endpoint when neither is given.
+    #[arg(long)]
+    chain_id: Option<u64>,
+}
+
+#[derive(Parser)]
+struct FetchIpfsArgs {
+    /// IPFS HTTP gateway serving `/ipfs/<cid>`
+    #[arg(long, default_value = DEFAULT_IPFS_GATEWAY)]
+    gateway: String,
+    /// Only resolve the runtime codes of this chain
+    #[arg(long)]
+    chain_id: Option<u64>,
+}
+
+#[derive(Parser)]
+struct FetchPackageArgs {
+    /// npm package, optionally with a version, e.g. `@openzeppelin/contracts@4.9.3`,
+    /// can be repeated
+    #[arg(long)]
+    npm: Vec<String>,
+    /// GitHub repository, optionally with a tag, e.g. `transmissions11/solmate@v7`,
+    /// can be repeated
     #[arg(long)]
-    metadata_contracts_root: Option<String>,
+    github: Vec<String>,
+    /// Only fetch the newest releases of packages given without a version
+    #[arg(long)]
+    latest: Option<usize>,
+    /// API listing the versions and files of the packages
+    #[arg(long, default_value = DEFAULT_PACKAGE_DATA_URL)]
+    data_url: String,
+    /// CDN serving the files of the packages
+    #[arg(long, default_value = DEFAULT_PACKAGE_CDN_URL)]
+    cdn_url: String,
+}
+
+#[derive(Parser)]
+struct ScreenArgs {
+    /// Address of a deployed contract, can be repeated
+    #[arg(long)]
+    address: Vec<String>,
+    /// File with one address per line, in the format of `fetch-deployments`
+    #[arg(long)]
+    addresses: Option<String>,
+    /// JSON-RPC endpoint of the chain, overrides the `rpc_url` of the selected profile
+    #[arg(long)]
+    rpc_url: Option<String>,
+    /// Minimum similarity of the opcodes, from 0 to 1, for a similar contract
+    /// to be reported as the source of a code
+    #[arg(long, default_value_t = 0.5)]
+    min_similarity: f64,
+    /// Print one json object per address
+    #[arg(long, default_value_t = false)]
+    json: bool,
+    /// Number of addresses whose code is fetched concurrently, within the
+    /// rate limit of the endpoint
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+}
+
+#[derive(Parser)]
+struct MatchBytecodeArgs {
+    /// File with the hex encoded runtime bytecode, e.g. of an unverified deployment
+    #[arg(long)]
+    file: String,
+    /// Number of most similar contracts to list
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Parser)]
+struct AbiVectorsArgs {
+    /// Text signature of an indexed function, e.g. `transfer(address,uint256)`
+    #[arg(
+        long,
+        required_unless_present = "selector",
+        conflicts_with = "selector"
+    )]
+    signature: Option<String>,
+    /// Selector of an indexed function, the signature implemented by the most
+    /// contracts is used
+    #[arg(long)]
+    selector: Option<String>,
+    /// Argument of the call in the order of the parameters, e.g. `0xdead...`,
+    /// `100` or `[1,2]`, can be repeated. Without arguments, random ones are
+    /// generated.
+    #[arg(long)]
+    arg: Vec<String>,
+    /// Number of encodings with random arguments
+    #[arg(long, default_value_t = 10)]
+    count: usize,
+    /// Seed of the random arguments, the same seed gives the same encodings
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+#[derive(Parser)]
+struct DecodeArgs {
+    /// The 0x prefixed calldata of a transaction or call
+    #[arg(long)]
+    data: String,
+}
+
+#[derive(Parser)]
+struct DecodeRevertArgs {
+    /// The 0x prefixed revert data of a failed transaction or call
+    #[arg(long)]
+    data: String,
+}
+
+#[derive(Parser)]
+struct DecodeLogArgs {
+    /// The 0x prefixed topics of the log, topic0 first, comma separated or
+    /// repeated
+    #[arg(long, required = true, value_delimiter = ',')]
+    topics: Vec<String>,
+    /// The 0x prefixed data of the log
+    #[arg(long, default_value = "0x")]
+    data: String,
+}
+
+#[derive(Parser)]
+struct AnalyzeArgs {
+    /// Tag contracts whose compiler version and settings match known compiler bugs
+    #[arg(long, default_value_t = false)]
+    compiler_bugs: bool,
+    /// The solidity bug list `bugs.json`, defaults to the bundled list
+    #[arg(long)]
+    bugs_file: Option<String>,
+    /// Flag source files whose `pragma solidity` range contradicts the compiler
+    /// version in the metadata
+    #[arg(long, default_value_t = false)]
+    pragma_mismatch: bool,
+    /// Record the use of language features, e.g. unchecked blocks, custom
+    /// errors or create2, in the `feature_usage` table
+    #[arg(long, default_value_t = false)]
+    features: bool,
+    /// Record the `import` statements of the sources in the `import_edge`
+    /// table, queried by `import-graph`
+    #[arg(long, default_value_t = false)]
+    imports: bool,
+    /// Number of contracts to analyze at once
+    #[arg(long, default_value_t = 1000)]
+    chunk_size: u64,
+}
+
+#[derive(Parser)]
+struct AstQueryArgs {
+    /// The query, e.g. `FunctionCall[expression.name=selfdestruct]`
+    #[arg(long)]
+    query: AstQuery,
+    /// Name of the query, matches are stored as findings of kind
+    /// `ast-query:<name>` and replace the matches of earlier runs
+    #[arg(long)]
+    name: String,
+    /// Severity of the stored findings
+    #[arg(long, default_value = "info")]
+    severity: String,
+    /// Only query this contract
+    #[arg(long)]
+    contract_id: Option<String>,
+    /// Number of contracts compiled concurrently
+    #[arg(long, default_value_t = 100)]
+    chunk_size: u64,
+}
+
+#[derive(Parser)]
+struct ExportFunctionAstArgs {
+    /// Print the AST of this function as JSON
+    #[arg(long, conflicts_with = "output")]
+    function_id: Option<String>,
+    /// Only export the functions of this contract
+    #[arg(long)]
+    contract_id: Option<String>,
+    /// File to write the ASTs of all indexed functions to, one JSON object per
+    /// line with the function id, contract id, contract name and signature
+    #[arg(long, required_unless_present = "function_id")]
+    output: Option<String>,
+    /// Number of contracts compiled concurrently
+    #[arg(long, default_value_t = 100)]
+    chunk_size: u64,
+    /// Optionally write a reproducibility manifest of the export to this file
+    #[arg(long)]
+    manifest: Option<String>,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+#[derive(Parser)]
+struct ImportGraphArgs {
+    /// Print the import graph of this contract in the DOT format of graphviz
+    #[arg(long, required_unless_present_any = ["imported", "symbol"])]
+    contract_id: Option<String>,
+    /// List the contracts importing a file whose path matches this glob
+    /// pattern, e.g. `*/access/AccessControl.sol`
+    #[arg(long)]
+    imported: Option<String>,
+    /// List the contracts importing this symbol by name, e.g. `AccessControl`
+    #[arg(long)]
+    symbol: Option<String>,
+}
+
+#[derive(Parser)]
+struct CloneReportArgs {
+    /// Number of most cloned implementations to list
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+#[derive(Parser)]
+struct DeployerReportArgs {
+    /// File with one `address,tag` per line tagging deployers with their
+    /// organization, the deployments of addresses with the same tag are grouped
+    #[arg(long)]
+    tags: Option<String>,
+    /// List the deployments of this cluster, by its tag or root deployer,
+    /// instead of the clusters
+    #[arg(long)]
+    cluster: Option<String>,
+    /// Number of largest clusters to list
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+    /// Print the clusters with their addresses and contract ids as JSON
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+#[derive(Parser)]
+struct CompilerTimelineArgs {
+    /// Release dates of the solidity compiler in the format of
+    /// `bugs_by_version.json` of the solidity repository, a bundled list is
+    /// used when missing
+    #[arg(long)]
+    releases_file: Option<String>,
+    /// Print the timeline as csv, json or a bar chart
+    #[arg(long, value_enum, default_value_t = TimelineFormat::Chart)]
+    format: TimelineFormat,
+    /// Width of the longest bar of the chart
+    #[arg(long, default_value_t = 60)]
+    width: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum TimelineFormat {
+    Csv,
+    Json,
+    Chart,
+}
+
+#[derive(Parser)]
+struct MergeDbArgs {
+    /// DuckDB file to merge into the database, can be repeated
+    #[arg(long, required = true)]
+    from: Vec<String>,
+}
+
+#[derive(Parser)]
+struct ImportArgs {
+    /// Parquet or JSONL dump of contracts, globs are expanded, e.g. `dump/*.parquet`
+    #[arg(long)]
+    contracts: String,
+    /// Parquet or JSONL dump of the functions of the contracts
+    #[arg(long)]
+    functions: Option<String>,
+    /// Number of contracts stored at once
+    #[arg(long, default_value_t = 1000)]
+    chunk_size: usize,
+}
+
+#[derive(Parser)]
+struct ImportBigqueryArgs {
+    /// Parquet, JSONL or CSV export of `crypto_ethereum.contracts`, can be
+    /// repeated and globs are expanded, e.g. `export/contracts-*.parquet`
+    #[arg(long, required = true)]
+    contracts: Vec<String>,
+    /// Chain of the dataset, e.g. 1 for `crypto_ethereum`
+    #[arg(long, default_value_t = 1)]
+    chain_id: u64,
+}
+
+#[derive(Parser)]
+struct ExportSiteArgs {
+    /// Folder to write the site to
+    #[arg(long)]
+    output: String,
+    /// A contract id to include, can be repeated
+    #[arg(long)]
+    contract_id: Vec<String>,
+    /// Include the contracts whose name matches this glob pattern, e.g. `Uniswap*`
+    #[arg(long)]
+    name: Option<String>,
+    /// Optionally write a reproducibility manifest of the export to this file
+    #[arg(long)]
+    manifest: Option<String>,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+#[derive(Parser)]
+struct ReleaseArgs {
+    /// Folder to write `contracts.jsonl`, `functions.jsonl` and `manifest.json` to
+    #[arg(long)]
+    output: String,
+    /// Snapshot id of a previous release, only the contracts and functions
+    /// stored since are exported, all of them when missing
+    #[arg(long)]
+    since: Option<String>,
+    /// Number of rows read from the database at once
+    #[arg(long, default_value_t = 1000)]
+    chunk_size: u64,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+#[derive(Parser)]
+struct ExportTripletsArgs {
+    /// Folder to write `triplets.jsonl` and `manifest.json` to
+    #[arg(long)]
+    output: String,
+    /// Number of functions sampled to build the triplets from
+    #[arg(long, default_value_t = 20000)]
+    sample: usize,
+    /// Seed of the samples, the same seed gives the same triplets
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Minimum similarity of the positive to the anchor, from 0 to 1
+    #[arg(long, default_value_t = 0.3)]
+    min_positive_similarity: f64,
+    /// Number of random functions the hardest negative is chosen from
+    #[arg(long, default_value_t = 8)]
+    negative_candidates: usize,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+#[derive(Parser)]
+struct ExportEnvArgs {
+    /// The gzipped tarball to write, e.g. `env.tar.gz`
+    #[arg(long)]
+    output: String,
+    /// Contract ids to bundle, can be repeated
+    #[arg(long)]
+    contract_id: Vec<String>,
+    /// Bundle the contracts whose name matches this glob pattern, e.g. `Uniswap*`
+    #[arg(long)]
+    name: Option<String>,
+    /// Bundle a random sample of this many contracts
+    #[arg(long)]
+    sample: Option<usize>,
+    /// Seed of the random sample, the same seed selects the same contracts
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    #[command(flatten)]
+    license: LicenseFilterArgs,
+}
+
+#[derive(Parser)]
+struct DatasetCardArgs {
+    /// File to write the markdown card to, stdout when missing
+    #[arg(long)]
+    output: Option<String>,
+    /// Title of the card
+    #[arg(long, default_value = "Smart contract sources")]
+    title: String,
+    /// Number of the largest licenses, compilers and chains listed, the others
+    /// are summed up
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+}
+
+#[derive(Parser)]
+struct XrefArgs {
+    /// The 0x prefixed address, matched case insensitively
+    #[arg(long)]
+    address: String,
+}
+
+#[derive(Parser)]
+struct ImportSignaturesArgs {
+    /// Text file with one signature per line, e.g. `Transfer(address,address,uint256)`
+    #[arg(long)]
+    file: String,
+    /// The kind of the signatures in the file
+    #[arg(long, value_enum)]
+    kind: SignatureKind,
+}
+
+#[derive(Parser)]
+struct MatchSlotArgs {
+    /// Storage slot observed on chain, decimal or 0x prefixed hex
+    #[arg(long)]
+    slot: String,
+    /// Mapping key observed together with the slot, e.g. an address
+    #[arg(long)]
+    key: Option<String>,
+    /// Highest declared slot to consider as the base of a mapping or array
+    #[arg(long, default_value_t = 256)]
+    max_base_slot: u64,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Preprocess the contracts with the given options
+    PreProcess(PreProcessArgs),
+    /// Compile all contracts and store populate the `function` table
+    IndexFunctions(IndexFunctionsArgs),
+    /// Download all solc binaries
+    DownloadSolc(DownloadSolcArgs),
+    /// Export source code of a contract
+    ExportSource(ExportSourceArgs),
+    /// Compile a contract and write its artifacts to disk
+    Compile(CompileArgs),
+    /// Compile again the contracts recorded in the `compile_error` table and
+    /// index the ones which compile
+    RetryFailed(RetryFailedArgs),
+    /// Compare the effective compiler settings of two contracts, e.g. to find
+    /// why the same sources give different bytecodes
+    DiffSettings(DiffSettingsArgs),
+    /// Decode immutable values of a contract from its deployed bytecode
+    ExtractImmutables(ExtractImmutablesArgs),
+    /// Compare the metadata hash of a recompiled contract with the one embedded
+    /// in its deployed bytecode
+    VerifyMetadata(VerifyMetadataArgs),
+    /// List contracts implementing all functions, events and errors of an interface
+    ConformsTo(ConformsToArgs),
+    /// Report contracts near or over the EIP-170 contract size limit
+    SizeReport(SizeReportArgs),
+    /// Compile all or a sample of the contracts without storing anything and report the success rate
+    CompileSweep(CompileSweepArgs),
+    /// Compile contracts with several solc versions their pragmas allow and
+    /// store the bytecode sizes and gas estimates of each version
+    IndexVersions(IndexVersionsArgs),
+    /// Search the indexed contracts
+    Search(SearchArgs),
+    /// Fetch the creation transactions of deployed contracts from the block explorer
+    FetchDeployments(FetchDeploymentsArgs),
+    /// Fetch the verified source code of deployed contracts from Etherscan or Blockscout and store them
+    FetchEtherscan(FetchEtherscanArgs),
+    /// Fetch the runtime code of deployed contracts, verified or not, from a
+    /// JSON-RPC endpoint and store it
+    FetchBytecode(FetchBytecodeArgs),
+    /// Recover the sources of stored runtime codes without a verified contract
+    /// from the metadata hash in the code and IPFS
+    FetchIpfs(FetchIpfsArgs),
+    /// Fetch releases of libraries from npm or GitHub tags and store each one
+    /// as a contract with all its sources
+    FetchPackage(FetchPackageArgs),
+    /// Run analysis passes over the stored contracts and store their findings
+    Analyze(AnalyzeArgs),
+    /// Find AST nodes matching a query in the compiled contracts and store them as findings
+    AstQuery(AstQueryArgs),
+    /// Export the AST subtree of indexed functions as JSON, one function or all
+    /// functions of the selected contracts
+    ExportFunctionAst(ExportFunctionAstArgs),
+    /// Print the import graph of a contract, or list the contracts importing a
+    /// file or symbol, from the edges recorded by `analyze --imports`
+    ImportGraph(ImportGraphArgs),
+    /// List the most cloned implementations among EIP-1167 minimal proxy deployments
+    CloneReport(CloneReportArgs),
+    /// Group the deployments by deployer, the factories it deployed and
+    /// organization tags
+    DeployerReport(DeployerReportArgs),
+    /// Count the solidity contracts by release month of their compiler version
+    CompilerTimeline(CompilerTimelineArgs),
+    /// Rank the indexed contracts by opcode similarity with a runtime bytecode,
+    /// e.g. to attribute an unverified deployment to a known source
+    MatchBytecode(MatchBytecodeArgs),
+    /// Find the best known source of the code deployed at each address, by
+    /// exact code and then by bytecode similarity, to triage address lists
+    Screen(ScreenArgs),
+    /// Generate example calldata of an indexed function, with random or given
+    /// arguments, e.g. as fuzzing seeds or decoding test vectors
+    AbiVectors(AbiVectorsArgs),
+    /// Decode calldata with the indexed and imported function signatures
+    /// matching its selector
+    Decode(DecodeArgs),
+    /// Decode revert data as an `Error(string)` reason, a `Panic(uint256)` code
+    /// or an indexed or imported custom error, with the contracts defining it
+    DecodeRevert(DecodeRevertArgs),
+    /// Decode an event log with the indexed and imported event signatures
+    /// matching its topic0
+    DecodeLog(DecodeLogArgs),
+    /// Suggest state variables which could live at a storage slot observed on chain
+    MatchSlot(MatchSlotArgs),
+    /// Import known text signatures of functions, events or errors
+    ImportSignatures(ImportSignaturesArgs),
+    /// List the contracts and functions embedding an address, as a literal, an
+    /// immutable value or a constructor argument
+    Xref(XrefArgs),
+    /// Merge the contracts, functions and other tables of other databases, e.g.
+    /// shards built on other machines, skipping rows already stored
+    MergeDb(MergeDbArgs),
+    /// Import contracts, and optionally their functions, from Parquet or JSONL
+    /// dumps of another pipeline
+    Import(ImportArgs),
+    /// Import the deployed contracts of an export of the BigQuery
+    /// `crypto_ethereum.contracts` table into the `bytecode` table
+    ImportBigquery(ImportBigqueryArgs),
+    /// Render a static HTML site with the highlighted sources and the indexed
+    /// functions of the selected contracts
+    ExportSite(ExportSiteArgs),
+    /// Export the contracts and functions stored since a previous release as
+    /// dumps readable by `import`, with a manifest of the release
+    Release(ReleaseArgs),
+    /// Export (anchor, positive, negative) function triplets for contrastive
+    /// embedding training, the positive implementing the same signature as the
+    /// anchor
+    ExportTriplets(ExportTripletsArgs),
+    /// Bundle a subset of the contracts with the compiler binaries they need,
+    /// the database schema and a script indexing them, to replicate the
+    /// indexing on an offline machine
+    ExportEnv(ExportEnvArgs),
+    /// Generate a markdown dataset card with the counts, license, compiler,
+    /// language and chain distributions, deduplication methodology and known
+    /// limitations of the database, to publish with its exports
+    DatasetCard(DatasetCardArgs),
+}
+
+fn export_manifest(storage: &Storage, args: &ExportSourceArgs) -> Result<ExportManifest> {
+    Ok(ExportManifest::new("export-source", storage.snapshot_id()?)
+        .filter("contract_id", &args.contract_id)
+        .filter("format", format!("{:?}", args.format).to_lowercase())
+        .filter("only", args.only.iter().join(","))
+        .filter(
+            "license_allowlist",
+            args.license
+                .new_filter()
+                .map(|f| f.allowlist())
+                .unwrap_or_default(),
+        )
+        .row_count("contract", 1))
+}
+
+async fn export_source(storage: &mut Storage, args: &ExportSourceArgs) -> Result<()> {
+    let contract = storage
+        .get_contract(&args.contract_id)?
+        .expect("Contract not found");
+    if let Some(filter) = args.license.filter(storage)? {
+        let license = storage
+            .contract_license(&args.contract_id)?
+            .unwrap_or_default();
+        if !filter.allows(&license) {
+            return Err(eyre::eyre!(
+                "Contract {} has license '{license}', not in the allowlist {}",
+                args.contract_id,
+                filter.allowlist()
+            ));
+        }
+    }
+
+    if args.stdout {
+        let content = match (&args.file, args.format) {
+            (Some(file), _) => contract.source_file_content(file)?.into_bytes(),
+            (None, ExportFormat::Json) => contract.source_json(&args.only)?.into_bytes(),
+            (None, ExportFormat::Zip) => contract.source_zip(&args.only)?,
+            (None, ExportFormat::Dir) => {
+                return Err(eyre::eyre!(
+                    "Exporting to stdout needs --file or --format json or zip"
+                ))
+            }
+        };
+        std::io::stdout().write_all(&content)?;
+
+        if let Some(path) = &args.manifest {
+            export_manifest(storage, args)?
+                .filter("file", args.file.as_deref().unwrap_or_default())
+                .output("-", &content)
+                .write(path)
+                .await?;
+        }
+        return Ok(());
+    }
+
+    let output_folder = args
+        .output_folder
+        .as_deref()
+        .expect("Output folder is required");
+    let files = match args.format {
+        ExportFormat::Dir => {
+            contract
+                .export_source_code(output_folder, &args.only)
+                .await?
+        }
+        ExportFormat::Zip => vec![
+            contract
+                .export_source_zip(output_folder, &args.only)
+                .await?,
+        ],
+        ExportFormat::Json => vec![
+            contract
+                .export_source_json(output_folder, &args.only)
+                .await?,
+        ],
+    };
+
+    if let Some(path) = &args.manifest {
+        export_manifest(storage, args)?
+            .outputs(Path::new(output_folder), &files)
+            .await?
+            .write(path)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Settings a contract compiles with, with the solc version it fell back to
+/// when it failed with its recorded one, and the overrides applied
+fn effective_settings(
+    storage: &Storage,
+    id: &str,
+    overrides: &CompilerOverrides,
+) -> Result<EffectiveSettings> {
+    let mut contract = storage
+        .get_contract(id)?
+        .ok_or_else(|| eyre::eyre!("Contract {id} not found"))?;
+    if let Some(version) = storage.compiler_fallback(id)? {
+        contract.metadata.compiler_version = format!("v{version}");
+    }
+    overrides.apply(&mut contract.metadata)?;
+    EffectiveSettings::of(&contract)
+}
+
+async fn diff_settings(storage: &mut Storage, args: &DiffSettingsArgs) -> Result<()> {
+    let a = effective_settings(storage, &args.a, &args.overrides)?;
+    let b = effective_settings(storage, &args.b, &args.overrides)?;
+    let diffs = settings_diff::diff_settings(&a, &b);
+
+    if args.json {
+        let report = serde_json::json!({"a": a, "b": b, "diffs": diffs});
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for d in diffs.iter() {
+            println!("{}: {} | {}", d.setting, d.a, d.b);
+        }
+    }
+    info!("Differing settings: {}", diffs.len());
+
+    Ok(())
+}
+
+async fn compile_contract(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &CompileArgs,
+) -> Result<()> {
+    let mut contract = storage
+        .get_contract(&args.contract_id)?
+        .ok_or_else(|| eyre::eyre!("Contract {} not found", args.contract_id))?;
+    args.overrides.apply(&mut contract.metadata)?;
+
+    let output = contract.compile_cached(artifact_cache).await?;
+    for diagnostic in output.output().errors.iter() {
+        eprintln!("{diagnostic}");
+    }
+    if output.has_compiler_errors() {
+        return Err(eyre::eyre!(
+            "Failed to compile contract with id {}",
+            args.contract_id
+        ));
+    }
+
+    let written = contract.write_artifacts(&args.output_dir).await?;
+    for path in written.iter() {
+        println!("{}", path.display());
+    }
+    info!("Artifacts written: {}", written.len());
+
+    Ok(())
+}
+
+async fn retry_failed(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &RetryFailedArgs,
+) -> Result<()> {
+    let mut contract_ids = storage
+        .compile_errors()?
+        .into_iter()
+        .map(|e| e.contract_id)
+        .unique()
+        .collect::<Vec<_>>();
+    if let Some(limit) = args.limit {
+        contract_ids.truncate(limit);
+    }
+
+    let mut output = IndexOutput::default();
+    let mut compiled = 0;
+    for contract_id in contract_ids.iter() {
+        let Some(mut contract) = storage.get_contract(contract_id)? else {
+            warn!("Contract {contract_id} of a compile error not found");
+            continue;
+        };
+        let recorded_version = contract.metadata.compiler_version.clone();
+        args.overrides.apply(&mut contract.metadata)?;
+        if matches!(contract.source, ContractSource::Vyper(_)) {
+            let version = vyper_version(&contract.metadata.compiler_version)?;
+            if let Err(e) = install_vyper(fetcher, &version).await {
+                error!("Failed to install vyper {version} {e}");
+            }
+        }
+
+        let failure = match contract.compile_cached(artifact_cache).await {
+            Err(e) => Some(e.to_string()),
+            Ok(compiled) if compiled.has_compiler_errors() => Some(compiled.to_string()),
+            Ok(_) => None,
+        };
+        if let Some(error) = failure {
+            warn!("Contract {contract_id} still fails to compile {error}");
+            output.compile_errors.push(CompileError {
+                contract_id: contract_id.clone(),
+                compiler_version: contract.metadata.compiler_version.clone(),
+                error,
+            });
+            continue;
+        }
+
+        let extracted = match contract.source {
+            ContractSource::Vyper(_) => IndexOutput::from_vyper_contract(&contract),
+            _ => IndexOutput::from_contract(&contract),
+        };
+        // the failure stays recorded, the contracts retried before are kept
+        let extracted = match extracted {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                warn!("Failed to extract functions from contract {contract_id} {e}");
+                output.compile_errors.push(CompileError {
+                    contract_id: contract_id.clone(),
+                    compiler_version: contract.metadata.compiler_version.clone(),
+                    error: format!("Failed to extract: {e}"),
+                });
+                continue;
+            }
+        };
+        info!(
+            "Compiled contract {contract_id}, {} functions extracted",
+            extracted.functions.len()
+        );
+        output.extend(extracted);
+        if contract.metadata.compiler_version != recorded_version {
+            output.compiler_fallbacks.push(CompilerFallback {
+                contract_id: contract_id.clone(),
+                recorded_version,
+                compiled_version: contract
+                    .metadata
+                    .compiler_version
+                    .trim_start_matches('v')
+                    .to_string(),
+            });
+        }
+        compiled += 1;
+    }
+
+    storage.store_index_output(&output)?;
+    println!(
+        "{compiled} of {} failed contracts compiled",
+        contract_ids.len()
+    );
+
+    Ok(())
+}
+
+async fn extract_immutables(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &ExtractImmutablesArgs,
+) -> Result<()> {
+    let mut contract = storage
+        .get_contract(&args.contract_id)?
+        .ok_or_else(|| eyre::eyre!("Contract {} not found", args.contract_id))?;
+    args.overrides.apply(&mut contract.metadata)?;
+    let deployed_bytecode = decode_hex(&std::fs::read_to_string(&args.deployed_bytecode)?)?;
+
+    contract.compile_cached(artifact_cache).await?;
+    let immutables = contract.extract_immutables(&deployed_bytecode, args.address.clone())?;
+    for immutable in immutables.iter() {
+        println!("{} {} = {}", immutable.ty, immutable.name, immutable.value);
+    }
+
+    storage.store_immutables(&immutables)
+}
+
+async fn verify_metadata(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &VerifyMetadataArgs,
+) -> Result<()> {
+    let mut contract = storage
+        .get_contract(&args.contract_id)?
+        .ok_or_else(|| eyre::eyre!("Contract {} not found", args.contract_id))?;
+    args.overrides.apply(&mut contract.metadata)?;
+    let deployed_bytecode = decode_hex(&std::fs::read_to_string(&args.deployed_bytecode)?)?;
+    let deployed_metadata_hash = cbor_metadata(&deployed_bytecode).and_then(|m| m.hash);
+
+    contract.compile_cached(artifact_cache).await?;
+    let hash = extract_metadata_hashes(&contract)?
+        .into_iter()
+        .find(|h| h.contract_name == contract.metadata.contract_name)
+        .ok_or_else(|| eyre::eyre!("No metadata for the main contract"))?;
+
+    let metadata_match =
+        storage.store_metadata_verification(&hash, deployed_metadata_hash.as_deref())?;
+    println!(
+        "{} {} compiled={} deployed={} keccak={}",
+        if metadata_match { "MATCH" } else { "MISMATCH" },
+        hash.contract_name,
+        hash.metadata_hash.as_deref().unwrap_or("-"),
+        deployed_metadata_hash.as_deref().unwrap_or("-"),
+        hash.metadata_keccak
+    );
+
+    let (matched, verified) = storage.metadata_match_count()?;
+    info!("Metadata matches: {matched} of {verified} verified artifacts");
+
+    Ok(())
+}
+
+async fn conforms_to(storage: &mut Storage, args: &ConformsToArgs) -> Result<()> {
+    let abi = load_interface(
+        &args.interface,
+        args.interface_name.as_deref(),
+        args.solc_version.as_deref(),
+    )
+    .await?;
+
+    let selectors: Vec<String> = abi.functions().map(selector_hex).unique().collect();
+    let topics: Vec<String> = abi.events().map(topic0_hex).unique().collect();
+    let errors: Vec<String> = abi.errors().map(error_selector_hex).unique().collect();
+    if selectors.is_empty() && topics.is_empty() && errors.is_empty() {
+        return Err(eyre::eyre!(
+            "The interface does not define any function, event or error"
+        ));
+    }
+
+    let contracts = storage.contracts_conforming_to(&selectors, &topics, &errors)?;
+    for (contract_id, contract_name) in contracts.iter() {
+        println!("{contract_id} {contract_name}");
+    }
+    info!("Contracts conforming to the interface: {}", contracts.len());
+
+    Ok(())
+}
+
+async fn size_report(storage: &mut Storage, args: &SizeReportArgs) -> Result<()> {
+    let artifacts = storage.artifacts_larger_than(EIP170_LIMIT.saturating_sub(args.margin))?;
+    for artifact in artifacts.iter() {
+        let status = if artifact.runtime_size > EIP170_LIMIT {
+            "OVER"
+        } else {
+            "NEAR"
+        };
+        println!(
+            "{status} {} {}:{} {} bytes",
+            artifact.contract_id,
+            artifact.source_path,
+            artifact.contract_name,
+            artifact.runtime_size
+        );
+        for function in storage.largest_functions(artifact, args.top_functions)? {
+            println!("    {:>6} {}", function.size, function.function_name);
+        }
+    }
+    info!("Contracts near or over the size limit: {}", artifacts.len());
+
+    Ok(())
+}
+
+/// Hashes of the artifacts of the contract, `None` when it does not compile
+/// without errors
+async fn compile_hashes(mut contract: PlainContract) -> Option<BTreeMap<String, String>> {
+    let started = Instant::now();
+    let hashes = match contract.compile().await {
+        Ok(output) if !output.has_compiler_errors() => Some(artifact_hashes(&output)),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("Failed to compile contract with id {} {}", contract.id(), e);
+            None
+        }
+    };
+    let success = hashes.is_some();
+    debug!(
+        stage = "compile",
+        contract_id = contract.id().as_str(),
+        duration_ms = started.elapsed().as_millis() as u64,
+        outcome = if success { "ok" } else { "error" };
+        "Compiled contract {}", contract.id()
+    );
+    hashes
+}
+
+async fn compile_sweep(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    args: &CompileSweepArgs,
+) -> Result<()> {
+    let contracts = match args.sample {
+        Some(size) => storage.sample_contracts(size, args.seed)?,
+        None => storage.get_contracts(0, storage.count_contracts()? as u64)?,
+    };
+    let vyper_versions: HashSet<_> = contracts
+        .iter()
+        .filter(|c| matches!(c.source, ContractSource::Vyper(_)))
+        .filter_map(|c| vyper_version(&c.metadata.compiler_version).ok())
+        .collect();
+    for version in vyper_versions {
+        if let Err(e) = install_vyper(fetcher, &version).await {
+            error!("Failed to install vyper {version} {e}");
+        }
+    }
+
+    let pb = ProgressBar::new(contracts.len() as u64);
+    let mut report = SweepReport::default();
+    let check_determinism = args.check_determinism;
+    for chunk in contracts.chunks(args.chunk_size) {
+        let outcomes = try_join_all(chunk.iter().cloned().map(|contract| {
+            task::spawn(async move {
+                let version = contract.metadata.compiler_version.trim_start_matches('v');
+                let version = version.split('+').next().unwrap_or_default().to_string();
+                let source_type = contract.source.source_type().to_string();
+                let id = contract.id();
+                let first = compile_hashes(contract.clone()).await;
+                // each compilation uses a new temporary folder
+                let differing = match &first {
+                    Some(first) if check_determinism => {
+                        let second = compile_hashes(contract).await.unwrap_or_default();
+                        Some(differing_artifacts(first, &second))
+                    }
+                    _ => None,
+                };
+                (id, version, source_type, first.is_some(), differing)
+            })
+        }))
+        .await?;
+        for (id, version, source_type, success, differing) in outcomes {
+            report.add(&version, &source_type, success);
+            if let Some(differing) = differing {
+                report.add_determinism(&id, differing);
+            }
+        }
+        pb.inc(chunk.len() as u64);
+    }
+    pb.finish_and_clear();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    let print = |name: &str, stats: &SweepStats| {
+        println!(
+            "{name:<24} {:>6}/{:<6} {:>6.2}%",
+            stats.succeeded,
+            stats.attempted,
+            stats.success_rate()
+        )
+    };
+    print("total", &report.total);
+    println!("\nby compiler version");
+    for (version, stats) in report.by_compiler_version.iter() {
+        print(version, stats);
+    }
+    println!("\nby source type");
+    for (source_type, stats) in report.by_source_type.iter() {
+        print(source_type, stats);
+    }
+    if let Some(checked) = report.determinism_checked {
+        println!(
+            "\nnondeterministic {}/{checked}",
+            report.nondeterministic.len()
+        );
+        for n in report.nondeterministic.iter() {
+            println!("{} {}", n.contract_id, n.artifacts.join(" "));
+        }
+    }
+
+    Ok(())
+}
+
+async fn index_versions(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &IndexVersionsArgs,
+) -> Result<()> {
+    let contracts = if !args.contract_id.is_empty() {
+        args.contract_id
+            .iter()
+            .map(|id| {
+                storage
+                    .get_contract(id)?
+                    .ok_or_else(|| eyre::eyre!("Contract {id} not found"))
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        match args.sample {
+            Some(size) => storage.sample_contracts(size, args.seed)?,
+            None => storage.get_contracts(0, storage.count_contracts()? as u64)?,
+        }
+    };
+    // vyper releases are not listed by svm
+    let contracts = contracts
+        .into_iter()
+        .filter(|c| !matches!(c.source, ContractSource::Vyper(_)))
+        .collect_vec();
+    let available = versions::available_versions();
+
+    let pb = ProgressBar::new(contracts.len() as u64);
+    let (mut compiled, mut stored) = (0, 0);
+    for contract in contracts.iter() {
+        pb.inc(1);
+        let mut selected = match versions::select_versions(contract, &available, args.max_versions)
+        {
+            Ok(selected) => selected,
+            Err(e) => {
+                warn!("No compiler versions for contract {}: {e}", contract.id());
+                continue;
+            }
+        };
+        if !args.force {
+            let done = storage.versioned_solc_versions(&contract.id())?;
+            selected.retain(|v| !done.contains(&v.to_string()));
+        }
+        if selected.is_empty() {
+            continue;
+        }
+        let (artifacts, estimates) =
+            versions::compile_versions(contract, artifact_cache, &selected).await?;
+        compiled += selected.len();
+        stored += artifacts.len();
+        storage.store_versioned(&artifacts, &estimates)?;
+    }
+    pb.finish_and_clear();
+    info!("Compilations: {compiled}, versioned artifacts stored: {stored}");
+
+    Ok(())
+}
+
+async fn search(storage: &mut Storage, args: &SearchArgs) -> Result<()> {
+    if let Some(topic0) = &args.topic0 {
+        return search_topic0(storage, &normalize_hash(topic0));
+    }
+    if let Some(deployer) = &args.deployer {
+        return search_deployer(storage, &deployer.to_lowercase());
+    }
+    if let Some(literal) = &args.literal {
+        return search_literal(storage, literal);
+    }
+    if let Some(name) = &args.type_name {
+        return search_type_definitions(storage, name);
+    }
+    if let Some(opcode) = &args.assembly_opcode {
+        return search_assembly(storage, &opcode.to_lowercase());
+    }
+    if let Some(chain_id) = args.chain_id {
+        let address = args.address.as_deref().map(str::to_lowercase);
+        return search_chain(storage, chain_id, address.as_deref());
+    }
+    if let Some(address) = &args.address {
+        return search_address(storage, &address.to_lowercase());
+    }
+    if let Some(contract_id) = &args.occurrences {
+        return search_occurrences(storage, contract_id);
+    }
+    if let Some(keccak_id) = &args.keccak_id {
+        return search_keccak_id(storage, keccak_id);
+    }
+    if let Some(kind) = &args.external_call {
+        return search_external_calls(storage, kind, args.target_kind.as_deref());
+    }
+    if let Some(name) = &args.modifier {
+        return search_modifier(storage, name);
+    }
+    if let Some(name) = &args.state_variable {
+        return search_state_variables(storage, name, args.variable_type.as_deref());
+    }
+    if args.storage_type.is_none() && args.slot.is_none() {
+        return Err(eyre::eyre!("At least one search filter should be provided"));
+    }
+
+    let slot = args
+        .slot
+        .as_deref()
+        .map(parse_word)
+        .transpose()?
+        .map(|slot| slot.to_string());
+    let slots = storage.search_storage_slots(args.storage_type.as_deref(), slot.as_deref(), &[])?;
+    for s in slots.iter() {
+        println!(
+            "{} {} slot {} {} {}",
+            s.contract_id, s.contract_name, s.slot, s.ty, s.label
+        );
+    }
+    info!("Matching state variables: {}", slots.len());
+
+    Ok(())
+}
+
+fn search_topic0(storage: &mut Storage, topic0: &str) -> Result<()> {
+    let events = storage.events_by_topic0(topic0)?;
+    for e in events.iter() {
+        println!("{} {} {}", e.contract_id, e.contract_name, e.signature);
+    }
+    for signature in storage.known_signatures(SignatureKind::Event, topic0)? {
+        println!("known {signature}");
+    }
+    info!("Matching indexed events: {}", events.len());
+
+    Ok(())
+}
+
+fn search_deployer(storage: &mut Storage, deployer: &str) -> Result<()> {
+    let deployments = storage.deployments_by(deployer)?;
+    for d in deployments.iter() {
+        println!(
+            "{} {} {} block {} tx {}",
+            d.chain_id,
+            d.address,
+            d.contract_id.as_deref().unwrap_or("-"),
+            d.block_number.map(|n| n.to_string()).unwrap_or("-".into()),
+            d.tx_hash
+        );
+    }
+    info!("Contracts deployed by {deployer}: {}", deployments.len());
+
+    Ok(())
+}
+
+fn search_literal(storage: &mut Storage, literal: &str) -> Result<()> {
+    let functions = storage.functions_with_literal(literal)?;
+    for (f, kind) in functions.iter() {
+        println!(
+            "{} {} {} {kind}",
+            f.contract_id, f.contract_name, f.signature
+        );
+    }
+    info!("Functions using {literal}: {}", functions.len());
+
+    Ok(())
+}
+
+fn search_assembly(storage: &mut Storage, opcode: &str) -> Result<()> {
+    let functions = storage.functions_with_assembly(opcode)?;
+    for (f, opcodes) in functions.iter() {
+        println!(
+            "{} {} {} {opcodes}",
+            f.contract_id, f.contract_name, f.signature
+        );
+    }
+    info!(
+        "Functions with assembly using {opcode}: {}",
+        functions.len()
+    );
+
+    Ok(())
+}
+
+fn search_chain(storage: &mut Storage, chain_id: u64, address: Option<&str>) -> Result<()> {
+    let contracts = storage.contracts_on_chain(chain_id, address)?;
+    for (id, name, address) in contracts.iter() {
+        println!("{} {id} {name}", address.as_deref().unwrap_or("-"));
+    }
+    info!("Contracts on chain {chain_id}: {}", contracts.len());
+
+    Ok(())
+}
+
+fn search_address(storage: &mut Storage, address: &str) -> Result<()> {
+    let addresses = storage.contracts_at(address)?;
+    for a in addresses.iter() {
+        println!("{} {} {}", a.chain_id, a.address, a.contract_id);
+    }
+    info!("Contracts at {address}: {}", addresses.len());
+
+    Ok(())
+}
+
+fn search_occurrences(storage: &mut Storage, contract_id: &str) -> Result<()> {
+    let occurrences = storage.occurrences(contract_id)?;
+    for o in occurrences.iter() {
+        let chain_id = o.chain_id.map(|c| c.to_string());
+        println!(
+            "{} {} {} {}",
+            o.dataset,
+            o.path,
+            chain_id.as_deref().unwrap_or("-"),
+            o.address.as_deref().unwrap_or("-")
+        );
+    }
+    info!("Occurrences of {contract_id}: {}", occurrences.len());
+
+    Ok(())
+}
+
+fn search_keccak_id(storage: &mut Storage, keccak_id: &str) -> Result<()> {
+    let contract_ids = storage.contract_ids_by_keccak_id(keccak_id)?;
+    for contract_id in contract_ids.iter() {
+        println!("{contract_id}");
+    }
+    info!(
+        "Contracts with keccak id {keccak_id}: {}",
+        contract_ids.len()
+    );
+
+    Ok(())
+}
+
+fn search_external_calls(
+    storage: &mut Storage,
+    kind: &str,
+    target_kind: Option<&str>,
+) -> Result<()> {
+    let calls = storage.external_calls(kind, target_kind)?;
+    for (f, c) in calls.iter() {
+        println!(
+            "{} {} {} {} {} {}",
+            f.contract_id, f.contract_name, f.signature, c.target, c.target_kind, c.member
+        );
+    }
+    info!("External calls of kind {kind}: {}", calls.len());
+
+    Ok(())
+}
+
+fn search_modifier(storage: &mut Storage, name: &str) -> Result<()> {
+    let functions = storage.functions_with_modifier(name)?;
+    let mut definitions = HashSet::new();
+    for (f, m, source_code) in functions.iter() {
+        println!(
+            "{} {} {} {}",
+            f.contract_id, f.contract_name, f.signature, m.invocation
+        );
+        if !source_code.is_empty() && definitions.insert(source_code) {
+            println!("{source_code}");
+        }
+    }
+    info!("Functions with modifier {name}: {}", functions.len());
+
+    Ok(())
+}
+
+fn search_state_variables(storage: &mut Storage, name: &str, ty: Option<&str>) -> Result<()> {
+    let variables = storage.state_variables(name, ty)?;
+    for (v, slot) in variables.iter() {
+        let slot = match slot {
+            Some((slot, offset)) => format!("slot {slot} offset {offset}"),
+            None => "no slot".into(),
+        };
+        println!(
+            "{} {} {} {} {} {} {slot}",
+            v.contract_id, v.contract_name, v.ty, v.visibility, v.mutability, v.name
+        );
+    }
+    info!("State variables named {name}: {}", variables.len());
+
+    Ok(())
+}
+
+fn search_type_definitions(storage: &mut Storage, name: &str) -> Result<()> {
+    let definitions = storage.type_definitions(name)?;
+    for d in definitions.iter() {
+        println!(
+            "{} {} {} {} {{{}}}",
+            d.contract_id, d.filename, d.kind, d.name, d.members
+        );
+    }
+    info!("Definitions of {name}: {}", definitions.len());
+
+    Ok(())
+}
+
+async fn fetch_deployments(
+    storage: &mut Storage,
+    explorer: &Explorer<'_>,
+    chain_id: u64,
+    args: &FetchDeploymentsArgs,
+) -> Result<()> {
+    let addresses = read_addresses(&args.addresses).await?;
+    let mut deployments = Vec::with_capacity(addresses.len());
+    for creation in explorer
+        .contract_creations(&addresses.keys().cloned().collect_vec())
+        .await?
+    {
+        let contract_id = addresses
+            .get(&creation.contract_address.to_lowercase())
+            .cloned()
+            .flatten();
+        let mut deployment = Deployment::from_creation(chain_id, creation, contract_id)?;
+        if args.detect_clones {
+            let code = explorer.code(&deployment.address).await?;
+            deployment.implementation = eip1167_implementation(&code);
+        }
+        deployments.push(deployment);
+    }
+    storage.store_deployments(&deployments)?;
+    info!(
+        "Stored {} deployments on chain {chain_id}",
+        deployments.len()
+    );
+
+    Ok(())
+}
+
+/// Verified contracts at the addresses, unverified contracts are skipped
+async fn fetch_verified(
+    explorer: &Explorer<'_>,
+    chain_id: Option<u64>,
+    addresses: &[String],
+) -> Result<Vec<PlainContract>> {
+    let mut contracts = vec![];
+    for address in addresses {
+        for raw in explorer.source_code(address).await? {
+            if raw.source_code.is_empty() {
+                match chain_id {
+                    Some(chain_id) => {
+                        warn!("No verified source code for {address} on chain {chain_id}")
+                    }
+                    None => warn!("No verified source code for {address}"),
+                }
+                continue;
+            }
+            let mut contract = PlainContract::from_etherscan_raw(&raw);
+            contract.metadata.chain_id = chain_id;
+            contract.metadata.address = Some(address.clone());
+            contracts.push(contract);
+        }
+    }
+    Ok(contracts)
+}
+
+async fn fetch_etherscan(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    url: &str,
+    api_key: Option<String>,
+    chain_ids: &[Option<u64>],
+    args: &FetchEtherscanArgs,
+) -> Result<()> {
+    let mut addresses = args.address.iter().map(|a| a.to_lowercase()).collect_vec();
+    if let Some(path) = &args.addresses {
+        addresses.extend(read_addresses(path).await?.into_keys().sorted());
+    }
+    if addresses.is_empty() {
+        return Err(eyre::eyre!(
+            "At least one of the address or addresses should be provided"
+        ));
+    }
+    let addresses = addresses.into_iter().unique().collect_vec();
+    if chain_ids.len() > 1 && !is_etherscan_v2(url) {
+        return Err(eyre::eyre!(
+            "Fetching several chains needs the Etherscan V2 API, {url} serves a single chain"
+        ));
+    }
+
+    // the chains are fetched concurrently through one fetcher, so that the
+    // requests of all chains together stay within the rate limit of the key
+    let fetches = chain_ids.iter().map(|chain_id| {
+        let explorer = Explorer::with_url(fetcher, url, api_key.clone()).chain(*chain_id);
+        let chain_id = explorer.chain_id().or(*chain_id);
+        let addresses = &addresses;
+        async move { fetch_verified(&explorer, chain_id, addresses).await }
+    });
+    let contracts = try_join_all(fetches)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect_vec();
+    let count = contracts.len();
+    let contracts = contracts
+        .into_iter()
+        .map(|contract| (fetched_path(&contract), contract))
+        .collect_vec();
+    store_occurring(storage, &redact_url(url), &contracts)?;
+    info!("Stored {count} contracts");
+
+    Ok(())
+}
+
+async fn fetch_bytecode(
+    storage: &mut Storage,
+    rpc: &RpcClient<'_>,
+    chain_id: Option<u64>,
+    args: &FetchBytecodeArgs,
+) -> Result<()> {
+    let mut addresses: Vec<(String, Option<String>)> = args
+        .address
+        .iter()
+        .map(|a| (a.to_lowercase(), None))
+        .collect();
+    if let Some(path) = &args.addresses {
+        addresses.extend(read_addresses(path).await?.into_iter().sorted());
+    }
+    if addresses.is_empty() {
+        return Err(eyre::eyre!(
+            "At least one of the address or addresses should be provided"
+        ));
+    }
+    let chain_id = match chain_id {
+        Some(chain_id) => chain_id,
+        None => rpc.chain_id().await?,
+    };
+
+    let mut bytecodes = vec![];
+    for (address, contract_id) in addresses.into_iter().unique_by(|(a, _)| a.clone()) {
+        let code = rpc.code(&address).await?;
+        if code.is_empty() {
+            warn!("No code at {address}");
+            continue;
+        }
+        bytecodes.push(DeployedBytecode::new(
+            chain_id,
+            &address,
+            &code,
+            contract_id,
+        ));
+    }
+    storage.store_bytecodes(&bytecodes)?;
+    let (total, linked) = storage.count_bytecodes()?;
+    info!("Stored {} bytecodes on chain {chain_id}", bytecodes.len());
+    info!("Total bytecodes: {total}, with source: {linked}");
+
+    Ok(())
+}
+
+/// An analysis pass producing findings of one kind for a contract
+type AnalysisPass<'a> = (
+    &'static str,
+    Box<dyn Fn(&PlainContract) -> Result<Vec<Finding>> + 'a>,
+);
+
+async fn analyze(storage: &mut Storage, args: &AnalyzeArgs) -> Result<()> {
+    let bugs = match args.compiler_bugs {
+        true => load_bugs(args.bugs_file.as_deref())?,
+        false => vec![],
+    };
+    let mut passes: Vec<AnalysisPass> = vec![];
+    if args.compiler_bugs {
+        passes.push((
+            COMPILER_BUG_FINDING,
+            Box::new(|contract| find_compiler_bugs(contract, &bugs)),
+        ));
+    }
+    if args.pragma_mismatch {
+        passes.push((PRAGMA_MISMATCH_FINDING, Box::new(find_pragma_mismatches)));
+    }
+    if passes.is_empty() && !args.features && !args.imports {
+        return Err(eyre::eyre!("At least one analysis pass should be selected"));
+    }
+
+    let mut offset = 0;
+    loop {
+        let contracts = storage.get_contracts(offset, args.chunk_size)?;
+        if contracts.is_empty() {
+            break;
+        }
+        offset += contracts.len() as u64;
+
+        for (kind, pass) in passes.iter() {
+            let mut contract_ids = Vec::with_capacity(contracts.len());
+            let mut findings = vec![];
+            for contract in contracts.iter() {
+                if matches!(contract.source, ContractSource::Vyper(_)) {
+                    continue;
+                }
+                match pass(contract) {
+                    Ok(found) => {
+                        contract_ids.push(contract.id());
+                        findings.extend(found);
+                    }
+                    Err(e) => warn!(
+                        "Failed to run {kind} analysis on contract with id {} {}",
+                        contract.id(),
+                        e
+                    ),
+                }
+            }
+            storage.store_findings(kind, &contract_ids, &findings)?;
+        }
+
+        if args.features {
+            let mut contract_ids = Vec::with_capacity(contracts.len());
+            let mut usages = vec![];
+            for contract in contracts.iter() {
+                if matches!(contract.source, ContractSource::Vyper(_)) {
+                    continue;
+                }
+                match find_features(contract) {
+                    Ok(found) => {
+                        contract_ids.push(contract.id());
+                        usages.extend(found);
+                    }
+                    Err(e) => warn!(
+                        "Failed to run feature analysis on contract with id {} {}",
+                        contract.id(),
+                        e
+                    ),
+                }
+            }
+            storage.store_feature_usages(&contract_ids, &usages)?;
+        }
+
+        if args.imports {
+            let mut contract_ids = Vec::with_capacity(contracts.len());
+            let mut edges = vec![];
+            for contract in contracts.iter() {
+                if matches!(contract.source, ContractSource::Vyper(_)) {
+                    continue;
+                }
+                match find_imports(contract) {
+                    Ok(found) => {
+                        contract_ids.push(contract.id());
+                        edges.extend(found);
+                    }
+                    Err(e) => warn!(
+                        "Failed to read the imports of contract with id {} {}",
+                        contract.id(),
+                        e
+                    ),
+                }
+            }
+            storage.store_import_edges(&contract_ids, &edges)?;
+        }
+    }
+
+    if args.compiler_bugs {
+        for (name, contracts) in storage.count_findings(COMPILER_BUG_FINDING)? {
+            println!("{name} {contracts}");
+        }
+    }
+    if args.pragma_mismatch {
+        let findings = storage.findings(PRAGMA_MISMATCH_FINDING)?;
+        for f in findings.iter() {
+            println!("{} {} {}", f.contract_id, f.name, f.detail);
+        }
+        info!("Source files with mismatching pragmas: {}", findings.len());
+    }
+    if args.features {
+        for a in storage.feature_adoption()? {
+            println!("{} {} {}/{}", a.version, a.feature, a.contracts, a.total);
+        }
+    }
+    info!("Analyzed contracts: {offset}");
+
+    Ok(())
+}
+
+async fn ast_query(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &AstQueryArgs,
+) -> Result<()> {
+    let kind = format!("{AST_QUERY_FINDING}:{}", args.name);
+    let mut offset = 0;
+    loop {
+        let contracts = match &args.contract_id {
+            Some(_) if offset > 0 => vec![],
+            Some(id) => storage.get_contract(id)?.into_iter().collect(),
+            None => storage.get_contracts(offset, args.chunk_size)?,
+        };
+        if contracts.is_empty() {
+            break;
+        }
+        offset += contracts.len() as u64;
+
+        // contracts indexed with their ASTs are not compiled again
+        let mut queried = vec![];
+        let mut uncompiled = vec![];
+        for mut contract in contracts
+            .into_iter()
+            .filter(|c| !matches!(c.source, ContractSource::Vyper(_)))
+        {
+            let asts = storage.contract_source_asts(&contract.id())?;
+            if asts.is_empty() {
+                uncompiled.push(contract);
+                continue;
+            }
+            contract.source_files = Some(contract.get_source_files()?);
+            queried.push((contract, asts));
+        }
+
+        let compiled = try_join_all(uncompiled.into_iter().map(|mut contract| {
+            let artifact_cache = artifact_cache.cloned();
+            task::spawn(async move {
+                match contract.compile_cached(artifact_cache.as_ref()).await {
+                    Ok(output) if !output.has_compiler_errors() => Some(contract),
+                    _ => {
+                        warn!("Failed to compile contract with id {}", contract.id());
+                        None
+                    }
+                }
+            })
+        }))
+        .await?;
+        for contract in compiled.into_iter().flatten() {
+            let asts = source_asts(&contract)?;
+            queried.push((contract, asts));
+        }
+
+        let mut contract_ids = vec![];
+        let mut findings = vec![];
+        for (contract, asts) in queried.iter() {
+            let found = query_contract(contract, asts, &args.query, &kind, &args.severity)?;
+            for f in found.iter() {
+                println!(
+                    "{} {} {}",
+                    f.contract_id,
+                    f.name,
+                    f.detail.lines().next().unwrap_or_default()
+                );
+            }
+            contract_ids.push(contract.id());
+            findings.extend(found);
+        }
+        storage.store_findings(&kind, &contract_ids, &findings)?;
+        info!("Matches: {}", findings.len());
+    }
+    info!("Queried contracts: {offset}");
+
+    Ok(())
+}
+
+fn function_ast_manifest(
+    storage: &Storage,
+    args: &ExportFunctionAstArgs,
+    license_filter: Option<&LicenseFilter>,
+    exported: usize,
+) -> Result<ExportManifest> {
+    Ok(
+        ExportManifest::new("export-function-ast", storage.snapshot_id()?)
+            .filter(
+                "function_id",
+                args.function_id.as_deref().unwrap_or_default(),
+            )
+            .filter(
+                "contract_id",
+                args.contract_id.as_deref().unwrap_or_default(),
+            )
+            .filter(
+                "license_allowlist",
+                license_filter.map(|f| f.allowlist()).unwrap_or_default(),
+            )
+            .row_count("function", exported),
+    )
+}
+
+async fn export_function_ast(
+    storage: &mut Storage,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &ExportFunctionAstArgs,
+) -> Result<()> {
+    let license_filter = args.license.filter(storage)?;
+    let allowed = |storage: &Storage, contract_id: &str| -> Result<bool> {
+        let Some(filter) = &license_filter else {
+            return Ok(true);
+        };
+        let license = storage.contract_license(contract_id)?.unwrap_or_default();
+        Ok(filter.allows(&license))
+    };
+
+    if let Some(id) = &args.function_id {
+        let function = storage
+            .get_function(id)?
+            .ok_or_else(|| eyre::eyre!("Function {id} not found"))?;
+        let mut contract = storage
+            .get_contract(&function.contract_id)?
+            .ok_or_else(|| eyre::eyre!("Contract {} not found", function.contract_id))?;
+        if !allowed(storage, &function.contract_id)? {
+            return Err(eyre::eyre!(
+                "Contract {} of function {id} has a license not in the allowlist",
+                function.contract_id
+            ));
+        }
+        contract.compile_cached(artifact_cache).await?;
+        let asts = source_asts(&contract)?;
+        let ast = function_ast(&asts, &function.contract_name, &function.selector)
+            .ok_or_else(|| eyre::eyre!("No definition of {} in the AST", function.signature))?;
+        let content = serde_json::to_string_pretty(ast)?;
+        println!("{content}");
+
+        if let Some(path) = &args.manifest {
+            function_ast_manifest(storage, args, license_filter.as_ref(), 1)?
+                .output("-", format!("{content}\n").as_bytes())
+                .write(path)
+                .await?;
+        }
+        return Ok(());
+    }
+
+    let output = args
+        .output
+        .as_deref()
+        .ok_or_else(|| eyre::eyre!("Export needs --output or --function-id"))?;
+    let mut out = std::io::BufWriter::new(std::fs::File::create(output)?);
+    let (mut offset, mut exported) = (0, 0);
+    loop {
+        let contracts = match &args.contract_id {
+            Some(_) if offset > 0 => vec![],
+            Some(id) => storage.get_contract(id)?.into_iter().collect(),
+            None => storage.get_contracts(offset, args.chunk_size)?,
+        };
+        if contracts.is_empty() {
+            break;
+        }
+        offset += contracts.len() as u64;
+
+        let mut selected = vec![];
+        for contract in contracts {
+            let id = contract.id();
+            if matches!(contract.source, ContractSource::Vyper(_)) {
+                continue;
+            }
+            if !allowed(storage, &id)? {
+                debug!("Skip contract {id} with a license not in the allowlist");
+                continue;
+            }
+            selected.push(contract);
+        }
+        let compiled = try_join_all(selected.into_iter().map(|mut contract| {
+            let artifact_cache = artifact_cache.cloned();
+            task::spawn(async move {
+                match contract.compile_cached(artifact_cache.as_ref()).await {
+                    Ok(output) if !output.has_compiler_errors() => Some(contract),
+                    _ => {
+                        warn!("Failed to compile contract with id {}", contract.id());
+                        None
+                    }
+                }
+            })
+        }))
+        .await?;
+
+        for contract in compiled.into_iter().flatten() {
+            let asts = source_asts(&contract)?;
+            for f in storage.contract_functions(&contract.id())? {
+                let Some(ast) = function_ast(&asts, &f.contract_name, &f.selector) else {
+                    debug!(
+                        "No definition of {} {} in the AST",
+                        f.contract_name, f.signature
+                    );
+                    continue;
+                };
+                serde_json::to_writer(
+                    &mut out,
+                    &serde_json::json!({
+                        "function_id": f.id,
+                        "contract_id": f.contract_id,
+                        "contract_name": f.contract_name,
+                        "signature": f.signature,
+                        "ast": ast,
+                    }),
+                )?;
+                out.write_all(b"\n")?;
+                exported += 1;
+            }
+        }
+    }
+    out.flush()?;
+    drop(out);
+    info!("Exported the AST of {exported} functions to {output}");
+
+    if let Some(path) = &args.manifest {
+        let output = Path::new(output);
+        let root = output.parent().unwrap_or(Path::new(""));
+        function_ast_manifest(storage, args, license_filter.as_ref(), exported)?
+            .outputs(root, &[output.to_path_buf()])
+            .await?
+            .write(path)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn import_graph(storage: &mut Storage, args: &ImportGraphArgs) -> Result<()> {
+    let edges = storage.import_edges(
+        args.contract_id.as_deref(),
+        args.imported.as_deref(),
+        args.symbol.as_deref(),
+    )?;
+    match &args.contract_id {
+        Some(id) if args.imported.is_none() && args.symbol.is_none() => {
+            print!("{}", to_dot(id, &edges));
+        }
+        _ => {
+            for e in edges.iter() {
+                println!(
+                    "{} {} -> {} {}",
+                    e.contract_id, e.from_file, e.to_file, e.symbols
+                );
+            }
+            info!(
+                "Importing contracts: {}",
+                edges.iter().map(|e| &e.contract_id).unique().count()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn clone_report(storage: &mut Storage, args: &CloneReportArgs) -> Result<()> {
+    let groups = storage.clone_groups(args.limit)?;
+    for g in groups.iter() {
+        println!(
+            "{} {} {} clones {}",
+            g.chain_id,
+            g.implementation,
+            g.contract_id.as_deref().unwrap_or("-"),
+            g.clones
+        );
+    }
+    info!("Cloned implementations: {}", groups.len());
+
+    Ok(())
+}
+
+async fn deployer_report(storage: &mut Storage, args: &DeployerReportArgs) -> Result<()> {
+    let tags = match &args.tags {
+        Some(path) => read_tags(path).await?,
+        None => HashMap::new(),
+    };
+    let deployments = storage.deployments()?;
+    let create2_contract_ids = storage.contract_ids_using("create2")?;
+    let clusters = deployer_clusters(&deployments, &tags, &create2_contract_ids);
+
+    if let Some(name) = &args.cluster {
+        let cluster = clusters
+            .iter()
+            .find(|c| c.name == *name || c.name == name.to_lowercase())
+            .ok_or_else(|| eyre::eyre!("No deployer cluster {name}"))?;
+        for d in deployments.iter().filter(|d| {
+            cluster
+                .addresses
+                .binary_search(&(d.chain_id, d.address.clone()))
+                .is_ok()
+        }) {
+            println!(
+                "{} {} {} deployer {}",
+                d.chain_id,
+                d.address,
+                d.contract_id.as_deref().unwrap_or("-"),
+                d.deployer
+            );
+        }
+        info!("Deployments of {name}: {}", cluster.addresses.len());
+        return Ok(());
+    }
+
+    let shown = &clusters[..clusters.len().min(args.limit)];
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(shown)?);
+    } else {
+        for c in shown.iter() {
+            println!(
+                "{} deployers {} create2 factories {} deployments {} contracts {}",
+                c.name,
+                c.deployers.len(),
+                c.create2_factories.len(),
+                c.addresses.len(),
+                c.contract_ids.len()
+            );
+        }
+    }
+    info!("Deployer clusters: {}", clusters.len());
+
+    Ok(())
+}
+
+async fn compiler_timeline(storage: &mut Storage, args: &CompilerTimelineArgs) -> Result<()> {
+    let releases = load_releases(args.releases_file.as_deref())?;
+    let count = storage.backfill_solc_versions()?;
+    if count > 0 {
+        info!("Filled in the solc version of {count} contracts");
+    }
+    let counts = storage.contracts_by_compiler_version()?;
+    let timeline = CompilerTimeline::new(&counts, &releases);
+    match args.format {
+        TimelineFormat::Csv => print!("{}", timeline.to_csv()),
+        TimelineFormat::Json => println!("{}", serde_json::to_string_pretty(&timeline)?),
+        TimelineFormat::Chart => print!("{}", timeline.chart(args.width)),
+    }
+    if timeline.unknown > 0 {
+        warn!(
+            "Contracts with a compiler version without release date: {}",
+            timeline.unknown
+        );
+    }
+    info!("Months: {}", timeline.months.len());
+
+    Ok(())
+}
+
+async fn fetch_ipfs(
+    storage: &mut Storage,
+    ipfs: &IpfsClient<'_>,
+    args: &FetchIpfsArgs,
+) -> Result<()> {
+    let bytecodes = storage.unlinked_bytecodes(args.chain_id)?;
+    let mut contracts = vec![];
+    let mut unresolved = 0;
+    for b in bytecodes.iter() {
+        let code = decode_hex(&b.code)?;
+        match ipfs
+            .resolve(&code, Some(b.chain_id), Some(b.address.clone()))
+            .await
+        {
+            Ok(Some(contract)) => {
+                println!("{} {} {}", b.chain_id, b.address, contract.id());
+                contracts.push((fetched_path(&contract), contract));
+            }
+            Ok(None) => unresolved += 1,
+            Err(error) => {
+                warn!("Could not resolve the sources of {}: {error}", b.address);
+                unresolved += 1;
+            }
+        }
+    }
+    let count = contracts.len();
+    store_occurring(storage, &args.gateway, &contracts)?;
+    info!(
+        "Resolved {count} of {} runtime codes, {unresolved} without IPFS metadata or sources",
+        bytecodes.len()
+    );
+
+    Ok(())
+}
+
+async fn fetch_package(
+    storage: &mut Storage,
+    client: &PackageClient<'_>,
+    args: &FetchPackageArgs,
+) -> Result<()> {
+    let specs = args
+        .npm
+        .iter()
+        .map(|spec| PackageSpec::parse(Registry::Npm, spec))
+        .chain(
+            args.github
+                .iter()
+                .map(|spec| PackageSpec::parse(Registry::GitHub, spec)),
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut count = 0;
+    for spec in specs.iter() {
+        let versions = match &spec.version {
+            Some(version) => vec![version.clone()],
+            None => {
+                let versions = client.versions(spec).await?;
+                versions
+                    .into_iter()
+                    .take(args.latest.unwrap_or(usize::MAX))
+                    .collect()
+            }
+        };
+        let stored = storage.package_versions(spec.registry.as_str(), &spec.name)?;
+        for version in versions.iter().filter(|v| !stored.contains(*v)) {
+            match client.release(spec, version).await {
+                Ok((contract, release)) => {
+                    println!(
+                        "{} {} {}",
+                        release.package, release.version, release.contract_id
+                    );
+                    let path = format!("{}@{}", release.package, release.version);
+                    store_occurring(storage, spec.registry.as_str(), &[(path, contract)])?;
+                    storage.store_package_releases(&[release])?;
+                    count += 1;
+                }
+                Err(error) => warn!("Could not fetch {}@{version}: {error}", spec.name),
+            }
+        }
+    }
+    info!("Stored {count} package releases");
+
+    Ok(())
+}
+
+async fn match_bytecode(storage: &mut Storage, args: &MatchBytecodeArgs) -> Result<()> {
+    let code = decode_hex(&std::fs::read_to_string(&args.file)?)?;
+    let signature = minhash(&code);
+    let signatures = storage.opcode_signatures()?;
+    let ranked = signatures
+        .iter()
+        .filter_map(|s| {
+            let other = signature_from_hex(&s.signature)?;
+            Some((similarity(&signature, &other), s))
+        })
+        .sorted_by(|(a, _), (b, _)| b.total_cmp(a))
+        .take(args.top)
+        .collect_vec();
+    for (score, s) in ranked.iter() {
+        println!(
+            "{score:.3} {} {} {} opcodes",
+            s.contract_id, s.contract_name, s.opcodes
+        );
+    }
+    info!(
+        "Compared {} opcodes with {} indexed contracts",
+        opcodes(&code).len(),
+        signatures.len()
+    );
+
+    Ok(())
+}
+
+/// The code deployed at the address, or at the implementation of an EIP-1167
+/// proxy with the implementation
+async fn screened_code(rpc: &RpcClient<'_>, address: &str) -> Result<(Option<String>, Vec<u8>)> {
+    let code = rpc.code(address).await?;
+    match eip1167_implementation(&code) {
+        Some(implementation) => {
+            let code = rpc.code(&implementation).await?;
+            Ok((Some(implementation), code))
+        }
+        None => Ok((None, code)),
+    }
+}
+
+/// Match the screened code of an address against the indexed contracts
+fn screen_code(
+    storage: &Storage,
+    signatures: &[(Vec<u64>, OpcodeSignature)],
+    address: &str,
+    (implementation, code): (Option<String>, Vec<u8>),
+    min_similarity: f64,
+) -> Result<ScreenResult> {
+    let mut result = ScreenResult::unknown(address);
+    result.implementation = implementation;
+    if code.is_empty() {
+        return Ok(result);
+    }
+
+    let deployed = DeployedBytecode::new(0, address, &code, None);
+    if let Some((contract_id, contract_name)) =
+        storage.exact_code_match(&deployed.code_hash, &skeleton_hash(&code))?
+    {
+        result.method = "exact";
+        result.confidence = 1.0;
+        result.contract_id = Some(contract_id);
+        result.contract_name = Some(contract_name);
+    } else if let Some((score, s)) = most_similar(&code, signatures, min_similarity) {
+        result.method = "similar";
+        result.confidence = score;
+        result.contract_id = Some(s.contract_id.clone());
+        result.contract_name = Some(s.contract_name.clone());
+    }
+    Ok(result)
+}
+
+async fn screen(storage: &mut Storage, rpc: &RpcClient<'_>, args: &ScreenArgs) -> Result<()> {
+    let mut addresses = args.address.iter().map(|a| a.to_lowercase()).collect_vec();
+    if let Some(path) = &args.addresses {
+        addresses.extend(read_addresses(path).await?.into_keys().sorted());
+    }
+    if addresses.is_empty() {
+        return Err(eyre::eyre!(
+            "At least one of the address or addresses should be provided"
+        ));
+    }
+    if args.concurrency == 0 {
+        return Err(eyre::eyre!("--concurrency should be at least 1"));
+    }
+    let addresses = addresses.into_iter().unique().collect_vec();
+    let hashed = storage.backfill_skeleton_hashes()?;
+    if hashed > 0 {
+        info!("Hashed the runtime code of {hashed} artifacts");
+    }
+    let signatures = decode_signatures(storage.opcode_signatures()?);
+
+    // codes are fetched concurrently and screened in the order of the addresses
+    let mut codes = stream::iter(addresses.iter())
+        .map(|address| async move { (address, screened_code(rpc, address).await) })
+        .buffered(args.concurrency);
+    let mut matched = 0;
+    while let Some((address, code)) = codes.next().await {
+        let result = match code
+            .and_then(|code| screen_code(storage, &signatures, address, code, args.min_similarity))
+        {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to screen {address}: {e}");
+                continue;
+            }
+        };
+        if result.contract_id.is_some() {
+            matched += 1;
+        }
+        if args.json {
+            println!("{}", serde_json::to_string(&result)?);
+        } else {
+            println!(
+                "{address} {} {:.3} {} {}{}",
+                result.method,
+                result.confidence,
+                result.contract_id.as_deref().unwrap_or("-"),
+                result.contract_name.as_deref().unwrap_or("-"),
+                result
+                    .implementation
+                    .as_ref()
+                    .map(|i| format!(" via {i}"))
+                    .unwrap_or_default()
+            );
+        }
+    }
+    info!(
+        "Addresses with a known source: {matched}/{}",
+        addresses.len()
+    );
+
+    Ok(())
+}
+
+async fn abi_vectors(storage: &mut Storage, args: &AbiVectorsArgs) -> Result<()> {
+    let signature = match (&args.signature, &args.selector) {
+        (Some(signature), _) => {
+            let selector = selector_hex(&parse_signature(signature)?);
+            let indexed = storage.function_signatures(&selector)?;
+            if !indexed.iter().any(|(s, _)| s == signature) {
+                return Err(eyre::eyre!("No indexed function {signature}"));
+            }
+            signature.clone()
+        }
+        (None, Some(selector)) => storage
+            .function_signatures(&selector.to_lowercase())?
+            .into_iter()
+            .next()
+            .map(|(signature, _)| signature)
+            .ok_or_else(|| eyre::eyre!("No indexed function with selector {selector}"))?,
+        (None, None) => {
+            return Err(eyre::eyre!(
+                "Select a function with --signature or --selector"
+            ))
+        }
+    };
+    let function = parse_signature(&signature)?;
+    let selector = selector_hex(&function);
+
+    let vectors = match args.arg.is_empty() {
+        false => vec![encode_call(&function, &args.arg)?],
+        true => {
+            let types = input_types(&function)?;
+            let mut rng = StdRng::seed_from_u64(args.seed);
+            (0..args.count)
+                .map(|_| {
+                    let values = types
+                        .iter()
+                        .map(|ty| random_value(ty, &mut rng))
+                        .collect_vec();
+                    let calldata = function.abi_encode_input(&values)?;
+                    Ok((values, calldata))
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+    for (values, calldata) in vectors.iter() {
+        let vector = serde_json::json!({
+            "signature": signature,
+            "selector": selector,
+            "args": values.iter().map(format_value).collect_vec(),
+            "calldata": hex::encode_prefixed(calldata),
+        });
+        println!("{vector}");
+    }
+    info!("Generated {} encodings of {signature}", vectors.len());
+
+    Ok(())
+}
+
+async fn decode(storage: &mut Storage, args: &DecodeArgs) -> Result<()> {
+    let calldata = decode_hex(&args.data)?;
+    if calldata.len() < 4 {
+        return Err(eyre::eyre!("Calldata is shorter than a selector"));
+    }
+    let selector = hex::encode_prefixed(&calldata[..4]);
+
+    // indexed signatures first, most implemented first, then imported ones
+    let mut candidates = storage
+        .function_signatures(&selector)?
+        .into_iter()
+        .map(|(signature, contracts)| (signature, format!("contracts {contracts}")))
+        .collect_vec();
+    for signature in storage.known_signatures(SignatureKind::Function, &selector)? {
+        if !candidates.iter().any(|(s, _)| *s == signature) {
+            candidates.push((signature, "known".into()));
+        }
+    }
+
+    let mut decoded = 0;
+    for (signature, origin) in candidates.iter() {
+        let function = match parse_signature(signature) {
+            Ok(function) => function,
+            Err(e) => {
+                warn!("Skipping invalid signature {signature}: {e}");
+                continue;
+            }
+        };
+        let Some(values) = decode_call(&function, &calldata) else {
+            continue;
+        };
+        println!("{signature} {origin}");
+        for (ty, value) in input_types(&function)?.iter().zip(values.iter()) {
+            println!("  {ty} {}", format_value(value));
+        }
+        decoded += 1;
+    }
+    info!(
+        "Signatures decoding {selector}: {decoded} of {}",
+        candidates.len()
+    );
+
+    Ok(())
+}
+
+async fn decode_revert(storage: &mut Storage, args: &DecodeRevertArgs) -> Result<()> {
+    let data = decode_hex(&args.data)?;
+    if data.is_empty() {
+        println!("Reverted without data");
+        return Ok(());
+    }
+    if data.len() < 4 {
+        return Err(eyre::eyre!("Revert data is shorter than a selector"));
+    }
+    let selector = hex::encode_prefixed(&data[..4]);
+
+    if selector == ERROR_STRING_SELECTOR || selector == PANIC_SELECTOR {
+        let signature = match selector == ERROR_STRING_SELECTOR {
+            true => "Error(string)",
+            false => "Panic(uint256)",
+        };
+        let values = decode_error(&parse_error_signature(signature)?, &data)
+            .ok_or_else(|| eyre::eyre!("Invalid {signature} revert data"))?;
+        match &values[..] {
+            [DynSolValue::Uint(code, _)] => {
+                println!("{signature} {code:#x} {}", panic_reason(*code))
+            }
+            [value] => println!("{signature} {}", format_value(value)),
+            _ => unreachable!(),
+        }
+        return Ok(());
+    }
+
+    // indexed errors first with the contracts defining them, then imported ones
+    let indexed = storage.errors_by_selector(&selector)?;
+    let mut candidates = indexed
+        .iter()
+        .map(|e| e.signature.clone())
+        .unique()
+        .collect_vec();
+    for signature in storage.known_signatures(SignatureKind::Error, &selector)? {
+        if !candidates.contains(&signature) {
+            candidates.push(signature);
+        }
+    }
+
+    let mut decoded = 0;
+    for signature in candidates.iter() {
+        let error = match parse_error_signature(signature) {
+            Ok(error) => error,
+            Err(e) => {
+                warn!("Skipping invalid signature {signature}: {e}");
+                continue;
+            }
+        };
+        let Some(values) = decode_error(&error, &data) else {
+            continue;
+        };
+        let contracts = indexed
+            .iter()
+            .filter(|e| e.signature == *signature)
+            .collect_vec();
+        match contracts.is_empty() {
+            true => println!("{signature} known"),
+            false => println!("{signature} contracts {}", contracts.len()),
+        }
+        // the definition names the parameters
+        if let Some(source) = contracts.iter().find_map(|e| e.source_code.as_deref()) {
+            println!("  {source}");
+        }
+        for (param, value) in error.inputs.iter().zip(values.iter()) {
+            println!("  {} {}", param.selector_type(), format_value(value));
+        }
+        for e in contracts {
+            println!("  defined in {} {}", e.contract_id, e.contract_name);
+        }
+        decoded += 1;
+    }
+    info!(
+        "Signatures decoding {selector}: {decoded} of {}",
+        candidates.len()
+    );
+
+    Ok(())
+}
+
+async fn decode_log(storage: &mut Storage, args: &DecodeLogArgs) -> Result<()> {
+    let topics = args
+        .topics
+        .iter()
+        .map(|t| {
+            t.trim()
+                .parse::<B256>()
+                .map_err(|e| eyre::eyre!("Invalid topic {t}: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let data = decode_hex(&args.data)?;
+    let topic0 = hex::encode_prefixed(topics[0]);
+
+    // indexed events first, most declared first, then imported ones. Only the
+    // events indexed with their full signature tell which parameters are
+    // indexed, the leading ones are assumed to be for the others.
+    let mut candidates: Vec<(String, String)> = vec![];
+    let events = storage.events_by_topic0(&topic0)?;
+    let counts = events
+        .iter()
+        .map(|e| {
+            e.full_signature
+                .clone()
+                .unwrap_or_else(|| e.signature.clone())
+        })
+        .counts();
+    for (signature, contracts) in counts
+        .into_iter()
+        .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+    {
+        candidates.push((signature, format!("contracts {contracts}")));
+    }
+    for signature in storage.known_signatures(SignatureKind::Event, &topic0)? {
+        candidates.push((signature, "known".into()));
+    }
+
+    let mut decoded = vec![];
+    for (signature, origin) in candidates.iter() {
+        let event = match parse_event_signature(signature, topics.len() - 1) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Skipping invalid signature {signature}: {e}");
+                continue;
+            }
+        };
+        let Some(values) = decode_event(&event, &topics, &data) else {
+            continue;
+        };
+        // a full and a plain signature can decode to the same parameters
+        if decoded.contains(&(event.signature(), values.clone())) {
+            continue;
+        }
+        println!("{} {origin}", event.full_signature());
+        for (param, value) in event.inputs.iter().zip(values.iter()) {
+            println!("  {} {}", param.selector_type(), format_value(value));
+        }
+        decoded.push((event.signature(), values));
+    }
+    info!(
+        "Signatures decoding {topic0}: {} of {}",
+        decoded.len(),
+        candidates.len()
+    );
+
+    Ok(())
+}
+
+async fn merge_db(storage: &mut Storage, args: &MergeDbArgs) -> Result<()> {
+    for db_file in args.from.iter() {
+        let stats = storage.merge(db_file)?;
+        if stats.conflicts > 0 {
+            warn!(
+                "{} contracts of {db_file} have the id of a different stored contract, kept the stored ones",
+                stats.conflicts
+            );
+        }
+        info!(
+            "Merged {db_file}: {} contracts, {} functions, {} rows of other tables",
+            stats.contracts, stats.functions, stats.rows
+        );
+    }
+    info!("Total contracts: {}", storage.count_contracts()?);
+
+    Ok(())
+}
+
+async fn import(storage: &mut Storage, args: &ImportArgs) -> Result<()> {
+    let rows = read_contracts(&storage.conn, &args.contracts)?;
+    let mut contract_ids = HashMap::new();
+    let mut contracts = vec![];
+    for (index, row) in rows.into_iter().enumerate() {
+        let row = row.and_then(|row| Ok((row.to_contract()?, row)));
+        let (contract, row) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                warn!("Skipping row {index} of {}: {e}", args.contracts);
+                continue;
+            }
+        };
+        if let Some(id) = &row.id {
+            contract_ids.insert(id.clone(), contract.id());
+        }
+        // rows are found again by their id in the dump, else by their index
+        let path = row.id.unwrap_or_else(|| index.to_string());
+        contracts.push((path, contract));
+    }
+    let count = contracts.len();
+    for chunk in contracts.chunks(args.chunk_size.max(1)) {
+        store_occurring(storage, &args.contracts, chunk)?;
+    }
+    info!("Imported contracts: {count}");
+
+    if let Some(path) = &args.functions {
+        let functions = read_functions(&storage.conn, path, &contract_ids)?;
+        let quarantined = storage.store_checked_functions(&functions)?;
+        info!("Imported functions: {}", functions.len() - quarantined);
+        if quarantined > 0 {
+            warn!("Quarantined {quarantined} functions with an invalid signature or selector");
+        }
+    }
+    info!("Total contracts: {}", storage.count_contracts()?);
+
+    Ok(())
+}
+
+async fn import_bigquery(storage: &mut Storage, args: &ImportBigqueryArgs) -> Result<()> {
+    for path in args.contracts.iter() {
+        let bytecodes = read_bigquery_contracts(&storage.conn, path, args.chain_id)?;
+        storage.store_bytecodes(&bytecodes)?;
+        info!("Imported {path}: {} contracts", bytecodes.len());
+    }
+    let (total, linked) = storage.count_bytecodes()?;
+    info!("Total bytecodes: {total}, with source: {linked}");
+
+    Ok(())
+}
+
+async fn export_site(storage: &mut Storage, args: &ExportSiteArgs) -> Result<()> {
+    let mut ids = args.contract_id.clone();
+    if let Some(name) = &args.name {
+        ids.extend(storage.contract_ids_by_name(name)?);
+    }
+    if ids.is_empty() {
+        return Err(eyre::eyre!("Select contracts with --contract-id or --name"));
+    }
+
+    let license_filter = args.license.filter(storage)?;
+    let mut contracts = vec![];
+    for id in ids.into_iter().unique() {
+        let Some(contract) = storage.get_contract(&id)? else {
+            warn!("Contract {id} not found");
+            continue;
+        };
+        if let Some(filter) = &license_filter {
+            let license = storage.contract_license(&id)?.unwrap_or_default();
+            if !filter.allows(&license) {
+                debug!("Skip contract {id} with license '{license}'");
+                continue;
+            }
+        }
+        let functions = storage.contract_functions(&id)?;
+        let overview = Overview::new(
+            &contract.metadata.contract_name,
+            &functions,
+            &storage.contract_external_calls(&id)?,
+            &storage.contract_storage_slots(&id)?,
+        );
+        contracts.push((contract, functions, overview));
+    }
+    let files = site::export_site(Path::new(&args.output), &contracts)?;
+    info!(
+        "Exported {} contracts to {}: {} files",
+        contracts.len(),
+        args.output,
+        files.len()
+    );
+
+    if let Some(path) = &args.manifest {
+        ExportManifest::new("export-site", storage.snapshot_id()?)
+            .filter("contract_id", args.contract_id.iter().join(","))
+            .filter("name", args.name.as_deref().unwrap_or_default())
+            .filter(
+                "license_allowlist",
+                license_filter.map(|f| f.allowlist()).unwrap_or_default(),
+            )
+            .row_count("contract", contracts.len())
+            .outputs(Path::new(&args.output), &files)
+            .await?
+            .write(path)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn release(storage: &mut Storage, args: &ReleaseArgs) -> Result<()> {
+    if let Some(since) = &args.since {
+        if !storage.has_release(since)? {
+            return Err(eyre::eyre!("No release at snapshot {since}"));
+        }
+    }
+    let since = args.since.as_deref();
+    let license_filter = args.license.filter(storage)?;
+    let allowed = |license: &str| license_filter.as_ref().is_none_or(|f| f.allows(license));
+    let root = Path::new(&args.output);
+    tokio::fs::create_dir_all(root).await?;
+
+    let chunk_size = args.chunk_size.max(1);
+    let contracts_path = root.join("contracts.jsonl");
+    let mut contracts = std::io::BufWriter::new(std::fs::File::create(&contracts_path)?);
+    let (mut after, mut contract_ids) = (String::new(), vec![]);
+    loop {
+        let chunk = storage.contracts_since(since, &after, chunk_size)?;
+        let Some((last, _, _)) = chunk.last() else {
+            break;
+        };
+        after = last.clone();
+        for (id, contract, _) in chunk.iter().filter(|(_, _, license)| allowed(license)) {
+            serde_json::to_writer(&mut contracts, &ContractRow::from_contract(contract)?)?;
+            contracts.write_all(b"\n")?;
+            contract_ids.push(id.clone());
+        }
+    }
+    contracts.flush()?;
+
+    let functions_path = root.join("functions.jsonl");
+    let mut functions = std::io::BufWriter::new(std::fs::File::create(&functions_path)?);
+    let (mut after, mut function_ids) = (String::new(), vec![]);
+    loop {
+        let chunk = storage.functions_since(since, &after, chunk_size)?;
+        let Some((last, _)) = chunk.last() else {
+            break;
+        };
+        after = last.id.clone();
+        for (function, _) in chunk.iter().filter(|(_, license)| allowed(license)) {
+            serde_json::to_writer(&mut functions, function)?;
+            functions.write_all(b"\n")?;
+            function_ids.push(function.id.clone());
+        }
+    }
+    functions.flush()?;
+
+    let (contract_count, function_count) = (contract_ids.len(), function_ids.len());
+    let snapshot_id = storage.snapshot_id()?;
+    ExportManifest::new("release", snapshot_id.clone())
+        .filter("since", since.unwrap_or_default())
+        .filter(
+            "license_allowlist",
+            license_filter.map(|f| f.allowlist()).unwrap_or_default(),
+        )
+        .row_count("contract", contract_count)
+        .row_count("function", function_count)
+        .outputs(root, &[contracts_path, functions_path])
+        .await?
+        .write(&root.join("manifest.json").to_string_lossy())
+        .await?;
+    // contracts and functions left out by the license filter are not released
+    storage.record_release(&snapshot_id, since, &contract_ids, &function_ids)?;
+    info!(
+        "Released {contract_count} contracts and {function_count} functions to {} at snapshot {snapshot_id}",
+        args.output
+    );
+
+    Ok(())
+}
+
+async fn export_triplets(storage: &mut Storage, args: &ExportTripletsArgs) -> Result<()> {
+    let license_filter = args.license.filter(storage)?;
+    let functions = storage
+        .sample_functions(args.sample, args.seed)?
+        .into_iter()
+        .filter(|(_, license)| license_filter.as_ref().is_none_or(|f| f.allows(license)))
+        .map(|(function, _)| function)
+        .collect::<Vec<_>>();
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let triplets = build_triplets(
+        &functions,
+        args.min_positive_similarity,
+        args.negative_candidates,
+        &mut rng,
+    );
+
+    let root = Path::new(&args.output);
+    tokio::fs::create_dir_all(root).await?;
+    let triplets_path = root.join("triplets.jsonl");
+    let mut out = std::io::BufWriter::new(std::fs::File::create(&triplets_path)?);
+    for triplet in triplets.iter() {
+        serde_json::to_writer(&mut out, triplet)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+
+    ExportManifest::new("export-triplets", storage.snapshot_id()?)
+        .filter("sample", args.sample)
+        .filter("seed", args.seed)
+        .filter("min_positive_similarity", args.min_positive_similarity)
+        .filter("negative_candidates", args.negative_candidates)
+        .filter(
+            "license_allowlist",
+            license_filter.map(|f| f.allowlist()).unwrap_or_default(),
+        )
+        .row_count("function", functions.len())
+        .row_count("triplet", triplets.len())
+        .outputs(root, &[triplets_path])
+        .await?
+        .write(&root.join("manifest.json").to_string_lossy())
+        .await?;
+    info!(
+        "Exported {} triplets from {} sampled functions to {}",
+        triplets.len(),
+        functions.len(),
+        args.output
+    );
+
+    Ok(())
+}
+
+async fn export_env(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    args: &ExportEnvArgs,
+) -> Result<()> {
+    let mut contract_ids = args.contract_id.clone();
+    if let Some(pattern) = &args.name {
+        contract_ids.extend(storage.contract_ids_by_name(pattern)?);
+    }
+    let mut contracts = vec![];
+    for id in contract_ids.iter().unique() {
+        let contract = storage
+            .get_contract(id)?
+            .ok_or_else(|| eyre::eyre!("Contract {id} not found"))?;
+        contracts.push(contract);
+    }
+    if let Some(size) = args.sample {
+        contracts.extend(storage.sample_contracts(size, args.seed)?);
+    }
+    contracts = contracts
+        .into_iter()
+        .unique_by(|c| c.id())
+        .collect::<Vec<_>>();
+    if let Some(filter) = args.license.filter(storage)? {
+        contracts.retain(|c| {
+            storage
+                .contract_license(&c.id())
+                .ok()
+                .flatten()
+                .is_some_and(|license| filter.allows(&license))
+        });
+    }
+    if contracts.is_empty() {
+        return Err(eyre::eyre!(
+            "No contracts selected, use --contract-id, --name or --sample"
+        ));
+    }
 
-    /// Folder containing etherscan contracts. Each contract contains a json file
-    /// which contains both the metadata and the source code
-    #[arg(long)]
-    etherscan_contracts_root: Option<String>,
+    let mut files: Vec<(String, Vec<u8>, bool)> = vec![];
+    let mut rows = vec![];
+    for contract in contracts.iter() {
+        serde_json::to_writer(&mut rows, &ContractRow::from_contract(contract)?)?;
+        rows.push(b'\n');
+    }
+    files.push(("contracts.jsonl".into(), rows, false));
+    files.push(("schema.sql".into(), schema_sql().into_bytes(), false));
+    files.push((
+        "replicate.sh".into(),
+        REPLICATE_SCRIPT.as_bytes().to_vec(),
+        true,
+    ));
+    // the running binary, so the environment indexes with the same version
+    files.push((
+        TOOL_ENTRY.into(),
+        tokio::fs::read(std::env::current_exe()?).await?,
+        true,
+    ));
 
-    /// Optionally ignore errors during processing (default: false)
-    #[arg(long, action = ArgAction::SetTrue, default_value_t = false)]
-    ignore_errors: bool,
+    let (solc_versions, vyper_versions) = required_compilers(&contracts);
+    for version in solc_versions.iter() {
+        let solc = find_solc(version)?;
+        files.push((
+            solc_entry(version),
+            tokio::fs::read(&solc.solc).await?,
+            true,
+        ));
+    }
+    for version in vyper_versions.iter() {
+        let path = install_vyper(fetcher, version).await?;
+        files.push((vyper_entry(version), tokio::fs::read(&path).await?, true));
+    }
 
-    /// Chunk size, for faster importing contracts
-    #[arg(long)]
-    chunk_size: usize,
-}
+    let mut manifest = ExportManifest::new("export-env", storage.snapshot_id()?)
+        .filter("contract_id", args.contract_id.join(","))
+        .filter("name", args.name.as_deref().unwrap_or_default())
+        .filter(
+            "sample",
+            args.sample.map(|s| s.to_string()).unwrap_or_default(),
+        )
+        .filter("seed", args.seed)
+        .filter(
+            "license_allowlist",
+            args.license
+                .new_filter()
+                .map(|f| f.allowlist())
+                .unwrap_or_default(),
+        )
+        .filter("solc", solc_versions.iter().join(","))
+        .filter("vyper", vyper_versions.iter().join(","))
+        .row_count("contract", contracts.len());
+    for (path, content, _) in files.iter() {
+        manifest = manifest.output(path, content);
+    }
 
-#[derive(Parser)]
-struct IndexFunctionsArgs {
-    /// How many contracts to process in one go
-    #[arg(long)]
-    chunk_size: usize,
+    let mut bundle = EnvBundle::create(&args.output)?;
+    bundle.add(
+        "manifest.json",
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+        false,
+    )?;
+    for (path, content, executable) in files.iter() {
+        bundle.add(path, content, *executable)?;
+    }
+    bundle.finish()?;
+    info!(
+        "Bundled {} contracts, {} solc and {} vyper versions to {}",
+        contracts.len(),
+        solc_versions.len(),
+        vyper_versions.len(),
+        args.output
+    );
+
+    Ok(())
 }
 
-#[derive(Parser)]
-struct DownloadSolcArgs {
-    /// Root folder for storing solc binaries
-    #[arg(long)]
-    solc_folder: Option<String>,
+async fn dataset_card(storage: &mut Storage, args: &DatasetCardArgs) -> Result<()> {
+    let count = storage.backfill_licenses()?;
+    if count > 0 {
+        info!("Filled in the license of {count} contracts");
+    }
+    let count = storage.backfill_languages()?;
+    if count > 0 {
+        info!("Filled in the languages of {count} contracts");
+    }
+    let stats = storage.dataset_stats()?;
+    let card = stats.to_markdown(&args.title, args.top);
+    match &args.output {
+        Some(path) => {
+            tokio::fs::write(path, &card).await?;
+            info!(
+                "Dataset card of {} contracts written to {path}",
+                stats.contracts
+            );
+        }
+        None => print!("{card}"),
+    }
+
+    Ok(())
 }
 
-#[derive(Parser)]
-struct ExportSourceArgs {
-    /// The contract id to export
-    #[arg(long)]
-    contract_id: String,
-    /// Output folder to store the source code
-    #[arg(long)]
-    output_folder: String,
+async fn xref(storage: &mut Storage, args: &XrefArgs) -> Result<()> {
+    if !is_address(&args.address) {
+        return Err(eyre::eyre!("Invalid address {}", args.address));
+    }
+    let references = storage.address_references(&args.address.to_lowercase())?;
+    for r in references.iter() {
+        println!(
+            "{} {} {} {}",
+            r.contract_id, r.contract_name, r.origin, r.name
+        );
+    }
+    info!("References to {}: {}", args.address, references.len());
+
+    Ok(())
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Preprocess the contracts with the given options
-    PreProcess(PreProcessArgs),
-    /// Compile all contracts and store populate the `function` table
-    IndexFunctions(IndexFunctionsArgs),
-    /// Download all solc binaries
-    DownloadSolc,
-    /// Export source code of a contract
-    ExportSource(ExportSourceArgs),
+async fn import_signatures(storage: &mut Storage, args: &ImportSignaturesArgs) -> Result<()> {
+    let signatures = read_signatures(&args.file, args.kind).await?;
+    storage.store_known_signatures(&signatures)?;
+    info!("Imported {} {} signatures", signatures.len(), args.kind);
+
+    Ok(())
 }
 
-/// Search for all folders containing `metadata.json` and process them
-pub async fn process_metadata_contracts(root: &str, ignore_errors: bool) -> Vec<PlainContract> {
-    let mut contracts = Vec::with_capacity(12800);
-    for entry in WalkDir::new(root)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| e.file_type().is_dir())
-    {
-        let dir_path = entry.path();
-        let metadata_path = dir_path.join("metadata.json");
+async fn match_slot(storage: &mut Storage, args: &MatchSlotArgs) -> Result<()> {
+    let observed = parse_word(&args.slot)?;
+    let key = args.key.as_deref().map(parse_word).transpose()?;
 
-        if metadata_path.exists() {
-            match PlainContract::from_folder(&dir_path.to_string_lossy()).await {
-                Ok(c) => {
-                    contracts.push(c);
-                }
-                Err(error) => {
-                    if !ignore_errors {
-                        panic!("Process file failed with error {error}")
-                    }
-                }
-            }
+    for candidate in candidate_slots(observed, key, args.max_base_slot) {
+        let (description, base, encodings): (_, _, &[&str]) = match candidate {
+            SlotMatch::Direct(base) => ("variable", base, &[]),
+            SlotMatch::MappingEntry(base) => ("mapping entry", base, &["mapping"]),
+            SlotMatch::ArrayData(base) => ("array data", base, &["dynamic_array", "bytes"]),
+        };
+        let base = base.to_string();
+        for s in storage.search_storage_slots(None, Some(&base), encodings)? {
+            println!(
+                "{description} at slot {base}: {} {} {} {}",
+                s.contract_id, s.contract_name, s.ty, s.label
+            );
         }
     }
-    contracts
+
+    Ok(())
 }
 
-/// Search and process etherscan json files and process
-pub async fn process_etherscan_contracts(root: &str, ignore_errors: bool) -> Vec<PlainContract> {
-    let mut contracts = Vec::with_capacity(12800);
-    for entry in WalkDir::new(root)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| {
-            let folder = {
-                match e.path().parent() {
-                    None => return false,
-                    Some(parent) => match parent.file_name() {
-                        None => return false,
-                        Some(name) => name.to_string_lossy(),
-                    },
-                }
-            };
-            let filename = e.file_name().to_string_lossy();
-
-            filename.starts_with(&*folder)
-                && e.file_type().is_file()
-                && e.file_name()
-                    .to_string_lossy()
-                    .to_lowercase()
-                    .ends_with(".json")
+/// Deployed addresses of the contracts whose chain and address are known
+fn contract_addresses<'a>(
+    contracts: impl Iterator<Item = &'a PlainContract>,
+) -> Vec<ContractAddress> {
+    contracts
+        .filter_map(|contract| {
+            let Metadata {
+                chain_id, address, ..
+            } = &contract.metadata;
+            Some(ContractAddress {
+                chain_id: (*chain_id)?,
+                address: address.clone()?,
+                contract_id: contract.id(),
+            })
         })
-    {
-        let path = entry.path();
-        match PlainContract::from_etherscan_json(&path.to_string_lossy()).await {
-            Ok(c) => {
-                contracts.push(c);
-            }
-            Err(error) => {
-                if ignore_errors {
-                    debug!("Process file failed with error {error} {path:?}")
-                } else {
-                    panic!("Process file failed with error {error} {path:?}")
-                }
-            }
-        }
-    }
+        .collect()
+}
 
-    contracts
+/// Store contracts with the dataset and path they were read or fetched from,
+/// so that a source deployed on several chains or addresses keeps them all
+fn store_occurring(
+    storage: &Storage,
+    dataset: &str,
+    contracts: &[(String, PlainContract)],
+) -> Result<()> {
+    let occurrences = contracts
+        .iter()
+        .map(|(path, contract)| ContractOccurrence {
+            contract_id: contract.id(),
+            dataset: dataset.into(),
+            path: path.clone(),
+            chain_id: contract.metadata.chain_id,
+            address: contract.metadata.address.clone(),
+        })
+        .collect_vec();
+    let addresses = contract_addresses(contracts.iter().map(|(_, c)| c));
+    storage.store_contracts(contracts.iter().map(|(_, c)| c.clone()).collect())?;
+    storage.store_occurrences(&occurrences)?;
+    storage.store_addresses(&addresses)?;
+    Ok(())
 }
 
-async fn export_source(storage: &mut Storage, args: &ExportSourceArgs) -> Result<()> {
-    let contract = storage
-        .get_contract(&args.contract_id)?
-        .expect("Contract not found");
+/// The chain and address of a fetched contract as the path of its occurrence,
+/// e.g. `1/0x5e8422345238f34275888049021821e8e08caa1f`
+fn fetched_path(contract: &PlainContract) -> String {
+    let Metadata {
+        chain_id, address, ..
+    } = &contract.metadata;
+    format!(
+        "{}/{}",
+        chain_id.map(|c| c.to_string()).unwrap_or_default(),
+        address.as_deref().unwrap_or_default()
+    )
+}
 
-    contract.export_source_code(&args.output_folder).await
+/// Store contracts with the paths they were read from, recording the paths in
+/// the ingest manifest once the contracts are stored, then run the hooks
+fn store_ingested(
+    storage: &Storage,
+    hooks: &Hooks,
+    dataset: &str,
+    contracts: Vec<(String, PlainContract)>,
+) -> Result<()> {
+    let entries = contracts
+        .iter()
+        .map(|(path, contract)| (path.clone(), contract.id()))
+        .collect_vec();
+    let started = Instant::now();
+    store_occurring(storage, dataset, &contracts)?;
+    // Paths are only recorded once the hooks accepted them, so a chunk whose
+    // hook failed is ingested again on the next run
+    hooks.run(&contracts)?;
+    storage.record_ingested(&entries)?;
+    debug!(
+        stage = "store",
+        dataset = dataset,
+        contracts = contracts.len() as u64,
+        duration_ms = started.elapsed().as_millis() as u64,
+        outcome = "ok";
+        "Stored {} contracts of {dataset}", contracts.len()
+    );
+    Ok(())
 }
 
-async fn preprocess_contracts(storage: &mut Storage, args: &PreProcessArgs) -> Result<()> {
+async fn preprocess_contracts(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    hooks: &Hooks,
+    chain_id: u64,
+    args: &PreProcessArgs,
+) -> Result<()> {
     let PreProcessArgs {
         metadata_contracts_root,
         etherscan_contracts_root,
+        sourcify_root,
+        build_info_root,
+        dataset_root,
+        dataset_format: _,
+        custom_pattern: _,
+        custom_command: _,
+        foundry_project,
+        truffle_project,
+        hf_dataset,
+        hf_path,
+        hf_revision,
         ignore_errors,
+        resume,
+        watch,
+        settle_secs: _,
+        chain_id: _,
+        contract_hook: _,
+        chunk_hook: _,
         chunk_size,
     } = args;
-    match (metadata_contracts_root, etherscan_contracts_root) {
-        (None, None) => {
-            panic!("At least one of the metadata_contracts_root or etherscan_contracts_root should be provided")
-        }
-        (Some(metadata_contracts_root), None) => {
-            let mut contracts =
-                process_metadata_contracts(metadata_contracts_root, *ignore_errors).await;
+    if metadata_contracts_root.is_empty()
+        && etherscan_contracts_root.is_empty()
+        && sourcify_root.is_empty()
+        && build_info_root.is_empty()
+        && dataset_root.is_empty()
+        && foundry_project.is_empty()
+        && truffle_project.is_empty()
+        && hf_dataset.is_empty()
+    {
+        return Err(eyre::eyre!(
+            "At least one of the metadata_contracts_root, etherscan_contracts_root, sourcify_root, build_info_root, dataset_root, foundry_project, truffle_project or hf_dataset should be provided"
+        ));
+    }
+    let registry = dataset_registry(chain_id, args)?;
 
-            info!("Total contracts: {}", contracts.len());
+    let skip = match resume {
+        true => storage.ingested_paths()?,
+        false => HashSet::new(),
+    };
+    if *resume {
+        info!("Resuming, skipping {} ingested paths", skip.len());
+    }
+    let skip = Arc::new(skip);
 
-            let total_countracts = contracts.len();
-            let pb = ProgressBar::new(total_countracts as u64);
+    // contracts read from every root, stored with the root as their dataset
+    let mut datasets: Vec<(String, Vec<(String, PlainContract)>)> = vec![];
+    for (root, format) in dataset_roots(args)? {
+        if format == DatasetFormat::Fiesta && is_archive(Path::new(&root)) {
+            info!("Processing plain contracts in archive {root}");
+            let ignore_errors = *ignore_errors;
+            let skip = skip.clone();
+            let archive = root.clone();
+            let archive_contracts = task::spawn_blocking(move || {
+                read_archive_contracts(Path::new(&archive), ignore_errors, &skip)
+            })
+            .await??;
+            datasets.push((root, archive_contracts));
+            continue;
+        }
+        info!("Processing {format} contracts in {root}");
+        let adapters = registry.select(format)?;
+        let contracts = read_dataset(&root, &adapters, *ignore_errors, &skip).await;
+        datasets.push((root, contracts));
+    }
+    for root in expand_roots(foundry_project)? {
+        if skip.contains(&root) {
+            continue;
+        }
+        info!("Processing foundry project {root}");
+        match FoundryProject::load(Path::new(&root)).and_then(|p| p.to_contract()) {
+            Ok(c) => datasets.push((root.clone(), vec![(root, c)])),
+            Err(error) if *ignore_errors => {
+                debug!("Process project failed with error {error} {root}")
+            }
+            Err(error) => {
+                return Err(eyre::eyre!(
+                    "Process project failed with error {error} {root}"
+                ))
+            }
+        }
+    }
+    for root in expand_roots(truffle_project)? {
+        if skip.contains(&root) {
+            continue;
+        }
+        info!("Processing truffle project {root}");
+        match truffle::load_project(Path::new(&root)) {
+            Ok(c) => datasets.push((root.clone(), vec![(root, c)])),
+            Err(error) if *ignore_errors => {
+                debug!("Process project failed with error {error} {root}")
+            }
+            Err(error) => {
+                return Err(eyre::eyre!(
+                    "Process project failed with error {error} {root}"
+                ))
+            }
+        }
+    }
 
-            pb.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",
-            )
-            .unwrap()
-            .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
-                write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
+    for repo in hf_dataset {
+        info!("Streaming contracts of {repo}/{hf_path} from Hugging Face");
+        let dataset = HfDataset::new(fetcher, repo, hf_revision);
+        let mut chunk = Vec::with_capacity(*chunk_size);
+        let mut stored = 0;
+        dataset
+            .stream_contracts(hf_path, *ignore_errors, &skip, |url, contract| {
+                chunk.push((url, contract));
+                if chunk.len() >= *chunk_size {
+                    stored += chunk.len();
+                    store_ingested(storage, hooks, repo, std::mem::take(&mut chunk))?;
+                    info!("Stored {stored} contracts of {repo}");
+                }
+                Ok(())
             })
-            .progress_chars("#>-"),
-        );
+            .await?;
+        stored += chunk.len();
+        store_ingested(storage, hooks, repo, chunk)?;
+        info!("Finished streaming {repo}: {stored}");
+    }
 
-            storage.disable_checkpoint()?;
-            contracts.chunks_mut(*chunk_size).for_each(|chunk| {
-                pb.inc(*chunk_size as u64);
-                let contracts = chunk.to_vec();
-                storage
-                    .store_contracts(contracts)
-                    .expect("Failed to store contracts");
-            });
+    let total_countracts: usize = datasets.iter().map(|(_, c)| c.len()).sum();
+    info!("Total contracts: {total_countracts}");
+
+    let pb = ProgressBar::new(total_countracts as u64);
 
-            storage.enable_checkpoint()?;
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",
+        )
+        .unwrap()
+        .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+            write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
+        })
+        .progress_chars("#>-"),
+    );
 
-            pb.finish();
+    storage.disable_checkpoint()?;
+    let stored = datasets.iter().try_for_each(|(dataset, contracts)| {
+        contracts.chunks(*chunk_size).try_for_each(|chunk| {
+            pb.inc(chunk.len() as u64);
+            store_ingested(storage, hooks, dataset, chunk.to_vec())
+        })
+    });
+    storage.enable_checkpoint()?;
+    stored?;
 
-            info!("Finished processing plain contracts: {}", contracts.len());
-            Ok(())
-        }
-        (None, Some(etherscan_contracts_root)) => {
-            let mut contracts =
-                process_etherscan_contracts(etherscan_contracts_root, *ignore_errors).await;
+    pb.finish();
 
-            info!("Total contracts: {}", contracts.len());
+    info!("Finished processing plain contracts: {total_countracts}");
 
-            let total_countracts = contracts.len();
-            let pb = ProgressBar::new(total_countracts as u64);
+    if *watch {
+        watch_roots(storage, hooks, chain_id, args).await?;
+    }
 
-            pb.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})",
-            )
-            .unwrap()
-            .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
-                write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap()
-            })
-            .progress_chars("#>-"),
-            );
+    Ok(())
+}
 
-            storage.disable_checkpoint()?;
-            contracts.chunks_mut(*chunk_size).for_each(|chunk| {
-                pb.inc(*chunk_size as u64);
-                let contracts = chunk.to_vec();
-                storage
-                    .store_contracts(contracts)
-                    .expect("Failed to store contracts");
-            });
+/// The adapters of the dataset formats, with the custom layout of the
+/// pre-process arguments
+fn dataset_registry(chain_id: u64, args: &PreProcessArgs) -> Result<DatasetRegistry> {
+    let custom = match (&args.custom_pattern, &args.custom_command) {
+        (Some(pattern), Some(command)) => Some(Custom::new(pattern, command)?),
+        _ => None,
+    };
+    Ok(DatasetRegistry::new(chain_id, custom))
+}
+
+/// The expanded roots of the pre-process arguments with their layout, in the
+/// order they are read
+fn dataset_roots(args: &PreProcessArgs) -> Result<Vec<(String, DatasetFormat)>> {
+    let mut roots = vec![];
+    for (patterns, format) in [
+        (&args.metadata_contracts_root, DatasetFormat::Fiesta),
+        (&args.etherscan_contracts_root, DatasetFormat::EtherscanJson),
+        (&args.sourcify_root, DatasetFormat::Sourcify),
+        (&args.build_info_root, DatasetFormat::Hardhat),
+        (&args.dataset_root, args.dataset_format),
+    ] {
+        roots.extend(
+            expand_roots(patterns)?
+                .into_iter()
+                .map(|root| (root, format)),
+        );
+    }
+    Ok(roots)
+}
 
-            storage.enable_checkpoint()?;
+/// Store the contracts added to the folders of the pre-process arguments until
+/// interrupted. Every batch of changes rescans the folders, skipping the paths
+/// in the ingest manifest, so a file which could not be read yet, e.g. a
+/// partially written json, is read again on the next change.
+async fn watch_roots(
+    storage: &mut Storage,
+    hooks: &Hooks,
+    chain_id: u64,
+    args: &PreProcessArgs,
+) -> Result<()> {
+    let registry = dataset_registry(chain_id, args)?;
+    let dataset_roots = dataset_roots(args)?
+        .into_iter()
+        .filter(|(root, _)| !is_archive(Path::new(root)))
+        .collect_vec();
+    let roots = dataset_roots
+        .iter()
+        .map(|(root, _)| root.clone())
+        .collect_vec();
+    if roots.is_empty() {
+        return Err(eyre::eyre!(
+            "Watching needs a metadata_contracts_root, etherscan_contracts_root, sourcify_root, build_info_root or dataset_root folder"
+        ));
+    }
 
-            pb.finish();
+    let watcher = RootWatcher::new(&roots)?;
+    let settle = Duration::from_secs(args.settle_secs);
+    info!("Watching {} folders for new contracts", roots.len());
+    loop {
+        let changed = task::block_in_place(|| watcher.wait(settle))?;
+        debug!("Changed paths: {changed:?}");
 
-            info!("Finished processing plain contracts: {}", contracts.len());
+        let skip = storage.ingested_paths()?;
+        let mut datasets = vec![];
+        for (root, format) in dataset_roots.iter() {
+            let adapters = registry.select(*format)?;
+            let contracts = read_changed(root, &changed, &adapters, true, &skip).await;
+            datasets.push((root, contracts));
+        }
 
-            Ok(())
+        let mut count = 0;
+        for (dataset, contracts) in datasets.iter() {
+            count += contracts.len();
+            for chunk in contracts.chunks(args.chunk_size) {
+                store_ingested(storage, hooks, dataset, chunk.to_vec())?;
+            }
         }
-        _ => {
-            panic!("Only one of metadata_contracts_root or etherscan_contracts_root should be provided")
+        if count > 0 {
+            info!("Stored {count} new contracts");
         }
     }
 }
 
-async fn index_functions(storage: &mut Storage, args: &IndexFunctionsArgs) -> Result<()> {
+async fn index_functions(
+    storage: &mut Storage,
+    fetcher: &HttpFetcher,
+    artifact_cache: Option<&ArtifactCache>,
+    args: &IndexFunctionsArgs,
+) -> Result<()> {
+    if args.max_concurrent_compiles == 0 {
+        return Err(eyre::eyre!(
+            "--max-concurrent-compiles should be at least 1"
+        ));
+    }
+    let compile_permits = Arc::new(Semaphore::new(args.max_concurrent_compiles));
+    let fallback_versions = args.fallback_versions;
+
     let total_countracts = storage.count_contracts()? as u64;
     let pb = ProgressBar::new(total_countracts);
     pb.set_style(
@@ -268,6 +3883,14 @@ async fn index_functions(storage: &mut Storage, args: &IndexFunctionsArgs) -> Re
             .progress_chars("#>-"),
     );
 
+    #[cfg(feature = "wasm-plugins")]
+    let plugins = Arc::new(
+        args.plugin
+            .iter()
+            .map(|path| plugins::WasmPlugin::load(path))
+            .collect::<Result<Vec<_>>>()?,
+    );
+
     let mut i: u64 = 0;
     let size = args.chunk_size as u64;
     loop {
@@ -288,33 +3911,117 @@ async fn index_functions(storage: &mut Storage, args: &IndexFunctionsArgs) -> Re
             contracts.push(contract);
         }
 
-        let functions = Arc::new(Mutex::new(Vec::new()));
+        let vyper_versions: HashSet<_> = contracts
+            .iter()
+            .filter(|c| matches!(c.source, ContractSource::Vyper(_)))
+            .filter_map(|c| vyper_version(&c.metadata.compiler_version).ok())
+            .collect();
+        for version in vyper_versions {
+            if let Err(e) = install_vyper(fetcher, &version).await {
+                error!("Failed to install vyper {version} {e}");
+            }
+        }
+
+        let output = Arc::new(Mutex::new(IndexOutput::default()));
 
         let compile_futures: Vec<_> = contracts
             .into_iter()
             .map(|mut contract| {
-                let functions = functions.clone();
+                let output = output.clone();
+                let compile_permits = compile_permits.clone();
+                let artifact_cache = artifact_cache.cloned();
+                #[cfg(feature = "wasm-plugins")]
+                let plugins = plugins.clone();
                 task::spawn(async move {
-                    if matches!(contract.source, ContractSource::Vyper(_)) {
-                        return;
-                    }
-                    if let Err(e) = contract.compile().await {
-                        error!("Failed to compile contract with id {} {}", contract.id(), e);
+                    let contract_id = contract.id();
+                    // released once compiled, the extraction needs no solc
+                    let permit = compile_permits.acquire().await;
+                    let started = Instant::now();
+                    let compiled = contract
+                        .compile_with_fallback(artifact_cache.as_ref(), fallback_versions)
+                        .await;
+                    drop(permit);
+                    let failure = match compiled {
+                        Err(e) => Some(e.to_string()),
+                        Ok((output, _)) if output.has_compiler_errors() => Some(output.to_string()),
+                        Ok((_, Some(version))) => {
+                            info!("Compiled contract {contract_id} with solc {version} instead of {}", contract.metadata.compiler_version);
+                            output.lock().await.compiler_fallbacks.push(CompilerFallback {
+                                contract_id: contract_id.clone(),
+                                recorded_version: contract.metadata.compiler_version.clone(),
+                                compiled_version: version,
+                            });
+                            None
+                        }
+                        Ok((_, None)) => None,
+                    };
+                    if let Some(e) = failure {
+                        error!(
+                            stage = "compile",
+                            contract_id = contract_id.as_str(),
+                            duration_ms = started.elapsed().as_millis() as u64,
+                            outcome = "error";
+                            "Failed to compile contract with id {contract_id} {e}"
+                        );
+                        output.lock().await.compile_errors.push(CompileError {
+                            contract_id,
+                            compiler_version: contract.metadata.compiler_version.clone(),
+                            error: e,
+                        });
                         return;
                     }
+                    debug!(
+                        stage = "compile",
+                        contract_id = contract_id.as_str(),
+                        duration_ms = started.elapsed().as_millis() as u64,
+                        outcome = "ok";
+                        "Compiled contract {contract_id}"
+                    );
 
-                    match contract.extract_functions() {
+                    let started = Instant::now();
+                    let extracted = match contract.source {
+                        ContractSource::Vyper(_) => IndexOutput::from_vyper_contract(&contract),
+                        _ => IndexOutput::from_contract(&contract),
+                    };
+                    match extracted {
                         Err(e) => {
                             log::error!(
-                                "Failed to extract functions from contract with id {} {}",
-                                contract.id(),
-                                e
+                                stage = "extract",
+                                contract_id = contract_id.as_str(),
+                                duration_ms = started.elapsed().as_millis() as u64,
+                                outcome = "error";
+                                "Failed to extract functions from contract with id {contract_id} {e}"
                             );
-                            panic!("Failed to extract functions from contract");
+                            // recorded as a failure, retried like the contracts
+                            // failing to compile
+                            output.lock().await.compile_errors.push(CompileError {
+                                contract_id,
+                                compiler_version: contract.metadata.compiler_version.clone(),
+                                error: format!("Failed to extract: {e}"),
+                            });
                         }
-                        Ok(funcs) => {
-                            let mut functions = functions.lock().await;
-                            functions.extend(funcs);
+                        Ok(extracted) => {
+                            #[cfg(feature = "wasm-plugins")]
+                            let extracted = {
+                                let mut extracted = extracted;
+                                for plugin in plugins.iter() {
+                                    match plugin.extract(&contract) {
+                                        Ok(rows) => extracted.plugin_rows.extend(rows),
+                                        Err(e) => error!("{e} on contract {}", contract.id()),
+                                    }
+                                }
+                                extracted
+                            };
+                            debug!(
+                                stage = "extract",
+                                contract_id = contract_id.as_str(),
+                                duration_ms = started.elapsed().as_millis() as u64,
+                                outcome = "ok";
+                                "Extracted {} functions from contract {contract_id}",
+                                extracted.functions.len()
+                            );
+                            let mut output = output.lock().await;
+                            output.extend(extracted);
                         }
                     }
                 })
@@ -325,8 +4032,8 @@ async fn index_functions(storage: &mut Storage, args: &IndexFunctionsArgs) -> Re
 
         i += size;
 
-        let functions = functions.lock().await;
-        storage.store_functions(&functions)?;
+        let output = output.lock().await;
+        storage.store_index_output(&output)?;
         pb.inc(size);
     }
 
@@ -339,23 +4046,151 @@ async fn index_functions(storage: &mut Storage, args: &IndexFunctionsArgs) -> Re
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
-    env_logger::init();
-
     let cli = Cli::parse();
+    logging::init(cli.log_format);
+
+    let config = Config::load(cli.config.as_deref())?;
+    let profile = config.profile(cli.profile.as_deref())?;
 
-    let duckdb_path = match cli.duckdb_path {
+    let duckdb_path = match cli.duckdb_path.or(profile.duckdb_path.clone()) {
         Some(path) => path.clone(),
         None => std::env::var("DUCKDB_PATH")
             .unwrap_or_else(|_| panic!("DUCKDB_PATH environment variable is not set")),
     };
     let mut storage = db::Storage::new(&duckdb_path)?;
+    let fetcher = HttpFetcher::new(cli.http.clone())?;
+    let artifact_cache = cli
+        .artifact_cache_dir
+        .as_deref()
+        .map(ArtifactCache::new)
+        .transpose()?;
+    set_offline(cli.offline);
+    if let Some(dir) = &cli.workspace_dir {
+        let max_size = cli.workspace_max_mb.map(|mb| mb * 1_000_000);
+        workspace::init(Workspace::new(dir, max_size)?)?;
+    }
 
-    match &cli.command {
-        Commands::IndexFunctions(args) => index_functions(&mut storage, args).await,
-        Commands::PreProcess(args) => preprocess_contracts(&mut storage, args).await,
-        Commands::DownloadSolc => download_all_solc_versions().await,
+    let result = match &cli.command {
+        Commands::IndexFunctions(args) => {
+            index_functions(&mut storage, &fetcher, artifact_cache.as_ref(), args).await
+        }
+        Commands::PreProcess(args) => {
+            let hooks = Hooks {
+                contract: args.contract_hook.clone().or(profile.contract_hook.clone()),
+                chunk: args.chunk_hook.clone().or(profile.chunk_hook.clone()),
+            };
+            let chain_id = args.chain_id.or(profile.chain_id).unwrap_or(1);
+            preprocess_contracts(&mut storage, &fetcher, &hooks, chain_id, args).await
+        }
+        Commands::DownloadSolc(args) => {
+            let platform = args.platform.map_or_else(SolcPlatform::host, Ok)?;
+            let solc_folder = args
+                .solc_folder
+                .as_deref()
+                .map_or_else(svm_home, Into::into);
+            download_all_solc_versions(&fetcher, platform, &solc_folder).await
+        }
         Commands::ExportSource(args) => export_source(&mut storage, args).await,
+        Commands::Compile(args) => {
+            compile_contract(&mut storage, artifact_cache.as_ref(), args).await
+        }
+        Commands::RetryFailed(args) => {
+            retry_failed(&mut storage, &fetcher, artifact_cache.as_ref(), args).await
+        }
+        Commands::DiffSettings(args) => diff_settings(&mut storage, args).await,
+        Commands::ExtractImmutables(args) => {
+            extract_immutables(&mut storage, artifact_cache.as_ref(), args).await
+        }
+        Commands::VerifyMetadata(args) => {
+            verify_metadata(&mut storage, artifact_cache.as_ref(), args).await
+        }
+        Commands::ConformsTo(args) => conforms_to(&mut storage, args).await,
+        Commands::SizeReport(args) => size_report(&mut storage, args).await,
+        Commands::CompileSweep(args) => compile_sweep(&mut storage, &fetcher, args).await,
+        Commands::IndexVersions(args) => {
+            index_versions(&mut storage, artifact_cache.as_ref(), args).await
+        }
+        Commands::Search(args) => search(&mut storage, args).await,
+        Commands::FetchDeployments(args) => {
+            let chain_id = args.chain_id.or(profile.chain_id).unwrap_or(1);
+            let explorer = Explorer::new(&fetcher, &profile)?.chain(Some(chain_id));
+            fetch_deployments(&mut storage, &explorer, chain_id, args).await
+        }
+        Commands::FetchEtherscan(args) => {
+            let (url, api_key) = match &args.blockscout_url {
+                Some(url) => (blockscout_api_url(url), args.blockscout_api_key.clone()),
+                None => (
+                    profile
+                        .explorer_url
+                        .clone()
+                        .unwrap_or(ETHERSCAN_V2_API_URL.into()),
+                    args.api_key.clone().or(profile.api_key.clone()),
+                ),
+            };
+            let chain_ids = match args.chain_id.is_empty() {
+                true => vec![profile.chain_id],
+                false => args.chain_id.iter().copied().map(Some).collect(),
+            };
+            fetch_etherscan(&mut storage, &fetcher, &url, api_key, &chain_ids, args).await
+        }
+        Commands::FetchBytecode(args) => {
+            let url = args
+                .rpc_url
+                .as_deref()
+                .or(profile.rpc_url.as_deref())
+                .ok_or_else(|| eyre::eyre!("No rpc_url in the selected profile"))?;
+            let rpc = RpcClient::new(&fetcher, url);
+            let chain_id = args.chain_id.or(profile.chain_id);
+            fetch_bytecode(&mut storage, &rpc, chain_id, args).await
+        }
+        Commands::FetchIpfs(args) => {
+            let ipfs = IpfsClient::new(&fetcher, &args.gateway);
+            fetch_ipfs(&mut storage, &ipfs, args).await
+        }
+        Commands::FetchPackage(args) => {
+            let client = PackageClient::new(&fetcher, &args.data_url, &args.cdn_url);
+            fetch_package(&mut storage, &client, args).await
+        }
+        Commands::AstQuery(args) => ast_query(&mut storage, artifact_cache.as_ref(), args).await,
+        Commands::ExportFunctionAst(args) => {
+            export_function_ast(&mut storage, artifact_cache.as_ref(), args).await
+        }
+        Commands::CloneReport(args) => clone_report(&mut storage, args).await,
+        Commands::ImportGraph(args) => import_graph(&mut storage, args).await,
+        Commands::DeployerReport(args) => deployer_report(&mut storage, args).await,
+        Commands::CompilerTimeline(args) => compiler_timeline(&mut storage, args).await,
+        Commands::Analyze(args) => analyze(&mut storage, args).await,
+        Commands::MatchSlot(args) => match_slot(&mut storage, args).await,
+        Commands::MatchBytecode(args) => match_bytecode(&mut storage, args).await,
+        Commands::Screen(args) => {
+            let url = args
+                .rpc_url
+                .as_deref()
+                .or(profile.rpc_url.as_deref())
+                .ok_or_else(|| eyre::eyre!("No rpc_url in the selected profile"))?;
+            let rpc = RpcClient::new(&fetcher, url);
+            screen(&mut storage, &rpc, args).await
+        }
+        Commands::AbiVectors(args) => abi_vectors(&mut storage, args).await,
+        Commands::Decode(args) => decode(&mut storage, args).await,
+        Commands::DecodeRevert(args) => decode_revert(&mut storage, args).await,
+        Commands::DecodeLog(args) => decode_log(&mut storage, args).await,
+        Commands::ImportSignatures(args) => import_signatures(&mut storage, args).await,
+        Commands::Xref(args) => xref(&mut storage, args).await,
+        Commands::MergeDb(args) => merge_db(&mut storage, args).await,
+        Commands::Import(args) => import(&mut storage, args).await,
+        Commands::ImportBigquery(args) => import_bigquery(&mut storage, args).await,
+        Commands::ExportSite(args) => export_site(&mut storage, args).await,
+        Commands::Release(args) => release(&mut storage, args).await,
+        Commands::ExportTriplets(args) => export_triplets(&mut storage, args).await,
+        Commands::ExportEnv(args) => export_env(&mut storage, &fetcher, args).await,
+        Commands::DatasetCard(args) => dataset_card(&mut storage, args).await,
+    };
+
+    if let Some(workspace) = workspace::get() {
+        workspace.finish()?;
     }
+    result
 }
 
 #[cfg(test)]
@@ -441,4 +4276,339 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn apply_compiler_overrides() -> Result<()> {
+        let recorded = PlainContract::single_file("contract A {}").metadata;
+        let args = RetryFailedArgs::try_parse_from([
+            "retry-failed",
+            "--optimizer",
+            "true",
+            "--override-optimizer-runs",
+            "1000",
+            "--via-ir",
+            "--override-evm-version",
+            "paris",
+            "--override-solc",
+            "0.8.20",
+        ])?;
+        let mut metadata = recorded.clone();
+        args.overrides.apply(&mut metadata)?;
+        assert!(metadata.optimization_used);
+        assert_eq!(metadata.runs, 1000);
+        assert_eq!(metadata.via_ir, Some(true));
+        assert_eq!(metadata.evm_version.as_deref(), Some("paris"));
+        assert_eq!(metadata.compiler_version, "v0.8.20");
+
+        // without overrides the recorded settings are kept
+        let args = RetryFailedArgs::try_parse_from(["retry-failed"])?;
+        let mut metadata = recorded.clone();
+        args.overrides.apply(&mut metadata)?;
+        assert_eq!(metadata.compiler_version, recorded.compiler_version);
+        assert_eq!(metadata.runs, 200);
+        assert_eq!(metadata.via_ir, None);
+
+        for invalid in [
+            ["retry-failed", "--override-evm-version", "shanghaii"],
+            ["retry-failed", "--override-solc", "latest"],
+        ] {
+            let args = RetryFailedArgs::try_parse_from(invalid)?;
+            assert!(args.overrides.apply(&mut recorded.clone()).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn retry_failed_with_overrides() -> Result<()> {
+        let mut storage = Storage::new(":memory:")?;
+        let contract = PlainContract::single_file("contract A {}");
+        let id = contract.id();
+        storage.store_contracts(vec![contract])?;
+        storage.store_compile_errors(&[CompileError {
+            contract_id: id.clone(),
+            compiler_version: "v0.8.19+commit.7dd6d404".into(),
+            error: "Stack too deep".into(),
+        }])?;
+        let fetcher = HttpFetcher::new(HttpOptions {
+            requests_per_second: 0.0,
+            max_retries: 0,
+            http_cache_dir: None,
+            refresh: false,
+            proxy: None,
+        })?;
+
+        // a version which was never released fails with the override, the
+        // failure is recorded with it and the stored contract is unchanged
+        let args = RetryFailedArgs::try_parse_from(["retry-failed", "--override-solc", "0.3.99"])?;
+        retry_failed(&mut storage, &fetcher, None, &args).await?;
+        let versions = storage
+            .compile_errors()?
+            .into_iter()
+            .map(|e| e.compiler_version)
+            .sorted()
+            .collect_vec();
+        assert_eq!(versions, ["v0.3.99", "v0.8.19+commit.7dd6d404"]);
+        let stored = storage.get_contract(&id)?.expect("stored contract");
+        assert_eq!(stored.metadata.compiler_version, "v0.8.19+commit.7dd6d404");
+
+        Ok(())
+    }
+
+    #[test]
+    fn effective_settings_with_fallback_and_overrides() -> Result<()> {
+        use crate::index::CompilerFallback;
+
+        let storage = Storage::new(":memory:")?;
+        let contract = PlainContract::single_file("pragma solidity ^0.8.0; contract A {}");
+        let id = contract.id();
+        storage.store_contracts(vec![contract])?;
+
+        let none = DiffSettingsArgs::try_parse_from(["diff-settings", "--a", &id, "--b", &id])?;
+        let recorded = effective_settings(&storage, &id, &none.overrides)?;
+        assert_eq!(recorded.compiler_version, "0.8.19");
+
+        storage.store_compiler_fallbacks(&[CompilerFallback {
+            contract_id: id.clone(),
+            recorded_version: "v0.8.19+commit.7dd6d404".into(),
+            compiled_version: "0.8.20".into(),
+        }])?;
+        let fallback = effective_settings(&storage, &id, &none.overrides)?;
+        assert_eq!(fallback.compiler_version, "0.8.20");
+
+        let args = DiffSettingsArgs::try_parse_from([
+            "diff-settings",
+            "--a",
+            &id,
+            "--b",
+            &id,
+            "--optimizer",
+            "true",
+            "--override-solc",
+            "0.8.21",
+        ])?;
+        let overridden = effective_settings(&storage, &id, &args.overrides)?;
+        assert_eq!(overridden.compiler_version, "0.8.21");
+        assert!(overridden.optimizer);
+        assert_eq!(
+            settings_diff::diff_settings(&fallback, &overridden).len(),
+            2
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn index_versions_skips_stored_versions() -> Result<()> {
+        use crate::versions::VersionedArtifact;
+
+        let mut storage = Storage::new(":memory:")?;
+        let contract = PlainContract::single_file("pragma solidity 0.8.20; contract A {}");
+        let id = contract.id();
+        storage.store_contracts(vec![contract])?;
+        storage.store_versioned(
+            &[VersionedArtifact {
+                contract_id: id.clone(),
+                contract_name: "A".into(),
+                solc_version: "0.8.20".into(),
+                runtime_size: 1,
+                creation_size: 1,
+                runtime_code_hash: "0x01".into(),
+                deployment_gas: None,
+            }],
+            &[],
+        )?;
+
+        // the only version the pragma allows is stored, nothing is compiled
+        let args = IndexVersionsArgs {
+            contract_id: vec![id.clone()],
+            sample: None,
+            seed: 42,
+            max_versions: 3,
+            force: false,
+        };
+        index_versions(&mut storage, None, &args).await?;
+        assert_eq!(storage.versioned_solc_versions(&id)?, ["0.8.20"]);
+        let hash: String = storage.conn.query_row(
+            "SELECT runtime_code_hash FROM versioned_artifact",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(hash, "0x01");
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_hook_leaves_paths_uningested() -> Result<()> {
+        let storage = Storage::new(":memory:")?;
+        let contract = PlainContract::single_file("contract A {}");
+        let contracts = vec![("a/A.sol".to_string(), contract)];
+
+        let failing = Hooks {
+            contract: None,
+            chunk: Some("exit 1".into()),
+        };
+        assert!(store_ingested(&storage, &failing, "local", contracts.clone()).is_err());
+        assert!(storage.ingested_paths()?.is_empty());
+
+        store_ingested(&storage, &Hooks::default(), "local", contracts)?;
+        assert!(storage.ingested_paths()?.contains("a/A.sol"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_contracts_of_several_chains() -> Result<()> {
+        use crate::cache::ResponseCache;
+
+        // responses are served from the cache, keyed by their url
+        let folder = tempfile::tempdir()?;
+        let cache_dir = folder.path().to_string_lossy().into_owned();
+        let cache = ResponseCache::new(&cache_dir)?;
+        let response = |source_code: &str| {
+            serde_json::json!({"status": "1", "message": "OK", "result": [{
+                "SourceCode": source_code,
+                "OptimizationUsed": "0",
+                "Runs": "200",
+                "ContractName": "A",
+                "CompilerVersion": "v0.8.19+commit.7dd6d404",
+                "ConstructorArguments": "",
+                "EVMVersion": "Default",
+            }]})
+            .to_string()
+        };
+        let url = |chain_id: u64| {
+            format!("{ETHERSCAN_V2_API_URL}?chainid={chain_id}&module=contract&action=getsourcecode&address=0xaa&apikey=KEY")
+        };
+        cache.put(&url(1), &response("contract A {}")).await?;
+        cache.put(&url(8453), &response("")).await?;
+        let fetcher = HttpFetcher::new(HttpOptions {
+            requests_per_second: 0.0,
+            max_retries: 0,
+            http_cache_dir: Some(cache_dir),
+            refresh: false,
+            proxy: None,
+        })?;
+
+        let mut storage = Storage::new(":memory:")?;
+        let args = FetchEtherscanArgs {
+            address: vec!["0xAA".into()],
+            addresses: None,
+            api_key: None,
+            blockscout_url: None,
+            blockscout_api_key: None,
+            chain_id: vec![1, 8453],
+        };
+        fetch_etherscan(
+            &mut storage,
+            &fetcher,
+            ETHERSCAN_V2_API_URL,
+            Some("KEY".into()),
+            &[Some(1), Some(8453)],
+            &args,
+        )
+        .await?;
+
+        // the contract unverified on the second chain is skipped
+        let contracts = storage.get_contracts(0, 10)?;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].metadata.contract_name, "A");
+        let occurrences = storage.occurrences(&contracts[0].id())?;
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].chain_id, Some(1));
+        assert_eq!(occurrences[0].address.as_deref(), Some("0xaa"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decode_skips_invalid_signatures() -> Result<()> {
+        use crate::signatures::KnownSignature;
+
+        let mut storage = Storage::new(":memory:")?;
+        let known = |signature: &str| KnownSignature {
+            kind: SignatureKind::Function,
+            signature: signature.into(),
+            hash: "0xa9059cbb".into(),
+        };
+        storage.store_known_signatures(&[
+            known("transfer(address,uint2x)"),
+            known("transfer(address,uint256)"),
+        ])?;
+
+        let args = DecodeArgs {
+            data: format!("0xa9059cbb{:0>64}{:0>64}", "1", "2"),
+        };
+        decode(&mut storage, &args).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn abi_vectors_without_function() -> Result<()> {
+        let mut storage = Storage::new(":memory:")?;
+        let args = AbiVectorsArgs {
+            signature: None,
+            selector: None,
+            arg: vec![],
+            count: 1,
+            seed: 0,
+        };
+        assert!(abi_vectors(&mut storage, &args).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decode_revert_skips_invalid_signatures() -> Result<()> {
+        use crate::signatures::KnownSignature;
+
+        let mut storage = Storage::new(":memory:")?;
+        let known = |signature: &str| KnownSignature {
+            kind: SignatureKind::Error,
+            signature: signature.into(),
+            hash: "0xcf479181".into(),
+        };
+        storage.store_known_signatures(&[
+            known("InsufficientBalance(uint2x,uint256)"),
+            known("InsufficientBalance(uint256,uint256)"),
+        ])?;
+
+        let args = DecodeRevertArgs {
+            data: format!("0xcf479181{:0>64}{:0>64}", "1", "2"),
+        };
+        decode_revert(&mut storage, &args).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decode_log_skips_invalid_signatures() -> Result<()> {
+        use crate::signatures::KnownSignature;
+
+        let mut storage = Storage::new(":memory:")?;
+        let topic0 = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+        let known = |signature: &str| KnownSignature {
+            kind: SignatureKind::Event,
+            signature: signature.into(),
+            hash: topic0.into(),
+        };
+        storage.store_known_signatures(&[
+            known("Transfer(addr,address,uint256)"),
+            known("Transfer(address,address,uint256)"),
+        ])?;
+
+        let args = DecodeLogArgs {
+            topics: vec![
+                topic0.into(),
+                format!("0x{:0>64}", "1"),
+                format!("0x{:0>64}", "2"),
+            ],
+            data: format!("0x{:0>64}", "3"),
+        };
+        decode_log(&mut storage, &args).await?;
+
+        Ok(())
+    }
 }
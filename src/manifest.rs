@@ -0,0 +1,80 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::Result;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A file written by an export
+#[derive(Debug, Serialize)]
+pub struct ExportedFile {
+    /// Path relative to the export root
+    pub path: String,
+    /// `0x` prefixed keccak256 of the content
+    pub keccak256: String,
+    pub size: usize,
+}
+
+/// Everything needed to reproduce and audit an export: the tool version, the
+/// database snapshot, the filters and the content of the written files. The
+/// manifest holds no timestamps, so exporting the same snapshot with the same
+/// filters always gives the same manifest.
+#[derive(Debug, Serialize)]
+pub struct ExportManifest {
+    pub tool_version: String,
+    pub db_snapshot_id: String,
+    pub command: String,
+    pub filters: BTreeMap<String, String>,
+    pub row_counts: BTreeMap<String, usize>,
+    pub outputs: Vec<ExportedFile>,
+}
+
+impl ExportManifest {
+    pub fn new(command: &str, db_snapshot_id: String) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").into(),
+            db_snapshot_id,
+            command: command.into(),
+            filters: BTreeMap::new(),
+            row_counts: BTreeMap::new(),
+            outputs: vec![],
+        }
+    }
+
+    pub fn filter(mut self, name: &str, value: impl ToString) -> Self {
+        self.filters.insert(name.into(), value.to_string());
+        self
+    }
+
+    pub fn row_count(mut self, table: &str, count: usize) -> Self {
+        self.row_counts.insert(table.into(), count);
+        self
+    }
+
+    /// Hash an output, `-` is used as path of stdout
+    pub fn output(mut self, path: &str, content: &[u8]) -> Self {
+        self.outputs.push(ExportedFile {
+            path: path.into(),
+            keccak256: hex::encode_prefixed(keccak256(content)),
+            size: content.len(),
+        });
+        self.outputs.sort_by(|a, b| a.path.cmp(&b.path));
+        self
+    }
+
+    /// Hash the written files, paths are recorded relative to `root`
+    pub async fn outputs(mut self, root: &Path, files: &[PathBuf]) -> Result<Self> {
+        for file in files {
+            let content = tokio::fs::read(file).await?;
+            let path = file.strip_prefix(root).unwrap_or(file).to_string_lossy();
+            self = self.output(&path, &content);
+        }
+        Ok(self)
+    }
+
+    pub async fn write(&self, path: &str) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
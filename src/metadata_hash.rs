@@ -0,0 +1,49 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::{ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bytecode::{cbor_metadata, object_bytes},
+    plain_contract::PlainContract,
+};
+
+/// Hashes of the solidity metadata JSON produced when recompiling a contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataHash {
+    pub contract_id: String,
+    pub contract_name: String,
+    /// `0x` prefixed keccak256 of the metadata JSON
+    pub metadata_keccak: String,
+    /// The metadata hash embedded in the recompiled runtime bytecode, e.g. `ipfs:0x1220...`
+    pub metadata_hash: Option<String>,
+}
+
+/// Hash the metadata JSON of every artifact in the compilation output
+pub fn extract_metadata_hashes(contract: &PlainContract) -> Result<Vec<MetadataHash>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let hashes = compilation_output
+        .artifacts()
+        .filter_map(|(contract_name, artifact)| {
+            let raw_metadata = artifact.raw_metadata.as_ref()?;
+            let metadata_hash = artifact
+                .deployed_bytecode
+                .as_ref()
+                .and_then(|b| b.bytecode.as_ref())
+                .and_then(|b| cbor_metadata(&object_bytes(&b.object)))
+                .and_then(|m| m.hash);
+            Some(MetadataHash {
+                contract_id: contract_id.clone(),
+                contract_name: contract_name.clone(),
+                metadata_keccak: hex::encode_prefixed(keccak256(raw_metadata.as_bytes())),
+                metadata_hash,
+            })
+        })
+        .collect();
+
+    Ok(hashes)
+}
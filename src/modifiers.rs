@@ -0,0 +1,185 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ast_query::{descendants, node_source, node_type, source_asts, source_content},
+    literals::visit_implementations,
+    plain_contract::PlainContract,
+};
+
+/// A modifier defined in a contract of a source file
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ContractModifier {
+    pub contract_id: String,
+    /// The source file of the contract defining the modifier
+    pub source_path: String,
+    /// The contract defining the modifier, e.g. `Ownable`
+    pub contract_name: String,
+    pub modifier_name: String,
+    /// Id of the definition in the AST of the compilation
+    pub ast_id: Option<i64>,
+    /// Source code of the modifier definition
+    pub source_code: String,
+}
+
+/// A modifier invoked by a function or constructor
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FunctionModifier {
+    /// Id of the function in the `function` or `function_definition` table
+    pub function_id: String,
+    pub contract_id: String,
+    /// Position of the invocation in the function header
+    pub position: u32,
+    pub modifier_name: String,
+    /// The invocation as written, e.g. `onlyRole(MINTER_ROLE)`
+    pub invocation: String,
+    /// AST id of the invoked definition, its `referencedDeclaration`, missing
+    /// for invocations indexed before it was stored
+    pub declaration_id: Option<i64>,
+}
+
+fn name(node: &Value) -> &str {
+    node.get("name").and_then(Value::as_str).unwrap_or_default()
+}
+
+/// The modifier definitions of a single source file AST, with the name of
+/// their contract
+pub fn ast_modifiers(ast: &Value) -> Vec<(&str, &Value)> {
+    let mut nodes = vec![];
+    descendants(ast, &mut nodes);
+    nodes
+        .into_iter()
+        .filter(|node| node_type(node) == Some("ContractDefinition"))
+        .flat_map(|contract| {
+            contract
+                .get("nodes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|node| node_type(node) == Some("ModifierDefinition"))
+                .map(move |node| (name(contract), node))
+        })
+        .collect()
+}
+
+/// The modifier invocations of a function definition, base constructor calls
+/// of constructors are left out
+pub fn modifier_invocations(function: &Value) -> Vec<&Value> {
+    function
+        .get("modifiers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|m| {
+            m.get("kind").and_then(Value::as_str) != Some("baseConstructorSpecifier")
+                && m.pointer("/modifierName/name").is_some()
+        })
+        .collect()
+}
+
+/// Return the modifiers defined in every compiled source file
+pub fn extract_modifiers(contract: &PlainContract) -> Result<Vec<ContractModifier>> {
+    let contract_id = contract.id();
+
+    let mut modifiers = vec![];
+    for (path, ast) in source_asts(contract)? {
+        let content = source_content(contract, &path);
+        for (contract_name, node) in ast_modifiers(&ast) {
+            modifiers.push(ContractModifier {
+                contract_id: contract_id.clone(),
+                source_path: path.clone(),
+                contract_name: contract_name.into(),
+                modifier_name: name(node).into(),
+                ast_id: node.get("id").and_then(Value::as_i64),
+                source_code: node_source(&content, node).into(),
+            });
+        }
+    }
+
+    Ok(modifiers)
+}
+
+/// Return the modifiers invoked by every function and constructor of every
+/// compiled contract, see [`visit_implementations`]
+pub fn extract_function_modifiers(contract: &PlainContract) -> Result<Vec<FunctionModifier>> {
+    let contract_id = contract.id();
+
+    let mut modifiers = vec![];
+    visit_implementations(contract, |function_id, path, function| {
+        let content = source_content(contract, path);
+        for (position, invocation) in modifier_invocations(function).into_iter().enumerate() {
+            let modifier_name = invocation
+                .pointer("/modifierName/name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let source = node_source(&content, invocation);
+            modifiers.push(FunctionModifier {
+                function_id: function_id.into(),
+                contract_id: contract_id.clone(),
+                position: position as u32,
+                modifier_name: modifier_name.into(),
+                invocation: match source.is_empty() {
+                    true => modifier_name.into(),
+                    false => source.into(),
+                },
+                declaration_id: invocation
+                    .pointer("/modifierName/referencedDeclaration")
+                    .and_then(Value::as_i64),
+            });
+        }
+    })?;
+
+    Ok(modifiers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn find_modifiers() {
+        let ast = json!({
+            "nodeType": "SourceUnit",
+            "nodes": [{
+                "nodeType": "ContractDefinition",
+                "name": "Ownable",
+                "nodes": [
+                    {"nodeType": "VariableDeclaration", "name": "owner"},
+                    {"nodeType": "ModifierDefinition", "name": "onlyOwner"}
+                ]
+            }, {
+                "nodeType": "ContractDefinition",
+                "name": "Vault",
+                "nodes": [
+                    {"nodeType": "ModifierDefinition", "name": "nonReentrant"},
+                    {"nodeType": "FunctionDefinition", "name": "withdraw"}
+                ]
+            }]
+        });
+        let modifiers: Vec<_> = ast_modifiers(&ast)
+            .into_iter()
+            .map(|(contract_name, node)| (contract_name, name(node)))
+            .collect();
+        assert_eq!(
+            modifiers,
+            [("Ownable", "onlyOwner"), ("Vault", "nonReentrant")]
+        );
+
+        let function = json!({
+            "nodeType": "FunctionDefinition",
+            "name": "mint",
+            "modifiers": [
+                {"nodeType": "ModifierInvocation", "kind": "modifierInvocation", "modifierName": {"nodeType": "IdentifierPath", "name": "onlyRole"}},
+                {"nodeType": "ModifierInvocation", "modifierName": {"nodeType": "Identifier", "name": "whenNotPaused"}},
+                {"nodeType": "ModifierInvocation", "kind": "baseConstructorSpecifier", "modifierName": {"nodeType": "IdentifierPath", "name": "ERC20"}}
+            ]
+        });
+        let names: Vec<_> = modifier_invocations(&function)
+            .into_iter()
+            .filter_map(|m| m.pointer("/modifierName/name")?.as_str())
+            .collect();
+        assert_eq!(names, ["onlyRole", "whenNotPaused"]);
+    }
+}
@@ -0,0 +1,335 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{calls::ExternalCall, functions::ContractFunction, layout::StorageSlot};
+
+/// Words of a function header which are not modifier invocations
+const HEADER_KEYWORDS: [&str; 11] = [
+    "public",
+    "external",
+    "internal",
+    "private",
+    "pure",
+    "view",
+    "payable",
+    "nonpayable",
+    "virtual",
+    "override",
+    "constant",
+];
+
+/// Access control modifiers not named `only...`, e.g. of solmate and
+/// OpenZeppelin's `AccessManaged`
+const ACCESS_MODIFIERS: [&str; 3] = ["auth", "requiresAuth", "restricted"];
+
+/// Names of state variables holding a privileged account, matched against
+/// their lowercase name
+const ROLE_VARIABLES: [&str; 12] = [
+    "owner",
+    "admin",
+    "governance",
+    "governor",
+    "guardian",
+    "operator",
+    "minter",
+    "pauser",
+    "keeper",
+    "manager",
+    "controller",
+    "authorized",
+];
+
+/// Members of token contracts moving tokens
+const TOKEN_TRANSFERS: [&str; 4] = [
+    "transfer",
+    "transferFrom",
+    "safeTransfer",
+    "safeTransferFrom",
+];
+
+/// A function of the ABI of the contract
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EntryPoint {
+    pub signature: String,
+    pub payable: bool,
+    /// Modifier invocations as written, e.g. `onlyRole(MINTER_ROLE)`
+    pub modifiers: Vec<String>,
+}
+
+/// Ether or tokens entering or leaving the contract through an entry point
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ValueFlow {
+    pub signature: String,
+    /// `ether in`, `ether out`, `tokens`, or `call` for low-level calls which
+    /// may send ether
+    pub kind: &'static str,
+    /// The call moving the value, e.g. `IERC20(token).safeTransfer`, empty
+    /// for ether received
+    pub call: String,
+}
+
+/// An account or role allowed to call restricted entry points
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Role {
+    pub name: String,
+    /// The modifier or state variable the role is derived from
+    pub source: String,
+    /// Signatures of the entry points restricted to the role
+    pub functions: Vec<String>,
+}
+
+/// An orientation on a contract assembled from its indexed functions,
+/// external calls and storage slots: what can be called, where value flows
+/// and who is privileged
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Overview {
+    pub entry_points: Vec<EntryPoint>,
+    pub value_flows: Vec<ValueFlow>,
+    pub roles: Vec<Role>,
+}
+
+/// Modifier invocations and the `payable` keyword of the header of a function
+/// definition, `None` for the declaration of a public state variable
+fn parse_header(source: &str) -> Option<(Vec<String>, bool)> {
+    let source = source.trim_start();
+    if !source.starts_with("function") {
+        return None;
+    }
+    // skip the parameter list, which may have nested parentheses
+    let start = source.find('(')?;
+    let mut depth = 0;
+    let mut end = start;
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            end = start + i + 1;
+            break;
+        }
+    }
+    let rest = &source[end..];
+    let rest = &rest[..rest.find(['{', ';']).unwrap_or(rest.len())];
+    let returns = Regex::new(r"\breturns\b").unwrap();
+    let rest = &rest[..returns.find(rest).map_or(rest.len(), |m| m.start())];
+
+    let word = Regex::new(r"([A-Za-z_$][\w$.]*)\s*(\([^)]*\))?").unwrap();
+    let mut modifiers = vec![];
+    let mut payable = false;
+    for captures in word.captures_iter(rest) {
+        match &captures[1] {
+            "payable" => payable = true,
+            name if HEADER_KEYWORDS.contains(&name) => {}
+            _ => modifiers.push(captures[0].split_whitespace().collect::<String>()),
+        }
+    }
+    Some((modifiers, payable))
+}
+
+/// The role a modifier restricts to, e.g. `owner` for `onlyOwner` and
+/// `MINTER_ROLE` for `onlyRole(MINTER_ROLE)`
+fn modifier_role(modifier: &str) -> Option<String> {
+    let (name, arguments) = match modifier.split_once('(') {
+        Some((name, arguments)) => (name, arguments.trim_end_matches(')')),
+        None => (modifier, ""),
+    };
+    if name == "onlyRole" && !arguments.is_empty() {
+        return Some(arguments.to_string());
+    }
+    if let Some(role) = name.strip_prefix("only").filter(|r| !r.is_empty()) {
+        let mut chars = role.chars();
+        let first = chars.next()?.to_lowercase();
+        return Some(first.chain(chars).collect());
+    }
+    ACCESS_MODIFIERS.contains(&name).then(|| name.to_string())
+}
+
+impl Overview {
+    /// The overview of the contract `contract_name`, from the indexed rows of
+    /// its compilation. Without functions of that name, e.g. for a mislabeled
+    /// contract, all functions are entry points.
+    pub fn new(
+        contract_name: &str,
+        functions: &[ContractFunction],
+        calls: &[ExternalCall],
+        slots: &[StorageSlot],
+    ) -> Self {
+        let mut entry_points = functions
+            .iter()
+            .filter(|f| f.contract_name == contract_name)
+            .collect::<Vec<_>>();
+        if entry_points.is_empty() {
+            entry_points = functions.iter().collect();
+        }
+
+        let mut overview = Overview::default();
+        let mut roles: BTreeMap<String, Role> = BTreeMap::new();
+        for function in entry_points {
+            let (modifiers, payable) = parse_header(&function.source_code).unwrap_or_default();
+            for modifier in modifiers.iter() {
+                let Some(name) = modifier_role(modifier) else {
+                    continue;
+                };
+                roles
+                    .entry(name.clone())
+                    .or_insert_with(|| Role {
+                        name,
+                        source: format!("modifier {modifier}"),
+                        functions: vec![],
+                    })
+                    .functions
+                    .push(function.signature.clone());
+            }
+            if payable {
+                overview.value_flows.push(ValueFlow {
+                    signature: function.signature.clone(),
+                    kind: "ether in",
+                    call: String::new(),
+                });
+            }
+            for call in calls.iter().filter(|c| c.function_id == function.id) {
+                let kind = match call.kind.as_str() {
+                    "send" | "transfer" => "ether out",
+                    "call" => "call",
+                    "interface" if TOKEN_TRANSFERS.contains(&call.member.as_str()) => "tokens",
+                    _ => continue,
+                };
+                overview.value_flows.push(ValueFlow {
+                    signature: function.signature.clone(),
+                    kind,
+                    call: format!("{}.{}", call.target, call.member),
+                });
+            }
+            overview.entry_points.push(EntryPoint {
+                signature: function.signature.clone(),
+                payable,
+                modifiers,
+            });
+        }
+
+        for slot in slots.iter().filter(|s| s.contract_name == contract_name) {
+            let label = slot.label.to_lowercase();
+            let holds_account = matches!(
+                slot.ty.as_str(),
+                "address" | "address payable" | "mapping(address => bool)"
+            );
+            if !holds_account || !ROLE_VARIABLES.iter().any(|r| label.contains(r)) {
+                continue;
+            }
+            let name = slot.label.trim_start_matches('_').to_string();
+            roles.entry(name.clone()).or_insert_with(|| Role {
+                name,
+                source: format!("state variable {} {}", slot.ty, slot.label),
+                functions: vec![],
+            });
+        }
+        overview.roles = roles.into_values().collect();
+        overview
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn overview_of_vault() {
+        let function = |id: &str, signature: &str, source_code: &str| ContractFunction {
+            id: id.into(),
+            contract_id: "a".into(),
+            contract_name: "Vault".into(),
+            function_name: signature.split('(').next().unwrap_or_default().into(),
+            filename: "Vault.sol".into(),
+            signature: signature.into(),
+            selector: "".into(),
+            source_code: source_code.into(),
+        };
+        let functions = [
+            function(
+                "1",
+                "deposit()",
+                "function deposit() external payable nonReentrant {",
+            ),
+            function(
+                "2",
+                "withdraw(address,uint256)",
+                "function withdraw(address to, uint256 amount) external onlyOwner returns (bool) {",
+            ),
+            function(
+                "3",
+                "mint(address,(uint256,uint256))",
+                "function mint(address to, Amount memory amount)\n        public\n        override(IVault)\n        onlyRole(MINTER_ROLE)\n    {",
+            ),
+            function("4", "owner()", "address public owner;"),
+        ];
+        let call = |function_id: &str, kind: &str, target: &str, member: &str| ExternalCall {
+            function_id: function_id.into(),
+            contract_id: "a".into(),
+            position: 0,
+            kind: kind.into(),
+            target: target.into(),
+            target_kind: "parameter".into(),
+            member: member.into(),
+        };
+        let calls = [
+            call("2", "call", "to", "call"),
+            call("3", "interface", "IERC20(token)", "safeTransfer"),
+            call("3", "interface", "oracle", "latestAnswer"),
+        ];
+        let slot = |label: &str, ty: &str| StorageSlot {
+            contract_id: "a".into(),
+            source_path: "main.sol".into(),
+            contract_name: "Vault".into(),
+            label: label.into(),
+            slot: "0".into(),
+            offset: 0,
+            ty: ty.into(),
+            type_id: format!("t_{ty}"),
+            encoding: "inplace".into(),
+        };
+        let slots = [
+            slot("owner", "address"),
+            slot("_guardian", "address"),
+            slot("totalShares", "uint256"),
+        ];
+
+        let overview = Overview::new("Vault", &functions, &calls, &slots);
+        assert_eq!(
+            overview.entry_points[0],
+            EntryPoint {
+                signature: "deposit()".into(),
+                payable: true,
+                modifiers: vec!["nonReentrant".into()],
+            }
+        );
+        assert_eq!(
+            overview.entry_points[2].modifiers,
+            ["onlyRole(MINTER_ROLE)"]
+        );
+        assert!(overview.entry_points[3].modifiers.is_empty());
+        assert_eq!(
+            overview
+                .value_flows
+                .iter()
+                .map(|f| (f.kind, f.call.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("ether in", ""),
+                ("call", "to.call"),
+                ("tokens", "IERC20(token).safeTransfer")
+            ]
+        );
+        assert_eq!(overview.roles[2].source, "modifier onlyOwner");
+        assert_eq!(
+            overview
+                .roles
+                .iter()
+                .map(|r| (r.name.as_str(), r.functions.len()))
+                .collect::<Vec<_>>(),
+            [("MINTER_ROLE", 1), ("guardian", 0), ("owner", 1)]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use eyre::{eyre, ContextCompat, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    plain_contract::{ContractSource, Metadata, PlainContract, SourceFile},
+    pragma::{minimum_version, solidity_pragmas},
+    utils::HttpFetcher,
+};
+
+/// jsDelivr API listing the versions and files of npm packages and GitHub repositories
+pub const DEFAULT_PACKAGE_DATA_URL: &str = "https://data.jsdelivr.com/v1/packages";
+/// jsDelivr CDN serving the files listed by the data API
+pub const DEFAULT_PACKAGE_CDN_URL: &str = "https://cdn.jsdelivr.net";
+
+/// Where a library is published
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Registry {
+    Npm,
+    GitHub,
+}
+
+impl Registry {
+    /// Name of the registry in the jsDelivr urls
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Registry::Npm => "npm",
+            Registry::GitHub => "gh",
+        }
+    }
+}
+
+/// A package and optionally one of its versions, e.g. `@openzeppelin/contracts@4.9.3`
+/// or `transmissions11/solmate@v7`
+#[derive(Debug, PartialEq)]
+pub struct PackageSpec {
+    pub registry: Registry,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl PackageSpec {
+    pub fn parse(registry: Registry, spec: &str) -> Result<Self> {
+        // the `@` of a scoped npm package is not a version separator
+        let (name, version) = match spec.rfind('@') {
+            Some(i) if i > 0 => (&spec[..i], Some(spec[i + 1..].to_string())),
+            _ => (spec, None),
+        };
+        if name.is_empty() || version.as_deref() == Some("") {
+            return Err(eyre!("Invalid package {spec}"));
+        }
+        if registry == Registry::GitHub && name.split('/').count() != 2 {
+            return Err(eyre!("Expected owner/repository, got {name}"));
+        }
+        Ok(Self {
+            registry,
+            name: name.into(),
+            version,
+        })
+    }
+
+    /// Prefix of the source paths of the package as they are imported, the
+    /// package name on npm and the repository name on GitHub
+    fn import_prefix(&self) -> &str {
+        match self.registry {
+            Registry::Npm => &self.name,
+            Registry::GitHub => self.name.rsplit('/').next().unwrap_or(&self.name),
+        }
+    }
+
+    /// A path of the package as it is imported. GitHub repositories are
+    /// imported through the remapping of foundry, `solmate/=lib/solmate/src/`,
+    /// so their `src` folder is left out.
+    fn import_path(&self, path: &str) -> String {
+        let path = path.trim_start_matches('/');
+        let path = match self.registry {
+            Registry::Npm => path,
+            Registry::GitHub => path.strip_prefix("src/").unwrap_or(path),
+        };
+        format!("{}/{path}", self.import_prefix())
+    }
+}
+
+/// A stored release of a library, the contract holds all its sources
+#[derive(Debug, PartialEq)]
+pub struct PackageRelease {
+    pub contract_id: String,
+    /// `npm` or `gh`
+    pub registry: String,
+    pub package: String,
+    pub version: String,
+}
+
+#[derive(Deserialize)]
+struct PackageVersions {
+    versions: Vec<PackageVersion>,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct PackageFiles {
+    files: Vec<PackageFile>,
+}
+
+#[derive(Deserialize)]
+struct PackageFile {
+    /// Path in the package starting with `/`
+    name: String,
+}
+
+/// Whether a file of a package is a library source, tests and scripts of
+/// GitHub repositories, also nested ones like `src/test`, and their
+/// dependencies in `lib`, are left out
+fn is_library_source(path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    let mut folders = path.split('/').rev().skip(1);
+    path.ends_with(".sol")
+        && !path.ends_with(".t.sol")
+        && !path.ends_with(".s.sol")
+        && !folders
+            .any(|folder| ["test", "tests", "script", "lib", "node_modules"].contains(&folder))
+}
+
+/// The release as a standard json contract named after the package, whose
+/// sources are named as they are imported, e.g.
+/// `@openzeppelin/contracts/token/ERC20/ERC20.sol`. The compiler is the lowest
+/// version allowed by the pragmas of all sources.
+pub fn release_contract(
+    spec: &PackageSpec,
+    version: &str,
+    sources: BTreeMap<String, String>,
+) -> Result<PlainContract> {
+    let pragmas: Vec<String> = sources.values().flat_map(|c| solidity_pragmas(c)).collect();
+    let compiler_version = minimum_version(&pragmas)?
+        .with_context(|| format!("No solidity pragma in {}@{version}", spec.name))?;
+    let input = serde_json::json!({
+        "language": "Solidity",
+        "sources": sources
+            .iter()
+            .map(|(path, content)| {
+                (spec.import_path(path), serde_json::json!({ "content": content }))
+            })
+            .collect::<serde_json::Map<_, _>>(),
+        "settings": {"optimizer": {"enabled": false, "runs": 200}},
+    });
+
+    let metadata = Metadata {
+        contract_name: spec.name.clone(),
+        compiler_version: compiler_version.to_string(),
+        runs: 200,
+        optimization_used: false,
+        bytecode_hash: "".into(),
+        constructor_arguments: None,
+        chain_id: None,
+        address: None,
+        evm_version: None,
+        via_ir: None,
+    };
+    let source = ContractSource::Json(SourceFile {
+        name: "contract.json".into(),
+        content: serde_json::to_string(&input)?,
+    });
+    Ok(PlainContract::new(metadata, source))
+}
+
+/// Client of the jsDelivr data API and CDN for npm packages and GitHub tags
+pub struct PackageClient<'a> {
+    fetcher: &'a HttpFetcher,
+    data_url: String,
+    cdn_url: String,
+}
+
+impl<'a> PackageClient<'a> {
+    pub fn new(fetcher: &'a HttpFetcher, data_url: &str, cdn_url: &str) -> Self {
+        Self {
+            fetcher,
+            data_url: data_url.trim_end_matches('/').into(),
+            cdn_url: cdn_url.trim_end_matches('/').into(),
+        }
+    }
+
+    /// Published versions of the package, newest first
+    pub async fn versions(&self, spec: &PackageSpec) -> Result<Vec<String>> {
+        let url = format!("{}/{}/{}", self.data_url, spec.registry.as_str(), spec.name);
+        let versions: PackageVersions =
+            serde_json::from_str(&self.fetcher.get_text(&url).await?)
+                .map_err(|e| eyre!("Invalid versions of {}: {e}", spec.name))?;
+        Ok(versions.versions.into_iter().map(|v| v.version).collect())
+    }
+
+    /// Solidity sources of a version of the package by their path in the package
+    pub async fn sources(
+        &self,
+        spec: &PackageSpec,
+        version: &str,
+    ) -> Result<BTreeMap<String, String>> {
+        let (registry, name) = (spec.registry.as_str(), &spec.name);
+        let url = format!(
+            "{}/{registry}/{name}@{version}?structure=flat",
+            self.data_url
+        );
+        let listed: PackageFiles = serde_json::from_str(&self.fetcher.get_text(&url).await?)
+            .map_err(|e| eyre!("Invalid files of {name}@{version}: {e}"))?;
+
+        let mut sources = BTreeMap::new();
+        for file in listed.files.iter().filter(|f| is_library_source(&f.name)) {
+            let url = format!("{}/{registry}/{name}@{version}{}", self.cdn_url, file.name);
+            sources.insert(file.name.clone(), self.fetcher.get_text(&url).await?);
+        }
+        Ok(sources)
+    }
+
+    /// The release of the package as a contract with its version
+    pub async fn release(
+        &self,
+        spec: &PackageSpec,
+        version: &str,
+    ) -> Result<(PlainContract, PackageRelease)> {
+        let sources = self.sources(spec, version).await?;
+        if sources.is_empty() {
+            return Err(eyre!("No solidity sources in {}@{version}", spec.name));
+        }
+        let contract = release_contract(spec, version, sources)?;
+        let release = PackageRelease {
+            contract_id: contract.id(),
+            registry: spec.registry.as_str().into(),
+            package: spec.name.clone(),
+            version: version.into(),
+        };
+        Ok((contract, release))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plain_contract::StandardJson;
+
+    #[test]
+    fn release_as_standard_json() -> Result<()> {
+        let spec = PackageSpec::parse(Registry::Npm, "@openzeppelin/contracts@4.9.3")?;
+        assert_eq!(spec.name, "@openzeppelin/contracts");
+        assert_eq!(spec.version.as_deref(), Some("4.9.3"));
+        assert_eq!(
+            PackageSpec::parse(Registry::GitHub, "transmissions11/solmate")?.version,
+            None
+        );
+        assert!(PackageSpec::parse(Registry::GitHub, "solmate@v7").is_err());
+
+        assert!(is_library_source("/token/ERC20/ERC20.sol"));
+        assert!(!is_library_source("/src/test/ERC20.t.sol"));
+        assert!(!is_library_source("/src/test/utils/mocks/MockERC20.sol"));
+        assert!(is_library_source("/src/tokens/ERC20.sol"));
+        assert!(!is_library_source("/lib/forge-std/src/Test.sol"));
+        assert!(!is_library_source("/package.json"));
+
+        let sources = BTreeMap::from([
+            (
+                "/token/ERC20/ERC20.sol".to_string(),
+                "pragma solidity ^0.8.0;\nimport \"../../utils/Context.sol\";".to_string(),
+            ),
+            (
+                "/utils/Context.sol".to_string(),
+                "pragma solidity ^0.8.1;".to_string(),
+            ),
+        ]);
+        let contract = release_contract(&spec, "4.9.3", sources)?;
+        assert_eq!(contract.metadata.contract_name, "@openzeppelin/contracts");
+        assert_eq!(contract.metadata.compiler_version, "0.8.1");
+        let ContractSource::Json(source) = &contract.source else {
+            panic!("Expected a standard json contract");
+        };
+        let input: StandardJson = serde_json::from_str(&source.content)?;
+        assert!(input
+            .sources
+            .contains_key("@openzeppelin/contracts/utils/Context.sol"));
+
+        let solmate = PackageSpec::parse(Registry::GitHub, "transmissions11/solmate@v7")?;
+        let sources = BTreeMap::from([(
+            "/src/tokens/ERC20.sol".to_string(),
+            "pragma solidity >=0.8.0;".to_string(),
+        )]);
+        let contract = release_contract(&solmate, "v7", sources)?;
+        assert_eq!(contract.metadata.contract_name, "transmissions11/solmate");
+        let ContractSource::Json(source) = &contract.source else {
+            panic!("Expected a standard json contract");
+        };
+        let input: StandardJson = serde_json::from_str(&source.content)?;
+        assert!(input.sources.contains_key("solmate/tokens/ERC20.sol"));
+
+        Ok(())
+    }
+}
@@ -1,24 +1,123 @@
+use alloy_dyn_abi::{DynSolType, JsonAbiExt};
+use alloy_primitives::{hex, keccak256};
 use duckdb::ToSql;
-use eyre::{ContextCompat, Result};
+use eyre::{ContextCompat, Result, WrapErr};
 use foundry_compilers::{
-    artifacts::{Node, NodeType::*, Settings},
-    multi::{MultiCompiler, MultiCompilerSettings},
+    artifacts::{
+        output_selection::{ContractOutputSelection, EvmOutputSelection},
+        ConfigurableContractArtifact, EvmVersion, Node, NodeType,
+        NodeType::*,
+        Settings,
+    },
+    contracts::VersionedContract,
+    multi::{MultiCompiler, MultiCompilerError, MultiCompilerSettings},
     solc::{Solc, SolcCompiler},
-    Project, ProjectCompileOutput, ProjectPathsConfig,
+    sources::VersionedSourceFile,
+    ArtifactFile, ArtifactOutput, Artifacts, ConfigurableArtifacts, Project, ProjectCompileOutput,
+    ProjectPathsConfig,
 };
 
+use glob::Pattern;
 use itertools::Itertools;
+use log::{debug, warn};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Display,
+    io::{Cursor, Write},
     path::{Component, Path, PathBuf},
 };
 use tokio::fs::{self, create_dir_all};
 use tokio_stream::{wrappers::ReadDirStream, StreamExt};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    abi::{decode_hex, format_value},
+    artifact_cache::ArtifactCache,
+    compiler_version::solc_version,
+    constructor::{ConstructorArgument, ImmutableValue},
+    functions::ContractFunction,
+    literals::is_address,
+    pragma::pragma_version,
+    utils::{find_solc, is_offline, simple_hash},
+    vyper::{vyper_function_source, vyper_path, vyper_version},
+    workspace,
+};
+
+/// Compiler outputs requested in addition to the default ABI and bytecode
+const EXTRA_OUTPUT: [ContractOutputSelection; 2] = [
+    ContractOutputSelection::StorageLayout,
+    ContractOutputSelection::Metadata,
+];
+
+/// The EVM version solc compiles for when none is selected, `None` before
+/// 0.4.21 which has no EVM version setting. Compiling old contracts for the
+/// latest EVM version they support gives a different bytecode, or fails.
+/// Also `None` from 0.8.30, whose default Prague has no [`EvmVersion`] yet,
+/// so that solc picks it.
+pub fn default_evm_version(version: &Version) -> Option<EvmVersion> {
+    let at_least = |minor, patch| *version >= Version::new(0, minor, patch);
+    let evm_version = if at_least(8, 30) {
+        return None;
+    } else if at_least(8, 25) {
+        EvmVersion::Cancun
+    } else if at_least(8, 20) {
+        EvmVersion::Shanghai
+    } else if at_least(8, 18) {
+        EvmVersion::Paris
+    } else if at_least(8, 7) {
+        EvmVersion::London
+    } else if at_least(8, 5) {
+        EvmVersion::Berlin
+    } else if at_least(5, 14) {
+        EvmVersion::Istanbul
+    } else if at_least(5, 5) {
+        EvmVersion::Petersburg
+    } else if at_least(4, 21) {
+        EvmVersion::Byzantium
+    } else {
+        return None;
+    };
+    Some(evm_version)
+}
 
-use crate::{functions::ContractFunction, utils::simple_hash};
+/// The other patch releases of the minor series of `version`, nearest first
+/// and the later one first at the same distance
+pub fn nearest_patch_versions(version: &Version, available: &[Version]) -> Vec<Version> {
+    available
+        .iter()
+        .filter(|v| v.major == version.major && v.minor == version.minor)
+        .filter(|v| v.patch != version.patch && v.pre.is_empty())
+        .map(|v| Version::new(v.major, v.minor, v.patch))
+        .unique()
+        .sorted_by_key(|v| (v.patch.abs_diff(version.patch), u64::MAX - v.patch))
+        .collect()
+}
+
+/// The versions tried after the recorded one failed: up to `attempts` of its
+/// [`nearest_patch_versions`], then the version inferred from the pragmas,
+/// which is tried even when `attempts` is 0
+pub fn fallback_versions(
+    recorded: Option<&Version>,
+    inferred: Option<Version>,
+    available: &[Version],
+    attempts: usize,
+) -> Vec<Version> {
+    let mut versions: Vec<_> = match recorded {
+        Some(version) => nearest_patch_versions(version, available)
+            .into_iter()
+            .take(attempts)
+            .collect(),
+        None => vec![],
+    };
+    if let Some(inferred) = inferred {
+        if recorded != Some(&inferred) && !versions.contains(&inferred) {
+            versions.push(inferred);
+        }
+    }
+    versions
+}
 
 /// Metadata of a contract
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -33,6 +132,31 @@ pub struct Metadata {
     pub optimization_used: bool,
     #[serde(rename = "BytecodeHash")]
     pub bytecode_hash: String,
+    /// ABI encoded constructor arguments used at deployment, hex encoded
+    #[serde(
+        rename = "ConstructorArguments",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub constructor_arguments: Option<String>,
+    /// Chain the contract was fetched from, when known
+    #[serde(rename = "ChainId", default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Lowercase `0x` prefixed address the contract is deployed at, when known
+    #[serde(rename = "Address", default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// EVM version the contract was compiled for, e.g. `istanbul`, the
+    /// default of its compiler version when missing
+    #[serde(
+        rename = "EVMVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub evm_version: Option<String>,
+    /// Whether the contract was compiled through the IR pipeline, as set in
+    /// the standard json input when missing
+    #[serde(rename = "ViaIR", default, skip_serializing_if = "Option::is_none")]
+    pub via_ir: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -41,12 +165,49 @@ pub struct EtherscanRawJson {
     pub source_code: String,
     #[serde(rename = "OptimizationUsed")]
     pub optimization_used: String,
-    #[serde(rename = "Runs")]
+    /// Blockscout returns the runs as `OptimizationRuns`, possibly as a number
+    #[serde(
+        rename = "Runs",
+        alias = "OptimizationRuns",
+        default,
+        deserialize_with = "string_or_number"
+    )]
     pub runs: String,
     #[serde(rename = "ContractName")]
     pub contract_name: String,
     #[serde(rename = "CompilerVersion")]
     pub compiler_version: String,
+    #[serde(rename = "ConstructorArguments", default)]
+    pub constructor_arguments: String,
+    /// Path of the main source file, only returned by Blockscout
+    #[serde(rename = "FileName", default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// The other source files of a multi-file contract, only returned by Blockscout
+    #[serde(
+        rename = "AdditionalSources",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub additional_sources: Vec<AdditionalSource>,
+    /// `Default` unless another EVM version was selected at verification
+    #[serde(rename = "EVMVersion", default)]
+    pub evm_version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdditionalSource {
+    #[serde(rename = "Filename")]
+    pub filename: String,
+    #[serde(rename = "SourceCode")]
+    pub source_code: String,
+}
+
+fn string_or_number<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => "".into(),
+        value => value.to_string(),
+    })
 }
 
 impl EtherscanRawJson {
@@ -55,21 +216,119 @@ impl EtherscanRawJson {
             contract_name: self.contract_name.clone(),
             compiler_version: self.compiler_version.clone(),
             runs: self.runs.parse().unwrap_or(0),
-            optimization_used: self.optimization_used == "1",
+            optimization_used: self.optimization_used == "1" || self.optimization_used == "true",
             bytecode_hash: "".into(),
+            constructor_arguments: Some(self.constructor_arguments.clone())
+                .filter(|args| !args.is_empty()),
+            chain_id: None,
+            address: None,
+            evm_version: Some(self.evm_version.clone())
+                .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("default")),
+            via_ir: None,
         }
     }
 }
 
+/// The solidity metadata json stored by Sourcify, only the fields needed to
+/// rebuild the contract
+#[derive(Debug, Deserialize)]
+struct SourcifyMetadata {
+    compiler: SourcifyCompiler,
+    #[serde(default)]
+    language: String,
+    settings: SourcifySettings,
+    sources: BTreeMap<String, SourcifySource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifyCompiler {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifySettings {
+    /// Source file and name of the verified contract
+    #[serde(rename = "compilationTarget")]
+    compilation_target: BTreeMap<String, String>,
+    optimizer: Option<SourcifyOptimizer>,
+    #[serde(rename = "evmVersion")]
+    evm_version: Option<String>,
+    #[serde(rename = "viaIR")]
+    via_ir: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SourcifyOptimizer {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub runs: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifySource {
+    content: Option<String>,
+}
+
+/// Build info written by Hardhat to `artifacts/build-info` and by Foundry to
+/// `out/build-info`, only the fields needed to rebuild the contract
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildInfo {
+    solc_version: Option<String>,
+    solc_long_version: Option<String>,
+    /// The standard json input of the compilation
+    input: serde_json::Value,
+    #[serde(default)]
+    output: BuildInfoOutput,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BuildInfoOutput {
+    /// Compiled contracts by source file and contract name
+    #[serde(default)]
+    contracts: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+impl BuildInfo {
+    /// The contract with the largest runtime bytecode, or the first source
+    /// file when the output is not recorded
+    fn main_contract(&self) -> Option<String> {
+        let largest = self
+            .output
+            .contracts
+            .values()
+            .flat_map(|contracts| contracts.iter())
+            .max_by_key(|(name, contract)| {
+                let code = contract["evm"]["deployedBytecode"]["object"].as_str();
+                (code.map(str::len).unwrap_or_default(), name.to_string())
+            })
+            .map(|(name, _)| name.clone());
+        largest.or_else(|| {
+            let sources = self.input["sources"].as_object()?;
+            let first = Path::new(sources.keys().next()?).file_stem()?;
+            Some(first.to_string_lossy().into_owned())
+        })
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SourceCodeEntry {
     pub content: String,
 }
 
+/// Standard json input passed to solc as is
+#[derive(Serialize)]
+struct StandardJsonInput {
+    language: &'static str,
+    sources: BTreeMap<String, SourceCodeEntry>,
+    settings: Settings,
+}
+
 /// Standard json input file
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StandardJson {
-    pub langauge: Option<String>,
+    pub language: Option<String>,
     pub name: Option<String>,
     pub sources: HashMap<String, SourceCodeEntry>,
     pub settings: Option<Settings>,
@@ -128,6 +387,26 @@ impl ToSql for ContractSourceType {
     }
 }
 
+/// The language of a source file by its extension, `solidity`, `vyper` or
+/// `yul`, else `default`
+pub fn file_language(name: &str, default: &'static str) -> &'static str {
+    match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some("sol") => "solidity",
+        Some("vy" | "vyi") => "vyper",
+        Some("yul") => "yul",
+        _ => default,
+    }
+}
+
+/// The extension of the files of a language, see [`file_language`]
+fn language_extension(language: &str) -> &'static str {
+    match language {
+        "vyper" => "vy",
+        "yul" => "yul",
+        _ => "sol",
+    }
+}
+
 /// A contract with metadata and source code
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlainContract {
@@ -137,32 +416,29 @@ pub struct PlainContract {
     pub compilation_output: Option<ProjectCompileOutput>,
     #[serde(skip)]
     pub source_files: Option<Vec<SourceFile>>,
+    /// Also request the gas estimates of solc, slower so off by default
+    #[serde(skip)]
+    pub estimate_gas: bool,
 }
 
-async fn source_from_multi_source_contract(path: &str) -> Result<ContractSource> {
-    // list all solidity files in the folder
-    let folder = fs::read_dir(path).await?;
-    let mut entries = ReadDirStream::new(folder);
-
-    let mut sources = Vec::new();
-    while let Some(entry) = entries.next().await {
-        match entry {
-            Ok(entry) => {
-                let path = entry.path();
-                if path.extension().map_or(false, |ext| ext == "sol") {
-                    sources.push(SourceFile {
-                        name: entry.file_name().to_string_lossy().into_owned(),
-                        content: fs::read_to_string(path).await?,
-                    });
-                }
-            }
-            Err(e) => eprintln!("Error reading directory entry: {}", e),
-        }
-    }
-    Ok(ContractSource::MultiSolidity(sources))
+/// Whether the file belongs to a contract folder of the metadata layout
+pub fn is_contract_file(name: &str) -> bool {
+    name == "metadata.json"
+        || name == "contract.json"
+        || name.ends_with(".sol")
+        || name.ends_with(".vy")
 }
 
 impl ContractSource {
+    pub fn source_type(&self) -> ContractSourceType {
+        match self {
+            ContractSource::SingleSolidity(_) => ContractSourceType::SingleSolidity,
+            ContractSource::MultiSolidity(_) => ContractSourceType::MultiSolidity,
+            ContractSource::Vyper(_) => ContractSourceType::Vyper,
+            ContractSource::Json(_) => ContractSourceType::Json,
+        }
+    }
+
     pub fn hash(&self) -> String {
         match self {
             ContractSource::SingleSolidity(source) => simple_hash(&source.content),
@@ -180,6 +456,20 @@ impl ContractSource {
         }
     }
 
+    /// Identity of the sources as referenced by the solc metadata, which has
+    /// the keccak256 of every source file: the keccak256 of the sorted source
+    /// hashes, `0x` prefixed. Unlike [`Self::hash`], whitespace is significant.
+    pub fn keccak_hash(&self) -> Result<String> {
+        let hashes: Vec<u8> = self
+            .get_source_files()?
+            .iter()
+            .map(|source| keccak256(source.content.as_bytes()))
+            .sorted()
+            .flat_map(|hash| hash.0)
+            .collect();
+        Ok(keccak256(hashes).to_string())
+    }
+
     fn get_source_files(&self) -> Result<Vec<SourceFile>> {
         match self {
             ContractSource::SingleSolidity(source) => Ok(vec![source.clone()]),
@@ -201,26 +491,88 @@ impl ContractSource {
         }
     }
 
-    async fn write_entries(dir: &Path, entries: &Vec<&SourceFile>) -> Result<()> {
-        create_dir_all(dir).await?;
-        for entry in entries {
-            let mut sanitized_path = sanitize_path(&entry.name);
-            if sanitized_path.extension().is_none() {
-                let with_extension = sanitized_path.with_extension("sol");
-                if !entries
-                    .iter()
-                    .any(|e| PathBuf::from(e.name.clone()) == with_extension)
-                {
-                    sanitized_path = with_extension;
+    /// The language of the source files without a known extension: the
+    /// language of a standard json, else of the source type
+    pub fn default_language(&self) -> Result<&'static str> {
+        let language = match self {
+            ContractSource::Vyper(_) => "vyper",
+            ContractSource::Json(source) => {
+                let json: StandardJson = serde_json::from_str(&source.content)?;
+                match json.language.unwrap_or_default().to_lowercase().as_str() {
+                    "vyper" => "vyper",
+                    "yul" => "yul",
+                    _ => "solidity",
                 }
             }
+            _ => "solidity",
+        };
+        Ok(language)
+    }
+
+    /// Number of source files by language, see [`file_language`]. Standard
+    /// json inputs may mix them, e.g. vyper contracts with solidity interfaces.
+    pub fn languages(&self) -> Result<BTreeMap<&'static str, usize>> {
+        let default = self.default_language()?;
+        Ok(self
+            .get_source_files()?
+            .iter()
+            .map(|f| file_language(&f.name, default))
+            .counts()
+            .into_iter()
+            .collect())
+    }
+
+    /// Whether the sources are compiled with vyper, i.e. contain vyper files
+    pub fn is_vyper(&self) -> bool {
+        self.languages()
+            .is_ok_and(|languages| languages.contains_key("vyper"))
+    }
+
+    /// The sanitized relative path of every entry, entries without extension
+    /// get the extension of `language` unless that clashes with another entry,
+    /// so that they are compiled by the compiler of the language
+    fn entry_paths<'a>(
+        entries: &[&'a SourceFile],
+        language: &str,
+    ) -> Vec<(PathBuf, &'a SourceFile)> {
+        entries
+            .iter()
+            .map(|entry| {
+                let mut sanitized_path = sanitize_path(&entry.name);
+                if sanitized_path.extension().is_none() {
+                    let with_extension =
+                        sanitized_path.with_extension(language_extension(language));
+                    if !entries.iter().any(|e| Path::new(&e.name) == with_extension) {
+                        sanitized_path = with_extension;
+                    }
+                }
+                (sanitized_path, *entry)
+            })
+            .collect()
+    }
+
+    /// Write the source files below `dir`, returns the written paths
+    async fn write_entries(
+        dir: &Path,
+        entries: &Vec<&SourceFile>,
+        language: &str,
+    ) -> Result<Vec<PathBuf>> {
+        Self::write_paths(dir, &Self::entry_paths(entries, language)).await
+    }
+
+    /// Write the source files to their relative paths below `dir`
+    async fn write_paths(dir: &Path, entries: &[(PathBuf, &SourceFile)]) -> Result<Vec<PathBuf>> {
+        create_dir_all(dir).await?;
+        let mut written = Vec::with_capacity(entries.len());
+        for (sanitized_path, entry) in entries {
             let joined = dir.join(sanitized_path);
             if let Some(parent) = joined.parent() {
                 create_dir_all(parent).await?;
-                fs::write(joined, &entry.content).await?;
+                fs::write(&joined, &entry.content).await?;
+                written.push(joined);
             }
         }
-        Ok(())
+        Ok(written)
     }
 }
 
@@ -239,6 +591,48 @@ pub(crate) fn sanitize_path(path: impl AsRef<Path>) -> PathBuf {
         .unwrap_or(sanitized)
 }
 
+/// Chain id and address of a Sourcify folder, from its path
+/// `.../<chain id>/<address>`
+fn sourcify_deployment(root: &Path) -> (Option<u64>, Option<String>) {
+    let address = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .filter(|name| is_address(name));
+    let chain_id = root
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_string_lossy().parse().ok());
+    match address {
+        Some(address) => (chain_id, Some(address)),
+        None => (None, None),
+    }
+}
+
+/// Address a contract of the Etherscan layout is deployed at, from its file
+/// name `<address>_<contract name>.json`
+pub fn etherscan_file_address(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy().to_lowercase();
+    let address = stem.split('_').next()?;
+    is_address(address).then(|| address.to_string())
+}
+
+/// Recursively collect all AST nodes of the given type
+pub(crate) fn collect_nodes<'a>(
+    nodes: &'a [Node],
+    node_type: &NodeType,
+    found: &mut Vec<&'a Node>,
+) {
+    for node in nodes {
+        if node.node_type == *node_type {
+            found.push(node);
+        }
+        collect_nodes(&node.nodes, node_type, found);
+        if let Some(body) = &node.body {
+            collect_nodes(std::slice::from_ref(body.as_ref()), node_type, found);
+        }
+    }
+}
+
 impl PlainContract {
     pub fn hash(&self) -> String {
         self.source.hash()
@@ -248,13 +642,37 @@ impl PlainContract {
         self.hash()
     }
 
+    /// See [`ContractSource::keccak_hash`]
+    pub fn keccak_id(&self) -> Result<String> {
+        self.source.keccak_hash()
+    }
+
     /// Parser a contract from etherscan json
     pub async fn from_etherscan_json(path: &str) -> Result<Self> {
-        let name = "contract.json".into();
         let content = fs::read_to_string(path).await?;
         let outer_json: EtherscanRawJson = serde_json::from_str(&content)?;
+        Ok(Self::from_etherscan_raw(&outer_json))
+    }
+
+    /// Parse a contract from the json returned by the etherscan API or stored
+    /// in etherscan json files
+    pub fn from_etherscan_raw(outer_json: &EtherscanRawJson) -> Self {
+        let name = "contract.json".into();
         let metadata = outer_json.to_metadata();
         let source_code = &outer_json.source_code;
+        if !outer_json.additional_sources.is_empty() {
+            let main = SourceFile {
+                name: outer_json.file_name.clone().unwrap_or("main.sol".into()),
+                content: source_code.clone(),
+            };
+            let others = outer_json.additional_sources.iter().map(|s| SourceFile {
+                name: s.filename.clone(),
+                content: s.source_code.clone(),
+            });
+            let sources = std::iter::once(main).chain(others).collect();
+            return Self::new(metadata, ContractSource::MultiSolidity(sources));
+        }
+
         let source_code = if source_code.starts_with("{{") {
             let len = source_code.len();
             &source_code[1..len - 1]
@@ -268,21 +686,155 @@ impl PlainContract {
                     name,
                     content: source_code.into(),
                 });
-                Ok(Self::new(metadata, source))
+                Self::new(metadata, source)
             }
-            Err(_) => Ok(Self::new(
+            Err(_) => Self::new(
                 metadata,
                 ContractSource::SingleSolidity(SourceFile {
                     name: "main.sol".into(),
                     content: source_code.into(),
                 }),
-            )),
+            ),
+        }
+    }
+
+    /// Parse a contract from a Hardhat or Foundry build info file. The standard
+    /// json input is kept as it is, the main contract is the largest compiled
+    /// contract.
+    pub async fn from_build_info(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).await?;
+        let build_info: BuildInfo = serde_json::from_str(&content)?;
+        if build_info.input["language"].as_str() != Some("Solidity") {
+            return Err(eyre::eyre!("Only solidity build info is supported: {path}"));
         }
+
+        let contract_name = build_info
+            .main_contract()
+            .with_context(|| format!("No contract in {path}"))?;
+        let compiler_version = build_info
+            .solc_long_version
+            .clone()
+            .or(build_info.solc_version.clone())
+            .with_context(|| format!("Missing compiler version in {path}"))?;
+        let optimizer: SourcifyOptimizer =
+            serde_json::from_value(build_info.input["settings"]["optimizer"].clone())
+                .unwrap_or_default();
+        let metadata = Metadata {
+            contract_name,
+            compiler_version,
+            runs: optimizer.runs,
+            optimization_used: optimizer.enabled,
+            bytecode_hash: "".into(),
+            constructor_arguments: None,
+            chain_id: None,
+            address: None,
+            evm_version: None,
+            via_ir: None,
+        };
+        let source = ContractSource::Json(SourceFile {
+            name: "contract.json".into(),
+            content: serde_json::to_string(&build_info.input)?,
+        });
+        Ok(Self::new(metadata, source))
+    }
+
+    /// Parse a contract from a folder of a Sourcify repository, e.g.
+    /// `contracts/full_match/<chain id>/<address>`, containing the solidity
+    /// `metadata.json` and the `sources` tree
+    pub async fn from_sourcify_folder(path: &str) -> Result<Self> {
+        let root = Path::new(path);
+        let content = fs::read_to_string(root.join("metadata.json")).await?;
+        let metadata: SourcifyMetadata = serde_json::from_str(&content)?;
+
+        let mut sources = BTreeMap::new();
+        for name in metadata.sources.keys() {
+            let file = root.join("sources").join(sanitize_path(name));
+            if let Ok(content) = fs::read_to_string(file).await {
+                sources.insert(name.clone(), content);
+            }
+        }
+
+        let (chain_id, address) = sourcify_deployment(root);
+        Self::from_solc_metadata(&content, sources, chain_id, address)
+            .wrap_err_with(|| format!("Invalid Sourcify contract {path}"))
+    }
+
+    /// Build a contract from the solidity metadata json and its source files by
+    /// name, sources missing from `sources` are taken from the `content` of the
+    /// metadata
+    pub fn from_solc_metadata(
+        metadata: &str,
+        mut sources: BTreeMap<String, String>,
+        chain_id: Option<u64>,
+        address: Option<String>,
+    ) -> Result<Self> {
+        let metadata: SourcifyMetadata = serde_json::from_str(metadata)?;
+        let mut files = Vec::with_capacity(metadata.sources.len());
+        for (name, source) in metadata.sources.iter() {
+            let content = match sources.remove(name) {
+                Some(content) => content,
+                None => source
+                    .content
+                    .clone()
+                    .with_context(|| format!("Missing source {name}"))?,
+            };
+            files.push(SourceFile {
+                name: name.clone(),
+                content,
+            });
+        }
+
+        let contract_name = metadata
+            .settings
+            .compilation_target
+            .values()
+            .next()
+            .context("Missing compilation target")?
+            .clone();
+        let optimizer = metadata.settings.optimizer.unwrap_or_default();
+        let contract_metadata = Metadata {
+            contract_name,
+            compiler_version: metadata.compiler.version,
+            runs: optimizer.runs,
+            optimization_used: optimizer.enabled,
+            bytecode_hash: "".into(),
+            constructor_arguments: None,
+            chain_id,
+            address,
+            evm_version: metadata.settings.evm_version,
+            via_ir: metadata.settings.via_ir,
+        };
+
+        let source = match (metadata.language.as_str(), files.len()) {
+            ("Vyper", _) => ContractSource::Vyper(files.remove(0)),
+            (_, 1) => ContractSource::SingleSolidity(files.remove(0)),
+            _ => ContractSource::MultiSolidity(files),
+        };
+        Ok(Self::new(contract_metadata, source))
     }
 
     /// Parse a contract from a folder path
     pub async fn from_folder(path: &str) -> Result<Self> {
-        let metadata = fs::read_to_string(format!("{}/metadata.json", path)).await?;
+        let folder = fs::read_dir(path).await?;
+        let mut entries = ReadDirStream::new(folder);
+
+        let mut files = BTreeMap::new();
+        while let Some(entry) = entries.next().await {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_contract_file(&name) && fs::metadata(entry.path()).await?.is_file() {
+                files.insert(name, fs::read_to_string(entry.path()).await?);
+            }
+        }
+        Self::from_files(files)
+    }
+
+    /// Parse a contract from the files of a contract folder, by file name,
+    /// e.g. read from an archive
+    pub fn from_files(mut files: BTreeMap<String, String>) -> Result<Self> {
+        let metadata = files
+            .remove("metadata.json")
+            .context("Missing metadata.json")?;
         let metadata: Metadata = serde_json::from_str(&metadata)?;
 
         // There are 4 types of contracts:
@@ -290,61 +842,123 @@ impl PlainContract {
         // 2. A single viper file: main.vy
         // 3. A single json file: contract.json
         // 4. A multi-source contract containing multiple solidity files
-        let contract_json = fs::read_to_string(format!("{}/contract.json", path)).await;
-        let solidity_source = fs::read_to_string(format!("{}/main.sol", path)).await;
-        let viper_source = fs::read_to_string(format!("{}/main.vy", path)).await;
-        match (contract_json, solidity_source, viper_source) {
-            (Ok(contract_json), _, _) => {
-                let name = "contract.json".into();
-                let content = contract_json;
-                let source = ContractSource::Json(SourceFile { name, content });
-                Ok(Self::new(metadata, source))
-            }
-            (_, Ok(solidity_source), _) => {
-                let name = "main.sol".into();
-                let content = solidity_source;
-                let source = ContractSource::SingleSolidity(SourceFile { name, content });
-                Ok(Self::new(metadata, source))
-            }
-            (_, _, Ok(viper_source)) => {
-                let name = "main.vy".into();
-                let content = viper_source;
-                let source = ContractSource::Vyper(SourceFile { name, content });
-                Ok(Self::new(metadata, source))
-            }
-            _ => Ok(Self::new(
-                metadata,
-                source_from_multi_source_contract(path).await?,
-            )),
-        }
+        let take = |files: &mut BTreeMap<String, String>, name: &str| {
+            files.remove(name).map(|content| SourceFile {
+                name: name.into(),
+                content,
+            })
+        };
+        let source = if let Some(source) = take(&mut files, "contract.json") {
+            ContractSource::Json(source)
+        } else if let Some(source) = take(&mut files, "main.sol") {
+            ContractSource::SingleSolidity(source)
+        } else if let Some(source) = take(&mut files, "main.vy") {
+            ContractSource::Vyper(source)
+        } else {
+            let sources = files
+                .into_iter()
+                .filter(|(name, _)| name.ends_with(".sol"))
+                .map(|(name, content)| SourceFile { name, content })
+                .collect();
+            ContractSource::MultiSolidity(sources)
+        };
+        Ok(Self::new(metadata, source))
     }
 
     pub fn get_source_files(&self) -> Result<Vec<SourceFile>> {
         self.source.get_source_files()
     }
 
-    /// Compile the contract
+    /// The EVM version to compile for: the recorded one, else the one of the
+    /// standard json, else the default of the solc version instead of the
+    /// latest one it supports
+    pub fn evm_version(
+        &self,
+        json_evm_version: Option<EvmVersion>,
+        solc_version: Option<&Version>,
+    ) -> Option<EvmVersion> {
+        let recorded_evm_version =
+            self.metadata
+                .evm_version
+                .as_deref()
+                .and_then(|v| match v.parse::<EvmVersion>() {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        warn!(
+                            "Ignoring EVM version of {}: {e}",
+                            self.metadata.contract_name
+                        );
+                        None
+                    }
+                });
+        recorded_evm_version
+            .or(json_evm_version)
+            .or_else(|| solc_version.and_then(default_evm_version))
+    }
+
+    /// The compiler outputs requested in addition to the ABI and bytecode
+    fn extra_output(&self) -> Vec<ContractOutputSelection> {
+        let mut extra_output = EXTRA_OUTPUT.to_vec();
+        if self.estimate_gas {
+            extra_output.push(ContractOutputSelection::Evm(
+                EvmOutputSelection::GasEstimates,
+            ));
+        }
+        extra_output
+    }
+
+    /// Compile the contract. Vyper contracts need their compiler installed
+    /// beforehand, see [`crate::vyper::install_vyper`].
     pub async fn compile(&mut self) -> Result<ProjectCompileOutput> {
-        let root = tempfile::tempdir()?;
+        // files are routed to the compiler of their language by extension, a
+        // standard json with vyper files needs the vyper compiler
+        let language = self.source.default_language()?;
+        let has_vyper = self.source.is_vyper();
+        if let ContractSource::Json(source) = &self.source {
+            let json: StandardJson = serde_json::from_str(&source.content)?;
+            if json.language.as_deref().unwrap_or("Solidity") == "Solidity" && !has_vyper {
+                return self.compile_standard_json(json);
+            }
+        }
+
+        let root = workspace::scratch_dir()?;
         let root_path = root.path();
         let source_path = root_path.join(&self.metadata.contract_name);
 
         let source_files = self.get_source_files()?;
 
-        let v = self.metadata.compiler_version.clone();
-        let v = v.trim_start_matches('v');
-        let version = Version::parse(v)?;
-        let version = Version::new(version.major, version.minor, version.patch);
-        let solc = Solc::find_or_install(&version)?;
-        let solc = SolcCompiler::Specific(solc);
-        let compiler = MultiCompiler::new(solc, None)?;
+        let (compiler, solc_version) = match has_vyper {
+            true => {
+                let version = vyper_version(&self.metadata.compiler_version)?;
+                let vyper = vyper_path(&version);
+                if !vyper.exists() {
+                    return Err(eyre::eyre!("Vyper {version} is not installed"));
+                }
+                (
+                    MultiCompiler::new(SolcCompiler::AutoDetect, Some(vyper))?,
+                    None,
+                )
+            }
+            false => {
+                let version = solc_version(&self.metadata.compiler_version)?;
+                let solc = find_solc(&version)?;
+                (
+                    MultiCompiler::new(SolcCompiler::Specific(solc), None)?,
+                    Some(version),
+                )
+            }
+        };
 
         let mut settings = Settings::default();
+        let mut json_evm_version = None;
+        let mut json_via_ir = None;
 
         // TODO json is parsed twice, also parsed in writting source files for ether json
         if let ContractSource::Json(ref source) = self.source {
             let json: StandardJson = serde_json::from_str(&source.content)?;
             settings = json.settings.context("Missing settings in json")?;
+            json_evm_version = settings.evm_version;
+            json_via_ir = settings.via_ir;
 
             for remapping in settings.remappings.iter_mut() {
                 let new_path = source_path.join(remapping.path.trim_start_matches('/'));
@@ -352,7 +966,16 @@ impl PlainContract {
             }
         }
 
-        ContractSource::write_entries(&source_path, &source_files.iter().collect()).await?;
+        // the optimizer details of a standard json are kept, whether it is enabled
+        // and its runs follow the metadata, which can be overridden
+        let mut optimizer = settings.optimizer.clone();
+        optimizer.enabled = Some(self.metadata.optimization_used);
+        optimizer.runs = Some(self.metadata.runs as usize);
+
+        let evm_version = self.evm_version(json_evm_version, solc_version.as_ref());
+
+        ContractSource::write_entries(&source_path, &source_files.iter().collect(), language)
+            .await?;
 
         let paths = ProjectPathsConfig::builder()
             .sources(source_path.clone())
@@ -360,12 +983,23 @@ impl PlainContract {
             .build_with_root(source_path.clone());
 
         let mut settings = MultiCompilerSettings::default();
-        let solc_settings = settings.solc.clone().with_ast();
+        let mut solc_settings = settings
+            .solc
+            .clone()
+            .with_ast()
+            .with_extra_output(self.extra_output());
+        solc_settings.optimizer = optimizer;
+        solc_settings.evm_version = evm_version;
+        solc_settings.via_ir = self.metadata.via_ir.or(json_via_ir);
         settings.solc = solc_settings;
+        // offline, the solc versions auto detected for the solidity files of a
+        // vyper contract are only picked among the installed ones
         let builder = Project::builder()
             .paths(paths)
             .ephemeral()
             .no_artifacts()
+            .set_offline(is_offline())
+            .artifacts(ConfigurableArtifacts::new(self.extra_output(), []))
             .settings(settings);
         let builder = builder.build(compiler)?;
         let output = builder.compile()?.with_stripped_file_prefixes(&source_path);
@@ -376,12 +1010,308 @@ impl PlainContract {
         Ok(output)
     }
 
+    /// An output without artifacts nor diagnostics, from compiling a project
+    /// without sources, so no compiler is run
+    pub fn empty_output() -> Result<ProjectCompileOutput> {
+        let root = workspace::scratch_dir()?;
+        let paths = ProjectPathsConfig::builder()
+            .sources(root.path())
+            .build_with_root(root.path());
+        let project = Project::builder()
+            .paths(paths)
+            .ephemeral()
+            .no_artifacts()
+            .build(MultiCompiler::new(SolcCompiler::AutoDetect, None)?)?;
+        Ok(project.compile()?)
+    }
+
+    /// A single file solidity contract named `A` with the content, recorded as
+    /// compiled with solc 0.8.19 without the optimizer
+    #[cfg(test)]
+    pub fn single_file(content: &str) -> PlainContract {
+        PlainContract::new(
+            Metadata {
+                contract_name: "A".into(),
+                compiler_version: "v0.8.19+commit.7dd6d404".into(),
+                runs: 200,
+                optimization_used: false,
+                bytecode_hash: "".into(),
+                constructor_arguments: None,
+                chain_id: None,
+                address: None,
+                evm_version: None,
+                via_ir: None,
+            },
+            ContractSource::SingleSolidity(SourceFile {
+                name: "main.sol".into(),
+                content: content.into(),
+            }),
+        )
+    }
+
+    /// A contract compiled to the given artifacts by source path and contract
+    /// name, and to the given source file ids by path, without running solc
+    #[cfg(test)]
+    pub fn with_artifacts(
+        artifacts: Vec<(&str, &str, serde_json::Value)>,
+        source_ids: &[(&str, u32)],
+    ) -> Result<PlainContract> {
+        let mut files: BTreeMap<PathBuf, BTreeMap<String, Vec<_>>> = BTreeMap::new();
+        for (path, name, artifact) in artifacts {
+            files.entry(path.into()).or_default().insert(
+                name.into(),
+                vec![ArtifactFile {
+                    artifact: serde_json::from_value::<ConfigurableContractArtifact>(artifact)?,
+                    file: PathBuf::from(format!("out/{path}/{name}.json")),
+                    version: Version::new(0, 8, 20),
+                    build_id: "b".into(),
+                }],
+            );
+        }
+        let mut output = Self::empty_output()?;
+        output.set_compiled_artifacts(Artifacts(files));
+        for (path, id) in source_ids {
+            output.output_mut().sources.0.insert(
+                path.into(),
+                vec![VersionedSourceFile {
+                    source_file: foundry_compilers::artifacts::SourceFile { id: *id, ast: None },
+                    version: Version::new(0, 8, 20),
+                    build_id: "b".into(),
+                }],
+            );
+        }
+
+        let mut contract = PlainContract::new(
+            Metadata {
+                contract_name: "A".into(),
+                compiler_version: "v0.8.20+commit.a1b79de6".into(),
+                runs: 200,
+                optimization_used: false,
+                bytecode_hash: "".into(),
+                constructor_arguments: None,
+                chain_id: None,
+                address: None,
+                evm_version: None,
+                via_ir: None,
+            },
+            ContractSource::SingleSolidity(SourceFile {
+                name: "main.sol".into(),
+                content: "".into(),
+            }),
+        );
+        contract.compilation_output = Some(output);
+        Ok(contract)
+    }
+
+    /// Compile a standard json input as is with solc, without writing its
+    /// sources to disk, so its settings such as remappings and libraries are
+    /// kept. As for other contracts, the optimizer follows the metadata, and
+    /// the AST and extra outputs are requested. The output keeps the
+    /// diagnostics of solc, a failing compilation has compiler errors.
+    fn compile_standard_json(&mut self, json: StandardJson) -> Result<ProjectCompileOutput> {
+        let version = solc_version(&self.metadata.compiler_version)?;
+        let solc = find_solc(&version)?;
+
+        let mut settings = json
+            .settings
+            .context("Missing settings in json")?
+            .with_ast()
+            .with_extra_output(self.extra_output());
+        settings.optimizer.enabled = Some(self.metadata.optimization_used);
+        settings.optimizer.runs = Some(self.metadata.runs as usize);
+        settings.evm_version = self.evm_version(settings.evm_version, Some(&version));
+        settings.via_ir = self.metadata.via_ir.or(settings.via_ir);
+        let input = StandardJsonInput {
+            language: "Solidity",
+            sources: json.sources.into_iter().collect(),
+            settings,
+        };
+
+        let output = solc.compile(&input)?;
+
+        let handler = ConfigurableArtifacts::new(self.extra_output(), []);
+        let mut artifacts: BTreeMap<PathBuf, BTreeMap<String, Vec<_>>> = BTreeMap::new();
+        for (file, contracts) in &output.contracts {
+            let source_file = output.sources.get(file);
+            for (name, contract) in contracts {
+                let artifact =
+                    handler.contract_to_artifact(file, name, contract.clone(), source_file);
+                artifacts
+                    .entry(file.clone())
+                    .or_default()
+                    .entry(name.clone())
+                    .or_default()
+                    .push(ArtifactFile {
+                        artifact,
+                        file: file.join(format!("{name}.json")),
+                        version: version.clone(),
+                        build_id: String::new(),
+                    });
+            }
+        }
+
+        // the diagnostics of solc are kept, as for a project compilation
+        let mut compile_output = Self::empty_output()?;
+        let aggregated = compile_output.output_mut();
+        aggregated.errors = output
+            .errors
+            .into_iter()
+            .map(MultiCompilerError::Solc)
+            .collect();
+        for (file, source_file) in output.sources {
+            aggregated
+                .sources
+                .as_mut()
+                .entry(file)
+                .or_default()
+                .push(VersionedSourceFile {
+                    source_file,
+                    version: version.clone(),
+                    build_id: String::new(),
+                });
+        }
+        for (file, contracts) in output.contracts {
+            let versioned = aggregated.contracts.as_mut().entry(file).or_default();
+            for (name, contract) in contracts {
+                versioned.entry(name).or_default().push(VersionedContract {
+                    contract,
+                    version: version.clone(),
+                    build_id: String::new(),
+                });
+            }
+        }
+        compile_output.set_compiled_artifacts(Artifacts(artifacts));
+        let output = compile_output;
+
+        self.source_files = Some(self.get_source_files()?);
+        self.compilation_output = Some(output.clone());
+        Ok(output)
+    }
+
+    /// Compile the contract, or restore its output from the artifact cache. A
+    /// successful compilation is added to the cache.
+    pub async fn compile_cached(
+        &mut self,
+        cache: Option<&ArtifactCache>,
+    ) -> Result<ProjectCompileOutput> {
+        let Some(cache) = cache else {
+            return self.compile().await;
+        };
+        let contract_id = self.id();
+        let compiler = format!(
+            "{}-{}-{}-{}{}{}",
+            self.metadata.compiler_version,
+            match self.metadata.optimization_used {
+                true => "optimized",
+                false => "unoptimized",
+            },
+            self.metadata.runs,
+            self.metadata.evm_version.as_deref().unwrap_or("default"),
+            match self.metadata.via_ir {
+                Some(true) => "-ir",
+                _ => "",
+            },
+            match self.estimate_gas {
+                true => "-gas",
+                false => "",
+            }
+        );
+        match cache.get(&contract_id, &compiler).await {
+            Ok(Some(output)) => {
+                self.source_files = Some(self.get_source_files()?);
+                self.compilation_output = Some(output.clone());
+                return Ok(output);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Ignoring cached artifacts of contract {contract_id}: {e}"),
+        }
+        let output = self.compile().await?;
+        cache.put(&contract_id, &compiler, &output).await?;
+        Ok(output)
+    }
+
+    /// Compile the contract with its compiler version or, when that fails,
+    /// with up to `attempts` of the nearest patch releases of the same minor
+    /// series, and last with the version inferred from the pragmas, also tried
+    /// when the recorded version is missing or invalid. Returns the output
+    /// with the version it was compiled with, when another one than recorded.
+    pub async fn compile_with_fallback(
+        &mut self,
+        cache: Option<&ArtifactCache>,
+        attempts: usize,
+    ) -> Result<(ProjectCompileOutput, Option<String>)> {
+        let first = self.compile_cached(cache).await;
+        let failed = match &first {
+            Ok(output) => output.has_compiler_errors(),
+            Err(_) => true,
+        };
+        // vyper releases are not listed by svm
+        if !failed || self.source.is_vyper() {
+            return first.map(|output| (output, None));
+        }
+        let recorded = self.metadata.compiler_version.clone();
+        let recorded_version = solc_version(&recorded).ok();
+
+        // offline, only the installed versions can be tried
+        let installed = Solc::installed_versions();
+        let released = match is_offline() {
+            true => vec![],
+            false => Solc::released_versions(),
+        };
+        let available = [installed.clone(), released.clone()].concat();
+        let inferred = pragma_version(self, &installed, &released).unwrap_or_else(|e| {
+            debug!("No compiler version from the pragmas of {}: {e}", self.id());
+            None
+        });
+        let alternatives =
+            fallback_versions(recorded_version.as_ref(), inferred, &available, attempts);
+
+        for alternative in alternatives {
+            self.metadata.compiler_version = format!("v{alternative}");
+            let output = self.compile_cached(cache).await;
+            self.metadata.compiler_version = recorded.clone();
+            match output {
+                Ok(output) if !output.has_compiler_errors() => {
+                    return Ok((output, Some(alternative.to_string())));
+                }
+                Ok(_) => debug!("Contract {} fails to compile with {alternative}", self.id()),
+                Err(e) => debug!(
+                    "Contract {} fails to compile with {alternative} {e}",
+                    self.id()
+                ),
+            }
+        }
+
+        self.compilation_output = first.as_ref().ok().cloned();
+        first.map(|output| (output, None))
+    }
+
+    /// Write every artifact of the compilation output as json to
+    /// `output_dir/<source file>/<contract name>.json`, returns the written paths
+    pub async fn write_artifacts(&self, output_dir: &str) -> Result<Vec<PathBuf>> {
+        let compilation_output = self
+            .compilation_output
+            .as_ref()
+            .context("No compilation output, did you forget to call compile()?")?;
+
+        let mut written = vec![];
+        for (file, name, artifact) in compilation_output.artifacts_with_files() {
+            let dir = Path::new(output_dir).join(sanitize_path(file));
+            create_dir_all(&dir).await?;
+            let path = dir.join(format!("{name}.json"));
+            fs::write(&path, serde_json::to_string_pretty(artifact)?).await?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
     pub fn new(metadata: Metadata, source: ContractSource) -> PlainContract {
         PlainContract {
             metadata,
             source,
             compilation_output: None,
             source_files: None,
+            estimate_gas: false,
         }
     }
 
@@ -490,22 +1420,33 @@ impl PlainContract {
         let functions = compilation_output
             .artifacts()
             .map(|(contract_name, contract)| {
+                // vyper artifacts have no AST, their contract has a single source
                 let filename = contract
                     .source_file()
                     .and_then(|f| f.ast)
                     .map(|ast| ast.absolute_path)
+                    .or_else(|| match &self.source {
+                        ContractSource::Vyper(source) => Some(source.name.clone()),
+                        _ => None,
+                    })
                     .unwrap_or("".into());
 
                 if let Some(ref abi) = contract.abi {
                     abi.functions()
                         .map(|f| {
                             let function_name = &f.name;
-                            let source_code = self
-                                .source_code_by_contract_and_function_name(
-                                    &contract_name,
-                                    function_name,
-                                )
-                                .unwrap_or("".into());
+                            let source_code = match &self.source {
+                                ContractSource::Vyper(source) => {
+                                    vyper_function_source(&source.content, function_name)
+                                }
+                                _ => self
+                                    .source_code_by_contract_and_function_name(
+                                        &contract_name,
+                                        function_name,
+                                    )
+                                    .ok(),
+                            }
+                            .unwrap_or("".into());
 
                             ContractFunction::from_abi(
                                 contract_id.clone(),
@@ -524,14 +1465,229 @@ impl PlainContract {
         Ok(functions.flatten().collect())
     }
 
-    /// Export source code to the output folder
-    pub async fn export_source_code(&self, output_folder: &str) -> Result<()> {
+    /// The compiled artifact of the contract named in the metadata
+    fn main_artifact(&self) -> Result<&ConfigurableContractArtifact> {
+        let compilation_output = self
+            .compilation_output
+            .as_ref()
+            .context("No compilation output, did you forget to call compile()?")?;
+        compilation_output
+            .artifacts()
+            .find(|(name, _)| *name == self.metadata.contract_name)
+            .map(|(_, artifact)| artifact)
+            .context("Main contract not found in compilation output")
+    }
+
+    /// Decode the constructor arguments recorded in the metadata with the
+    /// constructor ABI of the main contract.
+    pub fn extract_constructor_arguments(&self) -> Result<Vec<ConstructorArgument>> {
+        let Some(args) = &self.metadata.constructor_arguments else {
+            return Ok(vec![]);
+        };
+        let artifact = self.main_artifact()?;
+        let Some(constructor) = artifact
+            .abi
+            .as_ref()
+            .and_then(|abi| abi.constructor.as_ref())
+        else {
+            return Ok(vec![]);
+        };
+
+        let values = constructor.abi_decode_input(&decode_hex(args)?, false)?;
+        let contract_id = self.id();
+        let arguments = constructor
+            .inputs
+            .iter()
+            .zip(values.iter())
+            .enumerate()
+            .map(|(position, (param, value))| ConstructorArgument {
+                contract_id: contract_id.clone(),
+                contract_name: self.metadata.contract_name.clone(),
+                position: position as u32,
+                name: param.name.clone(),
+                ty: param.selector_type().into_owned(),
+                value: format_value(value),
+            })
+            .collect();
+
+        Ok(arguments)
+    }
+
+    /// Read the values of the immutable variables of the main contract from
+    /// its deployed bytecode, located by the `immutableReferences` of the
+    /// compiled artifact.
+    pub fn extract_immutables(
+        &self,
+        deployed_bytecode: &[u8],
+        address: Option<String>,
+    ) -> Result<Vec<ImmutableValue>> {
+        let compilation_output = self
+            .compilation_output
+            .as_ref()
+            .context("No compilation output, did you forget to call compile()?")?;
+        let artifact = self.main_artifact()?;
+        let references = &artifact
+            .deployed_bytecode
+            .as_ref()
+            .context("No deployed bytecode in artifact")?
+            .immutable_references;
+
+        // Immutables can be declared in a parent contract living in another file
+        let mut declarations = vec![];
+        for (_, artifact) in compilation_output.artifacts() {
+            if let Some(ast) = &artifact.ast {
+                collect_nodes(&ast.nodes, &VariableDeclaration, &mut declarations);
+            }
+        }
+
+        let contract_id = self.id();
+        let mut immutables = vec![];
+        for (ast_id, offsets) in references {
+            let Some(declaration) = declarations
+                .iter()
+                .find(|node| node.id.map(|id| id.to_string()).as_ref() == Some(ast_id))
+            else {
+                continue;
+            };
+            let Some(offset) = offsets.first() else {
+                continue;
+            };
+            let start = offset.start as usize;
+            let end = start + offset.length as usize;
+            let word = deployed_bytecode
+                .get(start..end)
+                .context("Deployed bytecode is shorter than the compiled bytecode")?;
+
+            let ty = declaration
+                .attribute::<serde_json::Value>("typeDescriptions")
+                .and_then(|t| t["typeString"].as_str().map(String::from))
+                .unwrap_or_default();
+            let abi_ty = match ty.as_str() {
+                t if t.starts_with("contract ") || t == "address payable" => "address",
+                t if t.starts_with("enum ") => "uint8",
+                t => t,
+            };
+            let value = DynSolType::parse(abi_ty)
+                .ok()
+                .and_then(|t| t.abi_decode(word).ok())
+                .map(|v| format_value(&v))
+                .unwrap_or_else(|| hex::encode_prefixed(word));
+
+            immutables.push(ImmutableValue {
+                contract_id: contract_id.clone(),
+                contract_name: self.metadata.contract_name.clone(),
+                address: address.clone(),
+                name: declaration.attribute("name").unwrap_or_default(),
+                ty,
+                value,
+            });
+        }
+
+        Ok(immutables)
+    }
+
+    /// The sanitized paths and source files to export, only the paths matching
+    /// one of the patterns when any is given
+    fn export_entries(&self, only: &[Pattern]) -> Result<Vec<(PathBuf, SourceFile)>> {
+        let source_files = self.get_source_files()?;
+        let source_files: Vec<&SourceFile> = source_files.iter().collect();
+        let language = self.source.default_language()?;
+        let entries: Vec<(PathBuf, SourceFile)> =
+            ContractSource::entry_paths(&source_files, language)
+                .into_iter()
+                .filter(|(path, _)| only.is_empty() || only.iter().any(|p| p.matches_path(path)))
+                .map(|(path, entry)| (path, entry.clone()))
+                .collect();
+        if entries.is_empty() {
+            return Err(eyre::eyre!("No source file to export"));
+        }
+        Ok(entries)
+    }
+
+    /// Export source code to `output_folder/<contract name>`, optionally only
+    /// the files matching `only`, returns the written paths
+    pub async fn export_source_code(
+        &self,
+        output_folder: &str,
+        only: &[Pattern],
+    ) -> Result<Vec<PathBuf>> {
         let root_path = PathBuf::from(output_folder);
         let source_path = root_path.join(&self.metadata.contract_name);
 
-        let source_files = self.get_source_files()?;
+        let entries = self.export_entries(only)?;
+        let entries: Vec<(PathBuf, &SourceFile)> = entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), entry))
+            .collect();
 
-        ContractSource::write_entries(&source_path, &source_files.iter().collect()).await
+        ContractSource::write_paths(&source_path, &entries).await
+    }
+
+    /// Zip archive of the source files, with the same layout as
+    /// [`Self::export_source_code`], and the metadata in `metadata.json`
+    pub fn source_zip(&self, only: &[Pattern]) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for (path, entry) in self.export_entries(only)? {
+            let path = Path::new(&self.metadata.contract_name).join(path);
+            zip.start_file(path.to_string_lossy(), options)?;
+            zip.write_all(entry.content.as_bytes())?;
+        }
+        zip.start_file(
+            format!("{}/metadata.json", self.metadata.contract_name),
+            options,
+        )?;
+        zip.write_all(serde_json::to_string_pretty(&self.metadata)?.as_bytes())?;
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// JSON object mapping the path of every exported source file to its content
+    pub fn source_json(&self, only: &[Pattern]) -> Result<String> {
+        let sources: BTreeMap<String, String> = self
+            .export_entries(only)?
+            .into_iter()
+            .map(|(path, entry)| (path.to_string_lossy().into_owned(), entry.content))
+            .collect();
+        Ok(serde_json::to_string_pretty(&sources)?)
+    }
+
+    /// Export the source files as `output_folder/<contract name>.json`, see
+    /// [`Self::source_json`], returns the written path
+    pub async fn export_source_json(
+        &self,
+        output_folder: &str,
+        only: &[Pattern],
+    ) -> Result<PathBuf> {
+        create_dir_all(output_folder).await?;
+        let path =
+            PathBuf::from(output_folder).join(format!("{}.json", self.metadata.contract_name));
+        fs::write(&path, self.source_json(only)?).await?;
+        Ok(path)
+    }
+
+    /// Content of a single source file, given by its path in the exported tree
+    /// or its original name
+    pub fn source_file_content(&self, name: &str) -> Result<String> {
+        self.export_entries(&[])?
+            .into_iter()
+            .find(|(path, entry)| path == Path::new(name) || entry.name == name)
+            .map(|(_, entry)| entry.content)
+            .with_context(|| format!("No source file {name}"))
+    }
+
+    /// Export the source files as `output_folder/<contract name>.zip`, returns
+    /// the written path
+    pub async fn export_source_zip(
+        &self,
+        output_folder: &str,
+        only: &[Pattern],
+    ) -> Result<PathBuf> {
+        create_dir_all(output_folder).await?;
+        let path =
+            PathBuf::from(output_folder).join(format!("{}.zip", self.metadata.contract_name));
+        fs::write(&path, self.source_zip(only)?).await?;
+        Ok(path)
     }
 }
 
@@ -586,4 +1742,378 @@ mod test {
         println!("{:?}", functions);
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compile_standard_json_input() -> Result<()> {
+        let contract = |content: &str| {
+            let json = serde_json::json!({
+                "language": "Solidity",
+                "sources": {"src/Token.sol": {"content": content}},
+                "settings": {"remappings": [], "optimizer": {"enabled": false}}
+            });
+            PlainContract::new(
+                Metadata {
+                    contract_name: "Token".into(),
+                    compiler_version: "v0.8.19+commit.7dd6d404".into(),
+                    runs: 200,
+                    optimization_used: false,
+                    bytecode_hash: "".into(),
+                    constructor_arguments: None,
+                    chain_id: None,
+                    address: None,
+                    evm_version: None,
+                    via_ir: None,
+                },
+                ContractSource::Json(SourceFile {
+                    name: "Token.json".into(),
+                    content: json.to_string(),
+                }),
+            )
+        };
+
+        let mut token = contract("contract Token { function f() public {} }");
+        let output = token.compile().await?;
+        assert!(!output.has_compiler_errors());
+        assert_eq!(
+            output
+                .artifacts_with_files()
+                .map(|(file, name, _)| format!("{}:{name}", file.display()))
+                .collect_vec(),
+            ["src/Token.sol:Token"]
+        );
+        let (_, artifact) = output.artifacts().next().unwrap();
+        assert!(artifact.abi.is_some() && artifact.ast.is_some());
+        assert!(output
+            .output()
+            .sources
+            .as_ref()
+            .contains_key(Path::new("src/Token.sol")));
+
+        // the diagnostics of a failing compilation are kept
+        let mut broken = contract("contract Token { function f() public { g(); } }");
+        let output = broken.compile().await?;
+        assert!(output.has_compiler_errors());
+        assert!(output
+            .output()
+            .errors
+            .iter()
+            .any(|e| e.to_string().contains("Undeclared identifier")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_source_as_zip() -> Result<()> {
+        let contract = PlainContract::from_folder("./contracts/demo").await?;
+        let zip = contract.source_zip(&[])?;
+
+        let archive = zip::ZipArchive::new(Cursor::new(zip))?;
+        let names: Vec<&str> = archive.file_names().sorted().collect();
+        assert_eq!(
+            names,
+            vec![
+                "AdvancedCounter/AdvancedCounter.sol",
+                "AdvancedCounter/Counter.sol",
+                "AdvancedCounter/ICounter.sol",
+                "AdvancedCounter/Math.sol",
+                "AdvancedCounter/metadata.json",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_only_matching_files() -> Result<()> {
+        let contract = PlainContract::from_folder("./contracts/demo").await?;
+        let only = [Pattern::new("*Counter.sol")?];
+
+        let dir = tempfile::tempdir()?;
+        let written = contract
+            .export_source_code(&dir.path().to_string_lossy(), &only)
+            .await?;
+        let names: Vec<_> = written
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|n| n.to_string_lossy())
+            .sorted()
+            .collect();
+        assert_eq!(
+            names,
+            vec!["AdvancedCounter.sol", "Counter.sol", "ICounter.sol"]
+        );
+
+        assert!(contract.source_zip(&[Pattern::new("Vault.sol")?]).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_to_json_and_single_file() -> Result<()> {
+        let contract = PlainContract::from_folder("./contracts/demo").await?;
+
+        let json: BTreeMap<String, String> =
+            serde_json::from_str(&contract.source_json(&[Pattern::new("Math.sol")?])?)?;
+        assert_eq!(json.keys().collect_vec(), vec!["Math.sol"]);
+        assert_eq!(contract.source_file_content("Math.sol")?, json["Math.sol"]);
+        assert!(contract.source_file_content("Vault.sol").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_sourcify_contract() -> Result<()> {
+        let contract = PlainContract::from_sourcify_folder(
+            "./contracts/sourcify/contracts/full_match/1/0x00000000000000000000000000000000000000c0",
+        )
+        .await?;
+
+        assert_eq!(contract.metadata.contract_name, "Counter");
+        assert_eq!(contract.metadata.chain_id, Some(1));
+        assert_eq!(
+            contract.metadata.address.as_deref(),
+            Some("0x00000000000000000000000000000000000000c0")
+        );
+        assert!(contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 200);
+        assert_eq!(contract.metadata.evm_version.as_deref(), Some("paris"));
+        assert_eq!(contract.metadata.via_ir, None);
+        let names = contract
+            .get_source_files()?
+            .into_iter()
+            .map(|f| f.name)
+            .collect_vec();
+        assert_eq!(
+            names,
+            vec![
+                "contracts/Counter.sol",
+                "contracts/ICounter.sol",
+                "contracts/Math.sol"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_blockscout_response() -> Result<()> {
+        let response = r#"{
+            "SourceCode": "import './Base.sol';\ncontract Token is Base {}",
+            "OptimizationUsed": "true",
+            "OptimizationRuns": 200,
+            "ContractName": "Token",
+            "CompilerVersion": "v0.8.19+commit.7dd6d404",
+            "FileName": "contracts/Token.sol",
+            "AdditionalSources": [
+                {"Filename": "contracts/Base.sol", "SourceCode": "contract Base {}"}
+            ]
+        }"#;
+        let raw: EtherscanRawJson = serde_json::from_str(response)?;
+        let contract = PlainContract::from_etherscan_raw(&raw);
+
+        assert!(contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 200);
+        let names = contract
+            .get_source_files()?
+            .into_iter()
+            .map(|f| f.name)
+            .collect_vec();
+        assert_eq!(names, vec!["contracts/Token.sol", "contracts/Base.sol"]);
+
+        assert_eq!(
+            etherscan_file_address(Path::new(
+                "contracts/0x9ca84eacf0d0775782ab5b34d01187b37f1ceea4_Bueno721Drop.json"
+            ))
+            .as_deref(),
+            Some("0x9ca84eacf0d0775782ab5b34d01187b37f1ceea4")
+        );
+        assert_eq!(etherscan_file_address(Path::new("0x9c/Token.json")), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn keccak_identity() -> Result<()> {
+        let file = |name: &str, content: &str| SourceFile {
+            name: name.into(),
+            content: content.into(),
+        };
+        let single = ContractSource::SingleSolidity(file("A.sol", "contract A {}"));
+        assert_eq!(
+            single.keccak_hash()?,
+            keccak256(keccak256("contract A {}")).to_string()
+        );
+
+        let multi = ContractSource::MultiSolidity(vec![
+            file("A.sol", "contract A {}"),
+            file("B.sol", "contract B {}"),
+        ]);
+        let reordered = ContractSource::MultiSolidity(vec![
+            file("lib/B.sol", "contract B {}"),
+            file("A.sol", "contract A {}"),
+        ]);
+        let reformatted = ContractSource::MultiSolidity(vec![
+            file("A.sol", "contract A {  }"),
+            file("B.sol", "contract B {}"),
+        ]);
+        assert_eq!(multi.keccak_hash()?, reordered.keccak_hash()?);
+        assert_ne!(multi.keccak_hash()?, reformatted.keccak_hash()?);
+        assert_eq!(multi.hash(), reformatted.hash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn count_source_languages() -> Result<()> {
+        let json = ContractSource::Json(SourceFile {
+            name: "contract.json".into(),
+            content: r##"{
+                "language": "Vyper",
+                "sources": {
+                    "contracts/Vault.vy": {"content": "# @version 0.3.10"},
+                    "interfaces/IERC20.vyi": {"content": "def transfer(to: address, amount: uint256) -> bool: ..."},
+                    "interfaces/IOracle.sol": {"content": "interface IOracle {}"},
+                    "Math": {"content": "@internal\ndef mul(a: uint256, b: uint256) -> uint256: ..."}
+                }
+            }"##
+            .into(),
+        });
+        assert_eq!(
+            json.languages()?,
+            BTreeMap::from([("solidity", 1), ("vyper", 3)])
+        );
+        assert!(json.is_vyper());
+        let entries = json.get_source_files()?;
+        assert_eq!(
+            ContractSource::entry_paths(&entries.iter().collect_vec(), "vyper")
+                .iter()
+                .map(|(path, _)| path.to_string_lossy().into_owned())
+                .sorted()
+                .collect_vec(),
+            [
+                "Math.vy",
+                "contracts/Vault.vy",
+                "interfaces/IERC20.vyi",
+                "interfaces/IOracle.sol"
+            ]
+        );
+
+        let multi = ContractSource::MultiSolidity(vec![
+            SourceFile {
+                name: "A.sol".into(),
+                content: "contract A {}".into(),
+            },
+            SourceFile {
+                name: "Util.yul".into(),
+                content: "object \"Util\" {}".into(),
+            },
+        ]);
+        assert_eq!(
+            multi.languages()?,
+            BTreeMap::from([("solidity", 1), ("yul", 1)])
+        );
+        assert!(!multi.is_vyper());
+
+        Ok(())
+    }
+
+    #[test]
+    fn order_fallback_compiler_versions() {
+        let versions = [
+            "0.8.17",
+            "0.8.18",
+            "0.8.19",
+            "0.8.20",
+            "0.8.21",
+            "0.7.6",
+            "0.8.22-nightly",
+        ]
+        .map(|v| Version::parse(v).unwrap());
+        assert_eq!(
+            nearest_patch_versions(&Version::new(0, 8, 19), &versions)
+                .iter()
+                .map(Version::to_string)
+                .collect_vec(),
+            ["0.8.20", "0.8.18", "0.8.21", "0.8.17"]
+        );
+        assert!(nearest_patch_versions(&Version::new(0, 6, 12), &versions).is_empty());
+
+        let fallbacks = |recorded: Option<Version>, inferred: Option<Version>, attempts| {
+            fallback_versions(recorded.as_ref(), inferred, &versions, attempts)
+                .iter()
+                .map(Version::to_string)
+                .collect_vec()
+        };
+        let recorded = Some(Version::new(0, 8, 19));
+        assert_eq!(
+            fallbacks(recorded.clone(), Some(Version::new(0, 7, 6)), 2),
+            ["0.8.20", "0.8.18", "0.7.6"]
+        );
+        // without patch releases to try the pragmas are still used
+        assert_eq!(
+            fallbacks(recorded.clone(), Some(Version::new(0, 7, 6)), 0),
+            ["0.7.6"]
+        );
+        // a version tried already is not tried again
+        assert_eq!(
+            fallbacks(recorded.clone(), Some(Version::new(0, 8, 19)), 0),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            fallbacks(recorded, Some(Version::new(0, 8, 20)), 1),
+            ["0.8.20"]
+        );
+        assert_eq!(fallbacks(None, Some(Version::new(0, 8, 21)), 3), ["0.8.21"]);
+    }
+
+    #[test]
+    fn select_evm_version() -> Result<()> {
+        let raw = |evm_version: &str| {
+            serde_json::from_str::<EtherscanRawJson>(&format!(
+                r#"{{"SourceCode": "contract A {{}}", "OptimizationUsed": "0", "Runs": "200", "ContractName": "A", "CompilerVersion": "v0.5.17+commit.d19bba13", "EVMVersion": "{evm_version}"}}"#
+            ))
+        };
+        assert_eq!(
+            raw("istanbul")?.to_metadata().evm_version.as_deref(),
+            Some("istanbul")
+        );
+        assert_eq!(raw("Default")?.to_metadata().evm_version, None);
+
+        let default = |v: &str| default_evm_version(&Version::parse(v).unwrap());
+        assert_eq!(default("0.4.11"), None);
+        assert_eq!(default("0.4.24"), Some(EvmVersion::Byzantium));
+        assert_eq!(default("0.5.17"), Some(EvmVersion::Istanbul));
+        assert_eq!(default("0.8.19"), Some(EvmVersion::Paris));
+        assert_eq!(default("0.8.24"), Some(EvmVersion::Shanghai));
+        assert_eq!(default("0.8.26"), Some(EvmVersion::Cancun));
+        assert_eq!(default("0.8.29"), Some(EvmVersion::Cancun));
+        assert_eq!(default("0.8.30"), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_build_info() -> Result<()> {
+        let contract = PlainContract::from_build_info(
+            "./contracts/hardhat/artifacts/build-info/0c6f7a2e5e4a3b2c1d0e9f8a7b6c5d4e.json",
+        )
+        .await?;
+
+        assert_eq!(contract.metadata.contract_name, "Counter");
+        assert_eq!(contract.metadata.compiler_version, "0.8.19+commit.7dd6d404");
+        assert!(contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 1000);
+        let names = contract
+            .get_source_files()?
+            .into_iter()
+            .map(|f| f.name)
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            names,
+            vec!["contracts/Counter.sol", "contracts/ICounter.sol"]
+        );
+
+        Ok(())
+    }
 }
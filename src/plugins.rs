@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+/// A row returned by an extractor plugin for one of its own tables
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PluginRow {
+    /// Name of the plugin, the file stem of its module
+    pub plugin: String,
+    pub contract_id: String,
+    /// Table chosen by the plugin, e.g. `reentrancy_guard`
+    pub table_name: String,
+    /// The row as a json object
+    pub row: String,
+}
+
+#[cfg(feature = "wasm-plugins")]
+pub use wasm::WasmPlugin;
+
+#[cfg(feature = "wasm-plugins")]
+mod wasm {
+    use super::*;
+    use eyre::{eyre, Result};
+    use serde_json::{json, Value};
+    use std::path::Path;
+    use wasmtime::{
+        Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    };
+
+    use crate::{ast_query::source_asts, plain_contract::PlainContract};
+
+    /// Fuel of a plugin run on a contract, roughly its number of instructions,
+    /// a plugin stuck in a loop fails instead of hanging the indexing
+    const PLUGIN_FUEL: u64 = 10_000_000_000;
+    /// Largest memory of a plugin instance, enough for the ASTs of big contracts
+    const PLUGIN_MEMORY: usize = 1 << 30;
+
+    /// One row of the output of a plugin, `[{"table": "...", "row": {...}}]`
+    #[derive(Deserialize)]
+    struct OutputRow {
+        table: String,
+        row: Value,
+    }
+
+    /// The input of the plugins for a compiled contract: its id, name, compiler
+    /// version and the AST of every source file
+    pub fn plugin_input(contract: &PlainContract) -> Result<Value> {
+        let sources = source_asts(contract)?
+            .into_iter()
+            .map(|(path, ast)| json!({"path": path, "ast": ast}))
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "contract_id": contract.id(),
+            "contract_name": contract.metadata.contract_name,
+            "compiler_version": contract.metadata.compiler_version,
+            "sources": sources,
+        }))
+    }
+
+    /// Rows of the json output of a plugin
+    pub fn parse_output(plugin: &str, contract_id: &str, output: &[u8]) -> Result<Vec<PluginRow>> {
+        let rows: Vec<OutputRow> = serde_json::from_slice(output)
+            .map_err(|e| eyre!("Invalid output of plugin {plugin}: {e}"))?;
+        rows.into_iter()
+            .map(|r| match r.row.is_object() {
+                true => Ok(PluginRow {
+                    plugin: plugin.into(),
+                    contract_id: contract_id.into(),
+                    table_name: r.table,
+                    row: r.row.to_string(),
+                }),
+                false => Err(eyre!(
+                    "Plugin {plugin} returned a row which is not an object"
+                )),
+            })
+            .collect()
+    }
+
+    /// An extractor compiled to WebAssembly. The module exports its `memory`,
+    /// `alloc(len: i32) -> i32` returning a buffer for the input, and
+    /// `extract(ptr: i32, len: i32) -> i64` reading the json input and
+    /// returning the pointer and length of the json output as `ptr << 32 | len`.
+    /// Every run is limited to [`PLUGIN_FUEL`] and [`PLUGIN_MEMORY`].
+    #[derive(Clone)]
+    pub struct WasmPlugin {
+        pub name: String,
+        engine: Engine,
+        module: Module,
+    }
+
+    impl WasmPlugin {
+        /// Compile the module at `path`, a `.wasm` binary or `.wat` text
+        pub fn load(path: &str) -> Result<Self> {
+            let name = Path::new(path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or(path.into());
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            let engine =
+                Engine::new(&config).map_err(|e| eyre!("Failed to load plugin {path}: {e}"))?;
+            let module = Module::from_file(&engine, path)
+                .map_err(|e| eyre!("Failed to load plugin {path}: {e}"))?;
+            Ok(Self {
+                name,
+                engine,
+                module,
+            })
+        }
+
+        /// Run the plugin on the json input in a fresh instance
+        pub(super) fn call(&self, input: &[u8]) -> wasmtime::Result<Vec<u8>> {
+            self.call_limited(input, PLUGIN_FUEL, PLUGIN_MEMORY)
+        }
+
+        /// Run the plugin with the given fuel and largest memory in bytes
+        pub(super) fn call_limited(
+            &self,
+            input: &[u8],
+            fuel: u64,
+            memory: usize,
+        ) -> wasmtime::Result<Vec<u8>> {
+            let limits = StoreLimitsBuilder::new().memory_size(memory).build();
+            let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(fuel)?;
+            let instance = Instance::new(&mut store, &self.module, &[])?;
+            let memory: Memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| wasmtime::Error::msg("No exported memory"))?;
+            let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+            let extract = instance.get_typed_func::<(i32, i32), i64>(&mut store, "extract")?;
+
+            let len = i32::try_from(input.len())?;
+            let ptr = alloc.call(&mut store, len)?;
+            memory.write(&mut store, ptr as u32 as usize, input)?;
+            let output = extract.call(&mut store, (ptr, len))? as u64;
+            let (ptr, len) = ((output >> 32) as usize, (output & 0xffff_ffff) as usize);
+            let mut buffer = vec![0; len];
+            memory.read(&store, ptr, &mut buffer)?;
+            Ok(buffer)
+        }
+
+        /// Rows extracted by the plugin from a compiled contract
+        pub fn extract(&self, contract: &PlainContract) -> Result<Vec<PluginRow>> {
+            let input = plugin_input(contract)?.to_string();
+            let output = self
+                .call(input.as_bytes())
+                .map_err(|e| eyre!("Plugin {} failed: {e}", self.name))?;
+            parse_output(&self.name, &contract.id(), &output)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wasm-plugins"))]
+mod test {
+    use super::*;
+    use eyre::{eyre, Result};
+    use wasm::parse_output;
+
+    #[test]
+    fn parse_plugin_output() -> Result<()> {
+        let rows = parse_output(
+            "guards",
+            "abc",
+            br#"[{"table": "reentrancy_guard", "row": {"function": "withdraw", "guarded": true}}]"#,
+        )?;
+        assert_eq!(
+            rows,
+            vec![PluginRow {
+                plugin: "guards".into(),
+                contract_id: "abc".into(),
+                table_name: "reentrancy_guard".into(),
+                row: r#"{"function":"withdraw","guarded":true}"#.into(),
+            }]
+        );
+        assert!(parse_output("guards", "abc", br#"[{"table": "t", "row": 1}]"#).is_err());
+        assert!(parse_output("guards", "abc", b"not json").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_wasm_plugin() -> Result<()> {
+        let output = r#"[{"table": "t", "row": {"a": 1}}]"#;
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "extract") (param i32 i32) (result i64)
+                    (i64.or (i64.shl (i64.const 1024) (i64.const 32)) (i64.const {}))))"#,
+            output.replace('"', "\\\""),
+            output.len()
+        );
+        let folder = tempfile::tempdir()?;
+        let path = folder.path().join("constant.wat");
+        std::fs::write(&path, wat)?;
+
+        let plugin = WasmPlugin::load(&path.to_string_lossy())?;
+        assert_eq!(plugin.name, "constant");
+        let output = plugin.call(b"{}").map_err(|e| eyre!("{e}"))?;
+        let rows = parse_output(&plugin.name, "abc", &output)?;
+        assert_eq!(rows[0].row, r#"{"a":1}"#);
+        // the run of a plugin is bounded
+        assert!(plugin.call_limited(b"{}", 0, 1 << 30).is_err());
+        assert!(plugin.call_limited(b"{}", 1_000, 0).is_err());
+
+        let looping = folder.path().join("looping.wat");
+        std::fs::write(
+            &looping,
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "extract") (param i32 i32) (result i64)
+                    (loop (br 0))
+                    (i64.const 0)))"#,
+        )?;
+        let plugin = WasmPlugin::load(&looping.to_string_lossy())?;
+        assert!(plugin.call_limited(b"{}", 1_000_000, 1 << 30).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use eyre::Result;
+use regex::Regex;
+use semver::{Op, Version, VersionReq};
+use std::collections::BTreeMap;
+
+use crate::{compiler_version::solc_version, finding::Finding, plain_contract::PlainContract};
+
+/// Kind of the findings stored for pragmas contradicting the compiler version
+pub const PRAGMA_MISMATCH_FINDING: &str = "pragma-mismatch";
+
+/// The source without comments
+pub fn strip_comments(content: &str) -> String {
+    let comments = Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap();
+    comments.replace_all(content, "").into_owned()
+}
+
+/// The version ranges of all `pragma solidity` directives in the source,
+/// directives inside comments are ignored
+pub fn solidity_pragmas(content: &str) -> Vec<String> {
+    let content = strip_comments(content);
+    let pragma = Regex::new(r"pragma\s+solidity\s+([^;]+);").unwrap();
+    pragma
+        .captures_iter(&content)
+        .map(|c| c[1].trim().to_string())
+        .collect()
+}
+
+/// Parse a solidity version range into semver requirements, one for each
+/// alternative separated by `||`
+pub fn parse_pragma(pragma: &str) -> Result<Vec<VersionReq>> {
+    let operator_space = Regex::new(r"([<>=^~]+)\s+").unwrap();
+    pragma
+        .split("||")
+        .map(|alternative| {
+            let alternative = operator_space.replace_all(alternative.trim(), "$1");
+            // a bare version is an exact match in solidity but a caret range in semver
+            let comparators = alternative
+                .split_whitespace()
+                .map(|c| match c.starts_with(|c: char| c.is_ascii_digit()) {
+                    true => format!("={c}"),
+                    false => c.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            Ok(VersionReq::parse(&comparators)?)
+        })
+        .collect()
+}
+
+/// Whether the compiler version satisfies the version range of the pragma
+pub fn pragma_allows(pragma: &str, version: &Version) -> Result<bool> {
+    Ok(parse_pragma(pragma)?.iter().any(|req| req.matches(version)))
+}
+
+/// The lowest version of a requirement, if bounded from below
+fn lower_bound(req: &VersionReq) -> Option<Version> {
+    req.comparators
+        .iter()
+        .filter_map(|c| {
+            let version = Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+            match c.op {
+                Op::Exact | Op::GreaterEq | Op::Caret | Op::Tilde | Op::Wildcard => Some(version),
+                Op::Greater => Some(Version::new(
+                    version.major,
+                    version.minor,
+                    version.patch + 1,
+                )),
+                _ => None,
+            }
+        })
+        .max()
+}
+
+/// The lowest compiler version allowed by all pragmas, used when a project
+/// does not pin its compiler version
+pub fn minimum_version(pragmas: &[String]) -> Result<Option<Version>> {
+    let mut minimum: Option<Version> = None;
+    for pragma in pragmas {
+        let lowest = parse_pragma(pragma)?.iter().filter_map(lower_bound).min();
+        minimum = minimum.max(lowest);
+    }
+    Ok(minimum)
+}
+
+/// The compiler version to use for sources with the pragmas: the highest
+/// installed release allowed by all of them, else the highest such release
+/// which can be downloaded
+pub fn best_version(
+    pragmas: &[String],
+    installed: &[Version],
+    released: &[Version],
+) -> Result<Option<Version>> {
+    let reqs = pragmas
+        .iter()
+        .map(|p| parse_pragma(p))
+        .collect::<Result<Vec<_>>>()?;
+    let allowed = |v: &&Version| {
+        v.pre.is_empty()
+            && reqs
+                .iter()
+                .all(|alternatives| alternatives.iter().any(|r| r.matches(v)))
+    };
+    Ok(installed
+        .iter()
+        .filter(allowed)
+        .max()
+        .or_else(|| released.iter().filter(allowed).max())
+        .cloned())
+}
+
+/// The latest release of every minor series allowed by all pragmas, oldest
+/// first, e.g. to compare the compilations of the same sources across
+/// compiler versions
+pub fn compatible_versions(pragmas: &[String], available: &[Version]) -> Result<Vec<Version>> {
+    let reqs = pragmas
+        .iter()
+        .map(|p| parse_pragma(p))
+        .collect::<Result<Vec<_>>>()?;
+    let mut latest: BTreeMap<(u64, u64), &Version> = BTreeMap::new();
+    for version in available.iter().filter(|v| {
+        v.pre.is_empty()
+            && reqs
+                .iter()
+                .all(|alternatives| alternatives.iter().any(|r| r.matches(v)))
+    }) {
+        let entry = latest
+            .entry((version.major, version.minor))
+            .or_insert(version);
+        *entry = (*entry).max(version);
+    }
+    Ok(latest.into_values().cloned().collect())
+}
+
+/// The pragmas of the solidity sources of the contract
+pub fn contract_pragmas(contract: &PlainContract) -> Result<Vec<String>> {
+    Ok(contract
+        .get_source_files()?
+        .iter()
+        .filter(|f| !f.name.ends_with(".vy"))
+        .flat_map(|f| solidity_pragmas(&f.content))
+        .collect())
+}
+
+/// The compiler version inferred from the pragmas of the solidity sources of
+/// the contract, see [`best_version`]. `None` without pragmas.
+pub fn pragma_version(
+    contract: &PlainContract,
+    installed: &[Version],
+    released: &[Version],
+) -> Result<Option<Version>> {
+    let pragmas = contract_pragmas(contract)?;
+    if pragmas.is_empty() {
+        return Ok(None);
+    }
+    best_version(&pragmas, installed, released)
+}
+
+/// Findings for every source file with a pragma the recorded compiler version
+/// does not satisfy, which usually means the metadata is mislabeled
+pub fn find_pragma_mismatches(contract: &PlainContract) -> Result<Vec<Finding>> {
+    let version = solc_version(&contract.metadata.compiler_version)?;
+    let contract_id = contract.id();
+
+    let mut findings = vec![];
+    for source in contract.get_source_files()? {
+        for pragma in solidity_pragmas(&source.content) {
+            if !pragma_allows(&pragma, &version)? {
+                findings.push(Finding {
+                    contract_id: contract_id.clone(),
+                    kind: PRAGMA_MISMATCH_FINDING.into(),
+                    name: source.name.clone(),
+                    severity: "high".into(),
+                    detail: format!(
+                        "pragma solidity {pragma} does not allow compiler version {version}"
+                    ),
+                });
+                break;
+            }
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_pragma_ranges() -> Result<()> {
+        let source = r"
+// pragma solidity ^0.4.0;
+pragma solidity >= 0.6.0 <0.8.0;
+/* pragma solidity 0.5.0; */
+pragma solidity 0.7.6 || ^0.8.0;
+";
+        let pragmas = solidity_pragmas(source);
+        assert_eq!(pragmas, vec![">= 0.6.0 <0.8.0", "0.7.6 || ^0.8.0"]);
+
+        let version = Version::new(0, 8, 7);
+        assert!(!pragma_allows(&pragmas[0], &version)?);
+        assert!(pragma_allows(&pragmas[1], &version)?);
+        assert!(!pragma_allows("0.7.6", &Version::new(0, 7, 7))?);
+        assert!(!pragma_allows("^0.6.2", &version)?);
+
+        let pragmas = vec!["^0.8.4".to_string(), ">=0.7.0 <0.9.0".to_string()];
+        assert_eq!(minimum_version(&pragmas)?, Some(Version::new(0, 8, 4)));
+        assert_eq!(minimum_version(&[])?, None);
+
+        let installed = [Version::new(0, 8, 4), Version::new(0, 8, 19)];
+        let released = [
+            Version::new(0, 7, 6),
+            Version::new(0, 8, 4),
+            Version::new(0, 8, 19),
+            Version::new(0, 8, 26),
+        ];
+        assert_eq!(
+            best_version(&pragmas, &installed, &released)?,
+            Some(Version::new(0, 8, 19))
+        );
+        let pragmas = vec![">=0.6.0 <0.8.0".to_string()];
+        assert_eq!(
+            best_version(&pragmas, &installed, &released)?,
+            Some(Version::new(0, 7, 6))
+        );
+        assert_eq!(best_version(&pragmas, &installed, &[])?, None);
+
+        let pragmas = vec![">=0.6.0".to_string()];
+        let available = [released.as_slice(), &[Version::new(0, 6, 12)]].concat();
+        assert_eq!(
+            compatible_versions(&pragmas, &available)?,
+            [
+                Version::new(0, 6, 12),
+                Version::new(0, 7, 6),
+                Version::new(0, 8, 26)
+            ]
+        );
+        assert!(compatible_versions(&["^0.5.0".to_string()], &available)?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::{eyre, ContextCompat, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{abi::decode_hex, layout::parse_word, utils::HttpFetcher};
+
+/// Runtime code deployed at an address, stored whether or not its source is
+/// known
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DeployedBytecode {
+    pub chain_id: u64,
+    /// Lowercase `0x` prefixed address
+    pub address: String,
+    /// `0x` prefixed keccak256 of the code, shared by identical deployments
+    pub code_hash: String,
+    /// `0x` prefixed runtime code
+    pub code: String,
+    /// The stored contract with the same chain and address, set once its
+    /// source is stored
+    pub contract_id: Option<String>,
+    /// Block of the deployment, when known
+    pub block_number: Option<u64>,
+}
+
+impl DeployedBytecode {
+    pub fn new(chain_id: u64, address: &str, code: &[u8], contract_id: Option<String>) -> Self {
+        Self {
+            chain_id,
+            address: address.to_lowercase(),
+            code_hash: keccak256(code).to_string(),
+            code: hex::encode_prefixed(code),
+            contract_id,
+            block_number: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// Response of a JSON-RPC call, either a result or an error
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+/// The result of a JSON-RPC response body
+fn parse_response<T: DeserializeOwned>(method: &str, body: &str) -> Result<T> {
+    let response: RpcResponse<T> =
+        serde_json::from_str(body).map_err(|e| eyre!("Unexpected response to {method}: {e}"))?;
+    if let Some(error) = response.error {
+        return Err(eyre!(
+            "RPC error {} for {method}: {}",
+            error.code,
+            error.message
+        ));
+    }
+    response
+        .result
+        .with_context(|| format!("No result for {method}"))
+}
+
+/// Client of the JSON-RPC endpoint of a node
+pub struct RpcClient<'a> {
+    fetcher: &'a HttpFetcher,
+    url: String,
+}
+
+impl<'a> RpcClient<'a> {
+    pub fn new(fetcher: &'a HttpFetcher, url: &str) -> Self {
+        Self {
+            fetcher,
+            url: url.into(),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let body = self
+            .fetcher
+            .post_json(&self.url, &request.to_string())
+            .await?;
+        parse_response(method, &body)
+    }
+
+    pub async fn chain_id(&self) -> Result<u64> {
+        let chain_id: String = self.call("eth_chainId", json!([])).await?;
+        Ok(parse_word(&chain_id)?.to::<u64>())
+    }
+
+    /// Runtime code at the address, empty for accounts without code
+    pub async fn code(&self, address: &str) -> Result<Vec<u8>> {
+        let code: String = self.call("eth_getCode", json!([address, "latest"])).await?;
+        decode_hex(&code)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_rpc_responses() -> Result<()> {
+        let code: String = parse_response(
+            "eth_getCode",
+            r#"{"jsonrpc":"2.0","id":1,"result":"0x6080"}"#,
+        )?;
+        assert_eq!(decode_hex(&code)?, vec![0x60, 0x80]);
+
+        let error = parse_response::<String>(
+            "eth_getCode",
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"invalid argument 0"}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "RPC error -32602 for eth_getCode: invalid argument 0"
+        );
+
+        let bytecode = DeployedBytecode::new(1, "0xAB", &[0x60, 0x80], None);
+        assert_eq!(bytecode.address, "0xab");
+        assert_eq!(bytecode.code, "0x6080");
+        assert_eq!(bytecode.code_hash.len(), 66);
+
+        Ok(())
+    }
+}
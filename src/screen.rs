@@ -0,0 +1,92 @@
+use serde::Serialize;
+
+use crate::similarity::{minhash, signature_from_hex, similarity, OpcodeSignature};
+
+/// The best known source of the code deployed at an address
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ScreenResult {
+    pub address: String,
+    /// Implementation screened instead of an EIP-1167 proxy
+    pub implementation: Option<String>,
+    /// `exact` for a code identical to an indexed one once metadata and
+    /// immutable values are ignored, `similar` for the most
+    /// similar opcodes, `none` without code or below the minimum similarity
+    pub method: &'static str,
+    /// 1 for an exact match, the estimated similarity of the opcodes else
+    pub confidence: f64,
+    pub contract_id: Option<String>,
+    pub contract_name: Option<String>,
+}
+
+impl ScreenResult {
+    pub fn unknown(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            implementation: None,
+            method: "none",
+            confidence: 0.0,
+            contract_id: None,
+            contract_name: None,
+        }
+    }
+}
+
+/// The opcode signatures of the indexed contracts decoded once, to be compared
+/// with every screened code
+pub fn decode_signatures(signatures: Vec<OpcodeSignature>) -> Vec<(Vec<u64>, OpcodeSignature)> {
+    signatures
+        .into_iter()
+        .filter_map(|s| Some((signature_from_hex(&s.signature)?, s)))
+        .collect()
+}
+
+/// The indexed contract whose opcodes are the most similar to the code, with
+/// the similarity, `None` below `min_similarity`
+pub fn most_similar<'a>(
+    code: &[u8],
+    signatures: &'a [(Vec<u64>, OpcodeSignature)],
+    min_similarity: f64,
+) -> Option<(f64, &'a OpcodeSignature)> {
+    let signature = minhash(code);
+    signatures
+        .iter()
+        .map(|(other, s)| (similarity(&signature, other), s))
+        .filter(|(score, _)| *score >= min_similarity)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{abi::decode_hex, similarity::signature_to_hex};
+    use eyre::Result;
+
+    #[test]
+    fn find_most_similar_contract() -> Result<()> {
+        let signature = |name: &str, code: &str| -> Result<OpcodeSignature> {
+            let code = decode_hex(code)?;
+            Ok(OpcodeSignature {
+                contract_id: name.into(),
+                contract_name: name.into(),
+                opcodes: 0,
+                signature: signature_to_hex(&minhash(&code)),
+            })
+        };
+        let signatures = decode_signatures(vec![
+            signature("a", "6080604052348015610010575b00")?,
+            signature(
+                "b",
+                "3660008037600080366000735af43d82803e903d91602b57fd5bf3",
+            )?,
+        ]);
+
+        // other push data, e.g. an immutable address
+        let code = decode_hex("6080604152348015610020575b00")?;
+        let (score, best) = most_similar(&code, &signatures, 0.5).unwrap();
+        assert_eq!(best.contract_id, "a");
+        assert_eq!(score, 1.0);
+        assert!(most_similar(&decode_hex("00")?, &signatures, 0.5).is_none());
+
+        Ok(())
+    }
+}
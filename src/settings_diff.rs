@@ -0,0 +1,183 @@
+use eyre::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    compiler_version::solc_version,
+    plain_contract::{ContractSource, PlainContract, StandardJson},
+};
+
+/// The compiler settings a contract is compiled with, from its metadata and
+/// the settings of its standard json input
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EffectiveSettings {
+    pub compiler_version: String,
+    pub optimizer: bool,
+    pub runs: u32,
+    /// Optimizer details of the standard json, as json
+    pub optimizer_details: Option<String>,
+    pub evm_version: Option<String>,
+    pub via_ir: bool,
+    /// As `context:prefix=path`
+    pub remappings: Vec<String>,
+    /// Linked libraries, as `file:Library=address`
+    pub libraries: Vec<String>,
+}
+
+impl EffectiveSettings {
+    pub fn of(contract: &PlainContract) -> Result<Self> {
+        let settings = match &contract.source {
+            ContractSource::Json(source) => {
+                serde_json::from_str::<StandardJson>(&source.content)?.settings
+            }
+            _ => None,
+        };
+        let compiler_version = match contract.source {
+            ContractSource::Vyper(_) => None,
+            _ => solc_version(&contract.metadata.compiler_version).ok(),
+        };
+        let evm_version = contract.evm_version(
+            settings.as_ref().and_then(|s| s.evm_version),
+            compiler_version.as_ref(),
+        );
+        let optimizer_details = settings
+            .as_ref()
+            .and_then(|s| s.optimizer.details.as_ref())
+            .map(serde_json::to_string)
+            .transpose()?;
+        let remappings = settings
+            .iter()
+            .flat_map(|s| s.remappings.iter().map(ToString::to_string))
+            .collect();
+        let libraries = settings
+            .iter()
+            .flat_map(|s| s.libraries.libs.iter())
+            .flat_map(|(file, libs)| {
+                libs.iter()
+                    .map(move |(name, address)| format!("{}:{name}={address}", file.display()))
+            })
+            .collect();
+
+        Ok(Self {
+            compiler_version: compiler_version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| contract.metadata.compiler_version.clone()),
+            optimizer: contract.metadata.optimization_used,
+            runs: contract.metadata.runs,
+            optimizer_details,
+            evm_version: evm_version.map(|v| v.to_string()),
+            via_ir: contract
+                .metadata
+                .via_ir
+                .or(settings.as_ref().and_then(|s| s.via_ir))
+                .unwrap_or(false),
+            remappings,
+            libraries,
+        })
+    }
+
+    /// The settings by name, lists joined with commas
+    fn fields(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("compiler_version", self.compiler_version.clone()),
+            ("optimizer", self.optimizer.to_string()),
+            ("runs", self.runs.to_string()),
+            (
+                "optimizer_details",
+                self.optimizer_details.clone().unwrap_or_default(),
+            ),
+            ("evm_version", self.evm_version.clone().unwrap_or_default()),
+            ("via_ir", self.via_ir.to_string()),
+            ("remappings", self.remappings.join(",")),
+            ("libraries", self.libraries.join(",")),
+        ])
+    }
+}
+
+/// A setting differing between two contracts
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SettingDiff {
+    pub setting: &'static str,
+    pub a: String,
+    pub b: String,
+}
+
+/// The settings differing between two contracts, by name
+pub fn diff_settings(a: &EffectiveSettings, b: &EffectiveSettings) -> Vec<SettingDiff> {
+    let b = b.fields();
+    a.fields()
+        .into_iter()
+        .filter(|(setting, value)| b[setting] != *value)
+        .map(|(setting, value)| SettingDiff {
+            setting,
+            a: value,
+            b: b[setting].clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plain_contract::{Metadata, SourceFile};
+
+    #[test]
+    fn diff_compiler_settings() -> Result<()> {
+        let metadata = |compiler_version: &str, runs| Metadata {
+            contract_name: "A".into(),
+            compiler_version: compiler_version.into(),
+            runs,
+            optimization_used: true,
+            bytecode_hash: "".into(),
+            constructor_arguments: None,
+            chain_id: None,
+            address: None,
+            evm_version: None,
+            via_ir: None,
+        };
+        let single = PlainContract::new(
+            metadata("v0.8.19+commit.7dd6d404", 200),
+            ContractSource::SingleSolidity(SourceFile {
+                name: "A.sol".into(),
+                content: "contract A {}".into(),
+            }),
+        );
+        let json = PlainContract::new(
+            metadata("v0.8.19+commit.7dd6d404", 1000),
+            ContractSource::Json(SourceFile {
+                name: "contract.json".into(),
+                content: r#"{
+                    "language": "Solidity",
+                    "sources": {"src/A.sol": {"content": "contract A {}"}},
+                    "settings": {
+                        "optimizer": {"enabled": true, "runs": 1000},
+                        "evmVersion": "london",
+                        "remappings": ["@oz/=lib/openzeppelin/"],
+                        "libraries": {"src/Math.sol": {"Math": "0x0000000000000000000000000000000000000001"}}
+                    }
+                }"#
+                .into(),
+            }),
+        );
+
+        let a = EffectiveSettings::of(&single)?;
+        assert_eq!(a.compiler_version, "0.8.19");
+        assert_eq!(a.evm_version.as_deref(), Some("paris"));
+        let b = EffectiveSettings::of(&json)?;
+        assert_eq!(b.remappings, ["@oz/=lib/openzeppelin/"]);
+        assert_eq!(
+            b.libraries,
+            ["src/Math.sol:Math=0x0000000000000000000000000000000000000001"]
+        );
+
+        let diffs = diff_settings(&a, &b);
+        assert_eq!(
+            diffs.iter().map(|d| d.setting).collect::<Vec<_>>(),
+            ["evm_version", "libraries", "remappings", "runs"]
+        );
+        assert_eq!(diffs[0].b, "london");
+        assert!(diff_settings(&a, &a).is_empty());
+
+        Ok(())
+    }
+}
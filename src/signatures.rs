@@ -0,0 +1,301 @@
+use alloy_primitives::{hex, keccak256};
+use clap::ValueEnum;
+use eyre::{eyre, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+/// The kind of a text signature, which decides how it is hashed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum SignatureKind {
+    /// Hashed to a 4 byte selector
+    Function,
+    /// Hashed to a 32 byte topic0
+    Event,
+    /// Hashed to a 4 byte selector
+    Error,
+}
+
+impl Display for SignatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureKind::Function => write!(f, "function"),
+            SignatureKind::Event => write!(f, "event"),
+            SignatureKind::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A text signature from an external signature database
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KnownSignature {
+    pub kind: SignatureKind,
+    pub signature: String,
+    /// `0x` prefixed selector or topic0
+    pub hash: String,
+}
+
+impl KnownSignature {
+    pub fn new(kind: SignatureKind, signature: &str) -> Self {
+        let signature: String = signature.split_whitespace().collect();
+        let digest = keccak256(signature.as_bytes());
+        let hash = match kind {
+            SignatureKind::Event => hex::encode_prefixed(digest),
+            SignatureKind::Function | SignatureKind::Error => hex::encode_prefixed(&digest[..4]),
+        };
+        Self {
+            kind,
+            signature,
+            hash,
+        }
+    }
+}
+
+/// Read text signatures from a file with one signature per line, empty lines
+/// and lines starting with `#` are skipped
+pub async fn read_signatures(path: &str, kind: SignatureKind) -> Result<Vec<KnownSignature>> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let signatures = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| KnownSignature::new(kind, line))
+        .collect();
+    Ok(signatures)
+}
+
+/// Normalize a user provided hash to the lowercase `0x` prefixed form used in
+/// the database
+pub fn normalize_hash(hash: &str) -> String {
+    format!("0x{}", hash.trim().trim_start_matches("0x").to_lowercase())
+}
+
+/// The 4 byte selector of a canonical function or error signature, `0x`
+/// prefixed
+pub fn selector(signature: &str) -> String {
+    hex::encode_prefixed(&keccak256(signature.as_bytes())[..4])
+}
+
+/// Split at the commas outside of parentheses
+fn split_top_level(list: &str) -> Result<Vec<&str>> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1).ok_or_else(|| eyre!("Unbalanced )"))?,
+            ',' if depth == 0 => {
+                parts.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(eyre!("Unbalanced ("));
+    }
+    parts.push(&list[start..]);
+    Ok(parts)
+}
+
+/// The canonical form of an elementary type, e.g. `uint256` for `uint`
+fn canonical_elementary(ty: &str, user_types: &HashMap<String, String>) -> Result<String> {
+    let sized = |prefix: &str, valid: &dyn Fn(u32) -> bool| {
+        ty.strip_prefix(prefix)
+            .and_then(|n| n.parse::<u32>().ok())
+            .is_some_and(valid)
+    };
+    let fixed = Regex::new(r"^u?fixed(\d+)x(\d+)$").unwrap();
+    Ok(match ty {
+        "uint" => "uint256".into(),
+        "int" => "int256".into(),
+        "byte" => "bytes1".into(),
+        "fixed" => "fixed128x18".into(),
+        "ufixed" => "ufixed128x18".into(),
+        // an external function is encoded as its address and selector
+        "function" => "bytes24".into(),
+        "address" | "bool" | "string" | "bytes" => ty.into(),
+        _ if sized("uint", &|n| n % 8 == 0 && (8..=256).contains(&n))
+            || sized("int", &|n| n % 8 == 0 && (8..=256).contains(&n))
+            || sized("bytes", &|n| (1..=32).contains(&n)) =>
+        {
+            ty.into()
+        }
+        _ if fixed.captures(ty).is_some_and(|c| {
+            let m: u32 = c[1].parse().unwrap_or(0);
+            let n: u32 = c[2].parse().unwrap_or(81);
+            m.is_multiple_of(8) && (8..=256).contains(&m) && n <= 80
+        }) =>
+        {
+            ty.into()
+        }
+        _ => {
+            // user defined value types by canonical or unqualified name,
+            // contracts and enums are not known here
+            let unqualified = |name: &str| name.rsplit('.').next().unwrap_or(name).to_string();
+            let underlying = user_types
+                .get(ty)
+                .or_else(|| {
+                    user_types
+                        .iter()
+                        .find(|(name, _)| unqualified(name) == unqualified(ty))
+                        .map(|(_, underlying)| underlying)
+                })
+                .ok_or_else(|| eyre!("Unknown type {ty}"))?;
+            canonical_elementary(underlying, &HashMap::new())?
+        }
+    })
+}
+
+/// The canonical form of a parameter type, without its name and data
+/// location, e.g. `uint256[]` for `uint[] memory amounts`
+fn canonical_type(param: &str, user_types: &HashMap<String, String>) -> Result<String> {
+    let param = param.trim();
+    let (base, rest) = match param.strip_prefix('(') {
+        // a tuple, with its own components
+        Some(_) => {
+            let mut depth = 0usize;
+            let end = param
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|(i, _)| i)
+                .ok_or_else(|| eyre!("Unbalanced ( in {param}"))?;
+            let components = match param[1..end].trim().is_empty() {
+                true => vec![],
+                false => split_top_level(&param[1..end])?
+                    .into_iter()
+                    .map(|c| canonical_type(c, user_types))
+                    .collect::<Result<Vec<_>>>()?,
+            };
+            (format!("({})", components.join(",")), &param[end + 1..])
+        }
+        None => {
+            let end = param
+                .find(|c: char| c.is_whitespace() || c == '[')
+                .unwrap_or(param.len());
+            let base = canonical_elementary(&param[..end], user_types)?;
+            (base, &param[end..])
+        }
+    };
+
+    let mut canonical = base;
+    let mut rest = rest.trim_start();
+    while let Some(array) = rest.strip_prefix('[') {
+        let end = array
+            .find(']')
+            .ok_or_else(|| eyre!("Unbalanced [ in {param}"))?;
+        let size = array[..end].trim();
+        if !size.is_empty() && size.parse::<u64>().is_err() {
+            return Err(eyre!("Invalid array size {size} in {param}"));
+        }
+        canonical.push_str(&format!("[{size}]"));
+        rest = array[end + 1..].trim_start();
+    }
+
+    // what remains is `payable`, the data location and the name
+    let mut words = rest.split_whitespace().peekable();
+    if canonical == "address" && words.peek() == Some(&"payable") {
+        words.next();
+    }
+    if words
+        .peek()
+        .is_some_and(|w| matches!(*w, "memory" | "calldata" | "storage"))
+    {
+        words.next();
+    }
+    let identifier = Regex::new(r"^[A-Za-z_$][A-Za-z0-9_$]*$").unwrap();
+    match (words.next(), words.next()) {
+        (None, _) => Ok(canonical),
+        (Some(name), None) if identifier.is_match(name) => Ok(canonical),
+        _ => Err(eyre!("Invalid parameter {param}")),
+    }
+}
+
+/// The canonical form of a function, error or event signature: elementary
+/// types in their full form, e.g. `uint256`, parameter names and data
+/// locations removed and user defined value types replaced by their
+/// underlying type, given by canonical or unqualified name
+pub fn normalize_signature(
+    signature: &str,
+    user_types: &HashMap<String, String>,
+) -> Result<String> {
+    let signature = signature.trim();
+    let open = signature
+        .find('(')
+        .ok_or_else(|| eyre!("Missing ( in {signature}"))?;
+    let name = signature[..open].trim();
+    let name = name.strip_prefix("function ").unwrap_or(name).trim();
+    let identifier = Regex::new(r"^[A-Za-z_$][A-Za-z0-9_$]*$").unwrap();
+    if !identifier.is_match(name) {
+        return Err(eyre!("Invalid name {name} in {signature}"));
+    }
+    let params = signature[open + 1..]
+        .strip_suffix(')')
+        .ok_or_else(|| eyre!("Missing ) in {signature}"))?;
+    let params = match params.trim().is_empty() {
+        true => vec![],
+        false => split_top_level(params)?
+            .into_iter()
+            .map(|p| canonical_type(p, user_types))
+            .collect::<Result<Vec<_>>>()?,
+    };
+    Ok(format!("{name}({})", params.join(",")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hash_known_signatures() {
+        let transfer =
+            KnownSignature::new(SignatureKind::Event, "Transfer(address,address,uint256)");
+        assert_eq!(
+            transfer.hash,
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+
+        let transfer = KnownSignature::new(SignatureKind::Function, "transfer(address, uint256)");
+        assert_eq!(transfer.signature, "transfer(address,uint256)");
+        assert_eq!(transfer.hash, "0xa9059cbb");
+    }
+
+    #[test]
+    fn normalize_function_signatures() {
+        let types = HashMap::from([("Vault.Price".to_string(), "uint128".to_string())]);
+        let normalize = |s: &str| normalize_signature(s, &types).ok();
+        assert_eq!(
+            normalize("transfer(address to, uint amount)").as_deref(),
+            Some("transfer(address,uint256)")
+        );
+        assert_eq!(
+            normalize("function swap(uint[] memory amounts, address payable to, bytes calldata)")
+                .as_deref(),
+            Some("swap(uint256[],address,bytes)")
+        );
+        assert_eq!(
+            normalize("submit((uint a, (bytes32 b, int c)[2] inner)[] orders, byte)").as_deref(),
+            Some("submit((uint256,(bytes32,int256)[2])[],bytes1)")
+        );
+        assert_eq!(
+            normalize("setPrice(Price price, Vault.Price)").as_deref(),
+            Some("setPrice(uint128,uint128)")
+        );
+        assert_eq!(normalize(" owner ( ) ").as_deref(), Some("owner()"));
+        assert_eq!(normalize("f(uint7)"), None);
+        assert_eq!(normalize("f(bytes33)"), None);
+        assert_eq!(normalize("f(Unknown)"), None);
+        assert_eq!(normalize("f(uint256 a b)"), None);
+        assert_eq!(normalize("f((uint256)"), None);
+        assert_eq!(normalize("1f()"), None);
+
+        assert_eq!(selector("transfer(address,uint256)"), "0xa9059cbb");
+    }
+}
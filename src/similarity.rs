@@ -0,0 +1,186 @@
+use eyre::{ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    bytecode::{cbor_metadata, instructions, object_bytes},
+    plain_contract::PlainContract,
+};
+
+/// Number of hash functions of a MinHash signature
+const SIGNATURE_SIZE: usize = 64;
+/// Length of the opcode sequences compared between two codes
+const SHINGLE_SIZE: usize = 4;
+
+/// MinHash signature of the opcodes of the runtime bytecode of a compiled contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpcodeSignature {
+    pub contract_id: String,
+    pub contract_name: String,
+    /// Number of opcodes of the runtime bytecode
+    pub opcodes: usize,
+    /// Hex encoded signature, see [`signature_to_hex`]
+    pub signature: String,
+}
+
+/// Opcodes of the runtime code, without the data of push instructions, which
+/// holds addresses, constants and jump targets, and without the solc metadata
+pub fn opcodes(code: &[u8]) -> Vec<u8> {
+    let code = match cbor_metadata(code) {
+        Some(_) => {
+            let cbor_len = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]);
+            &code[..code.len() - 2 - cbor_len as usize]
+        }
+        None => code,
+    };
+    instructions(code)
+        .into_iter()
+        .map(|(pc, _)| code[pc])
+        .collect()
+}
+
+/// splitmix64 finalizer, a cheap well distributed hash of a word
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// MinHash signature of a set of shingles
+fn minhash_shingles(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..SIGNATURE_SIZE as u64)
+        .map(|seed| {
+            let seed = mix(seed);
+            shingles
+                .iter()
+                .map(|s| mix(s ^ seed))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// MinHash signature of the sequences of `SHINGLE_SIZE` opcodes of the code.
+/// The share of equal values of two signatures estimates the Jaccard
+/// similarity of their sequences.
+pub fn minhash(code: &[u8]) -> Vec<u64> {
+    let shingles: HashSet<u64> = opcodes(code)
+        .windows(SHINGLE_SIZE)
+        .map(|w| w.iter().fold(0u64, |acc, op| acc << 8 | *op as u64))
+        .collect();
+    minhash_shingles(&shingles)
+}
+
+/// MinHash signature of the sequences of `SHINGLE_SIZE` tokens of a source,
+/// identifiers, numbers and punctuation, so that whitespace and comment
+/// markers do not matter
+pub fn source_minhash(source: &str) -> Vec<u64> {
+    let tokens = source
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            t.bytes().fold(0xcbf2_9ce4_8422_2325u64, |acc, b| {
+                (acc ^ b as u64).wrapping_mul(0x100_0000_01b3)
+            })
+        })
+        .collect::<Vec<_>>();
+    let shingles: HashSet<u64> = tokens
+        .windows(SHINGLE_SIZE.min(tokens.len().max(1)))
+        .map(|w| w.iter().fold(0u64, |acc, t| mix(acc ^ t)))
+        .collect();
+    minhash_shingles(&shingles)
+}
+
+pub fn signature_to_hex(signature: &[u64]) -> String {
+    signature.iter().map(|v| format!("{v:016x}")).collect()
+}
+
+pub fn signature_from_hex(hex: &str) -> Option<Vec<u64>> {
+    (0..hex.len())
+        .step_by(16)
+        .map(|i| u64::from_str_radix(hex.get(i..i + 16)?, 16).ok())
+        .collect()
+}
+
+/// Estimated Jaccard similarity of the opcode sequences of two signatures, from 0 to 1
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f64 / a.len().max(b.len()).max(1) as f64
+}
+
+/// Signatures of the runtime bytecode of every artifact in the compilation output
+pub fn extract_opcode_signatures(contract: &PlainContract) -> Result<Vec<OpcodeSignature>> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut signatures = vec![];
+    for (contract_name, artifact) in compilation_output.artifacts() {
+        let Some(deployed) = artifact
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|b| b.bytecode.as_ref())
+        else {
+            continue;
+        };
+        let code = object_bytes(&deployed.object);
+        if code.is_empty() {
+            // interfaces and abstract contracts
+            continue;
+        }
+        signatures.push(OpcodeSignature {
+            contract_id: contract_id.clone(),
+            contract_name,
+            opcodes: opcodes(&code).len(),
+            signature: signature_to_hex(&minhash(&code)),
+        });
+    }
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::abi::decode_hex;
+
+    #[test]
+    fn compare_opcode_signatures() -> Result<()> {
+        // PUSH1 0x80 PUSH1 0x40 MSTORE CALLVALUE DUP1 ISZERO PUSH2 0x0010 JUMPI
+        // followed by the metadata `a1 65 "solc" 0x000813`
+        let code = decode_hex("6080604052348015610010575b00a164736f6c6343000813000a")?;
+        assert_eq!(
+            opcodes(&code),
+            vec![0x60, 0x60, 0x52, 0x34, 0x80, 0x15, 0x61, 0x57, 0x5b, 0x00]
+        );
+
+        // other push data, e.g. an immutable address, and another compiler
+        let other = decode_hex("6080604152348015610020575b00a164736f6c6343000814000a")?;
+        let signature = minhash(&code);
+        assert_eq!(similarity(&signature, &minhash(&other)), 1.0);
+
+        let different = decode_hex("5f5f5f5f3560e01c8063a9059cbb1461001e57")?;
+        assert!(similarity(&signature, &minhash(&different)) < 0.2);
+
+        let hex = signature_to_hex(&signature);
+        assert_eq!(hex.len(), SIGNATURE_SIZE * 16);
+        assert_eq!(signature_from_hex(&hex), Some(signature));
+
+        Ok(())
+    }
+
+    #[test]
+    fn compare_source_signatures() {
+        let source = "function transfer(address to, uint256 amount) public returns (bool) { _transfer(msg.sender, to, amount); return true; }";
+        let reformatted = "function transfer(address to, uint256 amount)\n    public\n    returns (bool)\n{\n    _transfer(msg.sender, to, amount);\n    return true;\n}";
+        assert_eq!(
+            similarity(&source_minhash(source), &source_minhash(reformatted)),
+            1.0
+        );
+
+        let other = "function owner() public view returns (address) { return _owner; }";
+        assert!(similarity(&source_minhash(source), &source_minhash(other)) < 0.2);
+    }
+}
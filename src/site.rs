@@ -0,0 +1,352 @@
+use eyre::Result;
+use regex::Regex;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    functions::ContractFunction,
+    overview::Overview,
+    plain_contract::{PlainContract, SourceFile},
+};
+
+/// Stylesheet shared by all pages of the site
+const STYLE: &str = r#"body { font-family: sans-serif; margin: 2em auto; max-width: 72em; color: #222; }
+a { color: #0b5cad; text-decoration: none; }
+table { border-collapse: collapse; }
+td, th { padding: 0.2em 1em 0.2em 0; text-align: left; vertical-align: top; }
+code, pre { font-family: monospace; }
+pre { background: #f6f8fa; padding: 1em; overflow-x: auto; counter-reset: line; }
+pre .line { display: block; }
+pre .line::before { counter-increment: line; content: counter(line); display: inline-block; width: 3em; color: #999; }
+pre .line:target { background: #fff3b0; }
+.kw { color: #a626a4; }
+.ty { color: #0184bc; }
+.str { color: #50a14f; }
+.num { color: #986801; }
+.com { color: #a0a1a7; font-style: italic; }
+"#;
+
+/// Solidity and Vyper tokens, by the css class highlighting them
+const TOKENS: [(&str, &str); 5] = [
+    ("com", r"//[^\n]*|/\*[\s\S]*?\*/|#[^\n]*"),
+    ("str", r#""(?:[^"\\\n]|\\.)*"|'(?:[^'\\\n]|\\.)*'"#),
+    (
+        "kw",
+        r"\b(?:pragma|import|from|as|contract|interface|library|abstract|is|using|for|struct|enum|event|error|modifier|function|constructor|fallback|receive|returns?|if|else|while|do|break|continue|emit|revert|require|assert|new|delete|try|catch|unchecked|assembly|let|public|private|internal|external|pure|view|payable|virtual|override|constant|immutable|memory|storage|calldata|indexed|anonymous|def|self|pass)\b",
+    ),
+    (
+        "ty",
+        r"\b(?:address|bool|string|bytes\d*|u?int\d*|fixed|ufixed|mapping|HashMap|DynArray|Bytes|String)\b",
+    ),
+    ("num", r"\b(?:0x[0-9a-fA-F]+|\d[\d_]*(?:\.\d+)?(?:e\d+)?)\b"),
+];
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Highlighted html of the source, one `span.line` per line with the id
+/// `<anchor>-L<line>`. Tokens spanning several lines, e.g. block comments, are
+/// split so that every line is closed.
+pub fn highlight(source: &str, anchor: &str) -> String {
+    let pattern = TOKENS
+        .iter()
+        .map(|(class, pattern)| format!("(?P<{class}>{pattern})"))
+        .collect::<Vec<_>>()
+        .join("|");
+    let tokens = Regex::new(&pattern).unwrap();
+
+    // pieces of the source with the class of their token
+    let mut pieces: Vec<(Option<&str>, &str)> = vec![];
+    let mut end = 0;
+    for captures in tokens.captures_iter(source) {
+        let (class, token) = TOKENS
+            .iter()
+            .find_map(|(class, _)| captures.name(class).map(|m| (*class, m)))
+            .unwrap();
+        pieces.push((None, &source[end..token.start()]));
+        pieces.push((Some(class), token.as_str()));
+        end = token.end();
+    }
+    pieces.push((None, &source[end..]));
+
+    let mut lines = vec![String::new()];
+    for (class, text) in pieces {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().unwrap();
+            match class {
+                Some(class) => {
+                    let _ = write!(
+                        line,
+                        r#"<span class="{class}">{}</span>"#,
+                        escape_html(part)
+                    );
+                }
+                None => line.push_str(&escape_html(part)),
+            }
+        }
+    }
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                r#"<span class="line" id="{anchor}-L{}">{line}</span>"#,
+                i + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+/// File name of the page of a contract
+pub fn contract_page(contract_id: &str) -> String {
+    format!("{contract_id}.html")
+}
+
+/// Source file and line where the function is defined, by finding its source
+/// code in the file it was compiled from
+fn function_location(files: &[SourceFile], function: &ContractFunction) -> Option<(usize, usize)> {
+    let definition = function.source_code.trim();
+    if definition.is_empty() {
+        return None;
+    }
+    files.iter().enumerate().find_map(|(i, file)| {
+        let same_file =
+            file.name.ends_with(&function.filename) || function.filename.ends_with(&file.name);
+        let offset = file.content.find(definition).filter(|_| same_file)?;
+        Some((i, file.content[..offset].matches('\n').count() + 1))
+    })
+}
+
+/// The overview section of a contract page, empty when there is nothing to
+/// show, e.g. for a contract which is not indexed
+fn render_overview(overview: &Overview) -> String {
+    let mut body = String::new();
+    if !overview.entry_points.is_empty() {
+        body.push_str("<h3>Entry points</h3>\n<table>\n");
+        for e in overview.entry_points.iter() {
+            let _ = writeln!(
+                body,
+                "<tr><td><code>{}</code></td><td>{}</td><td><code>{}</code></td></tr>",
+                escape_html(&e.signature),
+                if e.payable { "payable" } else { "" },
+                escape_html(&e.modifiers.join(" "))
+            );
+        }
+        body.push_str("</table>\n");
+    }
+    if !overview.value_flows.is_empty() {
+        body.push_str("<h3>Value flows</h3>\n<table>\n");
+        for f in overview.value_flows.iter() {
+            let _ = writeln!(
+                body,
+                "<tr><td><code>{}</code></td><td>{}</td><td><code>{}</code></td></tr>",
+                escape_html(&f.signature),
+                f.kind,
+                escape_html(&f.call)
+            );
+        }
+        body.push_str("</table>\n");
+    }
+    if !overview.roles.is_empty() {
+        body.push_str("<h3>Privileged roles</h3>\n<table>\n");
+        for r in overview.roles.iter() {
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td><code>{}</code></td><td><code>{}</code></td></tr>",
+                escape_html(&r.name),
+                escape_html(&r.source),
+                escape_html(&r.functions.join(" "))
+            );
+        }
+        body.push_str("</table>\n");
+    }
+    match body.is_empty() {
+        true => body,
+        false => format!("<h2>Overview</h2>\n{body}"),
+    }
+}
+
+/// The page of a contract: its metadata, an overview of its entry points,
+/// value flows and privileged roles, the list of its files and functions
+/// linking to their definition, and the highlighted sources
+pub fn render_contract(
+    contract: &PlainContract,
+    functions: &[ContractFunction],
+    overview: &Overview,
+) -> Result<String> {
+    let files = contract.get_source_files()?;
+    let metadata = &contract.metadata;
+    let name = escape_html(&metadata.contract_name);
+
+    let mut body =
+        format!("<p><a href=\"index.html\">All contracts</a></p>\n<h1>{name}</h1>\n<table>\n");
+    let mut row = |key: &str, value: &str| {
+        let _ = writeln!(
+            body,
+            "<tr><th>{key}</th><td>{}</td></tr>",
+            escape_html(value)
+        );
+    };
+    row("Id", &contract.id());
+    row("Compiler", &metadata.compiler_version);
+    row(
+        "Optimizer",
+        &match metadata.optimization_used {
+            true => format!("enabled, {} runs", metadata.runs),
+            false => "disabled".into(),
+        },
+    );
+    if let Some(chain_id) = metadata.chain_id {
+        row("Chain", &chain_id.to_string());
+    }
+    if let Some(address) = &metadata.address {
+        row("Address", address);
+    }
+    body.push_str("</table>\n");
+    body.push_str(&render_overview(overview));
+    body.push_str("<h2>Files</h2>\n<ul>\n");
+    for (i, file) in files.iter().enumerate() {
+        let _ = writeln!(
+            body,
+            "<li><a href=\"#f{i}\">{}</a></li>",
+            escape_html(&file.name)
+        );
+    }
+    body.push_str("</ul>\n");
+
+    if !functions.is_empty() {
+        body.push_str("<h2>Functions</h2>\n<table>\n");
+        for f in functions {
+            let signature = escape_html(&f.signature);
+            let signature = match function_location(&files, f) {
+                Some((file, line)) => format!("<a href=\"#f{file}-L{line}\">{signature}</a>"),
+                None => signature,
+            };
+            let _ = writeln!(
+                body,
+                "<tr id=\"{}\"><td><code>{}</code></td><td>{}</td><td><code>{signature}</code></td></tr>",
+                f.selector,
+                f.selector,
+                escape_html(&f.contract_name)
+            );
+        }
+        body.push_str("</table>\n");
+    }
+
+    for (i, file) in files.iter().enumerate() {
+        let anchor = format!("f{i}");
+        let _ = write!(
+            body,
+            "<h2 id=\"{anchor}\">{}</h2>\n<pre><code>{}</code></pre>\n",
+            escape_html(&file.name),
+            highlight(&file.content, &anchor)
+        );
+    }
+    Ok(page(&metadata.contract_name, &body))
+}
+
+/// A contract of the site with its indexed functions and overview
+pub type SiteContract = (PlainContract, Vec<ContractFunction>, Overview);
+
+/// The index page listing the contracts of the site
+pub fn render_index(contracts: &[SiteContract]) -> String {
+    let mut body = String::from(
+        "<h1>Contracts</h1>\n<table>\n<tr><th>Name</th><th>Compiler</th><th>Functions</th><th>Id</th></tr>\n",
+    );
+    for (contract, functions, _) in contracts {
+        let id = contract.id();
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td><code>{id}</code></td></tr>",
+            contract_page(&id),
+            escape_html(&contract.metadata.contract_name),
+            escape_html(&contract.metadata.compiler_version),
+            functions.len()
+        );
+    }
+    body.push_str("</table>\n");
+    page("Contracts", &body)
+}
+
+/// Write the index, the page of every contract and the stylesheet to the
+/// output folder, returns the written files
+pub fn export_site(output: &Path, contracts: &[SiteContract]) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output)?;
+    let mut written = vec![];
+    let mut write = |name: String, content: String| -> Result<()> {
+        let path = output.join(name);
+        std::fs::write(&path, content)?;
+        written.push(path);
+        Ok(())
+    };
+    write("style.css".into(), STYLE.into())?;
+    write("index.html".into(), render_index(contracts))?;
+    for (contract, functions, overview) in contracts {
+        write(
+            contract_page(&contract.id()),
+            render_contract(contract, functions, overview)?,
+        )?;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_contract_page() -> Result<()> {
+        let source = "pragma solidity ^0.8.0;\n/* a\n   b */\ncontract A {\n    function f(uint256 x) public returns (string memory) {\n        return \"<ok>\";\n    }\n}\n";
+        let html = highlight(source, "f0");
+        assert!(html.starts_with(r#"<span class="line" id="f0-L1"><span class="kw">pragma</span>"#));
+        // the block comment is split over its lines
+        assert!(html
+            .contains(r#"<span class="line" id="f0-L3"><span class="com">   b */</span></span>"#));
+        assert!(html.contains(r#"<span class="str">&quot;&lt;ok&gt;&quot;</span>"#));
+        assert_eq!(html.lines().count(), source.split('\n').count());
+
+        let mut contract = PlainContract::single_file(source);
+        contract.metadata.optimization_used = true;
+        let function = ContractFunction {
+            id: "1".into(),
+            contract_id: contract.id(),
+            contract_name: "A".into(),
+            function_name: "f".into(),
+            filename: "main.sol".into(),
+            signature: "f(uint256)".into(),
+            selector: "0xb3de648b".into(),
+            source_code: "function f(uint256 x) public returns (string memory) {".into(),
+        };
+        let functions = [function];
+        let overview = Overview::new("A", &functions, &[], &[]);
+        let page = render_contract(&contract, &functions, &overview)?;
+        assert!(page.contains(r##"<a href="#f0-L5">f(uint256)</a>"##));
+        assert!(page.contains("<tr><th>Optimizer</th><td>enabled, 200 runs</td></tr>"));
+        assert!(page.contains("<h2>Overview</h2>\n<h3>Entry points</h3>"));
+        assert!(!page.contains("Privileged roles"));
+        let page = render_contract(&contract, &functions, &Overview::default())?;
+        assert!(!page.contains("Overview"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use eyre::{ContextCompat, Result};
+use foundry_compilers::artifacts::{sourcemap, Node, NodeType::*};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    bytecode::{instructions, object_bytes},
+    plain_contract::PlainContract,
+};
+
+/// Bytecode sizes of a compiled contract
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactSize {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    pub runtime_size: usize,
+    pub creation_size: usize,
+}
+
+/// Number of runtime bytecode bytes generated from the source of a function
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunctionSize {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    /// Function name qualified with the contract defining it, e.g. `ERC20.transfer`
+    pub function_name: String,
+    pub size: usize,
+}
+
+/// Source range of a function or modifier definition
+struct FunctionRange {
+    name: String,
+    file: usize,
+    start: usize,
+    length: usize,
+}
+
+/// Collect the source ranges of all functions and modifiers in the AST nodes
+fn function_ranges(nodes: &[Node], ranges: &mut Vec<FunctionRange>) {
+    for node in nodes {
+        if node.node_type != ContractDefinition {
+            continue;
+        }
+        let contract_name: String = node.attribute("name").unwrap_or_default();
+        for child in node.nodes.iter() {
+            if !matches!(child.node_type, FunctionDefinition | ModifierDefinition) {
+                continue;
+            }
+            let (Some(file), Some(length)) = (child.src.index, child.src.length) else {
+                continue;
+            };
+            // constructor, fallback and receive functions have no name
+            let name = match child.attribute::<String>("name") {
+                Some(name) if !name.is_empty() => name,
+                _ => child.attribute("kind").unwrap_or_default(),
+            };
+            ranges.push(FunctionRange {
+                name: format!("{contract_name}.{name}"),
+                file,
+                start: child.src.start,
+                length,
+            });
+        }
+    }
+}
+
+/// Compute bytecode sizes of every artifact in the compilation output and
+/// attribute runtime bytecode to functions using the deployed source map.
+pub fn extract_sizes(contract: &PlainContract) -> Result<(Vec<ArtifactSize>, Vec<FunctionSize>)> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut ranges = vec![];
+    for (_, artifact) in compilation_output.artifacts() {
+        if let Some(ast) = &artifact.ast {
+            function_ranges(&ast.nodes, &mut ranges);
+        }
+    }
+
+    let mut artifact_sizes = vec![];
+    let mut function_sizes = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        let source_path = file.display().to_string();
+        let creation_size = artifact
+            .bytecode
+            .as_ref()
+            .map(|b| object_bytes(&b.object).len())
+            .unwrap_or_default();
+        let Some(deployed) = artifact
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|b| b.bytecode.as_ref())
+        else {
+            continue;
+        };
+        let code = object_bytes(&deployed.object);
+        if code.is_empty() {
+            // interfaces and abstract contracts
+            continue;
+        }
+
+        artifact_sizes.push(ArtifactSize {
+            contract_id: contract_id.clone(),
+            source_path: source_path.clone(),
+            contract_name: contract_name.clone(),
+            runtime_size: code.len(),
+            creation_size,
+        });
+
+        let Some(Ok(source_map)) = deployed.source_map.as_deref().map(sourcemap::parse) else {
+            continue;
+        };
+
+        let mut sizes: HashMap<&str, usize> = HashMap::new();
+        for ((_, size), element) in instructions(&code).into_iter().zip(source_map.iter()) {
+            let Some(file) = element.index() else {
+                continue;
+            };
+            let start = element.offset() as usize;
+            let end = start + element.length() as usize;
+            // the innermost definition containing the instruction source
+            let owner = ranges
+                .iter()
+                .filter(|r| {
+                    r.file == file as usize && r.start <= start && end <= r.start + r.length
+                })
+                .min_by_key(|r| r.length);
+            if let Some(owner) = owner {
+                *sizes.entry(&owner.name).or_default() += size;
+            }
+        }
+
+        function_sizes.extend(sizes.into_iter().map(|(name, size)| FunctionSize {
+            contract_id: contract_id.clone(),
+            source_path: source_path.clone(),
+            contract_name: contract_name.clone(),
+            function_name: name.into(),
+            size,
+        }));
+    }
+
+    Ok((artifact_sizes, function_sizes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sizes_of_same_named_contracts() -> Result<()> {
+        let artifact = |code: &str| {
+            json!({
+                "bytecode": {"object": code},
+                "deployedBytecode": {"object": code}
+            })
+        };
+        let contract = PlainContract::with_artifacts(
+            vec![
+                ("src/v1/Token.sol", "Token", artifact("0x00")),
+                ("src/v2/Token.sol", "Token", artifact("0x6000")),
+            ],
+            &[],
+        )?;
+
+        let (sizes, _) = extract_sizes(&contract)?;
+        assert_eq!(
+            sizes
+                .iter()
+                .map(|s| (s.source_path.as_str(), s.runtime_size))
+                .collect::<Vec<_>>(),
+            [("src/v1/Token.sol", 1), ("src/v2/Token.sol", 2)]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use eyre::{ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::plain_contract::PlainContract;
+
+/// Source maps of a compiled contract, mapping the program counters of its
+/// bytecode to ranges of the source files by their file id
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactSourceMap {
+    pub contract_id: String,
+    /// The source file defining the contract, e.g. `src/Token.sol`
+    pub source_path: String,
+    pub contract_name: String,
+    pub creation_source_map: Option<String>,
+    pub runtime_source_map: Option<String>,
+}
+
+/// The path of a source file by the id the compiler gave it, the file index
+/// of the source maps and of the `src` attributes of the AST
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SourceFileId {
+    pub contract_id: String,
+    pub file_id: u32,
+    pub path: String,
+}
+
+/// Return the source maps of every artifact with bytecode and the ids of every
+/// compiled source file, imported files without contracts included
+pub fn extract_source_maps(
+    contract: &PlainContract,
+) -> Result<(Vec<ArtifactSourceMap>, Vec<SourceFileId>)> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    // source maps refer to any file of the compilation, e.g. a library
+    // inlined from an imported file
+    let mut files = BTreeMap::new();
+    for (path, file) in compilation_output.output().sources.sources() {
+        files.insert(file.id, path.display().to_string());
+    }
+
+    let mut source_maps = vec![];
+    for (file, contract_name, artifact) in compilation_output.artifacts_with_files() {
+        // outputs restored from the artifact cache may lack the sources
+        if let Some(source) = artifact.source_file() {
+            files
+                .entry(source.id)
+                .or_insert_with(|| file.display().to_string());
+        }
+
+        let creation_source_map = artifact
+            .bytecode
+            .as_ref()
+            .and_then(|b| b.source_map.clone());
+        let runtime_source_map = artifact
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|b| b.bytecode.as_ref())
+            .and_then(|b| b.source_map.clone());
+        if creation_source_map.is_none() && runtime_source_map.is_none() {
+            continue;
+        }
+        source_maps.push(ArtifactSourceMap {
+            contract_id: contract_id.clone(),
+            source_path: file.display().to_string(),
+            contract_name: contract_name.clone(),
+            creation_source_map,
+            runtime_source_map,
+        });
+    }
+
+    let files = files
+        .into_iter()
+        .map(|(file_id, path)| SourceFileId {
+            contract_id: contract_id.clone(),
+            file_id,
+            path,
+        })
+        .collect();
+    Ok((source_maps, files))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn source_maps_of_same_named_contracts() -> Result<()> {
+        let artifact = |map: &str| {
+            json!({
+                "bytecode": {"object": "0x00", "sourceMap": map},
+                "deployedBytecode": {"object": "0x00", "sourceMap": map}
+            })
+        };
+        // both tokens call a library of an imported file without artifacts
+        let contract = PlainContract::with_artifacts(
+            vec![
+                ("src/A.sol", "Token", artifact("0:1:0;2:3:2")),
+                ("src/B.sol", "Token", artifact("0:1:1;2:3:2")),
+            ],
+            &[("src/A.sol", 0), ("src/B.sol", 1), ("src/Lib.sol", 2)],
+        )?;
+
+        let (source_maps, files) = extract_source_maps(&contract)?;
+        assert_eq!(
+            source_maps
+                .iter()
+                .map(|m| (m.source_path.as_str(), m.runtime_source_map.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                ("src/A.sol", Some("0:1:0;2:3:2")),
+                ("src/B.sol", Some("0:1:1;2:3:2"))
+            ]
+        );
+        assert_eq!(
+            files
+                .iter()
+                .map(|f| (f.file_id, f.path.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, "src/A.sol"), (1, "src/B.sol"), (2, "src/Lib.sol")]
+        );
+
+        Ok(())
+    }
+}
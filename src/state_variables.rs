@@ -0,0 +1,149 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ast_query::{descendants, source_asts},
+    plain_contract::PlainContract,
+};
+
+/// Slot number in decimal and byte offset inside the slot of a variable
+pub type Slot = (String, i64);
+
+/// A state variable declared in a contract of a source file. Its slot is the
+/// [`crate::layout::StorageSlot`] with its name in the layout of the declaring
+/// contract.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct StateVariable {
+    pub contract_id: String,
+    /// The source file declaring the variable, e.g. `src/Vault.sol`
+    pub source_path: String,
+    /// The contract declaring the variable, e.g. `Ownable`
+    pub contract_name: String,
+    pub name: String,
+    /// Type as written in solidity, e.g. `mapping(address => uint256)`
+    pub ty: String,
+    /// `public`, `internal` or `private`
+    pub visibility: String,
+    /// `mutable`, `constant` or `immutable`
+    pub mutability: String,
+}
+
+fn string<'a>(node: &'a Value, key: &str) -> &'a str {
+    node.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Mutability of a variable declaration, solc before 0.6.5 only has the
+/// `constant` flag
+fn mutability(node: &Value) -> &str {
+    match node.get("mutability").and_then(Value::as_str) {
+        Some(mutability) => mutability,
+        None if node.get("constant").and_then(Value::as_bool) == Some(true) => "constant",
+        None => "mutable",
+    }
+}
+
+/// The state variable declarations of a single source file AST, with the name
+/// of their contract
+pub fn ast_state_variables(ast: &Value) -> Vec<(&str, &Value)> {
+    let mut nodes = vec![];
+    descendants(ast, &mut nodes);
+    nodes
+        .into_iter()
+        .filter(|node| string(node, "nodeType") == "ContractDefinition")
+        .flat_map(|contract| {
+            contract
+                .get("nodes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|node| {
+                    string(node, "nodeType") == "VariableDeclaration"
+                        && node.get("stateVariable").and_then(Value::as_bool) != Some(false)
+                })
+                .map(move |node| (string(contract, "name"), node))
+        })
+        .collect()
+}
+
+/// Return the state variables declared in every compiled source file
+pub fn extract_state_variables(contract: &PlainContract) -> Result<Vec<StateVariable>> {
+    let contract_id = contract.id();
+
+    let mut variables = vec![];
+    for (path, ast) in source_asts(contract)? {
+        for (contract_name, node) in ast_state_variables(&ast) {
+            variables.push(StateVariable {
+                contract_id: contract_id.clone(),
+                source_path: path.clone(),
+                contract_name: contract_name.into(),
+                name: string(node, "name").into(),
+                ty: node
+                    .pointer("/typeDescriptions/typeString")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .into(),
+                visibility: string(node, "visibility").into(),
+                mutability: mutability(node).into(),
+            });
+        }
+    }
+
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn find_state_variables() {
+        let variable = |name: &str, ty: &str, extra: Value| {
+            let mut node = json!({
+                "nodeType": "VariableDeclaration",
+                "name": name,
+                "stateVariable": true,
+                "visibility": "internal",
+                "typeDescriptions": {"typeString": ty}
+            });
+            node.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            node
+        };
+        let ast = json!({
+            "nodeType": "SourceUnit",
+            "nodes": [{
+                "nodeType": "ContractDefinition",
+                "name": "Vault",
+                "nodes": [
+                    variable("owner", "address", json!({"mutability": "mutable", "visibility": "public"})),
+                    variable("asset", "contract IERC20", json!({"mutability": "immutable"})),
+                    variable("FEE", "uint256", json!({"constant": true})),
+                    {"nodeType": "FunctionDefinition", "name": "deposit"}
+                ]
+            }]
+        });
+
+        let variables: Vec<_> = ast_state_variables(&ast)
+            .into_iter()
+            .map(|(contract_name, node)| {
+                (
+                    contract_name,
+                    string(node, "name"),
+                    string(node, "visibility"),
+                    mutability(node),
+                )
+            })
+            .collect();
+        assert_eq!(
+            variables,
+            [
+                ("Vault", "owner", "public", "mutable"),
+                ("Vault", "asset", "internal", "immutable"),
+                ("Vault", "FEE", "internal", "constant")
+            ]
+        );
+    }
+}
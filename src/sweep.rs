@@ -0,0 +1,158 @@
+use alloy_primitives::{hex, keccak256};
+use foundry_compilers::ProjectCompileOutput;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of contracts compiled in a sweep and how many of them succeeded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct SweepStats {
+    pub attempted: usize,
+    pub succeeded: usize,
+}
+
+impl SweepStats {
+    fn add(&mut self, success: bool) {
+        self.attempted += 1;
+        self.succeeded += success as usize;
+    }
+
+    /// Share of the attempted contracts compiling without errors, in percent
+    pub fn success_rate(&self) -> f64 {
+        match self.attempted {
+            0 => 0.0,
+            attempted => self.succeeded as f64 * 100.0 / attempted as f64,
+        }
+    }
+}
+
+/// `0x` prefixed keccak256 of every artifact of a compilation output, by
+/// `<source file>:<contract name>`
+pub fn artifact_hashes(output: &ProjectCompileOutput) -> BTreeMap<String, String> {
+    output
+        .artifacts_with_files()
+        .map(|(file, name, artifact)| {
+            let json = serde_json::to_vec(artifact).unwrap_or_default();
+            (
+                format!("{}:{name}", file.display()),
+                hex::encode_prefixed(keccak256(json)),
+            )
+        })
+        .collect()
+}
+
+/// Artifacts whose hash differs between two compilations of the same
+/// contract, or which only one of them produced
+pub fn differing_artifacts(
+    first: &BTreeMap<String, String>,
+    second: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let names: BTreeSet<_> = first.keys().chain(second.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| first.get(*name) != second.get(*name))
+        .cloned()
+        .collect()
+}
+
+/// A contract whose artifacts differ between two compilations
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Nondeterminism {
+    pub contract_id: String,
+    pub artifacts: Vec<String>,
+}
+
+/// Compilation success of a sweep over the corpus, segmented by compiler
+/// version and source type
+#[derive(Debug, Default, Serialize)]
+pub struct SweepReport {
+    pub total: SweepStats,
+    pub by_compiler_version: BTreeMap<String, SweepStats>,
+    pub by_source_type: BTreeMap<String, SweepStats>,
+    /// Contracts compiled twice by `--check-determinism`, i.e. the successful ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub determinism_checked: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nondeterministic: Vec<Nondeterminism>,
+}
+
+impl SweepReport {
+    /// Record the outcome of compiling one contract
+    pub fn add(&mut self, compiler_version: &str, source_type: &str, success: bool) {
+        self.total.add(success);
+        self.by_compiler_version
+            .entry(compiler_version.into())
+            .or_default()
+            .add(success);
+        self.by_source_type
+            .entry(source_type.into())
+            .or_default()
+            .add(success);
+    }
+
+    /// Record the artifacts differing between two successful compilations of
+    /// a contract, if any
+    pub fn add_determinism(&mut self, contract_id: &str, artifacts: Vec<String>) {
+        *self.determinism_checked.get_or_insert(0) += 1;
+        if !artifacts.is_empty() {
+            self.nondeterministic.push(Nondeterminism {
+                contract_id: contract_id.into(),
+                artifacts,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn segment_sweep_results() {
+        let mut report = SweepReport::default();
+        report.add("0.8.19", "json", true);
+        report.add("0.8.19", "single_sol", false);
+        report.add("0.7.6", "json", true);
+        report.add("0.7.6", "json", true);
+
+        assert_eq!(report.total.attempted, 4);
+        assert_eq!(report.total.success_rate(), 75.0);
+        assert_eq!(
+            report.by_compiler_version["0.8.19"],
+            SweepStats {
+                attempted: 2,
+                succeeded: 1
+            }
+        );
+        assert_eq!(report.by_source_type["json"].success_rate(), 100.0);
+        assert_eq!(report.by_source_type["single_sol"].success_rate(), 0.0);
+        assert_eq!(report.determinism_checked, None);
+    }
+
+    #[test]
+    fn report_differing_artifacts() {
+        let hashes = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(name, hash)| (name.to_string(), hash.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let first = hashes(&[("main.sol:A", "0x01"), ("main.sol:B", "0x02")]);
+        assert!(differing_artifacts(&first, &first).is_empty());
+        let second = hashes(&[
+            ("main.sol:A", "0x01"),
+            ("main.sol:B", "0x03"),
+            ("main.sol:C", "0x04"),
+        ]);
+        assert_eq!(
+            differing_artifacts(&first, &second),
+            ["main.sol:B", "main.sol:C"]
+        );
+
+        let mut report = SweepReport::default();
+        report.add_determinism("a", vec![]);
+        report.add_determinism("b", differing_artifacts(&first, &second));
+        assert_eq!(report.determinism_checked, Some(2));
+        assert_eq!(report.nondeterministic.len(), 1);
+        assert_eq!(report.nondeterministic[0].contract_id, "b");
+    }
+}
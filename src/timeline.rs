@@ -0,0 +1,194 @@
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+/// Release dates of the solidity compiler, in the format of
+/// https://github.com/ethereum/solidity/blob/develop/docs/bugs_by_version.json
+const BUNDLED_RELEASES: &str = include_str!("../data/solidity-releases.json");
+
+#[derive(Deserialize)]
+struct Release {
+    /// `YYYY-MM-DD`
+    released: String,
+}
+
+/// Release month, `YYYY-MM`, of every solidity version, e.g. `0.8.19`
+pub fn load_releases(path: Option<&str>) -> Result<HashMap<String, String>> {
+    let content = match path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => BUNDLED_RELEASES.to_string(),
+    };
+    let releases: HashMap<String, Release> = serde_json::from_str(&content)?;
+    releases
+        .into_iter()
+        .map(|(version, release)| match release.released.get(..7) {
+            Some(month) => Ok((version, month.to_string())),
+            None => Err(eyre!(
+                "Invalid release date of {version}: {}",
+                release.released
+            )),
+        })
+        .collect()
+}
+
+/// Contracts compiled with the versions released in a month
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TimelineMonth {
+    /// `YYYY-MM`
+    pub month: String,
+    pub contracts: u64,
+    /// Versions released in the month used by at least one contract
+    pub versions: Vec<String>,
+}
+
+/// Solidity contracts by release month of their compiler, months without a
+/// release are included with no contracts
+#[derive(Debug, Default, Serialize)]
+pub struct CompilerTimeline {
+    pub months: Vec<TimelineMonth>,
+    /// Contracts whose compiler version has no known release date, e.g. a
+    /// version newer than the release list
+    pub unknown: u64,
+}
+
+/// The month after `YYYY-MM`
+fn next_month(month: &str) -> Option<String> {
+    let (year, month) = month.split_once('-')?;
+    let (year, month): (u32, u32) = (year.parse().ok()?, month.parse().ok()?);
+    Some(match month {
+        12 => format!("{}-01", year + 1),
+        _ => format!("{year}-{:02}", month + 1),
+    })
+}
+
+impl CompilerTimeline {
+    /// Aggregate the number of contracts by compiler version, e.g. `0.8.19`
+    pub fn new(counts: &[(String, u64)], releases: &HashMap<String, String>) -> Self {
+        let mut timeline = Self::default();
+        let mut months: BTreeMap<&str, (u64, Vec<String>)> = BTreeMap::new();
+        for (version, contracts) in counts {
+            match releases.get(version) {
+                Some(month) => {
+                    let entry = months.entry(month).or_default();
+                    entry.0 += contracts;
+                    entry.1.push(version.clone());
+                }
+                None => timeline.unknown += contracts,
+            }
+        }
+
+        let (Some(first), Some(last)) = (months.keys().next(), months.keys().last()) else {
+            return timeline;
+        };
+        let (mut month, last) = (first.to_string(), last.to_string());
+        while month <= last {
+            let (contracts, mut versions) = months.remove(month.as_str()).unwrap_or_default();
+            versions.sort_by_key(|v| semver::Version::parse(v).ok());
+            let next = next_month(&month);
+            timeline.months.push(TimelineMonth {
+                month,
+                contracts,
+                versions,
+            });
+            match next {
+                Some(next) => month = next,
+                None => break,
+            }
+        }
+        timeline
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("month,contracts,versions\n");
+        for m in self.months.iter() {
+            let _ = writeln!(csv, "{},{},{}", m.month, m.contracts, m.versions.join(" "));
+        }
+        csv
+    }
+
+    /// Horizontal bar chart with one row per month, the longest bar is `width`
+    /// characters wide
+    pub fn chart(&self, width: usize) -> String {
+        let max = self.months.iter().map(|m| m.contracts).max().unwrap_or(0);
+        let mut chart = String::new();
+        for m in self.months.iter() {
+            let bar = match max {
+                0 => 0,
+                _ => (m.contracts as f64 / max as f64 * width as f64).ceil() as usize,
+            };
+            let _ = writeln!(
+                chart,
+                "{} {:<width$} {:>8} {}",
+                m.month,
+                "#".repeat(bar),
+                m.contracts,
+                m.versions.join(" ")
+            );
+        }
+        chart
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregate_by_release_month() -> Result<()> {
+        let releases = load_releases(None)?;
+        assert_eq!(releases["0.8.19"], "2023-02");
+
+        let counts = [
+            ("0.8.19".to_string(), 5),
+            ("0.8.18".to_string(), 2),
+            ("0.8.20".to_string(), 1),
+            ("0.9.0".to_string(), 3),
+        ];
+        let timeline = CompilerTimeline::new(&counts, &releases);
+        assert_eq!(timeline.unknown, 3);
+        assert_eq!(
+            timeline
+                .months
+                .iter()
+                .map(|m| m.month.as_str())
+                .collect::<Vec<_>>(),
+            ["2023-02", "2023-03", "2023-04", "2023-05"]
+        );
+        assert_eq!(timeline.months[0].contracts, 7);
+        assert_eq!(timeline.months[0].versions, ["0.8.18", "0.8.19"]);
+        assert_eq!(timeline.months[1].contracts, 0);
+        assert_eq!(
+            timeline.to_csv().lines().nth(1),
+            Some("2023-02,7,0.8.18 0.8.19")
+        );
+        assert!(timeline.chart(7).starts_with("2023-02 ####### "));
+        assert_eq!(next_month("2023-12").as_deref(), Some("2024-01"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundled_releases_are_complete() -> Result<()> {
+        let releases = load_releases(None)?;
+        assert_eq!(releases["0.8.30"], "2025-05");
+        assert_eq!(releases["0.1.0"], "2015-07");
+
+        // every patch release of a minor version is listed
+        let mut latest: BTreeMap<(u64, u64), u64> = BTreeMap::new();
+        for version in releases.keys() {
+            let version = semver::Version::parse(version)?;
+            let patch = latest.entry((version.major, version.minor)).or_default();
+            *patch = (*patch).max(version.patch);
+        }
+        for ((major, minor), patch) in latest {
+            for patch in 0..=patch {
+                assert!(releases.contains_key(&format!("{major}.{minor}.{patch}")));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    functions::ContractFunction,
+    similarity::{similarity, source_minhash},
+};
+
+/// A training example for contrastive embeddings of functions: the positive
+/// is another implementation of the anchor's signature, the negative a
+/// function with another signature
+#[derive(Debug, Serialize)]
+pub struct Triplet {
+    pub anchor_id: String,
+    pub anchor: String,
+    pub positive_id: String,
+    pub positive: String,
+    /// Estimated Jaccard similarity of the token sequences of the anchor and
+    /// the positive
+    pub positive_similarity: f64,
+    pub negative_id: String,
+    pub negative: String,
+    pub negative_similarity: f64,
+}
+
+/// Build a triplet for each function with another implementation of its
+/// signature at least `min_positive_similarity` similar, the most similar one
+/// being the positive. The negative is the most similar of
+/// `negative_candidates` random functions with another signature, i.e. a hard
+/// negative. Functions with the same source are considered once.
+pub fn build_triplets(
+    functions: &[ContractFunction],
+    min_positive_similarity: f64,
+    negative_candidates: usize,
+    rng: &mut impl Rng,
+) -> Vec<Triplet> {
+    let mut sources = HashSet::new();
+    let functions = functions
+        .iter()
+        .filter(|f| sources.insert(f.source_code.as_str()))
+        .collect::<Vec<_>>();
+    let signatures = functions
+        .iter()
+        .map(|f| source_minhash(&f.source_code))
+        .collect::<Vec<_>>();
+    let mut clusters: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, f) in functions.iter().enumerate() {
+        clusters.entry(f.signature.as_str()).or_default().push(i);
+    }
+
+    let mut triplets = vec![];
+    for cluster in clusters.values().filter(|c| c.len() > 1) {
+        for &anchor in cluster.iter() {
+            let Some((positive, positive_similarity)) = cluster
+                .iter()
+                .filter(|&&i| i != anchor)
+                .map(|&i| (i, similarity(&signatures[anchor], &signatures[i])))
+                .filter(|(_, s)| *s >= min_positive_similarity)
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            else {
+                continue;
+            };
+
+            // sampled with retries, as the signature of most candidates differs
+            let negative = (0..negative_candidates * 4)
+                .map(|_| rng.gen_range(0..functions.len()))
+                .filter(|&i| functions[i].signature != functions[anchor].signature)
+                .take(negative_candidates)
+                .map(|i| (i, similarity(&signatures[anchor], &signatures[i])))
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+            let Some((negative, negative_similarity)) = negative else {
+                continue;
+            };
+
+            triplets.push(Triplet {
+                anchor_id: functions[anchor].id.clone(),
+                anchor: functions[anchor].source_code.clone(),
+                positive_id: functions[positive].id.clone(),
+                positive: functions[positive].source_code.clone(),
+                positive_similarity,
+                negative_id: functions[negative].id.clone(),
+                negative: functions[negative].source_code.clone(),
+                negative_similarity,
+            });
+        }
+    }
+    triplets
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn build_function_triplets() {
+        let function = |id: &str, signature: &str, source_code: &str| ContractFunction {
+            id: id.into(),
+            contract_id: format!("contract-{id}"),
+            contract_name: "Token".into(),
+            function_name: signature.split('(').next().unwrap_or_default().into(),
+            filename: "Token.sol".into(),
+            signature: signature.into(),
+            selector: "0x00000000".into(),
+            source_code: source_code.into(),
+        };
+        let functions = [
+            function("a", "transfer(address,uint256)", "function transfer(address to, uint256 amount) public returns (bool) { _transfer(msg.sender, to, amount); return true; }"),
+            function("b", "transfer(address,uint256)", "function transfer(address to, uint256 value) public returns (bool) { _transfer(msg.sender, to, value); return true; }"),
+            // the same source as `a`, not a positive
+            function("c", "transfer(address,uint256)", "function transfer(address to, uint256 amount) public returns (bool) { _transfer(msg.sender, to, amount); return true; }"),
+            function("d", "owner()", "function owner() public view returns (address) { return _owner; }"),
+        ];
+
+        let triplets = build_triplets(&functions, 0.1, 4, &mut StdRng::seed_from_u64(0));
+        assert_eq!(
+            triplets
+                .iter()
+                .map(|t| (
+                    t.anchor_id.as_str(),
+                    t.positive_id.as_str(),
+                    t.negative_id.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            [("a", "b", "d"), ("b", "a", "d")]
+        );
+        assert!(triplets[0].positive_similarity > triplets[0].negative_similarity);
+
+        assert!(build_triplets(&functions, 1.0, 4, &mut StdRng::seed_from_u64(0)).is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+use eyre::{eyre, ContextCompat, Result};
+use log::warn;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::plain_contract::{
+    ContractSource, Metadata, PlainContract, SourceFile, SourcifyOptimizer,
+};
+
+/// Layout of a project compiled to Truffle style artifacts in `build/contracts`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectLayout {
+    Truffle,
+    Brownie,
+}
+
+impl ProjectLayout {
+    /// Detect the layout from the config file in the project root
+    pub fn detect(root: &Path) -> Option<Self> {
+        if !root.join("build/contracts").is_dir() {
+            return None;
+        }
+        if ["truffle-config.js", "truffle.js"]
+            .iter()
+            .any(|f| root.join(f).is_file())
+        {
+            return Some(Self::Truffle);
+        }
+        if ["brownie-config.yaml", "brownie-config.yml"]
+            .iter()
+            .any(|f| root.join(f).is_file())
+        {
+            return Some(Self::Brownie);
+        }
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactCompiler {
+    version: String,
+    /// Written by Brownie, Truffle keeps the settings in the metadata
+    optimizer: Option<SourcifyOptimizer>,
+    evm_version: Option<String>,
+}
+
+/// A contract of `build/contracts`, the format written by Truffle and followed
+/// by Brownie. Only the fields needed to rebuild the contract.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Artifact {
+    contract_name: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    source_path: String,
+    #[serde(default)]
+    deployed_bytecode: String,
+    /// The solidity metadata as a string, written by Truffle
+    metadata: Option<String>,
+    compiler: Option<ArtifactCompiler>,
+    /// `Solidity` or `Vyper`, written by Brownie
+    language: Option<String>,
+}
+
+impl Artifact {
+    fn metadata(&self) -> Value {
+        self.metadata
+            .as_deref()
+            .and_then(|m| serde_json::from_str(m).ok())
+            .unwrap_or_default()
+    }
+
+    /// Name of the source unit as compiled, e.g. `project:/contracts/A.sol`
+    /// for Truffle or `contracts/A.sol` for Brownie
+    fn source_name(&self) -> String {
+        self.metadata()["settings"]["compilationTarget"]
+            .as_object()
+            .and_then(|target| target.keys().next().cloned())
+            .unwrap_or_else(|| self.source_path.clone())
+    }
+
+    fn optimizer(&self) -> SourcifyOptimizer {
+        match self.compiler.as_ref().and_then(|c| c.optimizer.as_ref()) {
+            Some(optimizer) => SourcifyOptimizer {
+                enabled: optimizer.enabled,
+                runs: optimizer.runs,
+            },
+            None => serde_json::from_value(self.metadata()["settings"]["optimizer"].clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn evm_version(&self) -> Option<String> {
+        self.compiler
+            .as_ref()
+            .and_then(|c| c.evm_version.clone())
+            .or_else(|| {
+                self.metadata()["settings"]["evmVersion"]
+                    .as_str()
+                    .map(String::from)
+            })
+    }
+}
+
+/// Whether the source unit belongs to a package rather than to the project
+fn is_dependency(source_name: &str) -> bool {
+    source_name.starts_with('@') || source_name.contains("node_modules")
+}
+
+/// Remappings of the `compiler.solc.remappings` list of a Brownie config
+fn brownie_remappings(config: &str) -> Vec<String> {
+    let remapping = Regex::new(r#"^\s*-\s*["']?([^"'\s=]+=[^"'\s]+)["']?\s*$"#).unwrap();
+    config
+        .lines()
+        .filter_map(|line| remapping.captures(line).map(|c| c[1].to_string()))
+        .collect()
+}
+
+/// Read the artifacts of a Truffle or Brownie project, Brownie keeps the
+/// artifacts of packages in `build/contracts/dependencies`
+fn read_artifacts(root: &Path) -> Result<Vec<(Artifact, bool)>> {
+    let build = root.join("build/contracts");
+    let mut artifacts = vec![];
+    for entry in WalkDir::new(&build)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+    {
+        let path = entry.path();
+        let artifact: Artifact = match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(artifact) => artifact,
+            Err(e) => {
+                warn!("Skipping artifact {path:?}: {e}");
+                continue;
+            }
+        };
+        if artifact
+            .language
+            .as_deref()
+            .is_some_and(|l| l != "Solidity")
+        {
+            continue;
+        }
+        let dependency =
+            path.starts_with(build.join("dependencies")) || is_dependency(&artifact.source_name());
+        artifacts.push((artifact, dependency));
+    }
+    Ok(artifacts)
+}
+
+/// Parse a Truffle or Brownie project as a standard json contract built from
+/// the sources embedded in its artifacts. The main contract is the project
+/// contract with the largest deployed bytecode, the compiler settings are the
+/// ones it was compiled with.
+pub fn load_project(root: &Path) -> Result<PlainContract> {
+    let layout = ProjectLayout::detect(root)
+        .with_context(|| format!("Neither a Truffle nor a Brownie project: {root:?}"))?;
+    let artifacts = read_artifacts(root)?;
+    let (main, _) = artifacts
+        .iter()
+        .filter(|(_, dependency)| !dependency)
+        .max_by_key(|(a, _)| a.deployed_bytecode.len())
+        .with_context(|| format!("No project artifacts in {root:?}"))?;
+    let compiler_version = main
+        .compiler
+        .as_ref()
+        .map(|c| c.version.clone())
+        .with_context(|| format!("Missing compiler version of {}", main.contract_name))?;
+
+    let mut sources = serde_json::Map::new();
+    for (artifact, _) in artifacts.iter() {
+        if artifact.source.is_empty() {
+            continue;
+        }
+        sources
+            .entry(artifact.source_name())
+            .or_insert_with(|| serde_json::json!({ "content": artifact.source }));
+    }
+    if sources.is_empty() {
+        return Err(eyre!("No sources in the artifacts of {root:?}"));
+    }
+
+    let remappings: Vec<String> = match layout {
+        ProjectLayout::Truffle => main.metadata()["settings"]["remappings"]
+            .as_array()
+            .map(|r| {
+                r.iter()
+                    .filter_map(|r| r.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ProjectLayout::Brownie => ["brownie-config.yaml", "brownie-config.yml"]
+            .iter()
+            .find_map(|f| std::fs::read_to_string(root.join(f)).ok())
+            .map(|config| brownie_remappings(&config))
+            .unwrap_or_default(),
+    };
+    let optimizer = main.optimizer();
+    let mut settings = serde_json::json!({
+        "remappings": remappings,
+        "optimizer": {"enabled": optimizer.enabled, "runs": optimizer.runs},
+    });
+    if let Some(evm_version) = main.evm_version() {
+        settings["evmVersion"] = evm_version.into();
+    }
+    let input = serde_json::json!({
+        "language": "Solidity",
+        "sources": sources,
+        "settings": settings,
+    });
+
+    let metadata = Metadata {
+        contract_name: main.contract_name.clone(),
+        compiler_version,
+        runs: optimizer.runs,
+        optimization_used: optimizer.enabled,
+        bytecode_hash: "".into(),
+        constructor_arguments: None,
+        chain_id: None,
+        address: None,
+        evm_version: None,
+        via_ir: None,
+    };
+    let source = ContractSource::Json(SourceFile {
+        name: "contract.json".into(),
+        content: serde_json::to_string(&input)?,
+    });
+    Ok(PlainContract::new(metadata, source))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plain_contract::StandardJson;
+
+    fn source_names(contract: &PlainContract) -> Result<(Vec<String>, Vec<String>)> {
+        let ContractSource::Json(source) = &contract.source else {
+            panic!("Expected a standard json contract");
+        };
+        let json: StandardJson = serde_json::from_str(&source.content)?;
+        let remappings = json.settings.context("Missing settings")?.remappings;
+        let mut names: Vec<_> = json.sources.keys().cloned().collect();
+        names.sort();
+        Ok((names, remappings.iter().map(|r| r.to_string()).collect()))
+    }
+
+    #[test]
+    fn load_truffle_project() -> Result<()> {
+        let root = Path::new("./contracts/truffle");
+        assert_eq!(ProjectLayout::detect(root), Some(ProjectLayout::Truffle));
+        let contract = load_project(root)?;
+
+        assert_eq!(contract.metadata.contract_name, "MetaCoin");
+        assert_eq!(
+            contract.metadata.compiler_version,
+            "0.5.16+commit.9c3226ce.Emscripten.clang"
+        );
+        assert!(!contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 200);
+        let (sources, _) = source_names(&contract)?;
+        assert_eq!(
+            sources,
+            vec![
+                "project:/contracts/ConvertLib.sol",
+                "project:/contracts/MetaCoin.sol"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_brownie_project() -> Result<()> {
+        let root = Path::new("./contracts/brownie");
+        assert_eq!(ProjectLayout::detect(root), Some(ProjectLayout::Brownie));
+        let contract = load_project(root)?;
+
+        assert_eq!(contract.metadata.contract_name, "Token");
+        assert_eq!(contract.metadata.compiler_version, "0.8.4+commit.c7e474f2");
+        assert!(contract.metadata.optimization_used);
+        assert_eq!(contract.metadata.runs, 200);
+        let (sources, remappings) = source_names(&contract)?;
+        assert_eq!(
+            sources,
+            vec![
+                "OpenZeppelin/openzeppelin-contracts@4.0.0/contracts/token/ERC20/ERC20.sol",
+                "contracts/Token.sol"
+            ]
+        );
+        assert_eq!(
+            remappings,
+            vec!["@openzeppelin=OpenZeppelin/openzeppelin-contracts@4.0.0/"]
+        );
+
+        assert_eq!(
+            ProjectLayout::detect(Path::new("./contracts/foundry")),
+            None
+        );
+
+        Ok(())
+    }
+}
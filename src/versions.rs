@@ -0,0 +1,211 @@
+use alloy_primitives::{hex, keccak256};
+use eyre::{ContextCompat, Result};
+use foundry_compilers::solc::Solc;
+use log::debug;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    artifact_cache::ArtifactCache,
+    bytecode::object_bytes,
+    plain_contract::PlainContract,
+    pragma::{compatible_versions, contract_pragmas},
+    utils::is_offline,
+};
+
+/// A contract compiled with one of the solc versions its pragmas allow, to
+/// study the effect of the compiler version on the same sources
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionedArtifact {
+    pub contract_id: String,
+    pub contract_name: String,
+    pub solc_version: String,
+    pub runtime_size: usize,
+    pub creation_size: usize,
+    /// `0x` prefixed keccak256 of the runtime bytecode
+    pub runtime_code_hash: String,
+    /// Estimated gas of the deployment, `None` when solc estimates it as
+    /// infinite
+    pub deployment_gas: Option<u64>,
+}
+
+/// Gas estimate of an external or internal function for a solc version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionedGasEstimate {
+    pub contract_id: String,
+    pub contract_name: String,
+    pub solc_version: String,
+    /// As estimated by solc, e.g. `transfer(address,uint256)` for external
+    /// and `_transfer(address,address,uint256)` for internal functions
+    pub signature: String,
+    /// `None` when solc estimates it as infinite, e.g. for loops
+    pub gas: Option<u64>,
+}
+
+/// A gas estimate of solc, either a number or `infinite`
+fn parse_gas(estimate: &str) -> Option<u64> {
+    estimate.parse().ok()
+}
+
+/// The artifacts and gas estimates of a contract compiled with `solc_version`,
+/// which needs to be compiled with [`PlainContract::estimate_gas`] set
+pub fn extract_versioned(
+    contract: &PlainContract,
+    solc_version: &str,
+) -> Result<(Vec<VersionedArtifact>, Vec<VersionedGasEstimate>)> {
+    let compilation_output = contract
+        .compilation_output
+        .as_ref()
+        .context("No compilation output")?;
+    let contract_id = contract.id();
+
+    let mut artifacts = vec![];
+    let mut estimates = vec![];
+    for (contract_name, artifact) in compilation_output.artifacts() {
+        let Some(deployed) = artifact
+            .deployed_bytecode
+            .as_ref()
+            .and_then(|b| b.bytecode.as_ref())
+        else {
+            continue;
+        };
+        let code = object_bytes(&deployed.object);
+        if code.is_empty() {
+            // interfaces and abstract contracts
+            continue;
+        }
+        let creation_size = artifact
+            .bytecode
+            .as_ref()
+            .map(|b| object_bytes(&b.object).len())
+            .unwrap_or_default();
+
+        artifacts.push(VersionedArtifact {
+            contract_id: contract_id.clone(),
+            contract_name: contract_name.clone(),
+            solc_version: solc_version.to_string(),
+            runtime_size: code.len(),
+            creation_size,
+            runtime_code_hash: hex::encode_prefixed(keccak256(&code)),
+            deployment_gas: artifact
+                .gas_estimates
+                .as_ref()
+                .and_then(|g| parse_gas(&g.creation.total_cost)),
+        });
+
+        let Some(gas_estimates) = &artifact.gas_estimates else {
+            continue;
+        };
+        for (signature, gas) in gas_estimates
+            .external
+            .iter()
+            .chain(gas_estimates.internal.iter())
+        {
+            estimates.push(VersionedGasEstimate {
+                contract_id: contract_id.clone(),
+                contract_name: contract_name.clone(),
+                solc_version: solc_version.to_string(),
+                signature: signature.clone(),
+                gas: parse_gas(gas),
+            });
+        }
+    }
+    Ok((artifacts, estimates))
+}
+
+/// The installed solc releases and, unless offline, the downloadable ones
+pub fn available_versions() -> Vec<Version> {
+    let mut versions = Solc::installed_versions();
+    if !is_offline() {
+        versions.extend(Solc::released_versions());
+    }
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// The latest releases of the `max_versions` most recent minor series the
+/// pragmas of the contract allow, oldest first
+pub fn select_versions(
+    contract: &PlainContract,
+    available: &[Version],
+    max_versions: usize,
+) -> Result<Vec<Version>> {
+    let versions = compatible_versions(&contract_pragmas(contract)?, available)?;
+    let skip = versions.len().saturating_sub(max_versions);
+    Ok(versions.into_iter().skip(skip).collect())
+}
+
+/// Compile the contract with each of the versions, with its other settings
+/// unchanged, returns the artifacts and gas estimates of the versions it
+/// compiles with
+pub async fn compile_versions(
+    contract: &PlainContract,
+    cache: Option<&ArtifactCache>,
+    versions: &[Version],
+) -> Result<(Vec<VersionedArtifact>, Vec<VersionedGasEstimate>)> {
+    let mut contract = contract.clone();
+    contract.estimate_gas = true;
+
+    let mut artifacts = vec![];
+    let mut estimates = vec![];
+    for version in versions {
+        contract.metadata.compiler_version = format!("v{version}");
+        match contract.compile_cached(cache).await {
+            Ok(output) if !output.has_compiler_errors() => {
+                let (a, e) = extract_versioned(&contract, &version.to_string())?;
+                artifacts.extend(a);
+                estimates.extend(e);
+            }
+            Ok(_) => debug!("Contract {} fails to compile with {version}", contract.id()),
+            Err(e) => debug!(
+                "Contract {} fails to compile with {version} {e}",
+                contract.id()
+            ),
+        }
+    }
+    Ok((artifacts, estimates))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plain_contract::{ContractSource, SourceFile};
+
+    #[test]
+    fn parse_gas_estimates() {
+        assert_eq!(parse_gas("2415"), Some(2415));
+        assert_eq!(parse_gas("infinite"), None);
+        assert_eq!(parse_gas(""), None);
+    }
+
+    #[test]
+    fn select_compatible_versions() -> Result<()> {
+        let file = |name: &str, content: &str| SourceFile {
+            name: name.into(),
+            content: content.into(),
+        };
+        let mut contract = PlainContract::single_file("");
+        contract.source = ContractSource::MultiSolidity(vec![
+            file("A.sol", "pragma solidity >=0.6.0 <0.9.0; contract A {}"),
+            file("L.sol", "pragma solidity ^0.7.0 || ^0.8.0; library L {}"),
+        ]);
+        let available = [
+            "0.5.17", "0.6.12", "0.7.5", "0.7.6", "0.8.19", "0.8.20", "0.9.0",
+        ]
+        .map(|v| Version::parse(v).unwrap());
+
+        // the latest release of the most recent minor series all pragmas allow
+        let versions = |max_versions| -> Result<Vec<String>> {
+            Ok(select_versions(&contract, &available, max_versions)?
+                .iter()
+                .map(Version::to_string)
+                .collect())
+        };
+        assert_eq!(versions(1)?, ["0.8.20"]);
+        assert_eq!(versions(3)?, ["0.7.6", "0.8.20"]);
+        assert!(versions(0)?.is_empty());
+
+        Ok(())
+    }
+}
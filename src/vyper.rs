@@ -0,0 +1,179 @@
+use alloy_primitives::hex;
+use eyre::{eyre, ContextCompat, Result};
+use log::info;
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+use crate::utils::{is_offline, HttpFetcher};
+
+/// GitHub releases of vyper, each with a binary per platform
+const VYPER_RELEASES_URL: &str = "https://api.github.com/repos/vyperlang/vyper/releases/tags";
+
+#[derive(Deserialize)]
+struct Release {
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    /// e.g. `vyper.0.3.10+commit.91361694.linux`
+    name: String,
+    browser_download_url: String,
+    /// Hash of the binary computed by GitHub, e.g. `sha256:<hex>`
+    digest: Option<String>,
+}
+
+/// Version of a vyper compiler as recorded in the metadata, e.g.
+/// `vyper:0.3.10`, `v0.3.10` or `0.3.10+commit.91361694`
+pub fn vyper_version(compiler_version: &str) -> Result<Version> {
+    let v = compiler_version
+        .trim_start_matches("vyper:")
+        .trim_start_matches('v');
+    let version = Version::parse(v.split('+').next().unwrap_or_default())?;
+    Ok(Version::new(version.major, version.minor, version.patch))
+}
+
+/// Where the binary of a vyper version is installed, `~/.vvm/vyper-<version>`
+/// as done by vvm
+pub fn vyper_path(version: &Version) -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(home)
+        .join(".vvm")
+        .join(format!("vyper-{version}"))
+}
+
+/// The release binary for the operating system, as named by `std::env::consts::OS`
+fn platform_asset<'a>(assets: &'a [Asset], os: &str) -> Option<&'a Asset> {
+    let os = match os {
+        "macos" => "darwin",
+        os => os,
+    };
+    assets
+        .iter()
+        .find(|a| a.name.starts_with("vyper.") && a.name.contains(&format!(".{os}")))
+}
+
+/// Check the downloaded binary against the sha256 digest of its release asset
+fn verify_digest(binary: &[u8], asset: &Asset) -> Result<()> {
+    let expected = asset
+        .digest
+        .as_deref()
+        .and_then(|d| d.strip_prefix("sha256:"))
+        .with_context(|| format!("No sha256 digest of {} to verify it", asset.name))?;
+    let actual = hex::encode(Sha256::digest(binary));
+    match actual.eq_ignore_ascii_case(expected) {
+        true => Ok(()),
+        false => Err(eyre!(
+            "Downloaded {} has sha256 {actual} instead of {expected}",
+            asset.name
+        )),
+    }
+}
+
+/// Download the binary of the vyper version from its GitHub release unless it
+/// is already installed, returns its path. The binary is only installed when
+/// it matches the sha256 digest of the release.
+pub async fn install_vyper(fetcher: &HttpFetcher, version: &Version) -> Result<PathBuf> {
+    let path = vyper_path(version);
+    if path.exists() {
+        return Ok(path);
+    }
+    if is_offline() {
+        return Err(eyre!(
+            "Vyper {version} is not installed and cannot be downloaded offline"
+        ));
+    }
+    let release: Release = serde_json::from_str(
+        &fetcher
+            .get_text(&format!("{VYPER_RELEASES_URL}/v{version}"))
+            .await?,
+    )
+    .map_err(|e| eyre!("Invalid release of vyper {version}: {e}"))?;
+    let asset = platform_asset(&release.assets, std::env::consts::OS)
+        .with_context(|| format!("No vyper {version} binary for {}", std::env::consts::OS))?;
+    let binary = fetcher.get_bytes(&asset.browser_download_url).await?;
+    verify_digest(&binary, asset)?;
+
+    let parent = path.parent().context("No parent folder")?;
+    tokio::fs::create_dir_all(parent).await?;
+    // written aside and renamed, concurrent runs never see a partial binary
+    let partial = parent.join(format!(".vyper-{version}.partial"));
+    tokio::fs::write(&partial, binary).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&partial, std::fs::Permissions::from_mode(0o755)).await?;
+    }
+    tokio::fs::rename(&partial, &path).await?;
+    info!("Installed vyper {version} at {}", path.display());
+    Ok(path)
+}
+
+/// Source code of a top level function of a vyper contract, with its decorators
+pub fn vyper_function_source(content: &str, function_name: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let definition = format!("def {function_name}(");
+    let start = lines.iter().position(|l| l.starts_with(&definition))?;
+    let first = lines[..start]
+        .iter()
+        .rposition(|l| !l.starts_with('@'))
+        .map_or(0, |i| i + 1);
+    let body = lines[start + 1..]
+        .iter()
+        .take_while(|l| l.trim().is_empty() || l.starts_with([' ', '\t']))
+        .count();
+    let source = lines[first..=start + body].join("\n");
+    Some(source.trim_end().into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vyper_versions_and_sources() -> Result<()> {
+        assert_eq!(vyper_version("vyper:0.3.10")?, Version::new(0, 3, 10));
+        assert_eq!(
+            vyper_version("v0.2.4+commit.7949850")?,
+            Version::new(0, 2, 4)
+        );
+        assert!(vyper_path(&Version::new(0, 3, 10)).ends_with(".vvm/vyper-0.3.10"));
+
+        let assets = ["linux", "darwin", "windows.exe"].map(|os| Asset {
+            name: format!("vyper.0.3.10+commit.91361694.{os}"),
+            browser_download_url: os.into(),
+            digest: None,
+        });
+        assert_eq!(
+            platform_asset(&assets, "macos").map(|a| a.browser_download_url.as_str()),
+            Some("darwin")
+        );
+        assert!(platform_asset(&assets, "freebsd").is_none());
+
+        let asset = |digest: Option<&str>| Asset {
+            name: "vyper.0.3.10+commit.91361694.linux".into(),
+            browser_download_url: "linux".into(),
+            digest: digest.map(Into::into),
+        };
+        let binary = b"hello world\n";
+        let digest = "sha256:a948904f2f0f479b8f8197694b30184b0d2ed1c1cd2a1ec0fb85d299a192a447";
+        assert!(verify_digest(binary, &asset(Some(digest))).is_ok());
+        assert!(verify_digest(b"tampered\n", &asset(Some(digest))).is_err());
+        assert!(verify_digest(binary, &asset(None)).is_err());
+
+        let content = "balances: HashMap[address, uint256]\n\n@external\n@view\ndef balanceOf(owner: address) -> uint256:\n    return self.balances[owner]\n\n@external\ndef transfer(to: address, amount: uint256) -> bool:\n    self.balances[msg.sender] -= amount\n\n    self.balances[to] += amount\n    return True\n";
+        assert_eq!(
+            vyper_function_source(content, "balanceOf").as_deref(),
+            Some("@external\n@view\ndef balanceOf(owner: address) -> uint256:\n    return self.balances[owner]")
+        );
+        assert_eq!(
+            vyper_function_source(content, "transfer").map(|s| s.lines().count()),
+            Some(6)
+        );
+        assert_eq!(vyper_function_source(content, "approve"), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use eyre::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::Path,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+/// Filesystem notifications of files created or modified below a set of roots
+pub struct RootWatcher {
+    // dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+/// Whether the event may bring a new contract, removals and reads are ignored
+fn is_addition(event: &Event) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+}
+
+impl RootWatcher {
+    pub fn new(roots: &[String]) -> Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for root in roots {
+            watcher.watch(Path::new(root), RecursiveMode::Recursive)?;
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Block until a file is created or modified, then until no other change
+    /// happened for `settle`, so that a scraper writing several files, or
+    /// writing a file in several steps, is picked up once it is done. Returns
+    /// the paths which changed.
+    pub fn wait(&self, settle: Duration) -> Result<Vec<String>> {
+        let mut paths = vec![];
+        loop {
+            let event = match paths.is_empty() {
+                true => self.events.recv()?,
+                false => match self.events.recv_timeout(settle) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(error) => return Err(error.into()),
+                },
+            }?;
+            if is_addition(&event) {
+                paths.extend(event.paths.iter().map(|p| p.to_string_lossy().into_owned()));
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wait_for_new_files() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let watcher = RootWatcher::new(&[root.path().to_string_lossy().into_owned()])?;
+
+        let folder = root.path().join("0xab");
+        std::fs::create_dir(&folder)?;
+        std::fs::write(folder.join("0xab.json"), "{}")?;
+
+        // the file may be written before the new folder is watched, the
+        // creation of the folder is reported either way
+        let paths = watcher.wait(Duration::from_millis(200))?;
+        assert!(paths.iter().any(|p| p.ends_with("0xab")));
+
+        std::fs::write(folder.join("0xab.json"), "{\"a\": 1}")?;
+        let paths = watcher.wait(Duration::from_millis(200))?;
+        assert!(paths.iter().any(|p| p.ends_with("0xab.json")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use eyre::Result;
+use log::{debug, warn};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, SystemTime},
+};
+use tempfile::TempDir;
+
+/// A run directory untouched for this long is stale even if its process
+/// cannot be checked, e.g. without `/proc`
+const STALE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Scratch directories created between two checks of the workspace size
+const CHECK_INTERVAL: u64 = 64;
+
+static WORKSPACE: OnceLock<Workspace> = OnceLock::new();
+
+/// Scratch space of the compilations, in a `run-<host>-<pid>` directory of the
+/// workspace per process. The host tells apart the processes of containers
+/// sharing the workspace, whose pids are not visible to each other.
+///
+/// Directories of the run are removed when dropped, the run directory when
+/// the run finishes, and the run directories left behind by crashed processes
+/// when a run starts or the workspace grows past its maximum size.
+#[derive(Debug)]
+pub struct Workspace {
+    root: PathBuf,
+    run: PathBuf,
+    max_size: Option<u64>,
+    created: AtomicU64,
+    live: Mutex<HashSet<PathBuf>>,
+}
+
+/// A scratch directory, removed with its content when dropped
+pub struct ScratchDir {
+    dir: TempDir,
+}
+
+impl ScratchDir {
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Some(workspace) = WORKSPACE.get() {
+            workspace.live.lock().unwrap().remove(self.dir.path());
+        }
+    }
+}
+
+/// Whether the process is running, unknown without `/proc`
+fn process_alive(pid: u32) -> Option<bool> {
+    let proc = Path::new("/proc");
+    proc.is_dir().then(|| proc.join(pid.to_string()).exists())
+}
+
+/// Name of the host, or of the container, running the process
+fn hostname() -> String {
+    let name = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_default();
+    let name: String = name
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'))
+        .collect();
+    match name.is_empty() {
+        true => "localhost".into(),
+        false => name,
+    }
+}
+
+/// Host and pid of a run directory name, the host is missing in the names
+/// of older releases
+fn run_owner(name: &str) -> Option<(Option<&str>, u32)> {
+    let owner = name.strip_prefix("run-")?;
+    match owner.rsplit_once('-') {
+        Some((host, pid)) => Some((Some(host), pid.parse().ok()?)),
+        None => Some((None, owner.parse().ok()?)),
+    }
+}
+
+/// Total size of the files of a directory, in bytes
+pub fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+impl Workspace {
+    /// Create the run directory in `root`, removing the stale ones of previous
+    /// runs. `max_size` is in bytes.
+    pub fn new(root: &str, max_size: Option<u64>) -> Result<Self> {
+        let root = PathBuf::from(root);
+        let run = root.join(format!("run-{}-{}", hostname(), std::process::id()));
+        std::fs::create_dir_all(&run)?;
+        let workspace = Self {
+            root,
+            run,
+            max_size,
+            created: AtomicU64::new(0),
+            live: Mutex::new(HashSet::new()),
+        };
+        workspace.remove_stale()?;
+        Ok(workspace)
+    }
+
+    /// Remove the run directories of processes of this host which are no
+    /// longer running, and of any host once untouched for [`STALE_AGE`],
+    /// returns the number of removed directories
+    pub fn remove_stale(&self) -> Result<usize> {
+        let host = hostname();
+        let mut removed = 0;
+        for entry in std::fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path == self.run {
+                continue;
+            }
+            let Some((run_host, pid)) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(run_owner)
+            else {
+                continue;
+            };
+            let exited = run_host == Some(host.as_str()) && process_alive(pid) == Some(false);
+            let untouched = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| SystemTime::now().duration_since(t).ok())
+                .is_some_and(|age| age > STALE_AGE);
+            if exited || untouched {
+                debug!("Removing stale workspace directory {}", path.display());
+                std::fs::remove_dir_all(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove the directories of the run no scratch directory refers to, e.g.
+    /// left behind by a panicking compilation, returns their number
+    pub fn remove_orphans(&self) -> Result<usize> {
+        let live = self.live.lock().unwrap();
+        let mut removed = 0;
+        for entry in std::fs::read_dir(&self.run)? {
+            let path = entry?.path();
+            if !live.contains(&path) {
+                std::fs::remove_dir_all(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Clean up the workspace when it is larger than its maximum size, warning
+    /// if it still is
+    fn check_size(&self) -> Result<()> {
+        let Some(max_size) = self.max_size else {
+            return Ok(());
+        };
+        if dir_size(&self.root) <= max_size {
+            return Ok(());
+        }
+        self.remove_stale()?;
+        self.remove_orphans()?;
+        let size = dir_size(&self.root);
+        if size > max_size {
+            warn!(
+                "Workspace {} uses {} MB, more than its maximum of {} MB",
+                self.root.display(),
+                size / 1_000_000,
+                max_size / 1_000_000
+            );
+        }
+        Ok(())
+    }
+
+    pub fn scratch_dir(&self) -> Result<ScratchDir> {
+        if self
+            .created
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(CHECK_INTERVAL)
+        {
+            self.check_size()?;
+        }
+        // created under the lock, so it is registered before a concurrent
+        // `remove_orphans` can see it
+        let mut live = self.live.lock().unwrap();
+        let dir = tempfile::Builder::new()
+            .prefix("compile-")
+            .tempdir_in(&self.run)?;
+        live.insert(dir.path().to_path_buf());
+        Ok(ScratchDir { dir })
+    }
+
+    /// Remove the run directory, once no compilation is running
+    pub fn finish(&self) -> Result<()> {
+        if self.run.exists() {
+            std::fs::remove_dir_all(&self.run)?;
+        }
+        Ok(())
+    }
+}
+
+/// Use the workspace for the scratch directories of the process
+pub fn init(workspace: Workspace) -> Result<()> {
+    WORKSPACE
+        .set(workspace)
+        .map_err(|_| eyre::eyre!("The workspace is already set"))
+}
+
+/// The workspace of the process, if set
+pub fn get() -> Option<&'static Workspace> {
+    WORKSPACE.get()
+}
+
+/// A scratch directory in the workspace, else in the temporary directory of
+/// the system
+pub fn scratch_dir() -> Result<ScratchDir> {
+    match WORKSPACE.get() {
+        Some(workspace) => workspace.scratch_dir(),
+        None => Ok(ScratchDir {
+            dir: tempfile::tempdir()?,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clean_up_workspace() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().to_string_lossy().to_string();
+
+        // a run of a process which is no longer running, pids are below 2^22
+        let stale = dir.path().join(format!("run-{}-{}", hostname(), u32::MAX));
+        std::fs::create_dir_all(stale.join("compile-a"))?;
+        std::fs::write(stale.join("compile-a/main.sol"), "contract A {}")?;
+        // the pids of another container cannot be checked
+        let container = dir.path().join(format!("run-other-container-{}", u32::MAX));
+        std::fs::create_dir(&container)?;
+        let other = dir.path().join("other");
+        std::fs::create_dir(&other)?;
+
+        let workspace = Workspace::new(&root, Some(1))?;
+        if process_alive(u32::MAX).is_some() {
+            assert!(!stale.exists());
+        }
+        assert!(container.exists());
+        assert!(other.exists());
+        assert_eq!(run_owner("run-12"), Some((None, 12)));
+        assert_eq!(
+            run_owner("run-other-container-12"),
+            Some((Some("other-container"), 12))
+        );
+
+        let scratch = workspace.scratch_dir()?;
+        assert!(scratch.path().starts_with(&workspace.run));
+        std::fs::write(scratch.path().join("main.sol"), "contract A {}")?;
+        assert_eq!(dir_size(&workspace.run), 13);
+
+        let orphan = workspace.run.join("compile-orphan");
+        std::fs::create_dir(&orphan)?;
+        assert_eq!(workspace.remove_orphans()?, 1);
+        assert!(scratch.path().exists());
+
+        let path = scratch.path().to_path_buf();
+        drop(scratch);
+        assert!(!path.exists());
+
+        workspace.finish()?;
+        assert!(!workspace.run.exists());
+        Ok(())
+    }
+}